@@ -0,0 +1,120 @@
+use super::you::scheduled::format_schedule_time;
+use super::{widgets, GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{article, FeedKind, GLOBALS};
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    let naddr = match &app.page {
+        Page::Article(naddr) => naddr.clone(),
+        _ => return,
+    };
+
+    let article = match article::article(&naddr) {
+        Ok(Some(article)) => article,
+        Ok(None) => {
+            widgets::page_header(ui, "Article", |_ui| ());
+            ui.add_space(10.0);
+            ui.label("Looking for this article on the relays...");
+            ui.add_space(10.0);
+            if ui.button("Try again").clicked() {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::FetchNAddr(naddr.clone()));
+            }
+            return;
+        }
+        Err(e) => {
+            widgets::page_header(ui, "Article", |_ui| ());
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+
+    let title = if article.title.is_empty() {
+        article.d.clone()
+    } else {
+        article.title.clone()
+    };
+    widgets::page_header(ui, &title, |_ui| ());
+
+    app.vert_scroll_area().id_salt("article").show(ui, |ui| {
+        ui.add_space(10.0);
+        ui.horizontal_wrapped(|ui| {
+            ui.label("by");
+            let name = gossip_lib::names::best_name_from_pubkey_lookup(&article.author);
+            if ui.link(RichText::new(name).strong()).clicked() {
+                app.set_page(ctx, Page::Person(article.author));
+            }
+            let date = article.published_at.unwrap_or(article.created_at);
+            ui.label(RichText::new(format_schedule_time(date)).weak());
+            if article.published_at.is_some_and(|p| p < article.created_at) {
+                ui.label(
+                    RichText::new(format!(
+                        "(updated {})",
+                        format_schedule_time(article.created_at)
+                    ))
+                    .weak(),
+                );
+            }
+            if article.is_draft() {
+                ui.label(RichText::new("draft").italics());
+            }
+        });
+
+        if let Some(image) = &article.image {
+            ui.add_space(4.0);
+            widgets::break_anywhere_hyperlink_to(ui, app, "🖼 Header image", image);
+        }
+
+        if !article.summary.is_empty() {
+            ui.add_space(10.0);
+            ui.label(RichText::new(&article.summary).italics());
+        }
+
+        if !article.hashtags.is_empty() {
+            ui.add_space(4.0);
+            ui.horizontal_wrapped(|ui| {
+                for hashtag in &article.hashtags {
+                    if ui.link(format!("#{}", hashtag)).clicked() {
+                        app.search = hashtag.to_ascii_lowercase();
+                        app.set_page(ctx, Page::SearchLocal);
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::SearchLocally(app.search.clone()));
+                    }
+                }
+            });
+        }
+
+        ui.add_space(10.0);
+        ui.separator();
+        ui.add_space(10.0);
+
+        widgets::markdown(app, ui, &article.content);
+
+        ui.add_space(10.0);
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("Comments").clicked() {
+                app.set_page(
+                    ctx,
+                    Page::Feed(FeedKind::Thread {
+                        id: article.id,
+                        referenced_by: article.id,
+                        author: Some(article.author),
+                    }),
+                );
+            }
+            if Some(article.author) == GLOBALS.identity.public_key() && ui.button("Edit").clicked()
+            {
+                app.article_fields = article.fields();
+                app.article_hashtags = article.hashtags.join(", ");
+                app.article_preview = false;
+                app.set_page(ctx, Page::YourArticles);
+            }
+        });
+        ui.add_space(10.0);
+    });
+}
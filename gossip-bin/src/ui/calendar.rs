@@ -0,0 +1,254 @@
+use super::{widgets, GossipUi, Page};
+use chrono::{DateTime, Local, Utc};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use egui_file_dialog::FileDialog;
+use gossip_lib::calendar::{self, CalendarDate, EventTime};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{CalendarEvent, RsvpStatus, GLOBALS};
+use nostr_types::{PublicKey, Unixtime};
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    widgets::page_header(ui, "Calendar", |ui| {
+        if ui.button("Refresh").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::SubscribeCalendar);
+        }
+    });
+
+    // Save an event the user chose to export
+    app.calendar_file_dialog.update(ctx);
+    if let Some(path) = app.calendar_file_dialog.take_picked() {
+        if let Some(naddr) = app.calendar_export.take() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::ExportCalendarEvent(naddr, path));
+        }
+    }
+
+    ui.add_space(10.0);
+    ui.label(
+        "Calendar events (NIP-52) from the people you follow. Times are shown in your local time.",
+    );
+
+    ui.add_space(10.0);
+    ui.checkbox(&mut app.calendar_show_past, "Show past events");
+
+    let events = match calendar::calendar_events() {
+        Ok(events) => events,
+        Err(e) => {
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+
+    let now = Unixtime::now();
+    let (mut past, upcoming): (Vec<CalendarEvent>, Vec<CalendarEvent>) =
+        events.into_iter().partition(|e| e.ends_at() < now);
+    past.reverse();
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    app.vert_scroll_area().id_salt("calendar").show(ui, |ui| {
+        ui.heading("Upcoming");
+        ui.add_space(10.0);
+        if upcoming.is_empty() {
+            ui.label("Nobody you follow has anything coming up.");
+        }
+        for event in upcoming.iter() {
+            render_event(app, ctx, ui, event);
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+        }
+
+        if app.calendar_show_past {
+            ui.add_space(20.0);
+            ui.heading("Past");
+            ui.add_space(10.0);
+            for event in past.iter() {
+                render_event(app, ctx, ui, event);
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+            }
+        }
+    });
+}
+
+fn render_event(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, event: &CalendarEvent) {
+    ui.horizontal_wrapped(|ui| {
+        let title = if event.title.is_empty() {
+            "Untitled event"
+        } else {
+            event.title.as_str()
+        };
+        ui.label(RichText::new(title).heading());
+    });
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label(RichText::new(format_time(&event.time)).strong());
+        if let EventTime::Times {
+            start_tzid: Some(tzid),
+            ..
+        } = &event.time
+        {
+            ui.label(RichText::new(format!("(planned in {})", tzid)).weak());
+        }
+    });
+
+    for location in event.locations.iter() {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("📍");
+            if location.starts_with("https://") || location.starts_with("http://") {
+                ui.hyperlink(location);
+            } else {
+                ui.label(location);
+            }
+        });
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label("by");
+        person_link(app, ctx, ui, event.author);
+    });
+
+    if !event.summary.is_empty() {
+        ui.label(RichText::new(&event.summary).italics());
+    }
+    if !event.content.is_empty() {
+        ui.label(&event.content);
+    }
+
+    if !event.participants.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("With");
+            for (pubkey, role) in event.participants.iter() {
+                person_link(app, ctx, ui, *pubkey);
+                if !role.is_empty() {
+                    ui.label(RichText::new(format!("({})", role)).weak());
+                }
+            }
+        });
+    }
+
+    for link in event.links.iter() {
+        ui.hyperlink(link);
+    }
+
+    if !event.hashtags.is_empty() {
+        ui.label(
+            RichText::new(
+                event
+                    .hashtags
+                    .iter()
+                    .map(|t| format!("#{}", t))
+                    .collect::<Vec<String>>()
+                    .join(" "),
+            )
+            .weak(),
+        );
+    }
+
+    let rsvps = calendar::rsvps(event).unwrap_or_default();
+    let mine = GLOBALS
+        .identity
+        .public_key()
+        .and_then(|pk| rsvps.iter().find(|r| r.pubkey == pk))
+        .map(|r| r.status);
+
+    ui.add_space(5.0);
+    ui.horizontal_wrapped(|ui| {
+        for status in RsvpStatus::ALL.iter() {
+            let answered: Vec<String> = rsvps
+                .iter()
+                .filter(|r| r.status == *status)
+                .map(|r| gossip_lib::names::best_name_from_pubkey_lookup(&r.pubkey))
+                .collect();
+            let response = ui
+                .add_enabled(
+                    GLOBALS.identity.is_unlocked(),
+                    egui::SelectableLabel::new(
+                        mine == Some(*status),
+                        format!("{} ({})", status, answered.len()),
+                    ),
+                )
+                .on_hover_text(answered.join(", "));
+            if response.clicked() && mine != Some(*status) {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::RsvpCalendarEvent(
+                        event.naddr(vec![]),
+                        *status,
+                    ));
+            }
+        }
+
+        ui.add_space(10.0);
+        if ui.button("Export .ics").clicked() {
+            app.calendar_export = Some(event.naddr(vec![]));
+            app.calendar_file_dialog = FileDialog::new().default_file_name(&ics_file_name(event));
+            app.calendar_file_dialog.save_file();
+        }
+    });
+}
+
+fn person_link(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey) {
+    let name = gossip_lib::names::best_name_from_pubkey_lookup(&pubkey);
+    if ui.link(name).clicked() {
+        app.set_page(ctx, Page::Person(pubkey));
+    }
+}
+
+// All-day events show their dates as they are. Timed events show in local time.
+fn format_time(time: &EventTime) -> String {
+    match time {
+        EventTime::Dates { start, end } => match end {
+            Some(end) if *end != start.next() => {
+                // The end date is the day after the last day
+                format!("{} – {}", format_date(*start), format_date(end.previous()))
+            }
+            _ => format_date(*start),
+        },
+        EventTime::Times { start, end, .. } => {
+            let start = local_time(*start);
+            let mut s = start.format("%a %e %b %Y, %H:%M").to_string();
+            if let Some(end) = end {
+                let end = local_time(*end);
+                if end.date_naive() == start.date_naive() {
+                    s.push_str(&end.format(" – %H:%M").to_string());
+                } else {
+                    s.push_str(&end.format(" – %a %e %b %Y, %H:%M").to_string());
+                }
+            }
+            s
+        }
+    }
+}
+
+fn format_date(date: CalendarDate) -> String {
+    date.naive_date().format("%a %e %b %Y").to_string()
+}
+
+fn local_time(time: Unixtime) -> DateTime<Local> {
+    let time: DateTime<Utc> = DateTime::from_timestamp(time.0, 0).unwrap_or_default();
+    time.into()
+}
+
+// A file name for an exported event, from its title
+fn ics_file_name(event: &CalendarEvent) -> String {
+    let name: String = event
+        .title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-");
+    if name.is_empty() {
+        "event.ics".to_owned()
+    } else {
+        format!("{}.ics", name)
+    }
+}
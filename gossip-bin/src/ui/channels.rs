@@ -0,0 +1,112 @@
+use super::{widgets, GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{channels, FeedKind, GLOBALS};
+use nostr_types::{Id, NostrBech32, RelayUrl};
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    widgets::page_header(ui, "Public Channels", |_ui| ());
+
+    ui.add_space(10.0);
+    ui.label("Public chat channels (NIP-28) are open discussions that anybody can read and post in. The creator of a channel can hide messages and mute users.");
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.label("Join a channel:");
+        ui.add(
+            text_edit_line!(app, app.join_channel)
+                .hint_text("nevent1..., note1..., or hex id of the channel")
+                .desired_width(400.0),
+        );
+        if ui.button("Join").clicked() {
+            match parse_channel(&app.join_channel) {
+                Some((id, relays)) => {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::JoinChannel(id, relays));
+                    app.join_channel.clear();
+                }
+                None => GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Channel id not recognized.".to_owned()),
+            }
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let list = match channels::channels() {
+        Ok(list) => list,
+        Err(e) => {
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+
+    if list.is_empty() {
+        ui.label("You don't know of any channels yet.");
+        return;
+    }
+
+    app.vert_scroll_area().id_salt("channels").show(ui, |ui| {
+        for channel in list.iter() {
+            ui.horizontal(|ui| {
+                let name = channels::channel_name(channel.id);
+                if ui.link(RichText::new(name).strong()).clicked() {
+                    app.set_page(ctx, Page::Feed(FeedKind::Channel(channel.id)));
+                }
+                if channel.joined {
+                    ui.label(RichText::new("joined").italics());
+                }
+            });
+
+            if !channel.about.is_empty() {
+                ui.label(&channel.about);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("Open").clicked() {
+                    app.set_page(ctx, Page::Feed(FeedKind::Channel(channel.id)));
+                }
+                if channel.joined {
+                    if ui.button("Leave").clicked() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::LeaveChannel(channel.id));
+                    }
+                } else if ui.button("Join").clicked() {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::JoinChannel(channel.id, vec![]));
+                }
+            });
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+        }
+    });
+}
+
+// A channel is referenced by its creation event
+fn parse_channel(input: &str) -> Option<(Id, Vec<RelayUrl>)> {
+    let input = input.trim();
+    let input = input.strip_prefix("nostr:").unwrap_or(input);
+    match NostrBech32::try_from_string(input) {
+        Some(NostrBech32::NEvent(ne)) => {
+            let relays = ne
+                .relays
+                .iter()
+                .filter_map(|r| RelayUrl::try_from_unchecked_url(r).ok())
+                .collect();
+            Some((ne.id, relays))
+        }
+        Some(NostrBech32::Id(id)) => Some((id, vec![])),
+        Some(_) => None,
+        None => Id::try_from_hex_string(input).ok().map(|id| (id, vec![])),
+    }
+}
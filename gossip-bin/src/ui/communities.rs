@@ -0,0 +1,112 @@
+use super::{widgets, GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{communities, FeedKind, GLOBALS};
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    widgets::page_header(ui, "Communities", |ui| {
+        if ui.button("Discover").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::DiscoverCommunities);
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.label("Moderated communities (NIP-72) show only the posts that their moderators have approved. If you moderate a community, you will also see the posts awaiting your approval.");
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.label("Join a community:");
+        ui.add(
+            text_edit_line!(app, app.join_community)
+                .hint_text("naddr1..., or 34550:<pubkey>:<identifier>")
+                .desired_width(400.0),
+        );
+        if ui.button("Join").clicked() {
+            match communities::parse_community_identifier(&app.join_community) {
+                Some((author, d, relays)) => {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::JoinCommunity(author, d, relays));
+                    app.join_community.clear();
+                }
+                None => GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Community address not recognized.".to_owned()),
+            }
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let list = match communities::communities() {
+        Ok(list) => list,
+        Err(e) => {
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+
+    if list.is_empty() {
+        ui.label("You don't know of any communities yet. Press Discover to look for some.");
+        return;
+    }
+
+    app.vert_scroll_area()
+        .id_salt("communities")
+        .show(ui, |ui| {
+            for community in list.iter() {
+                let feed = FeedKind::Community(community.author, community.d.clone());
+
+                ui.horizontal(|ui| {
+                    let name = communities::community_name(community.author, &community.d);
+                    if ui.link(RichText::new(name).strong()).clicked() {
+                        app.set_page(ctx, Page::Feed(feed.clone()));
+                    }
+                    if community.joined {
+                        ui.label(RichText::new("joined").italics());
+                    }
+                    if communities::am_moderator(community.author, &community.d) {
+                        ui.label(RichText::new("moderator").italics());
+                    }
+                });
+
+                if let Ok(Some(definition)) =
+                    communities::definition(community.author, &community.d)
+                {
+                    if !definition.description.is_empty() {
+                        ui.label(&definition.description);
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Open").clicked() {
+                        app.set_page(ctx, Page::Feed(feed.clone()));
+                    }
+                    if community.joined {
+                        if ui.button("Leave").clicked() {
+                            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::LeaveCommunity(
+                                community.author,
+                                community.d.clone(),
+                            ));
+                        }
+                    } else if ui.button("Join").clicked() {
+                        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::JoinCommunity(
+                            community.author,
+                            community.d.clone(),
+                            vec![],
+                        ));
+                    }
+                });
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+            }
+        });
+}
@@ -5,6 +5,8 @@ use eframe::egui;
 use eframe::egui::vec2;
 use eframe::egui::Rect;
 use egui::{Context, Label, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::groups;
 use gossip_lib::FeedKind;
 use gossip_lib::Person;
 use gossip_lib::GLOBALS;
@@ -54,6 +56,10 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
     app.vert_scroll_area()
         .id_salt("dm_chat_list")
         .show(ui, |ui| {
+            if read_setting!(enable_relay_groups) {
+                groups_list(app, ctx, ui);
+            }
+
             let color = app.theme.accent_color();
             for channeldata in channels.drain(..) {
                 let row_response =
@@ -179,17 +185,85 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                     );
                     app.draft_needs_focus = true;
 
-                    // Maybe clear the draft, if we are going into a different channel than last
-                    // time
-                    if let Some(oldtarget) = &app.dm_draft_data_target {
-                        if *oldtarget != channeldata.dm_channel {
-                            app.dm_draft_data.clear();
-                        }
-                    } else {
-                        app.dm_draft_data.clear();
-                    }
-                    app.dm_draft_data_target = Some(channeldata.dm_channel.clone());
+                    // Switch to the draft for this channel, if we are going into a different
+                    // channel than last time
+                    app.open_dm_draft(channeldata.dm_channel.clone());
                 }
             }
         });
 }
+
+// Joined NIP-29 relay-based groups, listed above the direct message chats
+fn groups_list(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.heading("Groups");
+    ui.add_space(6.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Join a group:");
+        ui.add(
+            text_edit_line!(app, app.join_group)
+                .hint_text("relay.example.com'group-id or naddr1...")
+                .desired_width(400.0),
+        );
+        if ui.button("Join").clicked() {
+            match groups::parse_group_identifier(&app.join_group) {
+                Some((relay, group_id)) => {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::JoinGroup(relay, group_id));
+                    app.join_group.clear();
+                }
+                None => GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Group identifier not recognized.".to_owned()),
+            }
+        }
+    });
+
+    ui.add_space(6.0);
+
+    match groups::joined_groups() {
+        Ok(list) => {
+            if list.is_empty() {
+                ui.label("You have not joined any groups.");
+            }
+            for group in list.iter() {
+                ui.horizontal(|ui| {
+                    let name = groups::group_name(&group.relay, &group.group_id);
+                    if ui
+                        .link(RichText::new(name).strong().color(app.theme.accent_color()))
+                        .clicked()
+                    {
+                        app.set_page(
+                            ctx,
+                            Page::Feed(FeedKind::Group(
+                                group.relay.clone(),
+                                group.group_id.clone(),
+                            )),
+                        );
+                        app.draft_needs_focus = true;
+                    }
+                    ui.label(RichText::new(group.relay.as_str()).weak());
+                    if ui.button("Leave").clicked() {
+                        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::LeaveGroup(
+                            group.relay.clone(),
+                            group.group_id.clone(),
+                        ));
+                    }
+                });
+                if !group.about.is_empty() {
+                    ui.label(&group.about);
+                }
+            }
+        }
+        Err(e) => {
+            ui.label(format!("{}", e));
+        }
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+}
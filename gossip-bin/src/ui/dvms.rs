@@ -0,0 +1,318 @@
+use super::{widgets, GossipUi, Page};
+use crate::ui::widgets::CopyButton;
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::dvm::{self, Dvm, Job, JobRequest};
+use gossip_lib::{FeedKind, ZapState, GLOBALS};
+use nostr_types::{Id, NostrBech32, UncheckedUrl};
+
+const INPUT_TYPES: [&str; 4] = ["text", "url", "event", "job"];
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    widgets::page_header(ui, "Data vending machines", |ui| {
+        if ui.button("Discover").clicked() {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SubscribeDvms);
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.label("Data vending machines (NIP-90) do jobs for you, such as translating or summarizing a note, or picking notes for a feed. Some of them want to be paid with a zap first.");
+
+    let dvms = match dvm::dvms() {
+        Ok(dvms) => dvms,
+        Err(e) => {
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    app.vert_scroll_area().id_salt("dvms").show(ui, |ui| {
+        ui.heading("Ask for a job");
+        ui.add_space(10.0);
+        render_job_form(app, ui, &dvms);
+
+        ui.add_space(20.0);
+        ui.heading("Your jobs");
+        ui.add_space(10.0);
+        match dvm::jobs() {
+            Ok(jobs) if jobs.is_empty() => {
+                ui.label("You haven't asked for any jobs yet.");
+            }
+            Ok(jobs) => {
+                for job in jobs.iter() {
+                    render_job(app, ctx, ui, job);
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+                }
+            }
+            Err(e) => {
+                ui.label(format!("{}", e));
+            }
+        }
+
+        ui.add_space(20.0);
+        ui.heading("Machines");
+        ui.add_space(10.0);
+        if dvms.is_empty() {
+            ui.label(
+                "You don't know of any data vending machines yet. Press Discover to look for some.",
+            );
+        }
+        for dvm in dvms.iter() {
+            render_dvm(app, ctx, ui, dvm);
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+        }
+    });
+}
+
+fn render_job_form(app: &mut GossipUi, ui: &mut Ui, dvms: &[Dvm]) {
+    ui.horizontal(|ui| {
+        ui.label("Job:");
+        egui::ComboBox::from_id_salt("DvmJobKind")
+            .selected_text(dvm::job_kind_name(app.dvm_job_kind))
+            .show_ui(ui, |ui| {
+                for (kind, name) in dvm::JOB_KINDS.iter() {
+                    ui.selectable_value(&mut app.dvm_job_kind, *kind, *name);
+                }
+            });
+
+        // Only offer machines that take this kind of job
+        if app.dvm_job_target.is_some_and(|pk| {
+            !dvms
+                .iter()
+                .any(|d| d.pubkey == pk && d.takes(app.dvm_job_kind))
+        }) {
+            app.dvm_job_target = None;
+        }
+
+        ui.label("Ask:");
+        egui::ComboBox::from_id_salt("DvmJobTarget")
+            .selected_text(match app.dvm_job_target {
+                Some(pubkey) => dvm::dvm_name(pubkey),
+                None => "Any machine".to_owned(),
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut app.dvm_job_target, None, "Any machine");
+                for dvm in dvms.iter().filter(|d| d.takes(app.dvm_job_kind)) {
+                    ui.selectable_value(&mut app.dvm_job_target, Some(dvm.pubkey), dvm.name());
+                }
+            });
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Input:");
+        egui::ComboBox::from_id_salt("DvmJobInputType")
+            .selected_text(app.dvm_job_input_type.clone())
+            .show_ui(ui, |ui| {
+                for input_type in INPUT_TYPES.iter() {
+                    ui.selectable_value(
+                        &mut app.dvm_job_input_type,
+                        (*input_type).to_owned(),
+                        *input_type,
+                    );
+                }
+            });
+        ui.add(
+            text_edit_line!(app, app.dvm_job_input)
+                .hint_text(match app.dvm_job_input_type.as_str() {
+                    "url" => "https://...",
+                    "event" => "note1..., nevent1..., or a hex id",
+                    "job" => "The hex id of an earlier job request",
+                    _ => "Text for the job, if it needs any",
+                })
+                .desired_width(400.0),
+        );
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Parameters:");
+        ui.add(
+            text_edit_line!(app, app.dvm_job_params)
+                .hint_text("name=value, name=value (e.g. language=es)")
+                .desired_width(400.0),
+        );
+    });
+
+    if ui.button("Submit").clicked() {
+        let input = match parse_input(&app.dvm_job_input_type, &app.dvm_job_input) {
+            Some(input) => input,
+            None => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Event id not recognized.".to_owned());
+                return;
+            }
+        };
+        let params = app
+            .dvm_job_params
+            .split(',')
+            .filter_map(|p| p.split_once('='))
+            .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+            .filter(|(name, _)| !name.is_empty())
+            .collect();
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::SubmitDvmJob(JobRequest {
+                kind: app.dvm_job_kind,
+                input,
+                input_type: app.dvm_job_input_type.clone(),
+                params,
+                dvm: app.dvm_job_target,
+            }));
+        app.dvm_job_input.clear();
+        app.dvm_job_params.clear();
+    }
+}
+
+// Events are referred to by their hex id in job requests
+fn parse_input(input_type: &str, input: &str) -> Option<String> {
+    let input = input.trim();
+    if input_type != "event" || input.is_empty() {
+        return Some(input.to_owned());
+    }
+    let input = input.strip_prefix("nostr:").unwrap_or(input);
+    match NostrBech32::try_from_string(input) {
+        Some(NostrBech32::NEvent(ne)) => Some(ne.id.as_hex_string()),
+        Some(NostrBech32::Id(id)) => Some(id.as_hex_string()),
+        Some(_) => None,
+        None => Id::try_from_hex_string(input)
+            .ok()
+            .map(|id| id.as_hex_string()),
+    }
+}
+
+fn render_job(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, job: &Job) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(dvm::job_kind_name(job.kind())).strong());
+        if let Some(pubkey) = job.dvm() {
+            ui.label(format!("asked of {}", dvm::dvm_name(pubkey)));
+        }
+        ui.label(RichText::new(crate::date_ago::date_ago(job.request.created_at)).weak());
+    });
+
+    let input = job.input();
+    if !input.is_empty() {
+        ui.label(RichText::new(input).italics());
+    }
+
+    if job.feedback.is_empty() && job.results.is_empty() {
+        ui.label("Waiting for a machine to take the job...");
+    }
+
+    for feedback in job.feedback.iter() {
+        let pubkey = feedback.event.pubkey;
+        ui.horizontal_wrapped(|ui| {
+            ui.label(format!("{}:", dvm::dvm_name(pubkey)));
+            ui.label(RichText::new(&feedback.status).strong());
+            if !feedback.extra.is_empty() {
+                ui.label(&feedback.extra);
+            }
+            if feedback.payment_required() {
+                if let Some(msats) = feedback.amount {
+                    ui.label(format!("{} sats", msats / 1000));
+                }
+                match dvm::dvm_lnurl(pubkey) {
+                    Some(lnurl) => {
+                        if ui.button("Pay").clicked() {
+                            if GLOBALS.identity.is_unlocked() {
+                                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::ZapStart(
+                                    feedback.event.id,
+                                    pubkey,
+                                    UncheckedUrl(lnurl),
+                                ));
+                            } else {
+                                GLOBALS
+                                    .status_queue
+                                    .write()
+                                    .write("Your key is not setup.".to_string());
+                            }
+                        }
+                    }
+                    None => {
+                        ui.label(RichText::new("(no lightning address to pay)").weak());
+                    }
+                }
+            }
+        });
+
+        // The zap paying for the job
+        if app.note_being_zapped == Some(feedback.event.id) {
+            ui.horizontal_wrapped(|ui| {
+                app.render_zap_area(ui);
+            });
+            if ui
+                .add(CopyButton::new())
+                .on_hover_text("Copy Invoice")
+                .clicked()
+            {
+                ui.output_mut(|o| {
+                    if let ZapState::ReadyToPay(_id, ref invoice) = app.zap_state {
+                        o.commands
+                            .push(egui::OutputCommand::CopyText(invoice.to_owned()));
+                    }
+                });
+            }
+        }
+    }
+
+    for result in job.results.iter() {
+        ui.add_space(5.0);
+        ui.label(format!("Result from {}:", dvm::dvm_name(result.pubkey)));
+        if job.kind() == dvm::CONTENT_DISCOVERY {
+            let count = dvm::result_ids(result).len();
+            ui.horizontal(|ui| {
+                ui.label(format!("{} notes", count));
+                if job.dvm() == Some(result.pubkey) && ui.button("Open feed").clicked() {
+                    app.set_page(ctx, Page::Feed(FeedKind::DvmFeed(result.pubkey)));
+                }
+            });
+        } else {
+            ui.label(&result.content);
+        }
+    }
+}
+
+fn render_dvm(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, dvm: &Dvm) {
+    ui.horizontal(|ui| {
+        if ui.link(RichText::new(dvm.name()).strong()).clicked() {
+            app.set_page(ctx, Page::Person(dvm.pubkey));
+        }
+    });
+
+    let about = dvm.about();
+    if !about.is_empty() {
+        ui.label(about);
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Takes:");
+        ui.label(
+            dvm.kinds
+                .iter()
+                .map(|k| dvm::job_kind_name(*k))
+                .collect::<Vec<String>>()
+                .join(", "),
+        );
+    });
+
+    ui.horizontal(|ui| {
+        if dvm.takes(dvm::CONTENT_DISCOVERY) && ui.button("Open feed").clicked() {
+            app.set_page(ctx, Page::Feed(FeedKind::DvmFeed(dvm.pubkey)));
+        }
+        if ui.button("Ask for a job").clicked() {
+            if !dvm.takes(app.dvm_job_kind) {
+                app.dvm_job_kind = dvm.kinds[0];
+            }
+            app.dvm_job_target = Some(dvm.pubkey);
+        }
+    });
+}
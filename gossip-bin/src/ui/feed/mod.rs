@@ -346,6 +346,210 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
             ui.add_space(6.0);
             render_dm_feed(app, ui, channel);
         }
+        FeedKind::Channel(id) => {
+            let channel = gossip_lib::channels::channel(id).ok().flatten();
+            let joined = channel.as_ref().map(|c| c.joined).unwrap_or(false);
+
+            ui.add_space(10.0);
+            ui.allocate_ui_with_layout(
+                Vec2::new(ui.available_width(), ui.spacing().interact_size.y),
+                egui::Layout::left_to_right(egui::Align::Center),
+                |ui| {
+                    add_left_space(ui);
+                    ui.heading(gossip_lib::channels::channel_name(id));
+                    recompute_btn(app, ui);
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.add_space(16.0);
+                        if joined {
+                            if widgets::Button::bordered(&app.theme, "Leave")
+                                .small(true)
+                                .show(ui)
+                                .clicked()
+                            {
+                                let _ = GLOBALS
+                                    .to_overlord
+                                    .send(ToOverlordMessage::LeaveChannel(id));
+                            }
+                        } else if widgets::Button::bordered(&app.theme, "Join")
+                            .small(true)
+                            .show(ui)
+                            .clicked()
+                        {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::JoinChannel(id, vec![]));
+                        }
+                    });
+                },
+            );
+            if let Some(channel) = &channel {
+                if !channel.about.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        add_left_space(ui);
+                        ui.label(&channel.about);
+                    });
+                }
+            }
+            ui.add_space(6.0);
+
+            render_a_feed(app, ctx, ui, None, &scroll_widget_id, load_more);
+        }
+        FeedKind::Group(relay, group_id) => {
+            let group = gossip_lib::groups::group(&relay, &group_id).ok().flatten();
+            let joined = group.as_ref().map(|g| g.joined).unwrap_or(false);
+
+            ui.add_space(10.0);
+            ui.allocate_ui_with_layout(
+                Vec2::new(ui.available_width(), ui.spacing().interact_size.y),
+                egui::Layout::left_to_right(egui::Align::Center),
+                |ui| {
+                    add_left_space(ui);
+                    ui.heading(gossip_lib::groups::group_name(&relay, &group_id));
+                    ui.label(RichText::new(relay.as_str()).weak());
+                    recompute_btn(app, ui);
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.add_space(16.0);
+                        if joined {
+                            if widgets::Button::bordered(&app.theme, "Leave")
+                                .small(true)
+                                .show(ui)
+                                .clicked()
+                            {
+                                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::LeaveGroup(
+                                    relay.clone(),
+                                    group_id.clone(),
+                                ));
+                            }
+                        } else if widgets::Button::bordered(&app.theme, "Join")
+                            .small(true)
+                            .show(ui)
+                            .clicked()
+                        {
+                            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::JoinGroup(
+                                relay.clone(),
+                                group_id.clone(),
+                            ));
+                        }
+                    });
+                },
+            );
+            if let Some(group) = &group {
+                if !group.about.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        add_left_space(ui);
+                        ui.label(&group.about);
+                    });
+                }
+            }
+            ui.add_space(6.0);
+
+            render_a_feed(app, ctx, ui, None, &scroll_widget_id, load_more);
+        }
+        FeedKind::Community(author, d) => {
+            let definition = gossip_lib::communities::definition(author, &d)
+                .ok()
+                .flatten();
+            let joined = gossip_lib::communities::is_joined(author, &d);
+
+            ui.add_space(10.0);
+            ui.allocate_ui_with_layout(
+                Vec2::new(ui.available_width(), ui.spacing().interact_size.y),
+                egui::Layout::left_to_right(egui::Align::Center),
+                |ui| {
+                    add_left_space(ui);
+                    ui.heading(gossip_lib::communities::community_name(author, &d));
+                    recompute_btn(app, ui);
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.add_space(16.0);
+                        if joined {
+                            if widgets::Button::bordered(&app.theme, "Leave")
+                                .small(true)
+                                .show(ui)
+                                .clicked()
+                            {
+                                let _ = GLOBALS
+                                    .to_overlord
+                                    .send(ToOverlordMessage::LeaveCommunity(author, d.clone()));
+                            }
+                        } else if widgets::Button::bordered(&app.theme, "Join")
+                            .small(true)
+                            .show(ui)
+                            .clicked()
+                        {
+                            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::JoinCommunity(
+                                author,
+                                d.clone(),
+                                vec![],
+                            ));
+                        }
+                    });
+                },
+            );
+            if let Some(definition) = &definition {
+                if !definition.description.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        add_left_space(ui);
+                        ui.label(&definition.description);
+                    });
+                }
+            }
+            let moderators = gossip_lib::communities::moderators(author, &d).unwrap_or_default();
+            ui.horizontal_wrapped(|ui| {
+                add_left_space(ui);
+                ui.label("moderators:");
+                for pubkey in &moderators {
+                    let name = gossip_lib::names::best_name_from_pubkey_lookup(pubkey);
+                    if ui.link(name).clicked() {
+                        app.set_page(ctx, Page::Person(*pubkey));
+                    }
+                }
+            });
+            ui.add_space(6.0);
+
+            render_a_feed(app, ctx, ui, None, &scroll_widget_id, load_more);
+        }
+        FeedKind::DvmFeed(pubkey) => {
+            ui.add_space(10.0);
+            ui.allocate_ui_with_layout(
+                Vec2::new(ui.available_width(), ui.spacing().interact_size.y),
+                egui::Layout::left_to_right(egui::Align::Center),
+                |ui| {
+                    add_left_space(ui);
+                    ui.heading(gossip_lib::dvm::dvm_name(pubkey));
+                    recompute_btn(app, ui);
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.add_space(16.0);
+                        if widgets::Button::bordered(&app.theme, "Ask again")
+                            .small(true)
+                            .show(ui)
+                            .on_hover_text("Ask the machine for a fresh feed")
+                            .clicked()
+                        {
+                            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SubmitDvmJob(
+                                gossip_lib::JobRequest {
+                                    kind: gossip_lib::dvm::CONTENT_DISCOVERY,
+                                    input: "".to_owned(),
+                                    input_type: "".to_owned(),
+                                    params: vec![],
+                                    dvm: Some(pubkey),
+                                },
+                            ));
+                        }
+                    });
+                },
+            );
+            ui.horizontal_wrapped(|ui| {
+                add_left_space(ui);
+                ui.label("A feed picked for you by a data vending machine. Notes show up as the machine lists them and we fetch them.");
+            });
+            ui.add_space(6.0);
+
+            render_a_feed(app, ctx, ui, None, &scroll_widget_id, load_more);
+        }
     }
 
     // Handle any changes due to changes in which notes are visible
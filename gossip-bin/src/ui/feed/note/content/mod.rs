@@ -7,8 +7,8 @@ use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::FeedKind;
 use gossip_lib::GLOBALS;
 use nostr_types::{
-    ContentSegment, FileMetadata, Id, NAddr, NEvent, NostrBech32, NostrUrl, ParsedTag, PublicKey,
-    RelayUrl, Span,
+    ContentSegment, EventKind, FileMetadata, Id, NAddr, NEvent, NostrBech32, NostrUrl, ParsedTag,
+    PublicKey, RelayUrl, Span,
 };
 use std::{
     cell::{Ref, RefCell},
@@ -264,6 +264,9 @@ pub(super) fn render_hyperlink(
 
     if let (Ok(url), Some(nurl)) = (url::Url::try_from(link), app.try_check_url(link)) {
         if let Some(mimetype) = gossip_lib::media_url_mimetype(url.path()) {
+            // If it is a blossom blob, the author's blossom servers may have it too
+            GLOBALS.fetcher.set_blob_author(&nurl, note.event.pubkey);
+
             if mimetype.starts_with("image/") {
                 media::show_image(app, ui, nurl, privacy_issue, note.volatile, file_metadata);
             } else if mimetype.starts_with("video/") {
@@ -378,7 +381,11 @@ pub(super) fn render_parameterized_event_link(
     let name = format!("[{:?}: {}]", naddr.kind, naddr.d);
     // let name = format!("nostr:{}", naddr.as_bech32_string());
     if ui.link(&name).clicked() {
-        if let Ok(Some(prevent)) =
+        if naddr.kind == EventKind::LongFormContent || naddr.kind == EventKind::DraftLongFormContent
+        {
+            // The article page fetches it if we don't have it
+            app.set_page(ui.ctx(), Page::Article(naddr.to_owned()));
+        } else if let Ok(Some(prevent)) =
             GLOBALS
                 .db()
                 .get_replaceable_event(naddr.kind, naddr.author, &naddr.d)
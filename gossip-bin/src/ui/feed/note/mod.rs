@@ -19,7 +19,11 @@ use egui::{
     UiBuilder,
 };
 use gossip_lib::comms::ToOverlordMessage;
-use gossip_lib::{relay, DmChannel, FeedKind, Person, PersonTable, Table, ZapState, GLOBALS};
+use gossip_lib::{
+    git, moderation, ots, outbox, relay, zaps, Article, CountKind, DeliveryState, DmChannel,
+    DraftTarget, FeedKind, Moderation, Person, PersonTable, Table, TimestampStatus, ZapGoal,
+    ZapState, GLOBALS,
+};
 use nostr_types::{
     Event, EventDelegation, EventKind, EventReference, IdHex, NAddr, NEvent, NostrUrl, UncheckedUrl,
 };
@@ -798,8 +802,13 @@ pub fn render_note_inside_framing(
                                         app.draft_needs_focus = true;
                                         app.show_post_area = true;
 
-                                        app.draft_data.replying_to = Some(note.event.id);
-                                        if let Some(subject) = note.event.subject() {
+                                        app.open_draft(DraftTarget::Reply(note.event.id));
+                                        // Take the subject from the note, unless the draft has one
+                                        let subject = note
+                                            .event
+                                            .subject()
+                                            .filter(|_| !app.draft_data.include_subject);
+                                        if let Some(subject) = subject {
                                             let mut subject = subject.to_owned();
                                             if !subject.starts_with("Re: ") {
                                                 subject = format!("Re: {}", subject);
@@ -809,6 +818,17 @@ pub fn render_note_inside_framing(
                                             app.draft_data.include_subject = true;
                                         }
                                     }
+
+                                    if let Some(count) = GLOBALS
+                                        .relay_counts
+                                        .get(&CountKind::References(note.event.id))
+                                        .and_then(|counts| counts.count())
+                                    {
+                                        ui.label(RichText::new(format!("{}", count)).weak())
+                                            .on_hover_text(
+                                                "Replies, mentions and quotes counted by relays (NIP-45)",
+                                            );
+                                    }
                                 };
 
                                 ui.add_space(24.0);
@@ -848,6 +868,7 @@ pub fn render_note_inside_framing(
                                             .take(3)
                                             .collect();
 
+                                        app.open_draft(DraftTarget::Post);
                                         if !app.draft_data.draft.ends_with(' ')
                                             && !app.draft_data.draft.is_empty()
                                         {
@@ -1058,6 +1079,15 @@ pub fn render_note_inside_framing(
                                             _ => app.note_showing_reactions = Some(note.event.id),
                                         }
                                     }
+
+                                    if let Some(count) = GLOBALS
+                                        .relay_counts
+                                        .get(&CountKind::Reactions(note.event.id))
+                                        .and_then(|counts| counts.count())
+                                    {
+                                        ui.label(RichText::new(format!("({})", count)).weak())
+                                            .on_hover_text("Reactions counted by relays (NIP-45)");
+                                    }
                                 }
 
                                 if GLOBALS.delayed_posts.contains(&note.event.id) {
@@ -1254,6 +1284,92 @@ fn render_subject(ui: &mut Ui, event: &Event) {
     ui.reset_style();
 }
 
+// Long-form articles are read on their own page
+fn render_article_card(app: &mut GossipUi, ui: &mut Ui, article: &Article) {
+    ui.vertical(|ui| {
+        let title = if article.title.is_empty() {
+            article.d.as_str()
+        } else {
+            article.title.as_str()
+        };
+        ui.label(RichText::new(title).text_style(TextStyle::Name("subject".into())));
+        if !article.summary.is_empty() {
+            ui.add_space(4.0);
+            ui.label(&article.summary);
+        }
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            if ui.link("Read article").clicked() {
+                app.set_page(ui.ctx(), Page::Article(article.naddr(vec![])));
+            }
+            if article.is_draft() {
+                ui.label(RichText::new("draft").italics());
+            }
+        });
+    });
+}
+
+// Patches are shown as coloured diffs, cut short until opened
+fn render_patch(app: &mut GossipUi, ui: &mut Ui, event: &Event) {
+    const PATCH_PREVIEW_LINES: usize = 40;
+
+    ui.vertical(|ui| {
+        ui.label(RichText::new(git::subject(event)).text_style(TextStyle::Name("subject".into())));
+        if let Some((author, d)) = git::repo_of(event) {
+            ui.horizontal(|ui| {
+                ui.label("patch for");
+                if ui.link(git::repo_name(author, &d)).clicked() {
+                    app.set_page(ui.ctx(), Page::GitRepository(author, d));
+                }
+            });
+        }
+        ui.add_space(4.0);
+
+        let opened = app.opened.contains(&event.id);
+        let max_lines = if opened {
+            None
+        } else {
+            Some(PATCH_PREVIEW_LINES)
+        };
+        let truncated = widgets::diff(app, ui, &event.content, max_lines);
+
+        if truncated && ui.button("Show more ▼").clicked() {
+            app.opened.insert(event.id);
+            app.feed_note_height.remove(&event.id);
+        } else if opened && ui.button("Show less ▲").clicked() {
+            app.opened.remove(&event.id);
+            app.feed_note_height.remove(&event.id);
+        }
+    });
+}
+
+fn render_zap_goal(ui: &mut Ui, goal: &ZapGoal) {
+    ui.vertical(|ui| {
+        ui.label(RichText::new(&goal.description).text_style(TextStyle::Name("subject".into())));
+        if !goal.summary.is_empty() {
+            ui.label(RichText::new(&goal.summary).italics());
+        }
+        if let Some(url) = &goal.url {
+            ui.hyperlink(url);
+        }
+        ui.add_space(4.0);
+        render_zap_goal_progress(ui, goal);
+    });
+}
+
+fn render_zap_goal_progress(ui: &mut Ui, goal: &ZapGoal) {
+    let raised = goal.raised();
+    let mut text = format!("{} of {} sats", raised.0 / 1000, goal.amount.0 / 1000);
+    if goal.is_closed() {
+        text.push_str(" (closed)");
+    }
+    ui.add(
+        egui::ProgressBar::new(goal.progress(raised))
+            .desired_width(300.0)
+            .text(text),
+    );
+}
+
 fn render_note_between_header_and_footer(
     app: &mut GossipUi,
     ui: &mut Ui,
@@ -1293,6 +1409,22 @@ fn render_note_between_header_and_footer(
                             app.approved.insert(event.id);
                             app.feed_note_height.remove(&event.id); // will need to be recalculated.
                         }
+                    } else if let Some(moderation) = note
+                        .moderation
+                        .as_ref()
+                        .filter(|_| !app.approved.contains(&event.id))
+                    {
+                        let text = match moderation {
+                            Moderation::Hide(reason) => format!("Hidden by moderators: {}", reason),
+                            Moderation::Warn(reason) => {
+                                format!("Content-Warning (moderators): {}", reason)
+                            }
+                        };
+                        ui.label(RichText::new(text).monospace().italics());
+                        if ui.button("Show Post").clicked() {
+                            app.approved.insert(event.id);
+                            app.feed_note_height.remove(&event.id); // will need to be recalculated.
+                        }
                     } else if note.repost == Some(RepostType::Kind6Embedded) {
                         if note.embedded_event.is_some() {
                             let inner_note_data =
@@ -1361,6 +1493,12 @@ fn render_note_between_header_and_footer(
                                 }
                             }
                         }
+                    } else if let Some(article) = Article::from_event(event) {
+                        render_article_card(app, ui, &article);
+                    } else if let Some(goal) = ZapGoal::from_event(event) {
+                        render_zap_goal(ui, &goal);
+                    } else if event.kind == EventKind::Patches {
+                        render_patch(app, ui, event);
                     } else {
                         // Possible subject line
                         render_subject(ui, event);
@@ -1375,6 +1513,28 @@ fn render_note_between_header_and_footer(
                         );
                     }
                 });
+
+                // Notes raising funds for a zap goal show how far along it is
+                if let Some(goal) = zaps::goal_of(event).and_then(|(id, _)| ZapGoal::load(id)) {
+                    ui.add_space(4.0);
+                    ui.label(format!("Raising funds for: {}", goal.description));
+                    render_zap_goal_progress(ui, &goal);
+                }
+
+                // Moderators can approve posts awaiting approval in their community
+                if let FeedKind::Community(author, d) = GLOBALS.feed.get_feed_kind() {
+                    if GLOBALS.feed.is_pending_approval(event.id) {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new("Awaiting approval").italics());
+                            if ui.button("Approve").clicked() {
+                                let _ = GLOBALS.to_overlord.send(
+                                    ToOverlordMessage::ApproveCommunityPost(event.id, author, d),
+                                );
+                                app.feed_note_height.remove(&event.id);
+                            }
+                        });
+                    }
+                }
             });
     }
 }
@@ -1573,8 +1733,7 @@ fn note_actions(
                     app.draft_needs_focus = true;
                     app.show_post_area = true;
 
-                    app.draft_data.is_annotate = true;
-                    app.draft_data.replying_to = Some(note.event.id)
+                    app.open_draft(DraftTarget::Annotation(note.event.id));
                 }),
             )));
 
@@ -1595,6 +1754,22 @@ fn note_actions(
                 }
             }
 
+            // Timestamp it (NIP-03), unless it is already timestamped
+            if read_setting!(enable_timestamps)
+                && note.encryption != EncryptionType::Giftwrap
+                && ots::timestamp_status(note.event.id).is_none()
+                && !ots::is_timestamp_requested(note.event.id)
+            {
+                my_items.push(MoreMenuItem::Button(MoreMenuButton::new(
+                    "Timestamp",
+                    Box::new(|_, _| {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::RequestTimestamp(note.event.id));
+                    }),
+                )));
+            }
+
             items.push(MoreMenuItem::SubMenu(MoreMenuSubMenu::new(
                 "Manage", my_items, &menu,
             )))
@@ -1635,6 +1810,31 @@ fn note_actions(
         )));
     } // end Bookmark
 
+    // ---- Report ----
+    if GLOBALS
+        .identity
+        .public_key()
+        .is_some_and(|pk| pk != note.event.pubkey)
+    {
+        let id = note.event.id;
+        let mut report_items: Vec<MoreMenuItem> = Vec::new();
+        for reason in moderation::REPORT_TYPES {
+            report_items.push(MoreMenuItem::Button(MoreMenuButton::new(
+                format!("{}{}", reason[..1].to_uppercase(), &reason[1..]),
+                Box::new(move |_, _| {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::Report(id, reason.to_owned()));
+                }),
+            )));
+        }
+        items.push(MoreMenuItem::SubMenu(MoreMenuSubMenu::new(
+            "Report",
+            report_items,
+            &menu,
+        )));
+    } // end Report
+
     // ---- Open with ----
     if !note.event.kind.is_direct_message_related() {
         let mut my_items: Vec<MoreMenuItem> = Vec::new();
@@ -1908,6 +2108,32 @@ fn draw_seen_on(app: &mut GossipUi, ui: &mut Ui, note: &std::cell::Ref<NoteData>
                     } else {
                         ui.label("unknown");
                     }
+
+                    // Delivery report, if this is one of our events
+                    if let Ok(Some(report)) = outbox::delivery_report(note.event.id) {
+                        ui.separator();
+                        ui.label(RichText::new("Delivery").strong());
+                        for delivery in report.deliveries.iter() {
+                            let status = match delivery.state {
+                                DeliveryState::Pending => {
+                                    format!("… {} (attempt {})", delivery.relay, delivery.attempts)
+                                }
+                                DeliveryState::Accepted => format!("✔ {}", delivery.relay),
+                                DeliveryState::Rejected => {
+                                    format!("✖ {} {}", delivery.relay, delivery.message)
+                                }
+                                DeliveryState::Failed => format!(
+                                    "↻ {} {} (will retry)",
+                                    delivery.relay, delivery.message
+                                ),
+                                DeliveryState::GaveUp => format!(
+                                    "✖ {} gave up after {} attempts {}",
+                                    delivery.relay, delivery.attempts, delivery.message
+                                ),
+                            };
+                            ui.label(status);
+                        }
+                    }
                 });
             });
     }
@@ -1925,5 +2151,43 @@ fn draw_seen_on(app: &mut GossipUi, ui: &mut Ui, note: &std::cell::Ref<NoteData>
         }
     });
 
+    // NIP-03 timestamp badge
+    if read_setting!(enable_timestamps) {
+        let badge = match ots::timestamp_status(note.event.id) {
+            Some(TimestampStatus::Verified { height, time: when }) => {
+                let date = time::OffsetDateTime::from_unix_timestamp(when.0)
+                    .ok()
+                    .and_then(|t| {
+                        t.format(time::macros::format_description!("[year]-[month]-[day]"))
+                            .ok()
+                    })
+                    .unwrap_or_default();
+                Some(
+                    ui.label(RichText::new(format!("⏱ block {} / {}", height, date)).weak())
+                        .on_hover_text(format!(
+                            "OpenTimestamps: this note existed by Bitcoin block {} ({})",
+                            height, date
+                        )),
+                )
+            }
+            Some(TimestampStatus::Pending) => {
+                Some(ui.label(RichText::new("⏱").weak()).on_hover_text(
+                    "OpenTimestamps: this note has a timestamp that is not in a Bitcoin block yet",
+                ))
+            }
+            Some(TimestampStatus::Invalid(reason)) => Some(
+                ui.label(RichText::new("⏱ ✖").color(app.theme.warning_marker_text_color()))
+                    .on_hover_text(format!(
+                        "This note has a timestamp that does not check out. {}",
+                        reason
+                    )),
+            ),
+            Some(TimestampStatus::Checking) | None => None,
+        };
+        if let Some(badge) = badge {
+            return response | response2 | badge;
+        }
+    }
+
     response | response2
 }
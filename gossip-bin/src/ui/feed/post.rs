@@ -1,5 +1,6 @@
 use super::FeedNoteParams;
 use crate::ui::widgets::{InformationPopup, MoreMenuButton, MoreMenuItem};
+use crate::ui::you::scheduled::{format_schedule_time, parse_schedule_time};
 use crate::ui::{widgets, you, FeedKind, GossipUi, HighlightType, Label, Page, Sense, Theme};
 use eframe::egui;
 use eframe::epaint::text::LayoutJob;
@@ -11,7 +12,9 @@ use egui_winit::egui::{vec2, AboveOrBelow, Id};
 use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::{DmChannel, PersonTable, Relay, Table, GLOBALS};
 use memoize::memoize;
-use nostr_types::{ContentSegment, NostrBech32, NostrUrl, ParsedTag, ShatteredContent, Tag};
+use nostr_types::{
+    ContentSegment, NostrBech32, NostrUrl, ParsedTag, RelayUrl, ShatteredContent, Tag, Unixtime,
+};
 use std::collections::HashMap;
 
 #[memoize]
@@ -150,14 +153,162 @@ pub(in crate::ui) fn posting_area(
                 Page::Feed(FeedKind::DmChat(dm_channel)) => Some(dm_channel.clone()),
                 _ => None,
             };
-            match &dm_channel {
-                Some(dmc) => dm_posting_area(app, ctx, frame, ui, dmc),
-                None => real_posting_area(app, ctx, ui),
+            let channel: Option<nostr_types::Id> = match &app.page {
+                Page::Feed(FeedKind::Channel(id)) => Some(*id),
+                _ => None,
+            };
+            let group: Option<(RelayUrl, String)> = match &app.page {
+                Page::Feed(FeedKind::Group(relay, group_id)) => {
+                    Some((relay.clone(), group_id.clone()))
+                }
+                _ => None,
+            };
+            match (&dm_channel, channel, group) {
+                (Some(dmc), _, _) => dm_posting_area(app, ctx, frame, ui, dmc),
+                (None, Some(id), _) => channel_posting_area(app, ui, id),
+                (None, None, Some((relay, group_id))) => {
+                    group_posting_area(app, ui, relay, group_id)
+                }
+                (None, None, None) => real_posting_area(app, ctx, ui),
             }
         }
     });
 }
 
+fn channel_posting_area(app: &mut GossipUi, ui: &mut Ui, channel: nostr_types::Id) {
+    let joined = gossip_lib::channels::channel(channel)
+        .ok()
+        .flatten()
+        .map(|c| c.joined)
+        .unwrap_or(false);
+    if !joined {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("You need to ");
+            if ui.link("join this channel").clicked() {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::JoinChannel(channel, vec![]));
+            }
+            ui.label(" to post in it.");
+        });
+        return;
+    }
+
+    let mut draft = std::mem::take(&mut app.channel_draft);
+    if chat_compose_area(app, ui, &mut draft, "channel_compose_area") {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::PostChannelMessage {
+                channel,
+                content: draft.clone(),
+                tags: chat_tags(),
+            });
+        draft.clear();
+    }
+    app.channel_draft = draft;
+}
+
+fn group_posting_area(app: &mut GossipUi, ui: &mut Ui, relay: RelayUrl, group_id: String) {
+    let joined = gossip_lib::groups::group(&relay, &group_id)
+        .ok()
+        .flatten()
+        .map(|g| g.joined)
+        .unwrap_or(false);
+    if !joined {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("You need to ");
+            if ui.link("join this group").clicked() {
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::JoinGroup(
+                    relay.clone(),
+                    group_id.clone(),
+                ));
+            }
+            ui.label(" to post in it.");
+        });
+        return;
+    }
+
+    let mut draft = std::mem::take(&mut app.group_draft);
+    if chat_compose_area(app, ui, &mut draft, "group_compose_area") {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::PostGroupMessage {
+                relay,
+                group_id,
+                content: draft.clone(),
+                tags: chat_tags(),
+            });
+        draft.clear();
+    }
+    app.group_draft = draft;
+}
+
+// A compose box for chatting in a channel or group.
+// Returns true when the draft should be sent.
+fn chat_compose_area(app: &mut GossipUi, ui: &mut Ui, draft: &mut String, id_salt: &str) -> bool {
+    let mut send_now: bool = false;
+
+    let theme = app.theme;
+    let mut layouter = |ui: &Ui, text: &str, wrap_width: f32| {
+        let mut layout_job = textarea_highlighter(theme, text.to_owned(), Vec::new());
+        layout_job.wrap.max_width = wrap_width;
+        ui.fonts(|f| f.layout_job(layout_job))
+    };
+
+    let draft_response = ui.add(
+        text_edit_multiline!(app, *draft)
+            .id_salt(egui::Id::new(id_salt))
+            .hint_text("Type your message here")
+            .desired_width(f32::INFINITY)
+            .lock_focus(true)
+            .interactive(true)
+            .layouter(&mut layouter),
+    );
+    if app.draft_needs_focus {
+        app.draft_needs_focus = false;
+        draft_response.request_focus();
+    }
+
+    if !draft.is_empty() {
+        let modifiers = if cfg!(target_os = "macos") {
+            Modifiers {
+                command: true,
+                ..Default::default()
+            }
+        } else {
+            Modifiers {
+                ctrl: true,
+                ..Default::default()
+            }
+        };
+        if ui.input_mut(|i| i.consume_key(modifiers, Key::Enter)) {
+            send_now = true;
+        }
+    }
+
+    ui.add_space(8.0);
+
+    ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
+        if widgets::Button::primary(&app.theme, "Send")
+            .show(ui)
+            .clicked()
+            && !draft.is_empty()
+        {
+            send_now = true;
+        }
+    });
+
+    send_now
+}
+
+fn chat_tags() -> Vec<Tag> {
+    let mut tags: Vec<Tag> = Vec::new();
+    if let Some(delegatee_tag) = GLOBALS.delegation.get_delegatee_tag() {
+        tags.push(delegatee_tag);
+    }
+    tags
+}
+
 fn dm_posting_area(
     app: &mut GossipUi,
     ctx: &Context,
@@ -168,6 +319,9 @@ fn dm_posting_area(
     let compose_area_id: egui::Id = egui::Id::new("compose_area");
     let mut send_now: bool = false;
 
+    // The draft for this channel, however we got here
+    app.open_dm_draft(dm_channel.clone());
+
     let (bg_color, text_color, text, tooltip_text) = if dm_channel.can_use_nip17() {
         let text = "STRONG ENCRYPTION";
         let tt_text = "SECURED with Giftwrap DM technology (NIPs 17, 44, 59)";
@@ -408,6 +562,7 @@ fn dm_posting_area(
         if app.dm_draft_data.include_subject {
             tags.push(ParsedTag::Subject(app.dm_draft_data.subject.clone()).into_tag());
         }
+        tags.extend(app.dm_draft_data.attachment_tags());
 
         let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Post {
             content: app.dm_draft_data.draft.clone(),
@@ -521,6 +676,24 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
                     ui.add_space(10.0);
                 }
 
+                if app.draft_data.include_schedule {
+                    ui.horizontal(|ui| {
+                        ui.label("Publish at: ");
+                        ui.add(
+                            text_edit_line!(app, app.draft_data.schedule_at)
+                                .hint_text("YYYY-MM-DD HH:MM")
+                                .desired_width(160.0),
+                        );
+                        if parse_schedule_time(&app.draft_data.schedule_at).is_none() {
+                            ui.label(
+                                RichText::new("Use the format YYYY-MM-DD HH:MM (local time)")
+                                    .color(app.theme.warning_marker_text_color()),
+                            );
+                        }
+                    });
+                    ui.add_space(10.0);
+                }
+
                 // if we are tagging, we will consume arrow presses and enter key
                 let enter_key;
                 (app.draft_data.tagging_search_selected, enter_key) =
@@ -624,6 +797,8 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
     ui.horizontal(|ui| {
         let send_label = if app.draft_data.repost.is_some() {
             "Repost note"
+        } else if app.draft_data.include_schedule {
+            "Schedule note"
         } else {
             "Send note"
         };
@@ -675,6 +850,25 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
                     )));
                 }
 
+                if app.draft_data.include_schedule {
+                    items.push(MoreMenuItem::Button(MoreMenuButton::new(
+                        "Send Now Instead",
+                        Box::new(|_, app| {
+                            app.draft_data.include_schedule = false;
+                            app.draft_data.schedule_at = "".to_owned();
+                        }),
+                    )));
+                } else {
+                    items.push(MoreMenuItem::Button(MoreMenuButton::new(
+                        "Schedule for Later",
+                        Box::new(|_, app| {
+                            app.draft_data.include_schedule = true;
+                            app.draft_data.schedule_at =
+                                format_schedule_time(Unixtime(Unixtime::now().0 + 3600));
+                        }),
+                    )));
+                }
+
                 items.push(MoreMenuItem::Button(
                     MoreMenuButton::new(
                         "Show raw preview",
@@ -786,41 +980,69 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
         if app.draft_data.include_subject {
             tags.push(ParsedTag::Subject(app.draft_data.subject.clone()).into_tag());
         }
-        match app.draft_data.replying_to {
-            Some(replying_to_id) => {
-                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Post {
-                    content: replaced,
-                    tags,
-                    in_reply_to: Some(replying_to_id),
-                    annotation: app.draft_data.is_annotate,
-                    dm_channel: None,
-                });
+        tags.extend(app.draft_data.attachment_tags());
+
+        if app.draft_data.include_schedule && app.draft_data.repost.is_none() {
+            match parse_schedule_time(&app.draft_data.schedule_at) {
+                Some(publish_at) if publish_at.0 > Unixtime::now().0 => {
+                    let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SchedulePost {
+                        content: replaced,
+                        tags,
+                        in_reply_to: app.draft_data.replying_to,
+                        annotation: app.draft_data.is_annotate,
+                        publish_at,
+                    });
+                    app.reset_draft();
+                }
+                Some(_) => {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write("The scheduled time must be in the future.".to_owned());
+                }
+                None => {
+                    GLOBALS.status_queue.write().write(
+                        "Use the format YYYY-MM-DD HH:MM for the scheduled time.".to_owned(),
+                    );
+                }
             }
-            None => {
-                if let Some(event_id) = app.draft_data.repost {
-                    let _ = GLOBALS
-                        .to_overlord
-                        .send(ToOverlordMessage::Repost(event_id));
-                } else {
+        } else {
+            match app.draft_data.replying_to {
+                Some(replying_to_id) => {
                     let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Post {
                         content: replaced,
                         tags,
-                        in_reply_to: None,
+                        in_reply_to: Some(replying_to_id),
                         annotation: app.draft_data.is_annotate,
                         dm_channel: None,
                     });
                 }
+                None => {
+                    if let Some(event_id) = app.draft_data.repost {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::Repost(event_id));
+                    } else {
+                        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Post {
+                            content: replaced,
+                            tags,
+                            in_reply_to: None,
+                            annotation: app.draft_data.is_annotate,
+                            dm_channel: None,
+                        });
+                    }
+                }
             }
-        }
 
-        let is_reply = app.draft_data.replying_to.is_some();
+            let is_reply = app.draft_data.replying_to.is_some();
 
-        app.reset_draft();
+            app.reset_draft();
 
-        // So they can see it rendered and see the "Undo Send" button
-        if !is_reply {
-            if let Some(pubkey) = GLOBALS.identity.public_key() {
-                app.set_page(ctx, Page::Feed(FeedKind::Person(pubkey)));
+            // So they can see it rendered and see the "Undo Send" button
+            if !is_reply {
+                if let Some(pubkey) = GLOBALS.identity.public_key() {
+                    app.set_page(ctx, Page::Feed(FeedKind::Person(pubkey)));
+                }
             }
         }
     }
@@ -1059,9 +1281,20 @@ fn do_replacements(draft: &str, replacements: &HashMap<String, ContentSegment>)
 }
 
 fn offer_attachment(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, dm: bool) {
-    // Skip if no blossom servers configured:
-    let blossom_servers = GLOBALS.db().read_setting_blossom_servers();
-    if blossom_servers.split_whitespace().next().is_none() {
+    // Skip if no upload servers configured:
+    let blossom = GLOBALS
+        .db()
+        .read_setting_blossom_servers()
+        .split_whitespace()
+        .next()
+        .is_some();
+    let nip96 = GLOBALS
+        .db()
+        .read_setting_nip96_servers()
+        .split_whitespace()
+        .next()
+        .is_some();
+    if !blossom && !nip96 {
         return;
     }
 
@@ -1070,58 +1303,91 @@ fn offer_attachment(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, dm: bool) {
 
     // Attachment button
     if let Some(pathbuf) = &app.uploading {
-        if let Some(result) = GLOBALS.blossom_uploads.get(pathbuf) {
-            match result.value() {
-                Ok(bd) => {
-                    if dm {
-                        app.dm_draft_data.draft.push(' ');
-                        app.dm_draft_data.draft.push_str(&bd.url);
-                        if bd.url.len() > 5 && !bd.url[bd.url.len() - 5..].contains('.') {
-                            if let Some(ext) = pathbuf.extension() {
-                                app.dm_draft_data.draft.push('.');
-                                app.dm_draft_data.draft.push_str(&ext.to_string_lossy());
-                            }
-                        }
-                    } else {
-                        app.draft_data.draft.push(' ');
-                        app.draft_data.draft.push_str(&bd.url);
-                        if bd.url.len() > 5 && !bd.url[bd.url.len() - 5..].contains('.') {
-                            if let Some(ext) = pathbuf.extension() {
-                                app.draft_data.draft.push('.');
-                                app.draft_data.draft.push_str(&ext.to_string_lossy());
-                            }
-                        }
+        // Where the file went, and its imeta tag if the server described it
+        let result: Option<Result<(String, Option<Tag>), String>> = if app.upload_nip96 {
+            GLOBALS
+                .nip96_uploads
+                .get(pathbuf)
+                .map(|result| match result.value() {
+                    Ok(upload) => Ok((upload.url.clone(), Some(upload.imeta_tag()))),
+                    Err(e) => Err(format!("{e}")),
+                })
+        } else {
+            GLOBALS
+                .blossom_uploads
+                .get(pathbuf)
+                .map(|result| match result.value() {
+                    Ok(bd) => Ok((bd.url.clone(), None)),
+                    Err(e) => Err(format!("{e}")),
+                })
+        };
+
+        match result {
+            Some(Ok((url, imeta))) => {
+                let draft_data = if dm {
+                    &mut app.dm_draft_data
+                } else {
+                    &mut app.draft_data
+                };
+                draft_data.draft.push(' ');
+                draft_data.draft.push_str(&url);
+                // Blossom serves a blob under any extension, so add one for clients
+                // that guess the media type from it
+                if imeta.is_none() && url.len() > 5 && !url[url.len() - 5..].contains('.') {
+                    if let Some(ext) = pathbuf.extension() {
+                        draft_data.draft.push('.');
+                        draft_data.draft.push_str(&ext.to_string_lossy());
                     }
-                    clear_uploading = true;
                 }
-                Err(e) => {
-                    if ui
-                        .add(Label::new(format!("{e}")).sense(Sense::click()))
-                        .clicked()
-                    {
-                        clear_uploading = true;
-                        clear_upload = true;
-                    }
+                draft_data.attachments.extend(imeta);
+                clear_uploading = true;
+            }
+            Some(Err(e)) => {
+                if ui.add(Label::new(e).sense(Sense::click())).clicked() {
+                    clear_uploading = true;
+                    clear_upload = true;
                 }
             }
-        } else {
-            ui.label("Uploading...");
+            None => {
+                ui.label("Uploading...");
+            }
         }
 
         if clear_upload {
-            let _ = GLOBALS.blossom_uploads.remove(pathbuf);
+            if app.upload_nip96 {
+                let _ = GLOBALS.nip96_uploads.remove(pathbuf);
+            } else {
+                let _ = GLOBALS.blossom_uploads.remove(pathbuf);
+            }
         }
         if clear_uploading {
             app.uploading = None;
         }
+    } else if blossom && nip96 {
+        ui.menu_button(RichText::new("📎▼").size(14.0), |ui| {
+            if ui.button("Upload to your blossom server").clicked() {
+                app.upload_nip96 = false;
+                app.file_dialog.pick_file();
+                ui.close_menu();
+            }
+            if ui.button("Upload to your NIP-96 server").clicked() {
+                app.upload_nip96 = true;
+                app.file_dialog.pick_file();
+                ui.close_menu();
+            }
+        });
     } else if ui.button(RichText::new("📎").size(14.0)).clicked() {
+        app.upload_nip96 = nip96;
         app.file_dialog.pick_file();
     }
     app.file_dialog.update(ctx);
     if let Some(pathbuf) = app.file_dialog.take_picked() {
         app.uploading = Some(pathbuf.clone());
-        let _ = GLOBALS
-            .to_overlord
-            .send(ToOverlordMessage::BlossomUpload(pathbuf));
+        let message = if app.upload_nip96 {
+            ToOverlordMessage::Nip96Upload(pathbuf)
+        } else {
+            ToOverlordMessage::BlossomUpload(pathbuf)
+        };
+        let _ = GLOBALS.to_overlord.send(message);
     }
 }
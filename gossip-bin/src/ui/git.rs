@@ -0,0 +1,297 @@
+use super::widgets::CopyButton;
+use super::you::scheduled::format_schedule_time;
+use super::{widgets, GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{git, FeedKind, GitItem, GitStatus, GLOBALS};
+use nostr_types::PublicKey;
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    match &app.page {
+        Page::GitRepository(author, d) => {
+            let (author, d) = (*author, d.clone());
+            update_repository(app, ctx, ui, author, &d);
+        }
+        _ => update_list(app, ctx, ui),
+    }
+}
+
+fn update_list(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    widgets::page_header(ui, "Git Repositories", |_ui| ());
+
+    ui.add_space(10.0);
+    ui.label("Git repositories (NIP-34) take patches and issues over nostr. Following a repository subscribes to its patches, issues and replies on the relays it names.");
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.label("Follow a repository:");
+        ui.add(
+            text_edit_line!(app, app.follow_repository)
+                .hint_text("naddr1..., or 30617:<pubkey>:<identifier>")
+                .desired_width(400.0),
+        );
+        if ui.button("Follow").clicked() {
+            match git::parse_repo_identifier(&app.follow_repository) {
+                Some((author, d, relays)) => {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::FollowRepository(author, d, relays));
+                    app.follow_repository.clear();
+                }
+                None => GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Repository address not recognized.".to_owned()),
+            }
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let list = match git::followed_repositories() {
+        Ok(list) => list,
+        Err(e) => {
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+
+    if list.is_empty() {
+        ui.label("You aren't following any repositories yet.");
+        return;
+    }
+
+    app.vert_scroll_area()
+        .id_salt("git_repositories")
+        .show(ui, |ui| {
+            for repo in list.iter() {
+                ui.horizontal(|ui| {
+                    let name = git::repo_name(repo.author, &repo.d);
+                    if ui.link(RichText::new(name).strong()).clicked() {
+                        app.set_page(ctx, Page::GitRepository(repo.author, repo.d.clone()));
+                    }
+                    ui.label("by");
+                    let owner = gossip_lib::names::best_name_from_pubkey_lookup(&repo.author);
+                    if ui.link(owner).clicked() {
+                        app.set_page(ctx, Page::Person(repo.author));
+                    }
+                });
+
+                if let Ok(Some(announcement)) = git::announcement(repo.author, &repo.d) {
+                    if !announcement.description.is_empty() {
+                        ui.label(&announcement.description);
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Open").clicked() {
+                        app.set_page(ctx, Page::GitRepository(repo.author, repo.d.clone()));
+                    }
+                    if ui.button("Unfollow").clicked() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::UnfollowRepository(
+                                repo.author,
+                                repo.d.clone(),
+                            ));
+                    }
+                });
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+            }
+        });
+}
+
+fn update_repository(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, author: PublicKey, d: &str) {
+    widgets::page_header(ui, &git::repo_name(author, d), |ui| {
+        if git::is_followed(author, d) {
+            if ui.button("Unfollow").clicked() {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::UnfollowRepository(author, d.to_owned()));
+            }
+        } else if ui.button("Follow").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::FollowRepository(
+                    author,
+                    d.to_owned(),
+                    vec![],
+                ));
+        }
+    });
+
+    let announcement = git::announcement(author, d).ok().flatten();
+    let me = GLOBALS.identity.public_key();
+    let maintainer = match (&announcement, me) {
+        (Some(announcement), Some(me)) => announcement.is_maintainer(me),
+        (None, Some(me)) => me == author,
+        _ => false,
+    };
+
+    let items = match git::items(author, d) {
+        Ok(items) => items,
+        Err(e) => {
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+
+    app.vert_scroll_area()
+        .id_salt("git_repository")
+        .show(ui, |ui| {
+            ui.add_space(10.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label("by");
+                let name = gossip_lib::names::best_name_from_pubkey_lookup(&author);
+                if ui.link(RichText::new(name).strong()).clicked() {
+                    app.set_page(ctx, Page::Person(author));
+                }
+            });
+
+            match &announcement {
+                Some(announcement) => {
+                    if !announcement.description.is_empty() {
+                        ui.add_space(4.0);
+                        ui.label(&announcement.description);
+                    }
+                    for web in &announcement.web {
+                        widgets::break_anywhere_hyperlink_to(ui, app, web, web);
+                    }
+                    for clone in &announcement.clone {
+                        ui.horizontal(|ui| {
+                            ui.label("clone:");
+                            ui.label(RichText::new(clone).code());
+                            if ui.add(CopyButton::new()).clicked() {
+                                ui.output_mut(|o| {
+                                    o.commands
+                                        .push(egui::OutputCommand::CopyText(clone.clone()))
+                                });
+                            }
+                        });
+                    }
+                    if !announcement.maintainers.is_empty() {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label("maintainers:");
+                            for pubkey in &announcement.maintainers {
+                                let name = gossip_lib::names::best_name_from_pubkey_lookup(pubkey);
+                                if ui.link(name).clicked() {
+                                    app.set_page(ctx, Page::Person(*pubkey));
+                                }
+                            }
+                        });
+                    }
+                }
+                None => {
+                    ui.add_space(4.0);
+                    ui.label("Looking for the repository announcement on the relays...");
+                }
+            }
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+
+            let (issues, patches): (Vec<&GitItem>, Vec<&GitItem>) =
+                items.iter().partition(|item| item.is_issue());
+
+            ui.heading("Patches");
+            ui.add_space(10.0);
+            render_items(app, ctx, ui, &patches, maintainer, me, "No patches yet.");
+
+            ui.add_space(10.0);
+            ui.heading("Issues");
+            ui.add_space(10.0);
+            render_items(app, ctx, ui, &issues, maintainer, me, "No issues yet.");
+        });
+}
+
+fn render_items(
+    app: &mut GossipUi,
+    ctx: &Context,
+    ui: &mut Ui,
+    items: &[&GitItem],
+    maintainer: bool,
+    me: Option<PublicKey>,
+    empty: &str,
+) {
+    if items.is_empty() {
+        ui.label(empty);
+        ui.add_space(10.0);
+        return;
+    }
+
+    for item in items {
+        let event = &item.event;
+        let issue = item.is_issue();
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(RichText::new(item.status.label(issue)).italics());
+            let subject = git::subject(event);
+            if ui.link(RichText::new(subject).strong()).clicked() {
+                app.set_page(
+                    ctx,
+                    Page::Feed(FeedKind::Thread {
+                        id: event.id,
+                        referenced_by: event.id,
+                        author: Some(event.pubkey),
+                    }),
+                );
+            }
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            let name = gossip_lib::names::best_name_from_pubkey_lookup(&event.pubkey);
+            if ui.link(name).clicked() {
+                app.set_page(ctx, Page::Person(event.pubkey));
+            }
+            ui.label(RichText::new(format_schedule_time(event.created_at)).weak());
+            if item.status_at > event.created_at {
+                ui.label(
+                    RichText::new(format!(
+                        "({} {})",
+                        item.status.label(issue),
+                        format_schedule_time(item.status_at)
+                    ))
+                    .weak(),
+                );
+            }
+        });
+
+        // Maintainers may set any status, authors may open, close or draft their own
+        let author = me == Some(event.pubkey);
+        if maintainer || author {
+            ui.horizontal(|ui| {
+                for status in [
+                    GitStatus::Open,
+                    GitStatus::Applied,
+                    GitStatus::Closed,
+                    GitStatus::Draft,
+                ] {
+                    if status == item.status || (status == GitStatus::Applied && !maintainer) {
+                        continue;
+                    }
+                    let label = match status {
+                        GitStatus::Open => "Reopen",
+                        GitStatus::Applied if issue => "Resolve",
+                        GitStatus::Applied => "Mark applied",
+                        GitStatus::Closed => "Close",
+                        GitStatus::Draft => "Mark draft",
+                    };
+                    if ui.button(label).clicked() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::SetGitStatus(event.id, status));
+                    }
+                }
+            });
+        }
+
+        ui.add_space(10.0);
+    }
+}
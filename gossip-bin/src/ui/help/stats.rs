@@ -91,6 +91,18 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         ));
         ui.add_space(6.0);
 
+        ui.label(format!(
+            "Event Words: {} bytes",
+            GLOBALS.db().get_event_words_size().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Spam DMs: {} bytes",
+            GLOBALS.db().get_spam_dms_size().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
         ui.label(format!(
             "Hashtags: {} bytes",
             GLOBALS.db().get_hashtags_size().unwrap_or(0)
@@ -33,10 +33,16 @@ macro_rules! write_setting {
     };
 }
 
+mod article;
 mod assets;
+mod calendar;
+mod channels;
+mod communities;
 mod dm_chat_list;
+mod dvms;
 mod emojis;
 mod feed;
+mod git;
 mod handler;
 mod help;
 mod notifications;
@@ -70,16 +76,18 @@ use egui_winit::egui::Response;
 use egui_winit::egui::ViewportBuilder;
 use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::{
-    DmChannel, DmChannelData, Error, FeedKind, MediaLoadingResult, Person, PersonList, Private,
-    RunState, ZapState, GLOBALS,
+    draft, zaps, ArticleFields, BadgeFields, CountKind, DmChannel, DmChannelData, Draft,
+    DraftTarget, Error, FeedKind, MediaLoadingResult, Person, PersonList, Private, RunState,
+    ZapSplit, ZapState, GLOBALS,
 };
 use handler::Handlers;
 use nostr_types::ContentSegment;
 use nostr_types::RelayUrl;
 use nostr_types::{
-    EventKind, FileMetadata, Id, Metadata, MilliSatoshi, Profile, PublicKey, UncheckedUrl, Url,
+    EventKind, FileMetadata, Id, Metadata, MilliSatoshi, NAddr, NostrBech32, NostrUrl, ParsedTag,
+    Profile, PublicKey, Tag, UncheckedUrl, Unixtime, Url,
 };
-use widgets::ModalEntry;
+use widgets::{CopyButton, ModalEntry};
 
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
@@ -156,8 +164,15 @@ pub fn run() -> Result<(), Error> {
 
 #[derive(Debug, Clone, PartialEq)]
 enum Page {
+    Article(NAddr),
+    Calendar,
+    ChannelList,
+    CommunityList,
     DmChatList,
+    Dvms,
     Feed(FeedKind),
+    GitRepositories,
+    GitRepository(PublicKey, String),
     HandlerKinds,
     Handlers(EventKind),
     Notifications,
@@ -170,6 +185,13 @@ enum Page {
     YourMetadata,
     YourDelegation,
     YourNostrConnect,
+    YourScheduledPosts,
+    YourDrafts,
+    YourArticles,
+    YourBadges,
+    YourWallet,
+    YourMedia,
+    YourAccounts,
     RelaysActivityMonitor,
     RelaysCoverage,
     RelaysMine,
@@ -199,8 +221,23 @@ impl Page {
 impl Page {
     pub fn to_readable(&self) -> (&'static str /* Category */, String /* Name */) {
         match self {
+            Page::Article(naddr) => {
+                let title = match gossip_lib::article::article(naddr) {
+                    Ok(Some(article)) if !article.title.is_empty() => article.title,
+                    _ => naddr.d.clone(),
+                };
+                ("Article", title)
+            }
+            Page::Calendar => (SubMenu::Feeds.as_str(), "Calendar".into()),
+            Page::ChannelList => (SubMenu::Feeds.as_str(), "Public channels".into()),
+            Page::CommunityList => (SubMenu::Feeds.as_str(), "Communities".into()),
             Page::DmChatList => (SubMenu::Feeds.as_str(), "Private msgs".into()),
+            Page::Dvms => (SubMenu::Feeds.as_str(), "Data vending machines".into()),
             Page::Feed(feedkind) => ("Feed", feedkind.to_string()),
+            Page::GitRepositories => (SubMenu::Feeds.as_str(), "Git repositories".into()),
+            Page::GitRepository(author, d) => {
+                ("Repository", gossip_lib::git::repo_name(*author, d))
+            }
             Page::HandlerKinds => ("Event Handlers", "Event Handlers".into()),
             Page::Handlers(kind) => ("Event Handler", format!("{:?}", kind)),
             Page::Notifications => ("Notifications", "Notifications".into()),
@@ -229,6 +266,13 @@ impl Page {
             Page::YourMetadata => (SubMenu::Account.as_str(), "Profile".into()),
             Page::YourDelegation => (SubMenu::Account.as_str(), "Delegation".into()),
             Page::YourNostrConnect => (SubMenu::Account.as_str(), "Nostr Connect".into()),
+            Page::YourScheduledPosts => (SubMenu::Account.as_str(), "Scheduled Posts".into()),
+            Page::YourDrafts => (SubMenu::Account.as_str(), "Drafts".into()),
+            Page::YourArticles => (SubMenu::Account.as_str(), "Articles".into()),
+            Page::YourBadges => (SubMenu::Account.as_str(), "Badges".into()),
+            Page::YourWallet => (SubMenu::Account.as_str(), "Wallet".into()),
+            Page::YourMedia => (SubMenu::Account.as_str(), "Media".into()),
+            Page::YourAccounts => (SubMenu::Account.as_str(), "Accounts".into()),
             Page::RelaysActivityMonitor => (SubMenu::Relays.as_str(), "Active Relays".into()),
             Page::RelaysCoverage => (SubMenu::Relays.as_str(), "Coverage Report".into()),
             Page::RelaysMine => (SubMenu::Relays.as_str(), "My Relays".into()),
@@ -265,15 +309,28 @@ impl Page {
         }
 
         match self {
+            Page::Calendar => cat_name(self),
+            Page::ChannelList => cat_name(self),
+            Page::CommunityList => cat_name(self),
             Page::DmChatList => cat_name(self),
+            Page::Dvms => cat_name(self),
             Page::Feed(_) => name_cat(self),
+            Page::GitRepositories => cat_name(self),
             Page::PeopleLists | Page::PeopleList(_) => cat_name(self),
             Page::Person(_) => name_cat(self),
             Page::PersonFollows(_) => name_cat(self),
             Page::PersonFollowers(_) => name_cat(self),
-            Page::YourKeys | Page::YourMetadata | Page::YourDelegation | Page::YourNostrConnect => {
-                cat_name(self)
-            }
+            Page::YourKeys
+            | Page::YourMetadata
+            | Page::YourDelegation
+            | Page::YourNostrConnect
+            | Page::YourScheduledPosts
+            | Page::YourDrafts
+            | Page::YourArticles
+            | Page::YourBadges
+            | Page::YourWallet
+            | Page::YourMedia
+            | Page::YourAccounts => cat_name(self),
             Page::Wizard(_) => name_cat(self),
             _ => name(self),
         }
@@ -362,6 +419,10 @@ pub struct DraftData {
     pub repost: Option<Id>,
     pub replying_to: Option<Id>,
 
+    // Publish later, at this local time (YYYY-MM-DD HH:MM)
+    pub include_schedule: bool,
+    pub schedule_at: String,
+
     // Are you sure
     pub are_you_sure_cancel: bool, // true if we are asking
 
@@ -373,6 +434,9 @@ pub struct DraftData {
 
     // If this is an annotation
     pub is_annotate: bool,
+
+    // imeta tags for files we uploaded into the draft, from the server
+    pub attachments: Vec<Tag>,
 }
 
 impl Default for DraftData {
@@ -392,6 +456,8 @@ impl Default for DraftData {
             // The following are ignored for DMs
             repost: None,
             replying_to: None,
+            include_schedule: false,
+            schedule_at: "".to_owned(),
 
             are_you_sure_cancel: false,
 
@@ -401,6 +467,8 @@ impl Default for DraftData {
             tagging_search_results: Vec::new(),
 
             is_annotate: false,
+
+            attachments: Vec::new(),
         }
     }
 }
@@ -419,12 +487,102 @@ impl DraftData {
         self.content_warning = "".to_owned();
         self.repost = None;
         self.replying_to = None;
+        self.include_schedule = false;
+        self.schedule_at = "".to_owned();
         self.are_you_sure_cancel = false;
         self.tagging_search_substring = None;
         self.tagging_search_selected = None;
         self.tagging_search_searched = None;
         self.tagging_search_results.clear();
         self.is_annotate = false;
+        self.attachments.clear();
+    }
+
+    /// The imeta tags of the uploaded files that are still linked in the draft
+    pub fn attachment_tags(&self) -> Vec<Tag> {
+        self.attachments
+            .iter()
+            .filter(|tag| {
+                (1..)
+                    .map(|i| tag.get_index(i))
+                    .take_while(|f| !f.is_empty())
+                    .filter_map(|f| f.strip_prefix("url "))
+                    .any(|url| self.draft.contains(url))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The draft to save for this target
+    pub fn to_draft(&self, target: DraftTarget) -> Draft {
+        let mut tags: Vec<Tag> = Vec::new();
+        if self.include_subject {
+            tags.push(ParsedTag::Subject(self.subject.clone()).into_tag());
+        }
+        if self.include_content_warning {
+            tags.push(ParsedTag::ContentWarning(Some(self.content_warning.clone())).into_tag());
+        }
+        tags.extend(self.attachment_tags());
+
+        let mut mentions: Vec<(String, String)> = Vec::new();
+        for (pat, content) in self.replacements.iter() {
+            if let ContentSegment::NostrUrl(nostr_url) = content {
+                mentions.push((pat.clone(), format!("{}", nostr_url.0)));
+            }
+        }
+        mentions.sort();
+
+        Draft {
+            key: target.key(),
+            target,
+            content: self.draft.clone(),
+            tags,
+            mentions,
+            updated_at: Unixtime::now(),
+            encrypted: false,
+        }
+    }
+
+    /// Load a saved draft. This does not change what the draft is for.
+    pub fn load_draft(&mut self, draft: &Draft) {
+        self.draft = draft.content.clone();
+        for tag in draft.tags.iter() {
+            match tag.parse() {
+                Ok(ParsedTag::Subject(subject)) => {
+                    self.include_subject = true;
+                    self.subject = subject;
+                }
+                Ok(ParsedTag::ContentWarning(warning)) => {
+                    self.include_content_warning = true;
+                    self.content_warning = warning.unwrap_or_default();
+                }
+                _ if tag.tagname() == "imeta" => self.attachments.push(tag.clone()),
+                _ => {}
+            }
+        }
+        for (pat, bech32) in draft.mentions.iter() {
+            if let Some(nostr_bech32) = NostrBech32::try_from_string(bech32) {
+                self.replacements.insert(
+                    pat.clone(),
+                    ContentSegment::NostrUrl(NostrUrl(nostr_bech32)),
+                );
+            }
+        }
+        self.replacements_changed = true;
+    }
+}
+
+/// If two drafts are for the same thing and have the same contents
+fn same_draft(a: Option<&Draft>, b: &Draft) -> bool {
+    match a {
+        Some(a) => {
+            a.target == b.target
+                && a.content == b.content
+                && a.tags == b.tags
+                && a.mentions == b.mentions
+        }
+        // Nothing was loaded or saved, so an empty draft is no change
+        None => b.content.trim().is_empty(),
     }
 }
 
@@ -519,6 +677,20 @@ struct GossipUi {
     previous_draft_data: DraftData,
     dm_draft_data: DraftData,
     dm_draft_data_target: Option<DmChannel>,
+    saved_draft: Option<Draft>,
+    saved_dm_draft: Option<Draft>,
+    drafts_next_autosave: Instant,
+    channel_draft: String,
+    group_draft: String,
+    article_fields: ArticleFields,
+    article_hashtags: String,
+    article_preview: bool,
+
+    // Badges
+    badge_fields: BadgeFields,
+    badge_award: Option<String>,
+    badge_award_list: PersonList,
+    badge_award_to: HashSet<PublicKey>,
 
     // User entry: metadata
     editing_metadata: bool,
@@ -536,6 +708,7 @@ struct GossipUi {
     new_metadata_fieldname: String,
     import_priv: String,
     import_pub: String,
+    wallet_uri: String,
     search: String,
     entering_a_search_page: bool,
     search_started: bool,
@@ -551,6 +724,19 @@ struct GossipUi {
     nostr_connect_name: String,
     nostr_connect_relay1: String,
     nostr_connect_relay2: String,
+    editing_scheduled_post: Option<(u64, String, String)>, // key, content, publish time
+    join_channel: String,
+    join_community: String,
+    calendar_show_past: bool,
+    calendar_export: Option<NAddr>,
+    calendar_file_dialog: FileDialog,
+    dvm_job_kind: u32,
+    dvm_job_input: String,
+    dvm_job_input_type: String,
+    dvm_job_params: String,
+    dvm_job_target: Option<PublicKey>,
+    join_group: String,
+    follow_repository: String,
 
     // search result
     search_note_height: HashMap<Id, f32>,
@@ -578,6 +764,7 @@ struct GossipUi {
     note_being_zapped: Option<Id>,
     note_showing_zaps: Option<Id>,
     zap_amount_input: u64,
+    zap_anonymous: bool,
 
     wizard_state: WizardState,
 
@@ -590,6 +777,7 @@ struct GossipUi {
 
     file_dialog: FileDialog,
     uploading: Option<PathBuf>,
+    upload_nip96: bool,
 }
 
 impl Drop for GossipUi {
@@ -800,6 +988,18 @@ impl GossipUi {
             previous_draft_data: DraftData::default(),
             dm_draft_data: DraftData::default(),
             dm_draft_data_target: None,
+            saved_draft: None,
+            saved_dm_draft: None,
+            drafts_next_autosave: Instant::now(),
+            channel_draft: "".to_owned(),
+            group_draft: "".to_owned(),
+            article_fields: ArticleFields::default(),
+            article_hashtags: "".to_owned(),
+            article_preview: false,
+            badge_fields: BadgeFields::default(),
+            badge_award: None,
+            badge_award_list: PersonList::Followed,
+            badge_award_to: HashSet::new(),
             editing_metadata: false,
             metadata: Metadata::new(),
             delegatee_tag_str: "".to_owned(),
@@ -810,6 +1010,7 @@ impl GossipUi {
             delete_confirm: false,
             new_metadata_fieldname: String::new(),
             import_priv: "".to_owned(),
+            wallet_uri: "".to_owned(),
             import_pub: "".to_owned(),
             search: "".to_owned(),
             entering_a_search_page: false,
@@ -826,6 +1027,19 @@ impl GossipUi {
             nostr_connect_name: "".to_owned(),
             nostr_connect_relay1: "".to_owned(),
             nostr_connect_relay2: "".to_owned(),
+            editing_scheduled_post: None,
+            join_channel: "".to_owned(),
+            join_community: "".to_owned(),
+            calendar_show_past: false,
+            calendar_export: None,
+            calendar_file_dialog: FileDialog::new(),
+            dvm_job_kind: 5001,
+            dvm_job_input: "".to_owned(),
+            dvm_job_input_type: "text".to_owned(),
+            dvm_job_params: "".to_owned(),
+            dvm_job_target: None,
+            join_group: "".to_owned(),
+            follow_repository: "".to_owned(),
             search_note_height: HashMap::new(),
             search_person_height: HashMap::new(),
             collapsed: vec![],
@@ -838,6 +1052,7 @@ impl GossipUi {
             note_being_zapped: None,
             note_showing_zaps: None,
             zap_amount_input: 10,
+            zap_anonymous: false,
             wizard_state,
             theme_test: Default::default(),
             dm_channel_cache: vec![],
@@ -845,6 +1060,7 @@ impl GossipUi {
             dm_channel_error: None,
             file_dialog: FileDialog::new(),
             uploading: None,
+            upload_nip96: false,
         }
     }
 
@@ -915,6 +1131,8 @@ impl GossipUi {
             self.password3 = "".to_owned();
             self.import_priv.zeroize();
             self.import_priv = "".to_owned();
+            self.wallet_uri.zeroize();
+            self.wallet_uri = "".to_owned();
         }
     }
 
@@ -930,6 +1148,15 @@ impl GossipUi {
     fn set_page_inner(&mut self, ctx: &Context, page: Page) {
         // Setting the page often requires some associated actions:
         match &page {
+            Page::Article(naddr) => {
+                self.close_all_menus_except_feeds(ctx);
+                // Fetch the article if we don't have it yet
+                if let Ok(None) = gossip_lib::article::article(naddr) {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::FetchNAddr(naddr.to_owned()));
+                }
+            }
             Page::Feed(feed_kind) => {
                 let is_list = matches!(feed_kind, FeedKind::List(_, _));
                 GLOBALS.feed.switch_feed(feed_kind.clone());
@@ -940,6 +1167,22 @@ impl GossipUi {
                     self.close_all_menus_except_feeds(ctx);
                 }
             }
+            Page::Calendar => {
+                self.close_all_menus_except_feeds(ctx);
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::SubscribeCalendar);
+            }
+            Page::Dvms => {
+                self.close_all_menus_except_feeds(ctx);
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SubscribeDvms);
+            }
+            Page::GitRepository(author, d) => {
+                self.close_all_menus_except_feeds(ctx);
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::SubscribeRepository(*author, d.clone()));
+            }
             Page::PeopleLists => {
                 people::enter_page(self);
                 self.close_all_menus_except_feeds(ctx);
@@ -951,6 +1194,16 @@ impl GossipUi {
                 let _ = GLOBALS
                     .to_overlord
                     .send(ToOverlordMessage::UpdateMetadata(*pubkey));
+                // Fetch the badges they show, and were awarded
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::FetchBadges(*pubkey));
+                // Ask counting relays how many followers they have
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::RequestCounts(vec![
+                        CountKind::Followers(*pubkey),
+                    ]));
             }
             Page::PersonFollows(pubkey) => {
                 self.close_all_menus_except_feeds(ctx);
@@ -978,9 +1231,33 @@ impl GossipUi {
                         .send(ToOverlordMessage::TrackFollowers(*pubkey));
                 }
             }
-            Page::YourKeys | Page::YourMetadata | Page::YourDelegation | Page::YourNostrConnect => {
+            Page::YourKeys
+            | Page::YourMetadata
+            | Page::YourDelegation
+            | Page::YourNostrConnect
+            | Page::YourScheduledPosts
+            | Page::YourDrafts
+            | Page::YourArticles
+            | Page::YourAccounts => {
                 self.open_menu(ctx, SubMenu::Account);
             }
+            Page::YourBadges => {
+                self.open_menu(ctx, SubMenu::Account);
+                // Our badge definitions, and who we awarded them to
+                if let Some(pubkey) = GLOBALS.identity.public_key() {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::FetchBadges(pubkey));
+                }
+            }
+            Page::YourWallet => {
+                self.open_menu(ctx, SubMenu::Account);
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::RefreshWallet);
+            }
+            Page::YourMedia => {
+                self.open_menu(ctx, SubMenu::Account);
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::BlossomList);
+            }
             Page::RelaysActivityMonitor | Page::RelaysCoverage | Page::RelaysMine => {
                 self.relays.enter_page(None);
                 self.open_menu(ctx, SubMenu::Relays);
@@ -1090,6 +1367,11 @@ impl GossipUi {
                 self.add_global_feed(ui, ctx);
                 self.add_personal_notes(ui, ctx);
                 self.add_private_chats(ui, ctx);
+                self.add_public_channels(ui, ctx);
+                self.add_communities(ui, ctx);
+                self.add_calendar(ui, ctx);
+                self.add_dvms(ui, ctx);
+                self.add_git_repositories(ui, ctx);
                 self.add_search_submenu(ui, ctx);
 
                 ui.add_space(10.0);
@@ -1108,6 +1390,8 @@ impl GossipUi {
                     ui.add_space(6.0);
                     self.add_unlock_or_post(ui);
                     ui.add_space(6.0);
+                    self.add_account_switcher(ui);
+                    ui.add_space(6.0);
                     self.add_debug_area(ui);
                 });
             });
@@ -1256,6 +1540,56 @@ impl GossipUi {
         }
     }
 
+    fn add_public_channels(&mut self, ui: &mut Ui, ctx: &Context) {
+        if read_setting!(enable_public_channels)
+            && self
+                .add_selected_label(ui, self.page == Page::ChannelList, "Public channels")
+                .clicked()
+        {
+            self.set_page(ctx, Page::ChannelList);
+        }
+    }
+
+    fn add_communities(&mut self, ui: &mut Ui, ctx: &Context) {
+        if read_setting!(enable_communities)
+            && self
+                .add_selected_label(ui, self.page == Page::CommunityList, "Communities")
+                .clicked()
+        {
+            self.set_page(ctx, Page::CommunityList);
+        }
+    }
+
+    fn add_calendar(&mut self, ui: &mut Ui, ctx: &Context) {
+        if read_setting!(enable_calendar)
+            && self
+                .add_selected_label(ui, self.page == Page::Calendar, "Calendar")
+                .clicked()
+        {
+            self.set_page(ctx, Page::Calendar);
+        }
+    }
+
+    fn add_dvms(&mut self, ui: &mut Ui, ctx: &Context) {
+        if read_setting!(enable_dvms)
+            && self
+                .add_selected_label(ui, self.page == Page::Dvms, "Data vending machines")
+                .clicked()
+        {
+            self.set_page(ctx, Page::Dvms);
+        }
+    }
+
+    fn add_git_repositories(&mut self, ui: &mut Ui, ctx: &Context) {
+        if read_setting!(enable_git_repositories)
+            && self
+                .add_selected_label(ui, self.page == Page::GitRepositories, "Git repositories")
+                .clicked()
+        {
+            self.set_page(ctx, Page::GitRepositories);
+        }
+    }
+
     fn add_private_chats(&mut self, ui: &mut Ui, ctx: &Context) {
         if GLOBALS.identity.is_unlocked() {
             let response =
@@ -1330,6 +1664,15 @@ impl GossipUi {
             self.add_menu_item_page(ui, Page::YourKeys, None, true);
             self.add_menu_item_page(ui, Page::YourDelegation, None, true);
             self.add_menu_item_page(ui, Page::YourNostrConnect, None, true);
+            self.add_menu_item_page(ui, Page::YourScheduledPosts, None, true);
+            self.add_menu_item_page(ui, Page::YourDrafts, None, true);
+            self.add_menu_item_page(ui, Page::YourArticles, None, true);
+            self.add_menu_item_page(ui, Page::YourBadges, None, true);
+            if read_setting!(enable_wallet) {
+                self.add_menu_item_page(ui, Page::YourWallet, None, true);
+            }
+            self.add_menu_item_page(ui, Page::YourMedia, None, true);
+            self.add_menu_item_page(ui, Page::YourAccounts, None, true);
         });
         self.after_openable_menu(ui, &cstate);
     }
@@ -1480,6 +1823,40 @@ impl GossipUi {
         });
     }
 
+    fn add_account_switcher(&mut self, ui: &mut Ui) {
+        let accounts = gossip_lib::accounts::accounts().unwrap_or_default();
+        if accounts.len() < 2 {
+            return;
+        }
+
+        let active = GLOBALS.identity.public_key();
+        let active_name = match active {
+            Some(pubkey) => gossip_lib::names::best_name_from_pubkey_lookup(&pubkey),
+            None => "New account".to_owned(),
+        };
+
+        let width = ui.available_width() - 8.0;
+        egui::ComboBox::from_id_salt("account_switcher")
+            .width(width)
+            .selected_text(active_name)
+            .show_ui(ui, |ui| {
+                for account in accounts.iter() {
+                    let name = gossip_lib::names::best_name_from_pubkey_lookup(&account.pubkey);
+                    if ui
+                        .selectable_label(active == Some(account.pubkey), name)
+                        .clicked()
+                        && active != Some(account.pubkey)
+                    {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::SwitchAccount(account.pubkey));
+                    }
+                }
+            })
+            .response
+            .on_hover_text("Switch account");
+    }
+
     fn add_unlock_or_post(&mut self, ui: &mut Ui) {
         if !self.show_post_area_fn() && self.page.show_post_icon() {
             let text = if GLOBALS.identity.is_unlocked() {
@@ -1495,6 +1872,9 @@ impl GossipUi {
                 .show(ui);
             if response.clicked() {
                 self.show_post_area = true;
+                if self.draft_data.repost.is_none() && self.draft_data.replying_to.is_none() {
+                    self.open_draft(DraftTarget::Post);
+                }
                 if GLOBALS.identity.is_unlocked() {
                     self.draft_needs_focus = true;
                 } else {
@@ -2077,6 +2457,33 @@ impl GossipUi {
                 ui.vertical(|ui| {
                     let mut amt = 0;
 
+                    // Show how the note splits its zaps, if it does
+                    if let Ok(Some(event)) = GLOBALS.db().read_event(id) {
+                        if event.pubkey == pubkey {
+                            let splits: Vec<ZapSplit> = zaps::zap_splits(&event)
+                                .into_iter()
+                                .filter(|s| {
+                                    s.pubkey == pubkey || zaps::lnurl_of(s.pubkey).is_some()
+                                })
+                                .collect();
+                            let shares = zaps::zap_shares(&splits);
+                            if !shares.is_empty() {
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.label("This zap is split between");
+                                    for (recipient, share) in shares.iter() {
+                                        ui.label(format!(
+                                            "{} ({:.0}%)",
+                                            gossip_lib::names::best_name_from_pubkey_lookup(
+                                                recipient
+                                            ),
+                                            share * 100.0
+                                        ));
+                                    }
+                                });
+                            }
+                        }
+                    }
+
                     ui.horizontal(|ui| {
                         ui.label("Zap Amount:");
 
@@ -2099,6 +2506,8 @@ impl GossipUi {
                         if ui.button("Zap!").clicked() {
                             amt = self.zap_amount_input;
                         }
+                        ui.checkbox(&mut self.zap_anonymous, "Anonymous")
+                            .on_hover_text("Zap without saying who you are");
                     });
 
                     if amt > 0 {
@@ -2107,6 +2516,7 @@ impl GossipUi {
                             pubkey,
                             MilliSatoshi(amt * 1_000),
                             "".to_owned(),
+                            self.zap_anonymous,
                         ));
                     }
                 });
@@ -2118,6 +2528,34 @@ impl GossipUi {
                 // we have to copy it and get out of the borrow first
                 qr_string = Some(invoice.to_owned());
             }
+            ZapState::PayingWithWallet(_id) => {
+                ui.label("Paying with your wallet...");
+            }
+            ZapState::ReadyToPaySplit(_id, ref invoices) => {
+                let invoices = invoices.clone();
+                ui.vertical(|ui| {
+                    ui.label("Pay each of these invoices to complete the zap:");
+                    for (recipient, invoice) in invoices.iter() {
+                        ui.add_space(10.0);
+                        ui.horizontal(|ui| {
+                            ui.label(gossip_lib::names::best_name_from_pubkey_lookup(recipient));
+                            if ui
+                                .add(CopyButton::new())
+                                .on_hover_text("Copy Invoice")
+                                .clicked()
+                            {
+                                ui.output_mut(|o| {
+                                    o.commands.push(OutputCommand::CopyText(invoice.to_owned()));
+                                });
+                            }
+                        });
+                        self.render_qr(ui, &format!("zap {}", invoice), &invoice.to_uppercase());
+                    }
+                    if ui.button("Close").clicked() {
+                        *GLOBALS.current_zap.write() = ZapState::None;
+                    }
+                });
+            }
         };
 
         if let Some(qr) = qr_string {
@@ -2131,22 +2569,142 @@ impl GossipUi {
 
     fn reset_draft(&mut self) {
         if let Page::Feed(FeedKind::DmChat(_)) = &self.page {
+            if let Some(target) = self.dm_draft_target() {
+                let _ = draft::delete_draft(&target);
+            }
             self.dm_draft_data.clear();
             self.dm_draft_data_target = None;
+            self.saved_dm_draft = None;
         } else {
+            if let Some(target) = self.draft_target() {
+                let _ = draft::delete_draft(&target);
+            }
             self.previous_draft_data = self.draft_data.clone();
             self.draft_data.clear();
             self.show_post_area = false;
             self.draft_needs_focus = false;
+            self.saved_draft = Some(self.draft_data.to_draft(DraftTarget::Post));
+        }
+    }
+
+    /// What the post draft is for. Reposts have no draft.
+    fn draft_target(&self) -> Option<DraftTarget> {
+        if self.draft_data.repost.is_some() {
+            return None;
+        }
+        match self.draft_data.replying_to {
+            Some(id) if self.draft_data.is_annotate => Some(DraftTarget::Annotation(id)),
+            Some(id) => Some(DraftTarget::Reply(id)),
+            None => Some(DraftTarget::Post),
+        }
+    }
+
+    /// What the DM draft is for
+    fn dm_draft_target(&self) -> Option<DraftTarget> {
+        self.dm_draft_data_target
+            .as_ref()
+            .map(|channel| DraftTarget::DirectMessage(channel.keys().to_vec()))
+    }
+
+    /// Switch the post draft to this target, saving the current one and loading
+    /// the saved draft for the target if there is one
+    fn open_draft(&mut self, target: DraftTarget) {
+        if self.draft_target().as_ref() == Some(&target) && !self.draft_data.draft.is_empty() {
+            return;
+        }
+
+        self.autosave_drafts(true);
+
+        self.draft_data.clear();
+        match target {
+            DraftTarget::Reply(id) => self.draft_data.replying_to = Some(id),
+            DraftTarget::Annotation(id) => {
+                self.draft_data.replying_to = Some(id);
+                self.draft_data.is_annotate = true;
+            }
+            _ => {}
+        }
+        if let Ok(Some(saved)) = GLOBALS.runtime.block_on(draft::load_draft(&target)) {
+            self.draft_data.load_draft(&saved);
+        }
+        self.saved_draft = Some(self.draft_data.to_draft(target));
+    }
+
+    /// Switch the DM draft to this channel, saving the current one and loading
+    /// the saved draft for the channel if there is one
+    fn open_dm_draft(&mut self, channel: DmChannel) {
+        if self.dm_draft_data_target.as_ref() == Some(&channel) {
+            return;
+        }
+
+        self.autosave_drafts(true);
+
+        self.dm_draft_data.clear();
+        let target = DraftTarget::DirectMessage(channel.keys().to_vec());
+        if let Ok(Some(saved)) = GLOBALS.runtime.block_on(draft::load_draft(&target)) {
+            self.dm_draft_data.load_draft(&saved);
+        }
+        self.saved_dm_draft = Some(self.dm_draft_data.to_draft(target));
+        self.dm_draft_data_target = Some(channel);
+    }
+
+    /// Delete the saved draft for this target, and clear it if it is open
+    fn discard_draft(&mut self, target: &DraftTarget) {
+        if let Err(e) = draft::delete_draft(target) {
+            tracing::error!("{}", e);
+        }
+        if self.draft_target().as_ref() == Some(target) {
+            self.draft_data.clear();
+            self.show_post_area = false;
+            self.saved_draft = Some(self.draft_data.to_draft(DraftTarget::Post));
+        }
+        if self.dm_draft_target().as_ref() == Some(target) {
+            self.dm_draft_data.clear();
+            self.dm_draft_data_target = None;
+            self.saved_dm_draft = None;
+        }
+    }
+
+    /// Save the drafts if they changed. Unless forced, this happens at most once a second.
+    fn autosave_drafts(&mut self, force: bool) {
+        if !force && Instant::now() < self.drafts_next_autosave {
+            return;
+        }
+        self.drafts_next_autosave = Instant::now() + Duration::from_secs(1);
+
+        if let Some(target) = self.draft_target() {
+            let current = self.draft_data.to_draft(target);
+            if !same_draft(self.saved_draft.as_ref(), &current) {
+                if let Err(e) = GLOBALS.runtime.block_on(draft::save_draft(current.clone())) {
+                    tracing::error!("{}", e);
+                }
+                self.saved_draft = Some(current);
+            }
+        }
+
+        if let Some(target) = self.dm_draft_target() {
+            let current = self.dm_draft_data.to_draft(target);
+            if !same_draft(self.saved_dm_draft.as_ref(), &current) {
+                if let Err(e) = GLOBALS.runtime.block_on(draft::save_draft(current.clone())) {
+                    tracing::error!("{}", e);
+                }
+                self.saved_dm_draft = Some(current);
+            }
         }
     }
 
     fn show_post_area_fn(&self) -> bool {
-        if self.page == Page::DmChatList {
+        if self.page == Page::DmChatList || self.page == Page::ChannelList {
             return false;
         }
 
-        self.show_post_area || matches!(self.page, Page::Feed(FeedKind::DmChat(_)))
+        self.show_post_area
+            || matches!(
+                self.page,
+                Page::Feed(FeedKind::DmChat(_))
+                    | Page::Feed(FeedKind::Channel(_))
+                    | Page::Feed(FeedKind::Group(_, _))
+            )
     }
 
     #[inline]
@@ -2220,6 +2778,9 @@ impl eframe::App for GossipUi {
 
             // Init first page
             self.set_page_inner(ctx, self.page.clone());
+
+            // Restore the new post draft from last time
+            self.open_draft(DraftTarget::Post);
         }
 
         self.frame_count += 1;
@@ -2238,10 +2799,13 @@ impl eframe::App for GossipUi {
         // tracing::warn!("REPAINT: {:?}", ctx.repaint_causes());
 
         if *GLOBALS.read_runstate.borrow() == RunState::ShuttingDown {
+            self.autosave_drafts(true);
             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
             return;
         }
 
+        self.autosave_drafts(false);
+
         // How much scrolling has been requested by inputs during this frame?
         let compose_area_is_focused =
             ctx.memory(|mem| mem.has_focus(egui::Id::new("compose_area")));
@@ -2489,6 +3053,8 @@ impl eframe::App for GossipUi {
             ZapState::SeekingAmount(id, _, _, _) => Some(id),
             ZapState::LoadingInvoice(id, _) => Some(id),
             ZapState::ReadyToPay(id, _) => Some(id),
+            ZapState::PayingWithWallet(id) => Some(id),
+            ZapState::ReadyToPaySplit(id, _) => Some(id),
         };
 
         egui::CentralPanel::default()
@@ -2504,8 +3070,14 @@ impl eframe::App for GossipUi {
             .show(ctx, |ui| {
                 self.begin_ui(ui);
                 match self.page {
+                    Page::Article(_) => article::update(self, ctx, ui),
+                    Page::Calendar => calendar::update(self, ctx, ui),
+                    Page::ChannelList => channels::update(self, ctx, frame, ui),
+                    Page::CommunityList => communities::update(self, ctx, ui),
                     Page::DmChatList => dm_chat_list::update(self, ctx, frame, ui),
+                    Page::Dvms => dvms::update(self, ctx, ui),
                     Page::Feed(_) => feed::update(self, ctx, ui),
+                    Page::GitRepositories | Page::GitRepository(_, _) => git::update(self, ctx, ui),
                     Page::HandlerKinds => handler::update_all_kinds(self, ctx, ui),
                     Page::Handlers(kind) => handler::update_kind(self, ctx, ui, kind),
                     Page::Notifications => notifications::update(self, ui),
@@ -2517,7 +3089,14 @@ impl eframe::App for GossipUi {
                     Page::YourKeys
                     | Page::YourMetadata
                     | Page::YourDelegation
-                    | Page::YourNostrConnect => you::update(self, ctx, frame, ui),
+                    | Page::YourNostrConnect
+                    | Page::YourScheduledPosts
+                    | Page::YourDrafts
+                    | Page::YourArticles
+                    | Page::YourBadges
+                    | Page::YourWallet
+                    | Page::YourMedia
+                    | Page::YourAccounts => you::update(self, ctx, frame, ui),
                     Page::RelaysActivityMonitor
                     | Page::RelaysCoverage
                     | Page::RelaysMine
@@ -10,10 +10,11 @@ use egui_winit::egui::vec2;
 use egui_winit::egui::InnerResponse;
 use egui_winit::egui::Response;
 use egui_winit::egui::Widget;
+use gossip_lib::badges;
 use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::{
-    DmChannel, FeedKind, Freshness, People, Person, PersonList, PersonTable, Private, Table,
-    GLOBALS,
+    Badge, CountKind, DmChannel, FeedKind, Freshness, People, Person, PersonList, PersonTable,
+    Private, Table, GLOBALS,
 };
 use nostr_types::PublicKey;
 use serde_json::Value;
@@ -23,6 +24,7 @@ const AVATAR_COL_WIDTH: f32 = AVATAR_SIZE_F32 * 3.0;
 const AVATAR_COL_SPACE: f32 = 20.0;
 const AVATAR_COL_WIDTH_SPACE: f32 = AVATAR_COL_WIDTH + AVATAR_COL_SPACE * 2.0;
 const MIN_ITEM_WIDTH: f32 = 200.0;
+const BADGE_SIZE: f32 = 48.0;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     let (pubkey, person) = match &app.page {
@@ -187,6 +189,8 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
                 }
             }
 
+            show_badges(app, ctx, ui, pubkey, is_self);
+
             ui.add_space(10.0);
             ui.horizontal(|ui| {
                 ui.add_space(10.0);
@@ -594,8 +598,17 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
 
                             ui.add_space(BTN_SPACING);
 
-                            if widgets::Button::primary(&app.theme, "Their Followers")
+                            let followers_label = match GLOBALS
+                                .relay_counts
+                                .get(&CountKind::Followers(pubkey))
+                                .and_then(|counts| counts.count())
+                            {
+                                Some(count) => format!("Their Followers ({})", count),
+                                None => "Their Followers".to_owned(),
+                            };
+                            if widgets::Button::primary(&app.theme, followers_label)
                                 .show(ui)
+                                .on_hover_text("Count as reported by relays (NIP-45)")
                                 .clicked()
                             {
                                 app.set_page(ctx, Page::PersonFollowers(pubkey));
@@ -681,6 +694,101 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
     }
 }
 
+/// The badges a person shows on their profile (NIP-58). For the user, also the
+/// badges awarded to them, which they can accept or hide.
+fn show_badges(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, is_self: bool) {
+    let shown = badges::profile_badges(pubkey).unwrap_or_default();
+    let awarded: Vec<Badge> = if is_self {
+        badges::awards_to(pubkey)
+            .unwrap_or_default()
+            .drain(..)
+            .filter(|b| !badges::is_hidden(b.award))
+            .collect()
+    } else {
+        vec![]
+    };
+
+    if shown.is_empty() && awarded.is_empty() {
+        return;
+    }
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.add_space(10.0);
+        ui.heading("Badges");
+    });
+    ui.separator();
+    ui.add_space(10.0);
+
+    if !shown.is_empty() {
+        make_frame().show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for badge in shown.iter() {
+                    let response =
+                        widgets::paint_badge(app, ui, badge.definition.as_ref(), BADGE_SIZE)
+                            .on_hover_ui(|ui| badge_hover(ui, badge));
+                    if response.clicked() {
+                        app.set_page(ctx, Page::Person(badge.issuer));
+                    }
+                }
+            });
+        });
+    }
+
+    if !awarded.is_empty() {
+        make_frame().show(ui, |ui| {
+            ui.vertical(|ui| {
+                item_label(ui, "Awarded to you");
+                ui.add_space(ITEM_V_SPACE);
+                for badge in awarded.iter() {
+                    let accepted = shown.iter().any(|b| b.award == badge.award);
+                    ui.horizontal(|ui| {
+                        widgets::paint_badge(app, ui, badge.definition.as_ref(), BADGE_SIZE / 2.0)
+                            .on_hover_ui(|ui| badge_hover(ui, badge));
+                        ui.label(RichText::new(badge.name()).strong());
+                        ui.label(format!(
+                            "from {}",
+                            gossip_lib::names::best_name_from_pubkey_lookup(&badge.issuer)
+                        ));
+                        if accepted {
+                            ui.label(RichText::new("on your profile").weak());
+                        } else if ui.button("Accept").clicked() {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::AcceptBadge(badge.award));
+                        }
+                        if ui
+                            .button("Hide")
+                            .on_hover_text("Take it off your profile, and don't offer it again")
+                            .clicked()
+                        {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::HideBadge(badge.award));
+                        }
+                    });
+                }
+            });
+        });
+    }
+}
+
+fn badge_hover(ui: &mut Ui, badge: &Badge) {
+    ui.label(RichText::new(badge.name()).strong());
+    if let Some(definition) = &badge.definition {
+        if !definition.description.is_empty() {
+            ui.label(&definition.description);
+        }
+    }
+    ui.label(
+        RichText::new(format!(
+            "Awarded by {}",
+            gossip_lib::names::best_name_from_pubkey_lookup(&badge.issuer)
+        ))
+        .weak(),
+    );
+}
+
 /// A profile item
 fn profile_item(
     ui: &mut Ui,
@@ -1,7 +1,8 @@
 use crate::ui::GossipUi;
 use eframe::egui;
 use egui::widgets::Slider;
-use egui::{Context, Ui};
+use egui::{Context, RichText, Ui};
+use gossip_lib::{SpamFilterStatus, GLOBALS};
 
 pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     ui.heading("Content");
@@ -75,6 +76,87 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         reset_button!(app, ui, enable_comments);
     });
 
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.enable_calendar,
+            "Enable calendar events (NIP-52)",
+        )
+        .on_hover_text("Adds a Calendar page under Feeds showing events from people you follow, where you can RSVP and export events to other calendar apps. Takes effect fully only on restart.");
+        reset_button!(app, ui, enable_calendar);
+    });
+
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.enable_public_channels,
+            "Enable public chat channels (NIP-28)",
+        )
+        .on_hover_text("Takes effect fully only on restart.");
+        reset_button!(app, ui, enable_public_channels);
+    });
+
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.enable_relay_groups,
+            "Enable relay-based groups (NIP-29)",
+        )
+        .on_hover_text("Groups are listed with your direct messages. Gossip authenticates to the relays of groups you join without asking. Takes effect fully only on restart.");
+        reset_button!(app, ui, enable_relay_groups);
+    });
+
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.enable_git_repositories,
+            "Enable git collaboration (NIP-34)",
+        )
+        .on_hover_text("Follow git repositories and review their patches and issues. Repositories are listed under Feeds. Takes effect fully only on restart.");
+        reset_button!(app, ui, enable_git_repositories);
+    });
+
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.enable_moderation,
+            "Apply labels and reports from moderators (NIP-32, NIP-56)",
+        )
+        .on_hover_text("Notes that people on your Moderators list (or you) report or label are hidden or shown behind a content warning. Add people to the Moderators list under People > Lists. Takes effect fully only on restart.");
+        reset_button!(app, ui, enable_moderation);
+    });
+
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.enable_communities,
+            "Enable moderated communities (NIP-72)",
+        )
+        .on_hover_text("Adds a Communities page under Feeds. Community feeds only show posts approved by the community moderators. Takes effect fully only on restart.");
+        reset_button!(app, ui, enable_communities);
+    });
+
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.enable_dvms,
+            "Enable data vending machines (NIP-90)",
+        )
+        .on_hover_text("Adds a Data vending machines page under Feeds, where you can ask DVMs to do jobs and follow feeds that DVMs pick for you. Some DVMs ask to be paid with a zap.");
+        reset_button!(app, ui, enable_dvms);
+    });
+
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.enable_timestamps,
+            "Enable OpenTimestamps (NIP-03)",
+        )
+        .on_hover_text("Checks OpenTimestamps attestations of notes against Bitcoin block headers and shows when a note is proven to have existed. Also lets you ask for your own notes to be timestamped.");
+        reset_button!(app, ui, enable_timestamps);
+    });
+
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.enable_wallet,
+            "Enable Nostr Wallet Connect (NIP-47)",
+        )
+        .on_hover_text("Adds a Wallet page under Account, where you can connect a lightning wallet to pay your zaps with.");
+        reset_button!(app, ui, enable_wallet);
+    });
+
     ui.horizontal(|ui| {
         ui.checkbox(&mut app.unsaved_settings.reposts, "Enable reposts (show)");
         reset_button!(app, ui, reposts);
@@ -162,6 +244,62 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         reset_button!(app, ui, apply_spam_filter_on_global);
     });
 
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.apply_spam_filter_on_dms,
+            "Apply spam filtering script to direct messages",
+        )
+        .on_hover_text(
+            "Your filter.rhai script (if it exists) will be run on gift wrapped direct messages after they are unwrapped, hiding spam from your DM channels",
+        );
+        reset_button!(app, ui, apply_spam_filter_on_dms);
+    });
+
+    ui.add_space(10.0);
+
+    let status = GLOBALS.spam_filter_status.read().clone();
+    match status {
+        SpamFilterStatus::NotPresent => {
+            ui.label("No filter.rhai script was found in your gossip directory.");
+        }
+        SpamFilterStatus::Loaded(when) => {
+            ui.label(format!(
+                "Spam filter script loaded {}. It is reloaded when you change it.",
+                crate::date_ago::date_ago(when)
+            ));
+        }
+        SpamFilterStatus::CompileError(e) => {
+            ui.label(
+                RichText::new(format!("Spam filter script failed to compile: {}", e))
+                    .color(app.theme.warning_marker_text_color()),
+            );
+        }
+    }
+
+    let mut hits: Vec<(String, u64)> = GLOBALS
+        .spam_filter_hits
+        .iter()
+        .map(|entry| (entry.key().clone(), *entry.value()))
+        .collect();
+    if !hits.is_empty() {
+        hits.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ui.add_space(5.0);
+        ui.label("Rule hits since the script was loaded:");
+        egui::Grid::new("spam_filter_hits")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (rule, count) in hits.iter() {
+                    ui.label(rule);
+                    ui.label(format!("{}", count));
+                    ui.end_row();
+                }
+            });
+        if ui.button("Reset counts").clicked() {
+            GLOBALS.spam_filter_hits.clear();
+        }
+    }
+
     ui.add_space(10.0);
     ui.heading("Event Content Settings");
     ui.add_space(10.0);
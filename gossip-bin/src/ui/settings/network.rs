@@ -1,7 +1,7 @@
 use crate::ui::{GossipUi, Page};
 use eframe::egui;
 use egui::widgets::Slider;
-use egui::{Context, Ui};
+use egui::{Context, RichText, TextEdit, Ui};
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     ui.heading("Network Settings");
@@ -52,6 +52,34 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         reset_button!(app, ui, relay_auth_requires_approval);
     });
 
+    ui.add_space(10.0);
+    ui.heading("Proxy Settings");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("SOCKS5 proxy: ").on_hover_text("Relay connections and all HTTP fetches go through this proxy, e.g. socks5h://127.0.0.1:9050 for Tor. Host names are always resolved by the proxy. Leave empty to connect directly. Takes effect on restart.");
+        ui.add(
+            TextEdit::singleline(&mut app.unsaved_settings.proxy)
+                .hint_text("socks5h://127.0.0.1:9050")
+                .desired_width(300.0),
+        );
+        reset_button!(app, ui, proxy);
+    });
+    if !app.unsaved_settings.proxy.trim().is_empty() {
+        if let Err(e) = gossip_lib::proxy::validate_proxy(app.unsaved_settings.proxy.trim()) {
+            ui.label(RichText::new(format!("{}", e)).color(app.theme.warning_marker_text_color()));
+        }
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Per-relay proxies: ").on_hover_text("One relay per line, followed by the proxy to use for it, or 'direct' to bypass the proxy. For example: wss://relay.example.com direct");
+        ui.add(
+            TextEdit::multiline(&mut app.unsaved_settings.relay_proxy_overrides)
+                .desired_width(f32::INFINITY),
+        );
+    });
+    ui.label("Relays with .onion addresses can only be reached through a proxy such as Tor.");
+
     ui.add_space(10.0);
     ui.heading("Relay Settings");
     ui.add_space(10.0);
@@ -85,6 +113,24 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         reset_button!(app, ui, num_relays_for_counting);
     });
 
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.count_replies_and_reactions,
+            "Ask relays to count the replies, mentions and reactions of notes you view",
+        )
+        .on_hover_text("Only relays that support NIP-45 COUNT are asked. Followers are always counted when you visit a person's page.");
+        reset_button!(app, ui, count_replies_and_reactions);
+    });
+
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.negentropy_sync,
+            "Use negentropy sync when loading older feed events",
+        )
+        .on_hover_text("With relays that support NIP-77, only events we don't already have are downloaded. Other relays are queried normally.");
+        reset_button!(app, ui, negentropy_sync);
+    });
+
     ui.add_space(10.0);
     ui.heading("HTTP Fetch Settings");
     ui.add_space(10.0);
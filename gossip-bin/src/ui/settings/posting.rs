@@ -65,4 +65,36 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
     });
 
     ui.add_space(20.0);
+
+    ui.horizontal(|ui| {
+        ui.label("NIP-96 servers: ")
+            .on_hover_text("Specify your NIP-96 file storage servers (just the host and port if it is not 443). Separate them by spaces or newlines. Uploads go to the first one.");
+        ui.add(
+            TextEdit::multiline(
+                &mut app.unsaved_settings.nip96_servers)
+                .desired_width(f32::INFINITY)
+        );
+    });
+
+    ui.add_space(20.0);
+
+    ui.horizontal(|ui| {
+        ui.label("OpenTimestamps calendar: ")
+            .on_hover_text("The calendar server that timestamps your notes when you ask it to.");
+        ui.add(TextEdit::singleline(&mut app.unsaved_settings.ots_calendar).desired_width(300.0));
+        reset_button!(app, ui, ots_calendar);
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Bitcoin block header source: ")
+            .on_hover_text("Where to get Bitcoin block headers for checking timestamps. Headers are checked against block hashes that come with gossip, so the source isn't trusted. Either the path of a local file of raw 80-byte headers in height order, to check timestamps offline, or an Esplora API URL such as https://mempool.space/api, which will see which blocks you check. Empty by default. Headers are kept once checked.");
+        ui.add(
+            TextEdit::singleline(&mut app.unsaved_settings.ots_header_source)
+                .hint_text("Path to a headers file, or an Esplora API URL")
+                .desired_width(300.0),
+        );
+        reset_button!(app, ui, ots_header_source);
+    });
+
+    ui.add_space(20.0);
 }
@@ -0,0 +1,28 @@
+use crate::ui::GossipUi;
+use egui_winit::egui::{self, vec2, Image, Response, RichText, Ui};
+use gossip_lib::{BadgeDefinition, MediaLoadingResult};
+
+/// Paint the image of a badge at a square size. Until the image is loaded (or if
+/// it can't be), a medal is painted instead.
+pub(crate) fn paint_badge(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    definition: Option<&BadgeDefinition>,
+    size: f32,
+) -> Response {
+    let url = definition.and_then(|d| app.try_check_url(d.small_image()));
+    if let Some(url) = url {
+        if let MediaLoadingResult::Ready(texture) = app.try_get_media(ui.ctx(), url, false, None) {
+            return ui.add(
+                Image::new(&texture)
+                    .fit_to_exact_size(vec2(size, size))
+                    .sense(egui::Sense::click()),
+            );
+        }
+    }
+
+    ui.add_sized(
+        vec2(size, size),
+        egui::Label::new(RichText::new("🏅").size(size * 0.6)).sense(egui::Sense::click()),
+    )
+}
@@ -0,0 +1,79 @@
+use crate::ui::GossipUi;
+use eframe::egui;
+use egui::{Color32, FontId, Frame, Margin, RichText, Ui};
+
+/// Render a patch, as made by `git format-patch`, colouring the diff.
+///
+/// Shows at most `max_lines` lines, returning true if there was more.
+pub fn diff(app: &GossipUi, ui: &mut Ui, patch: &str, max_lines: Option<usize>) -> bool {
+    let dark = app.theme.dark_mode;
+    let added = if dark {
+        Color32::from_rgb(0x3f, 0xb9, 0x50)
+    } else {
+        Color32::from_rgb(0x1a, 0x7f, 0x37)
+    };
+    let removed = if dark {
+        Color32::from_rgb(0xf8, 0x51, 0x49)
+    } else {
+        Color32::from_rgb(0xcf, 0x22, 0x2e)
+    };
+    let hunk = if dark {
+        Color32::from_rgb(0x79, 0xc0, 0xff)
+    } else {
+        Color32::from_rgb(0x05, 0x50, 0xae)
+    };
+
+    let mut truncated = false;
+
+    Frame::NONE
+        .fill(ui.visuals().extreme_bg_color)
+        .inner_margin(Margin::same(8))
+        .show(ui, |ui| {
+            ui.set_min_width(ui.available_width());
+            ui.spacing_mut().item_spacing.y = 0.0;
+
+            // The commit message comes before the diff, and is not coloured
+            let mut in_diff = false;
+
+            for (count, line) in patch.lines().enumerate() {
+                if max_lines.is_some_and(|max| count >= max) {
+                    truncated = true;
+                    break;
+                }
+
+                if line.starts_with("diff --git") {
+                    in_diff = true;
+                } else if line == "-- " {
+                    // The signature after the diff
+                    in_diff = false;
+                }
+
+                let text = RichText::new(line).font(FontId::monospace(12.0));
+                let text = if !in_diff {
+                    if line.starts_with("Subject: ") {
+                        text.strong()
+                    } else {
+                        text
+                    }
+                } else if line.starts_with("diff --git")
+                    || line.starts_with("index ")
+                    || line.starts_with("--- ")
+                    || line.starts_with("+++ ")
+                {
+                    text.strong()
+                } else if line.starts_with("@@") {
+                    text.color(hunk)
+                } else if line.starts_with('+') {
+                    text.color(added)
+                } else if line.starts_with('-') {
+                    text.color(removed)
+                } else {
+                    text
+                };
+
+                ui.label(text);
+            }
+        });
+
+    truncated
+}
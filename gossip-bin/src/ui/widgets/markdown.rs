@@ -0,0 +1,325 @@
+use crate::ui::{GossipUi, Page};
+use eframe::egui;
+use egui::{FontId, Frame, Margin, RichText, Ui};
+use gossip_lib::FeedKind;
+use nostr_types::{EventKind, NostrBech32};
+
+// Inline pieces of a markdown line
+enum Inline {
+    Text(String),
+    Strong(String),
+    Emphasis(String),
+    Code(String),
+    Link { text: String, url: String },
+    Image { alt: String, url: String },
+}
+
+/// Render markdown, as used by long-form articles.
+///
+/// This covers the common subset: headings, paragraphs, emphasis, inline and fenced
+/// code, block quotes, lists, rules, links and images. `nostr:` links open in gossip.
+pub fn markdown(app: &mut GossipUi, ui: &mut Ui, text: &str) {
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code_block: Option<Vec<&str>> = None;
+
+    for line in text.lines() {
+        // Fenced code blocks are taken verbatim
+        if line.trim_start().starts_with("```") {
+            match code_block.take() {
+                Some(code) => render_code_block(ui, &code),
+                None => {
+                    flush_paragraph(app, ui, &mut paragraph);
+                    code_block = Some(Vec::new());
+                }
+            }
+            continue;
+        }
+        if let Some(code) = code_block.as_mut() {
+            code.push(line);
+            continue;
+        }
+
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            flush_paragraph(app, ui, &mut paragraph);
+            continue;
+        }
+
+        if let Some((level, heading)) = parse_heading(trimmed) {
+            flush_paragraph(app, ui, &mut paragraph);
+            let size = match level {
+                1 => 26.0,
+                2 => 22.0,
+                3 => 19.0,
+                _ => 17.0,
+            };
+            ui.add_space(8.0);
+            ui.label(RichText::new(heading).size(size).strong());
+            ui.add_space(4.0);
+            continue;
+        }
+
+        if is_rule(trimmed) {
+            flush_paragraph(app, ui, &mut paragraph);
+            ui.separator();
+            continue;
+        }
+
+        if let Some(quote) = trimmed.strip_prefix('>') {
+            flush_paragraph(app, ui, &mut paragraph);
+            ui.horizontal_wrapped(|ui| {
+                ui.label(RichText::new("┃ ").weak());
+                render_inlines(app, ui, quote.trim_start(), true);
+            });
+            continue;
+        }
+
+        if let Some((marker, item)) = parse_list_item(trimmed) {
+            flush_paragraph(app, ui, &mut paragraph);
+            ui.horizontal_wrapped(|ui| {
+                ui.add_space(12.0);
+                ui.label(marker);
+                render_inlines(app, ui, item, false);
+            });
+            continue;
+        }
+
+        paragraph.push(trimmed);
+    }
+
+    if let Some(code) = code_block {
+        render_code_block(ui, &code);
+    }
+    flush_paragraph(app, ui, &mut paragraph);
+}
+
+fn flush_paragraph(app: &mut GossipUi, ui: &mut Ui, paragraph: &mut Vec<&str>) {
+    if paragraph.is_empty() {
+        return;
+    }
+    let text = paragraph.join(" ");
+    paragraph.clear();
+
+    ui.horizontal_wrapped(|ui| {
+        render_inlines(app, ui, &text, false);
+    });
+    ui.add_space(8.0);
+}
+
+fn render_code_block(ui: &mut Ui, code: &[&str]) {
+    Frame::NONE
+        .fill(ui.visuals().extreme_bg_color)
+        .inner_margin(Margin::same(8))
+        .show(ui, |ui| {
+            ui.set_min_width(ui.available_width());
+            ui.label(RichText::new(code.join("\n")).font(FontId::monospace(13.0)));
+        });
+    ui.add_space(8.0);
+}
+
+fn render_inlines(app: &mut GossipUi, ui: &mut Ui, text: &str, quoted: bool) {
+    ui.spacing_mut().item_spacing.x = 0.0;
+    for inline in parse_inlines(text) {
+        match inline {
+            Inline::Text(t) => {
+                let rt = RichText::new(t);
+                ui.label(if quoted { rt.italics() } else { rt });
+            }
+            Inline::Strong(t) => {
+                ui.label(RichText::new(t).strong());
+            }
+            Inline::Emphasis(t) => {
+                ui.label(RichText::new(t).italics());
+            }
+            Inline::Code(t) => {
+                ui.label(RichText::new(t).code());
+            }
+            Inline::Link { text, url } => render_link(app, ui, &text, &url),
+            Inline::Image { alt, url } => {
+                let text = if alt.is_empty() {
+                    "🖼 image".to_owned()
+                } else {
+                    format!("🖼 {}", alt)
+                };
+                render_link(app, ui, &text, &url);
+            }
+        }
+    }
+}
+
+fn render_link(app: &mut GossipUi, ui: &mut Ui, text: &str, url: &str) {
+    let Some(bech32) = url.strip_prefix("nostr:") else {
+        super::break_anywhere_hyperlink_to(ui, app, text, url);
+        return;
+    };
+
+    let page = match NostrBech32::try_from_string(bech32) {
+        Some(NostrBech32::NAddr(naddr))
+            if naddr.kind == EventKind::LongFormContent
+                || naddr.kind == EventKind::DraftLongFormContent =>
+        {
+            Some(Page::Article(naddr))
+        }
+        Some(NostrBech32::Pubkey(pubkey)) => Some(Page::Person(pubkey)),
+        Some(NostrBech32::Profile(profile)) => Some(Page::Person(profile.pubkey)),
+        Some(NostrBech32::Id(id)) => Some(Page::Feed(FeedKind::Thread {
+            id,
+            referenced_by: id,
+            author: None,
+        })),
+        Some(NostrBech32::NEvent(ne)) => Some(Page::Feed(FeedKind::Thread {
+            id: ne.id,
+            referenced_by: ne.id,
+            author: ne.author,
+        })),
+        _ => None,
+    };
+
+    let text = if text == url && bech32.len() > 24 {
+        format!("nostr:{}…", &bech32[..20])
+    } else {
+        text.to_owned()
+    };
+
+    if ui.link(text).clicked() {
+        if let Some(page) = page {
+            app.set_page(ui.ctx(), page);
+        }
+    }
+}
+
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.starts_with(' ') {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && (compact.chars().all(|c| c == '-')
+            || compact.chars().all(|c| c == '*')
+            || compact.chars().all(|c| c == '_'))
+}
+
+fn parse_list_item(line: &str) -> Option<(String, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return Some(("• ".to_owned(), item));
+        }
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &line[digits..];
+        if let Some(item) = rest.strip_prefix(". ") {
+            return Some((format!("{}. ", &line[..digits]), item));
+        }
+    }
+
+    None
+}
+
+fn parse_inlines(text: &str) -> Vec<Inline> {
+    let mut output: Vec<Inline> = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let parsed = match c {
+            '*' | '_' => parse_emphasis(rest, c),
+            '`' => parse_delimited(rest, "`").map(|(t, len)| (Inline::Code(t), len)),
+            '!' if rest.starts_with("![") => {
+                parse_link(&rest[1..]).map(|(alt, url, len)| (Inline::Image { alt, url }, len + 1))
+            }
+            '[' => parse_link(rest).map(|(text, url, len)| (Inline::Link { text, url }, len)),
+            'h' | 'n' if plain.is_empty() || plain.ends_with(char::is_whitespace) => {
+                parse_bare_link(rest)
+            }
+            _ => None,
+        };
+
+        match parsed {
+            Some((inline, len)) => {
+                if !plain.is_empty() {
+                    output.push(Inline::Text(std::mem::take(&mut plain)));
+                }
+                output.push(inline);
+                rest = &rest[len..];
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    if !plain.is_empty() {
+        output.push(Inline::Text(plain));
+    }
+
+    output
+}
+
+// `**strong**`, `__strong__`, `*emphasis*` or `_emphasis_`
+fn parse_emphasis(text: &str, c: char) -> Option<(Inline, usize)> {
+    let double: String = [c, c].iter().collect();
+    if text.starts_with(&double) {
+        return parse_delimited(text, &double).map(|(t, len)| (Inline::Strong(t), len));
+    }
+    parse_delimited(text, &c.to_string()).map(|(t, len)| (Inline::Emphasis(t), len))
+}
+
+// Text between an opening and closing delimiter, and the length consumed
+fn parse_delimited(text: &str, delimiter: &str) -> Option<(String, usize)> {
+    let inner = text.strip_prefix(delimiter)?;
+    if inner.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let end = inner.find(delimiter)?;
+    if end == 0 {
+        return None;
+    }
+    Some((inner[..end].to_owned(), delimiter.len() * 2 + end))
+}
+
+// `[text](url)`, returning the text, the url, and the length consumed
+fn parse_link(text: &str) -> Option<(String, String, usize)> {
+    let inner = text.strip_prefix('[')?;
+    let close = inner.find("](")?;
+    let after = &inner[close + 2..];
+    let end = after.find(')')?;
+    let url = after[..end].trim();
+    if url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((
+        inner[..close].to_owned(),
+        url.to_owned(),
+        1 + close + 2 + end + 1,
+    ))
+}
+
+// A bare `http://`, `https://` or `nostr:` link, up to the next whitespace
+fn parse_bare_link(text: &str) -> Option<(Inline, usize)> {
+    if !text.starts_with("https://") && !text.starts_with("http://") && !text.starts_with("nostr:")
+    {
+        return None;
+    }
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    let url = text[..end].trim_end_matches(['.', ',', ';', ':', ')', '!', '?']);
+    Some((
+        Inline::Link {
+            text: url.to_owned(),
+            url: url.to_owned(),
+        },
+        url.len(),
+    ))
+}
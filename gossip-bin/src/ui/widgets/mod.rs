@@ -3,6 +3,9 @@ use std::sync::Arc;
 
 pub(crate) use avatar::{paint_avatar, paint_avatar_only, AvatarSize};
 
+mod badge;
+pub(crate) use badge::paint_badge;
+
 mod button;
 pub use button::Button;
 
@@ -12,6 +15,9 @@ pub(super) use contact_search::{capture_keyboard_for_search, show_contact_search
 mod link_context_menu;
 pub(super) use link_context_menu::{show_link_context, show_media_link_context};
 
+mod diff;
+pub use diff::diff;
+
 mod copy_button;
 pub(crate) mod list_entry;
 pub use copy_button::{CopyButton, COPY_SYMBOL_SIZE};
@@ -28,6 +34,9 @@ mod relay_entry;
 use nostr_types::RelayUrl;
 pub use relay_entry::RelayEntry;
 
+mod markdown;
+pub use markdown::markdown;
+
 mod modal_popup;
 pub use modal_popup::{modal_popup, modal_popup_dyn, ModalEntry};
 
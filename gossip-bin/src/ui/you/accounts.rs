@@ -0,0 +1,76 @@
+use super::{GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{accounts, names, GLOBALS};
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal_wrapped(|ui| {
+        ui.heading("Accounts");
+    });
+
+    ui.add_space(10.0);
+    ui.label("Each account keeps its own person lists, relay lists, bookmarks and direct messages. Events are shared between accounts.");
+
+    ui.add_space(10.0);
+    if ui.button("Add account").clicked() {
+        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::AddAccount);
+        app.set_page(ctx, Page::YourKeys);
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let list = match accounts::accounts() {
+        Ok(list) => list,
+        Err(e) => {
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+
+    if list.is_empty() {
+        ui.label("You have not set up an account yet.");
+        return;
+    }
+
+    let active = GLOBALS.identity.public_key();
+
+    app.vert_scroll_area().id_salt("accounts").show(ui, |ui| {
+        for account in list.iter() {
+            let is_active = active == Some(account.pubkey);
+
+            ui.horizontal(|ui| {
+                ui.label(
+                    RichText::new(names::best_name_from_pubkey_lookup(&account.pubkey)).strong(),
+                );
+                ui.label(RichText::new(names::pubkey_short(&account.pubkey)).weak());
+                ui.label(RichText::new(accounts::identity_kind(account)).weak());
+                if is_active {
+                    ui.label(RichText::new("active").italics());
+                }
+            });
+
+            if !is_active {
+                ui.horizontal(|ui| {
+                    if ui.button("Switch").clicked() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::SwitchAccount(account.pubkey));
+                    }
+                    if ui.button("Remove").clicked() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::RemoveAccount(account.pubkey));
+                    }
+                });
+            }
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+        }
+    });
+}
@@ -0,0 +1,194 @@
+use super::scheduled::format_schedule_time;
+use super::{GossipUi, Page};
+use crate::ui::widgets;
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{article, ArticleFields, GLOBALS};
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal_wrapped(|ui| {
+        ui.heading("Articles");
+    });
+
+    ui.add_space(10.0);
+    ui.label("Long-form articles (NIP-23) are written in markdown. Publishing an article again with the same identifier replaces the earlier version. Drafts are published to your relays too, but other clients won't show them as articles.");
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pubkey) => pubkey,
+        None => {
+            ui.label("You need to set up an identity to write articles.");
+            return;
+        }
+    };
+
+    app.vert_scroll_area()
+        .id_salt("your_articles")
+        .show(ui, |ui| {
+            editor(app, ui);
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+
+            ui.heading("My articles");
+            ui.add_space(10.0);
+
+            let articles = match article::articles_by(pubkey) {
+                Ok(articles) => articles,
+                Err(e) => {
+                    ui.label(format!("{}", e));
+                    return;
+                }
+            };
+
+            if articles.is_empty() {
+                ui.label("You haven't written any articles yet.");
+                return;
+            }
+
+            for article in articles.iter() {
+                let title = if article.title.is_empty() {
+                    article.d.clone()
+                } else {
+                    article.title.clone()
+                };
+
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(title).strong());
+                    if article.is_draft() {
+                        ui.label(RichText::new("draft").italics());
+                    }
+                    ui.label(RichText::new(format_schedule_time(article.created_at)).weak());
+                });
+
+                if !article.summary.is_empty() {
+                    ui.label(&article.summary);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Edit").clicked() {
+                        app.article_fields = article.fields();
+                        app.article_hashtags = article.hashtags.join(", ");
+                        app.article_preview = false;
+                    }
+                    if ui.button("Read").clicked() {
+                        app.set_page(ctx, Page::Article(article.naddr(vec![])));
+                    }
+                });
+
+                ui.add_space(10.0);
+            }
+        });
+}
+
+fn editor(app: &mut GossipUi, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        ui.heading(if app.article_fields.d.is_empty() {
+            "New article"
+        } else {
+            "Edit article"
+        });
+        if ui.button("New article").clicked() {
+            app.article_fields = ArticleFields::default();
+            app.article_hashtags.clear();
+            app.article_preview = false;
+        }
+    });
+
+    ui.add_space(10.0);
+    egui::Grid::new("article_fields")
+        .num_columns(2)
+        .striped(false)
+        .show(ui, |ui| {
+            ui.label("Title");
+            ui.add(text_edit_line!(app, app.article_fields.title).desired_width(500.0));
+            ui.end_row();
+
+            ui.label("Summary");
+            ui.add(text_edit_line!(app, app.article_fields.summary).desired_width(500.0));
+            ui.end_row();
+
+            ui.label("Image URL");
+            ui.add(text_edit_line!(app, app.article_fields.image).desired_width(500.0));
+            ui.end_row();
+
+            ui.label("Hashtags");
+            ui.add(
+                text_edit_line!(app, app.article_hashtags)
+                    .hint_text("comma separated")
+                    .desired_width(500.0),
+            );
+            ui.end_row();
+
+            ui.label("Identifier");
+            ui.add(
+                text_edit_line!(app, app.article_fields.d)
+                    .hint_text("made from the title if left empty")
+                    .desired_width(500.0),
+            );
+            ui.end_row();
+        });
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut app.article_preview, false, "Write");
+        ui.selectable_value(&mut app.article_preview, true, "Preview");
+    });
+    ui.add_space(4.0);
+
+    if app.article_preview {
+        let content = app.article_fields.content.clone();
+        widgets::markdown(app, ui, &content);
+    } else {
+        ui.add(
+            text_edit_multiline!(app, app.article_fields.content)
+                .hint_text("Write your article in markdown")
+                .desired_rows(20)
+                .desired_width(f32::INFINITY),
+        );
+    }
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        let ready =
+            !app.article_fields.title.trim().is_empty() && !app.article_fields.content.is_empty();
+        if ui
+            .add_enabled(ready, egui::Button::new("Save draft"))
+            .clicked()
+        {
+            post_article(app, true);
+        }
+        if ui
+            .add_enabled(ready, egui::Button::new("Publish"))
+            .clicked()
+        {
+            post_article(app, false);
+        }
+    });
+}
+
+fn post_article(app: &mut GossipUi, draft: bool) {
+    let mut fields = app.article_fields.clone();
+    fields.hashtags = app
+        .article_hashtags
+        .split(',')
+        .map(|h| h.trim().trim_start_matches('#').to_owned())
+        .filter(|h| !h.is_empty())
+        .collect();
+
+    // Keep editing under the same identifier, so saving again replaces this version
+    if fields.d.is_empty() {
+        fields.d = gossip_lib::new_identifier(&fields.title);
+        app.article_fields.d = fields.d.clone();
+    }
+
+    let _ = GLOBALS
+        .to_overlord
+        .send(ToOverlordMessage::PostArticle { fields, draft });
+}
@@ -0,0 +1,253 @@
+use super::{GossipUi, Page};
+use crate::ui::widgets;
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{badges, BadgeDefinition, BadgeFields, GLOBALS};
+use nostr_types::PublicKey;
+
+const BADGE_SIZE: f32 = 64.0;
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal_wrapped(|ui| {
+        ui.heading("Badges");
+    });
+
+    ui.add_space(10.0);
+    ui.label("Badges (NIP-58) are defined by their issuer and awarded to people, who may choose to show them on their profile. Badges awarded to you are on your profile page.");
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pubkey) => pubkey,
+        None => {
+            ui.label("You need to set up an identity to issue badges.");
+            return;
+        }
+    };
+
+    app.vert_scroll_area()
+        .id_salt("your_badges")
+        .show(ui, |ui| {
+            editor(app, ui);
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+
+            ui.heading("My badges");
+            ui.add_space(10.0);
+
+            let definitions = match badges::definitions_by(pubkey) {
+                Ok(definitions) => definitions,
+                Err(e) => {
+                    ui.label(format!("{}", e));
+                    return;
+                }
+            };
+
+            if definitions.is_empty() {
+                ui.label("You haven't defined any badges yet.");
+                return;
+            }
+
+            for definition in definitions.iter() {
+                ui.horizontal(|ui| {
+                    widgets::paint_badge(app, ui, Some(definition), BADGE_SIZE);
+                    ui.vertical(|ui| {
+                        ui.label(RichText::new(definition.display_name()).strong());
+                        if !definition.description.is_empty() {
+                            ui.label(&definition.description);
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Edit").clicked() {
+                                app.badge_fields = definition.fields();
+                            }
+                            if ui.button("Award").clicked() {
+                                app.badge_award = Some(definition.d.clone());
+                                app.badge_award_to.clear();
+                            }
+                        });
+                    });
+                });
+
+                if app.badge_award.as_deref() == Some(definition.d.as_str()) {
+                    ui.add_space(10.0);
+                    award(app, ctx, ui, pubkey, definition);
+                }
+
+                ui.add_space(10.0);
+            }
+        });
+}
+
+fn editor(app: &mut GossipUi, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        ui.heading(if app.badge_fields.d.is_empty() {
+            "New badge"
+        } else {
+            "Edit badge"
+        });
+        if ui.button("New badge").clicked() {
+            app.badge_fields = BadgeFields::default();
+        }
+    });
+
+    ui.add_space(10.0);
+    egui::Grid::new("badge_fields")
+        .num_columns(2)
+        .striped(false)
+        .show(ui, |ui| {
+            ui.label("Name");
+            ui.add(text_edit_line!(app, app.badge_fields.name).desired_width(500.0));
+            ui.end_row();
+
+            ui.label("Description");
+            ui.add(text_edit_line!(app, app.badge_fields.description).desired_width(500.0));
+            ui.end_row();
+
+            ui.label("Image URL");
+            ui.add(
+                text_edit_line!(app, app.badge_fields.image)
+                    .hint_text("1024x1024 is recommended")
+                    .desired_width(500.0),
+            );
+            ui.end_row();
+
+            ui.label("Thumbnail URL");
+            ui.add(text_edit_line!(app, app.badge_fields.thumb).desired_width(500.0));
+            ui.end_row();
+
+            ui.label("Identifier");
+            ui.add(
+                text_edit_line!(app, app.badge_fields.d)
+                    .hint_text("made from the name if left empty")
+                    .desired_width(500.0),
+            );
+            ui.end_row();
+        });
+
+    ui.add_space(10.0);
+    let ready = !app.badge_fields.name.trim().is_empty();
+    if ui
+        .add_enabled(ready, egui::Button::new("Publish"))
+        .clicked()
+    {
+        // Keep editing under the same identifier, so publishing again replaces this definition
+        if app.badge_fields.d.trim().is_empty() {
+            app.badge_fields.d = gossip_lib::new_identifier(&app.badge_fields.name);
+        }
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::DefineBadge(app.badge_fields.clone()));
+    }
+}
+
+// Choose people from a list to award a badge to
+fn award(
+    app: &mut GossipUi,
+    ctx: &Context,
+    ui: &mut Ui,
+    pubkey: PublicKey,
+    definition: &BadgeDefinition,
+) {
+    let lists = GLOBALS
+        .db()
+        .get_all_person_list_metadata()
+        .unwrap_or_default();
+
+    ui.horizontal(|ui| {
+        ui.label("Award to people in:");
+        let selected = lists
+            .iter()
+            .find(|(list, _)| *list == app.badge_award_list)
+            .map(|(_, metadata)| metadata.title.clone())
+            .unwrap_or_default();
+        egui::ComboBox::from_id_salt("BadgeAwardList")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for (list, metadata) in lists.iter() {
+                    if ui
+                        .selectable_value(&mut app.badge_award_list, *list, &metadata.title)
+                        .clicked()
+                    {
+                        app.badge_award_to.clear();
+                    }
+                }
+            });
+    });
+
+    let members: Vec<PublicKey> = GLOBALS
+        .db()
+        .get_people_in_list(app.badge_award_list)
+        .unwrap_or_default()
+        .drain(..)
+        .map(|(pk, _)| pk)
+        .filter(|pk| *pk != pubkey)
+        .collect();
+    let already = badges::awarded_to(pubkey, &definition.d).unwrap_or_default();
+
+    if members.is_empty() {
+        ui.label("There is nobody in this list.");
+    }
+
+    ui.horizontal(|ui| {
+        if ui.link("Select all").clicked() {
+            app.badge_award_to = members
+                .iter()
+                .filter(|pk| !already.contains(pk))
+                .copied()
+                .collect();
+        }
+        if ui.link("Select none").clicked() {
+            app.badge_award_to.clear();
+        }
+    });
+
+    for member in members.iter() {
+        ui.horizontal(|ui| {
+            let name = gossip_lib::names::best_name_from_pubkey_lookup(member);
+            if already.contains(member) {
+                ui.add_enabled(false, egui::Checkbox::new(&mut true, name));
+                ui.label(RichText::new("has it").weak());
+            } else {
+                let mut selected = app.badge_award_to.contains(member);
+                if ui.checkbox(&mut selected, name).changed() {
+                    if selected {
+                        app.badge_award_to.insert(*member);
+                    } else {
+                        app.badge_award_to.remove(member);
+                    }
+                }
+            }
+            if ui.link("profile").clicked() {
+                app.set_page(ctx, Page::Person(*member));
+            }
+        });
+    }
+
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        let count = app.badge_award_to.len();
+        if ui
+            .add_enabled(
+                count > 0,
+                egui::Button::new(format!("Award to {} people", count)),
+            )
+            .clicked()
+        {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::AwardBadge(
+                definition.d.clone(),
+                app.badge_award_to.drain().collect(),
+            ));
+            app.badge_award = None;
+        }
+        if ui.button("Cancel").clicked() {
+            app.badge_award = None;
+            app.badge_award_to.clear();
+        }
+    });
+}
@@ -0,0 +1,90 @@
+use super::scheduled::format_schedule_time;
+use super::{GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::{draft, DmChannel, DraftTarget, FeedKind, GLOBALS};
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal_wrapped(|ui| {
+        ui.heading("Drafts");
+    });
+
+    ui.add_space(10.0);
+    ui.label("Drafts are saved as you type, and kept until you send or cancel them.");
+    ui.label(
+        "Direct message drafts are encrypted, and only saved while your private key is unlocked.",
+    );
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let drafts = match GLOBALS.runtime.block_on(draft::drafts()) {
+        Ok(drafts) => drafts,
+        Err(e) => {
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+
+    if drafts.is_empty() {
+        ui.label("You have no saved drafts.");
+        return;
+    }
+
+    app.vert_scroll_area().id_salt("drafts").show(ui, |ui| {
+        for saved in drafts.iter() {
+            let description = match &saved.target {
+                DraftTarget::Post => "New post".to_owned(),
+                DraftTarget::Reply(id) => format!(
+                    "Reply to {}",
+                    gossip_lib::names::hex_id_short(&(*id).into())
+                ),
+                DraftTarget::Annotation(id) => format!(
+                    "Annotation of {}",
+                    gossip_lib::names::hex_id_short(&(*id).into())
+                ),
+                DraftTarget::DirectMessage(pubkeys) => {
+                    format!("Message to {}", DmChannel::new(pubkeys).name())
+                }
+            };
+
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(description).strong());
+                ui.label(RichText::new(format_schedule_time(saved.updated_at)).weak());
+            });
+
+            if saved.encrypted {
+                ui.label(RichText::new("Unlock your private key to read this draft.").italics());
+            } else {
+                let preview: String = saved.content.chars().take(280).collect();
+                ui.label(preview);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("Open").clicked() {
+                    match &saved.target {
+                        DraftTarget::DirectMessage(pubkeys) => {
+                            let channel = DmChannel::new(pubkeys);
+                            app.open_dm_draft(channel.clone());
+                            app.set_page(ctx, Page::Feed(FeedKind::DmChat(channel)));
+                        }
+                        target => {
+                            app.open_draft(target.clone());
+                            app.show_post_area = true;
+                        }
+                    }
+                    app.draft_needs_focus = true;
+                }
+                if ui.button("Delete").clicked() {
+                    app.discard_draft(&saved.target);
+                }
+            });
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+        }
+    });
+}
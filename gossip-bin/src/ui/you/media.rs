@@ -0,0 +1,126 @@
+use super::GossipUi;
+use chrono::{DateTime, Local, Utc};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::blossom::{self, BlobDescriptor};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::GLOBALS;
+use humansize::{format_size, DECIMAL};
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal_wrapped(|ui| {
+        ui.heading("Media");
+        if ui.button("Refresh").clicked() {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::BlossomList);
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.label("The files you have uploaded to your blossom servers. You can choose your blossom servers in Settings > Posting.");
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let servers = blossom::blossom_servers();
+    if servers.is_empty() {
+        ui.label("You have not configured any blossom servers.");
+        return;
+    }
+
+    // How each server is doing
+    egui::Grid::new("blossom_servers")
+        .num_columns(2)
+        .striped(false)
+        .show(ui, |ui| {
+            for server in servers.iter() {
+                ui.label(server);
+                match GLOBALS.blossom_blobs.get(server).as_deref() {
+                    None => ui.label("Loading..."),
+                    Some(Ok(blobs)) => ui.label(format!("{} files", blobs.len())),
+                    Some(Err(e)) => ui.label(RichText::new(format!("{}", e)).weak()),
+                };
+                ui.end_row();
+            }
+        });
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    // Every blob we have anywhere, with the servers that hold it
+    let mut blobs: Vec<(BlobDescriptor, Vec<String>)> = Vec::new();
+    for server in servers.iter() {
+        if let Some(Ok(list)) = GLOBALS.blossom_blobs.get(server).as_deref() {
+            for bd in list.iter() {
+                match blobs.iter_mut().find(|(b, _)| b.sha256 == bd.sha256) {
+                    Some((_, holders)) => holders.push(server.to_owned()),
+                    None => blobs.push((bd.clone(), vec![server.to_owned()])),
+                }
+            }
+        }
+    }
+    blobs.sort_by_key(|(bd, _)| std::cmp::Reverse(uploaded(bd)));
+
+    if blobs.is_empty() {
+        ui.label("No files.");
+        return;
+    }
+
+    app.vert_scroll_area().id_salt("your_media").show(ui, |ui| {
+        egui::Grid::new("blossom_blobs")
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                for (bd, holders) in blobs.iter() {
+                    ui.hyperlink_to(short_hash(&bd.sha256), &bd.url)
+                        .on_hover_text(&bd.sha256);
+                    ui.label(bd.mime_type.as_deref().unwrap_or_default());
+                    ui.label(format_size(bd.size, DECIMAL));
+                    ui.label(uploaded(bd).map(format_time).unwrap_or_default());
+                    ui.label(format!("on {} of {} servers", holders.len(), servers.len()))
+                        .on_hover_text(holders.join("\n"));
+                    ui.horizontal(|ui| {
+                        if holders.len() < servers.len()
+                            && ui
+                                .button("Mirror")
+                                .on_hover_text("Copy to all of your servers")
+                                .clicked()
+                        {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::BlossomMirror(bd.sha256.clone()));
+                        }
+                        if ui
+                            .button("Delete")
+                            .on_hover_text("Delete from all of your servers")
+                            .clicked()
+                        {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::BlossomDelete(bd.sha256.clone()));
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+    });
+}
+
+fn uploaded(bd: &BlobDescriptor) -> Option<u64> {
+    bd.uploaded.or(bd.created)
+}
+
+fn short_hash(hash: &str) -> String {
+    match hash.get(0..12) {
+        Some(start) => format!("{}…", start),
+        None => hash.to_owned(),
+    }
+}
+
+fn format_time(time: u64) -> String {
+    let time: DateTime<Utc> = DateTime::from_timestamp(time as i64, 0).unwrap_or_default();
+    let local: DateTime<Local> = time.into();
+    local.format("%e %b %Y, %H:%M").to_string()
+}
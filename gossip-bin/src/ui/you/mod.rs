@@ -7,9 +7,16 @@ use gossip_lib::{Globals, GLOBALS};
 use nostr_types::{KeySecurity, PublicKeyHex};
 use zeroize::Zeroize;
 
+mod accounts;
+mod articles;
+mod badges;
 mod delegation;
+mod drafts;
+mod media;
 mod metadata;
 mod nostr_connect;
+pub(super) mod scheduled;
+mod wallet;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     if app.page == Page::YourKeys {
@@ -119,6 +126,20 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         delegation::update(app, ctx, _frame, ui);
     } else if app.page == Page::YourNostrConnect {
         nostr_connect::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourScheduledPosts {
+        scheduled::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourDrafts {
+        drafts::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourArticles {
+        articles::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourBadges {
+        badges::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourWallet {
+        wallet::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourMedia {
+        media::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourAccounts {
+        accounts::update(app, ctx, _frame, ui);
     }
 }
 
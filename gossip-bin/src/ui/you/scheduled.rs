@@ -0,0 +1,136 @@
+use super::GossipUi;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{scheduled_post, GLOBALS};
+use nostr_types::Unixtime;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Parse a local time typed by the user as YYYY-MM-DD HH:MM
+pub(in crate::ui) fn parse_schedule_time(input: &str) -> Option<Unixtime> {
+    let naive = NaiveDateTime::parse_from_str(input.trim(), TIME_FORMAT).ok()?;
+    let local = Local.from_local_datetime(&naive).earliest()?;
+    Some(Unixtime(local.timestamp()))
+}
+
+/// Format a time as local time, the way parse_schedule_time() reads it
+pub(in crate::ui) fn format_schedule_time(time: Unixtime) -> String {
+    let time: DateTime<Utc> = DateTime::from_timestamp(time.0, 0).unwrap_or_default();
+    let local: DateTime<Local> = time.into();
+    local.format(TIME_FORMAT).to_string()
+}
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal_wrapped(|ui| {
+        ui.heading("Scheduled Posts");
+    });
+
+    ui.add_space(10.0);
+    ui.label("Posts are published when their time comes, while gossip is running and online. With a remote signer they are signed at that time.");
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let posts = match scheduled_post::scheduled_posts() {
+        Ok(posts) => posts,
+        Err(e) => {
+            ui.label(format!("{}", e));
+            return;
+        }
+    };
+
+    if posts.is_empty() {
+        ui.label("You have no scheduled posts.");
+        return;
+    }
+
+    app.vert_scroll_area()
+        .id_salt("scheduled_posts")
+        .show(ui, |ui| {
+            for post in posts.iter() {
+                let editing =
+                    matches!(app.editing_scheduled_post, Some((key, _, _)) if key == post.key);
+
+                if editing {
+                    let mut save = false;
+                    let mut stop = false;
+                    if let Some((_, content, publish_at)) = &mut app.editing_scheduled_post {
+                        ui.horizontal(|ui| {
+                            ui.label("Publish at: ");
+                            ui.add(
+                                text_edit_line!(app, *publish_at)
+                                    .hint_text("YYYY-MM-DD HH:MM")
+                                    .desired_width(160.0),
+                            );
+                        });
+                        ui.add(text_edit_multiline!(app, *content).desired_width(f32::INFINITY));
+
+                        let valid = parse_schedule_time(publish_at).is_some();
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(valid, egui::Button::new("Save")).clicked() {
+                                save = true;
+                            }
+                            if ui.button("Discard changes").clicked() {
+                                stop = true;
+                            }
+                            if !valid {
+                                ui.label(
+                                    RichText::new("Use the format YYYY-MM-DD HH:MM")
+                                        .color(app.theme.warning_marker_text_color()),
+                                );
+                            }
+                        });
+                    }
+                    if save {
+                        if let Some((key, content, publish_at)) = app.editing_scheduled_post.take()
+                        {
+                            if let Some(publish_at) = parse_schedule_time(&publish_at) {
+                                let _ = GLOBALS.to_overlord.send(
+                                    ToOverlordMessage::EditScheduledPost {
+                                        key,
+                                        content,
+                                        publish_at,
+                                    },
+                                );
+                            }
+                        }
+                    } else if stop {
+                        app.editing_scheduled_post = None;
+                    }
+                } else {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(format_schedule_time(post.publish_at)).strong());
+                        if post.in_reply_to.is_some() {
+                            ui.label(RichText::new("reply").weak());
+                        }
+                        if post.event.is_none() {
+                            ui.label(RichText::new("signed when published").weak());
+                        }
+                    });
+                    ui.label(&post.content);
+                    ui.horizontal(|ui| {
+                        if ui.button("Edit").clicked() {
+                            app.editing_scheduled_post = Some((
+                                post.key,
+                                post.content.clone(),
+                                format_schedule_time(post.publish_at),
+                            ));
+                        }
+                        if ui.button("Cancel post").clicked() {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::CancelScheduledPost(post.key));
+                        }
+                    });
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+            }
+        });
+}
@@ -0,0 +1,173 @@
+use super::GossipUi;
+use chrono::{DateTime, Local, Utc};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::GLOBALS;
+use zeroize::Zeroize;
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal_wrapped(|ui| {
+        ui.heading("Wallet");
+    });
+
+    ui.add_space(10.0);
+    ui.label("Connect a lightning wallet with Nostr Wallet Connect (NIP-47), and your zaps will be paid right here instead of through a QR code.");
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    if !GLOBALS.wallet.is_connected() {
+        offer_connect(app, ui);
+        return;
+    }
+
+    app.vert_scroll_area()
+        .id_salt("your_wallet")
+        .show(ui, |ui| {
+            show_connection(ui);
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+
+            if !GLOBALS.wallet.is_ready() {
+                ui.label("Unlock your key to use your wallet.");
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                ui.heading("Balance");
+                if ui.button("Refresh").clicked() {
+                    let _ = GLOBALS.to_overlord.send(ToOverlordMessage::RefreshWallet);
+                }
+            });
+            ui.add_space(10.0);
+            match *GLOBALS.wallet.balance.read() {
+                Some(msats) => ui.label(RichText::new(format_sats(msats)).heading()),
+                None => ui.label("Asking your wallet..."),
+            };
+
+            ui.add_space(20.0);
+            ui.heading("Payments");
+            ui.add_space(10.0);
+
+            let transactions = GLOBALS.wallet.transactions.read().clone();
+            if transactions.is_empty() {
+                ui.label("No payments yet.");
+                return;
+            }
+
+            egui::Grid::new("wallet_transactions")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    for transaction in transactions.iter() {
+                        let time = transaction.settled_at.unwrap_or(transaction.created_at);
+                        ui.label(format_time(time));
+                        if transaction.is_incoming() {
+                            ui.label("received");
+                        } else {
+                            ui.label("sent");
+                        }
+                        let mut amount = format_sats(transaction.amount);
+                        if let Some(fees) = transaction.fees_paid.filter(|f| *f > 0) {
+                            amount.push_str(&format!(" (+{} fee)", format_sats(fees)));
+                        }
+                        ui.label(amount);
+                        ui.label(transaction.description.as_deref().unwrap_or_default());
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+fn show_connection(ui: &mut Ui) {
+    ui.heading("Connected");
+    ui.add_space(10.0);
+
+    egui::Grid::new("wallet_connection")
+        .num_columns(2)
+        .striped(false)
+        .show(ui, |ui| {
+            if let Some(lud16) = GLOBALS.wallet.lud16() {
+                ui.label("Lightning address");
+                ui.label(lud16);
+                ui.end_row();
+            }
+
+            if let Some(pubkey) = GLOBALS.wallet.wallet_pubkey() {
+                ui.label("Wallet service");
+                ui.label(pubkey.as_hex_string());
+                ui.end_row();
+            }
+
+            ui.label("Relays");
+            ui.vertical(|ui| {
+                for relay in GLOBALS.wallet.relays().iter() {
+                    ui.label(relay.as_str());
+                }
+            });
+            ui.end_row();
+        });
+
+    ui.add_space(10.0);
+    if ui.button("Disconnect").clicked() {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::DisconnectWallet);
+    }
+}
+
+fn offer_connect(app: &mut GossipUi, ui: &mut Ui) {
+    if !GLOBALS.identity.is_unlocked() {
+        ui.label("You need to unlock your key before you can connect a wallet.");
+        return;
+    }
+
+    ui.heading("Connect a Wallet");
+    ui.add_space(10.0);
+    ui.label("Your wallet gives you a connection string to paste here. It lets gossip spend from your wallet, so it is kept encrypted under your passphrase like your private key.");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Connection string");
+        ui.add(
+            text_edit_line!(app, app.wallet_uri)
+                .hint_text("nostr+walletconnect://")
+                .desired_width(f32::INFINITY)
+                .password(true),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("Your passphrase");
+        ui.add(text_edit_line!(app, app.password).password(true));
+    });
+    if ui.button("Connect").clicked() {
+        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::ConnectWallet {
+            uri: app.wallet_uri.clone(),
+            password: app.password.clone(),
+        });
+        app.wallet_uri.zeroize();
+        app.wallet_uri = "".to_owned();
+        app.password.zeroize();
+        app.password = "".to_owned();
+    }
+}
+
+fn format_sats(msats: u64) -> String {
+    let sats = msats / 1000;
+    if sats == 1 {
+        "1 sat".to_owned()
+    } else {
+        format!("{} sats", sats)
+    }
+}
+
+fn format_time(time: i64) -> String {
+    let time: DateTime<Utc> = DateTime::from_timestamp(time, 0).unwrap_or_default();
+    let local: DateTime<Local> = time.into();
+    local.format("%e %b %Y, %H:%M").to_string()
+}
@@ -44,11 +44,15 @@ pub struct UnsavedSettings {
     pub automatically_fetch_metadata: bool,
     pub relay_connection_requires_approval: bool,
     pub relay_auth_requires_approval: bool,
+    pub proxy: String,
+    pub relay_proxy_overrides: String,
 
     // Relay settings
     pub num_relays_per_person: u8,
     pub max_relays: u8,
     pub num_relays_for_counting: u8,
+    pub count_replies_and_reactions: bool,
+    pub negentropy_sync: bool,
 
     // Feed Settings
     pub load_more_count: u64,
@@ -59,6 +63,15 @@ pub struct UnsavedSettings {
     pub show_mentions: bool,
     pub enable_picture_events: bool,
     pub enable_comments: bool,
+    pub enable_calendar: bool,
+    pub enable_public_channels: bool,
+    pub enable_relay_groups: bool,
+    pub enable_git_repositories: bool,
+    pub enable_moderation: bool,
+    pub enable_communities: bool,
+    pub enable_dvms: bool,
+    pub enable_timestamps: bool,
+    pub enable_wallet: bool,
     pub direct_messages: bool,
     pub future_allowance_secs: u64,
 
@@ -75,6 +88,7 @@ pub struct UnsavedSettings {
     pub apply_spam_filter_on_threads: bool,
     pub apply_spam_filter_on_inbox: bool,
     pub apply_spam_filter_on_global: bool,
+    pub apply_spam_filter_on_dms: bool,
 
     // Posting Settings
     pub pow: u8,
@@ -131,8 +145,11 @@ pub struct UnsavedSettings {
     pub cache_prune_period_days: u64,
 
     pub blossom_servers: String,
+    pub nip96_servers: String,
 
     pub undo_send_seconds: u64,
+    pub ots_calendar: String,
+    pub ots_header_source: String,
 }
 
 impl Default for UnsavedSettings {
@@ -149,15 +166,28 @@ impl Default for UnsavedSettings {
                 relay_connection_requires_approval
             ),
             relay_auth_requires_approval: default_setting!(relay_auth_requires_approval),
+            proxy: default_setting!(proxy),
+            relay_proxy_overrides: default_setting!(relay_proxy_overrides),
             num_relays_per_person: default_setting!(num_relays_per_person),
             max_relays: default_setting!(max_relays),
             num_relays_for_counting: default_setting!(num_relays_for_counting),
+            count_replies_and_reactions: default_setting!(count_replies_and_reactions),
+            negentropy_sync: default_setting!(negentropy_sync),
             load_more_count: default_setting!(load_more_count),
             reposts: default_setting!(reposts),
             show_long_form: default_setting!(show_long_form),
             show_mentions: default_setting!(show_mentions),
             enable_picture_events: default_setting!(enable_picture_events),
             enable_comments: default_setting!(enable_comments),
+            enable_calendar: default_setting!(enable_calendar),
+            enable_public_channels: default_setting!(enable_public_channels),
+            enable_relay_groups: default_setting!(enable_relay_groups),
+            enable_git_repositories: default_setting!(enable_git_repositories),
+            enable_moderation: default_setting!(enable_moderation),
+            enable_communities: default_setting!(enable_communities),
+            enable_dvms: default_setting!(enable_dvms),
+            enable_timestamps: default_setting!(enable_timestamps),
+            enable_wallet: default_setting!(enable_wallet),
             direct_messages: default_setting!(direct_messages),
             future_allowance_secs: default_setting!(future_allowance_secs),
             hide_mutes_entirely: default_setting!(hide_mutes_entirely),
@@ -176,6 +206,7 @@ impl Default for UnsavedSettings {
             apply_spam_filter_on_threads: default_setting!(apply_spam_filter_on_threads),
             apply_spam_filter_on_inbox: default_setting!(apply_spam_filter_on_inbox),
             apply_spam_filter_on_global: default_setting!(apply_spam_filter_on_global),
+            apply_spam_filter_on_dms: default_setting!(apply_spam_filter_on_dms),
             pow: default_setting!(pow),
             set_client_tag: default_setting!(set_client_tag),
             set_user_agent: default_setting!(set_user_agent),
@@ -229,7 +260,10 @@ impl Default for UnsavedSettings {
             prune_period_days: default_setting!(prune_period_days),
             cache_prune_period_days: default_setting!(prune_period_days),
             blossom_servers: default_setting!(blossom_servers),
+            nip96_servers: default_setting!(nip96_servers),
             undo_send_seconds: default_setting!(undo_send_seconds),
+            ots_calendar: default_setting!(ots_calendar),
+            ots_header_source: default_setting!(ots_header_source),
         }
     }
 }
@@ -246,15 +280,28 @@ impl UnsavedSettings {
             automatically_fetch_metadata: load_setting!(automatically_fetch_metadata),
             relay_connection_requires_approval: load_setting!(relay_connection_requires_approval),
             relay_auth_requires_approval: load_setting!(relay_auth_requires_approval),
+            proxy: load_setting!(proxy),
+            relay_proxy_overrides: load_setting!(relay_proxy_overrides),
             num_relays_per_person: load_setting!(num_relays_per_person),
             max_relays: load_setting!(max_relays),
             num_relays_for_counting: load_setting!(num_relays_for_counting),
+            count_replies_and_reactions: load_setting!(count_replies_and_reactions),
+            negentropy_sync: load_setting!(negentropy_sync),
             load_more_count: load_setting!(load_more_count),
             reposts: load_setting!(reposts),
             show_long_form: load_setting!(show_long_form),
             show_mentions: load_setting!(show_mentions),
             enable_picture_events: load_setting!(enable_picture_events),
             enable_comments: load_setting!(enable_comments),
+            enable_calendar: load_setting!(enable_calendar),
+            enable_public_channels: load_setting!(enable_public_channels),
+            enable_relay_groups: load_setting!(enable_relay_groups),
+            enable_git_repositories: load_setting!(enable_git_repositories),
+            enable_moderation: load_setting!(enable_moderation),
+            enable_communities: load_setting!(enable_communities),
+            enable_dvms: load_setting!(enable_dvms),
+            enable_timestamps: load_setting!(enable_timestamps),
+            enable_wallet: load_setting!(enable_wallet),
             direct_messages: load_setting!(direct_messages),
             future_allowance_secs: load_setting!(future_allowance_secs),
             hide_mutes_entirely: load_setting!(hide_mutes_entirely),
@@ -271,6 +318,7 @@ impl UnsavedSettings {
             apply_spam_filter_on_threads: load_setting!(apply_spam_filter_on_threads),
             apply_spam_filter_on_inbox: load_setting!(apply_spam_filter_on_inbox),
             apply_spam_filter_on_global: load_setting!(apply_spam_filter_on_global),
+            apply_spam_filter_on_dms: load_setting!(apply_spam_filter_on_dms),
             pow: load_setting!(pow),
             set_client_tag: load_setting!(set_client_tag),
             set_user_agent: load_setting!(set_user_agent),
@@ -322,7 +370,10 @@ impl UnsavedSettings {
             prune_period_days: load_setting!(prune_period_days),
             cache_prune_period_days: load_setting!(cache_prune_period_days),
             blossom_servers: load_setting!(blossom_servers),
+            nip96_servers: load_setting!(nip96_servers),
             undo_send_seconds: load_setting!(undo_send_seconds),
+            ots_calendar: load_setting!(ots_calendar),
+            ots_header_source: load_setting!(ots_header_source),
         }
     }
 
@@ -337,15 +388,28 @@ impl UnsavedSettings {
         save_setting!(automatically_fetch_metadata, self, txn);
         save_setting!(relay_connection_requires_approval, self, txn);
         save_setting!(relay_auth_requires_approval, self, txn);
+        save_setting!(proxy, self, txn);
+        save_setting!(relay_proxy_overrides, self, txn);
         save_setting!(num_relays_per_person, self, txn);
         save_setting!(max_relays, self, txn);
         save_setting!(num_relays_for_counting, self, txn);
+        save_setting!(count_replies_and_reactions, self, txn);
+        save_setting!(negentropy_sync, self, txn);
         save_setting!(load_more_count, self, txn);
         save_setting!(reposts, self, txn);
         save_setting!(show_long_form, self, txn);
         save_setting!(show_mentions, self, txn);
         save_setting!(enable_picture_events, self, txn);
         save_setting!(enable_comments, self, txn);
+        save_setting!(enable_calendar, self, txn);
+        save_setting!(enable_public_channels, self, txn);
+        save_setting!(enable_relay_groups, self, txn);
+        save_setting!(enable_git_repositories, self, txn);
+        save_setting!(enable_moderation, self, txn);
+        save_setting!(enable_communities, self, txn);
+        save_setting!(enable_dvms, self, txn);
+        save_setting!(enable_timestamps, self, txn);
+        save_setting!(enable_wallet, self, txn);
         save_setting!(direct_messages, self, txn);
         save_setting!(future_allowance_secs, self, txn);
         save_setting!(hide_mutes_entirely, self, txn);
@@ -360,6 +424,7 @@ impl UnsavedSettings {
         save_setting!(apply_spam_filter_on_threads, self, txn);
         save_setting!(apply_spam_filter_on_inbox, self, txn);
         save_setting!(apply_spam_filter_on_global, self, txn);
+        save_setting!(apply_spam_filter_on_dms, self, txn);
         save_setting!(pow, self, txn);
         save_setting!(set_client_tag, self, txn);
         save_setting!(set_user_agent, self, txn);
@@ -403,7 +468,10 @@ impl UnsavedSettings {
         save_setting!(prune_period_days, self, txn);
         save_setting!(cache_prune_period_days, self, txn);
         save_setting!(blossom_servers, self, txn);
+        save_setting!(nip96_servers, self, txn);
         save_setting!(undo_send_seconds, self, txn);
+        save_setting!(ots_calendar, self, txn);
+        save_setting!(ots_header_source, self, txn);
         txn.commit()?;
 
         let runstate = *GLOBALS.read_runstate.borrow();
@@ -1,4 +1,4 @@
-use gossip_lib::{GLOBALS, Person, PersonList, PersonTable, Private, Table};
+use gossip_lib::{GLOBALS, Moderation, Person, PersonList, PersonTable, Private, Table};
 use nostr_types::{
     ContentSegment, Event, EventDelegation, EventKind, EventReference, Id, MilliSatoshi, NAddr,
     NostrBech32, ParsedTag, PublicKey, RelayUrl, ShatteredContent, Unixtime,
@@ -165,6 +165,9 @@ pub struct NoteData {
 
     /// i-tag
     pub itag: Option<String>,
+
+    /// What our moderators said about this note
+    pub moderation: Option<Moderation>,
 }
 
 impl NoteData {
@@ -396,6 +399,8 @@ impl NoteData {
             }
         }
 
+        let moderation = gossip_lib::moderation::moderation(event.id).unwrap_or_default();
+
         NoteData {
             event,
             delegation,
@@ -417,6 +422,7 @@ impl NoteData {
             bookmarked,
             volatile,
             itag,
+            moderation,
         }
     }
 
@@ -0,0 +1,199 @@
+use crate::bookmarks::BookmarkList;
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::misc::Private;
+use crate::people::{PersonList, PersonListMetadata};
+use crate::relay::Relay;
+use crate::storage::{AccountsTable, Table};
+use nostr_types::{EventKind, Identity, PublicKey, Unixtime};
+use std::collections::HashMap;
+
+pub type Account = crate::storage::types::Account1;
+
+/// Relay usage bits that belong to an account. The rest are shared.
+const ACCOUNT_RELAY_BITS: u64 =
+    Relay::READ | Relay::WRITE | Relay::INBOX | Relay::OUTBOX | Relay::DM;
+
+/// All accounts, most recently used first
+pub fn accounts() -> Result<Vec<Account>, Error> {
+    let mut accounts = AccountsTable::filter_records(|_| true)?;
+    accounts.sort_by_key(|a| std::cmp::Reverse(a.last_used.0));
+    Ok(accounts)
+}
+
+/// What kind of identity an account has, for display
+pub fn identity_kind(account: &Account) -> &'static str {
+    match serde_json::from_str::<Identity>(&account.identity) {
+        Ok(Identity::Private(_)) => "Private key",
+        Ok(Identity::Remote(_)) => "Remote signer",
+        Ok(Identity::Public(_)) => "Public key only",
+        _ => "Unknown",
+    }
+}
+
+/// Remember the active identity as an account, so it can be switched back to later
+pub(crate) fn remember_active_account() -> Result<(), Error> {
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(()),
+    };
+    let identity = serde_json::to_string(&*GLOBALS.identity.inner.read_arc())?;
+    AccountsTable::modify(
+        pubkey,
+        |account| {
+            account.identity = identity.clone();
+            account.last_used = Unixtime::now();
+        },
+        None,
+    )?;
+    Ok(())
+}
+
+/// Forget an account that is not the active one
+pub(crate) fn remove_account(pubkey: PublicKey) -> Result<(), Error> {
+    if GLOBALS.identity.public_key() == Some(pubkey) {
+        return Ok(());
+    }
+    AccountsTable::delete_record(pubkey, None)
+}
+
+/// Switch to another account, or to no account at all (to add a new one).
+///
+/// The person lists and relay usage of the active account are stored away in its
+/// account record, and those of the new account are put in their place. Events are
+/// shared by all accounts. Wallet connections are stored per account already.
+pub(crate) async fn switch_account(to: Option<PublicKey>) -> Result<(), Error> {
+    let from = GLOBALS.identity.public_key();
+    if from == to {
+        return Ok(());
+    }
+
+    let target = match to {
+        Some(pubkey) => match AccountsTable::read_record(pubkey, None)? {
+            Some(account) => Some(account),
+            None => return Err(ErrorKind::General("No such account".to_owned()).into()),
+        },
+        None => None,
+    };
+
+    // Everything we need from the active account is read before we start writing
+    let current_lists = GLOBALS.db().get_all_person_list_metadata()?;
+    let stored_away = match from {
+        Some(pubkey) => {
+            let mut account = AccountsTable::read_or_create_record(pubkey, None)?;
+            account.identity = serde_json::to_string(&*GLOBALS.identity.inner.read_arc())?;
+            account.person_lists = person_list_memberships(&current_lists)?;
+            account.person_list_metadata = current_lists.clone();
+            account.relay_usage = GLOBALS
+                .db()
+                .filter_relays(|r| r.get_usage_bits() & ACCOUNT_RELAY_BITS != 0)?
+                .iter()
+                .map(|r| (r.url.clone(), r.get_usage_bits() & ACCOUNT_RELAY_BITS))
+                .collect();
+            account.last_used = Unixtime::now();
+            Some(account)
+        }
+        None => None,
+    };
+
+    // The swap happens in one transaction, so a failure part way through can't
+    // leave one account's lists mixed with another's
+    let mut txn = GLOBALS.db().get_write_txn()?;
+
+    // Store away the state of the active account
+    if let Some(mut account) = stored_away {
+        AccountsTable::write_record(&mut account, Some(&mut txn))?;
+    }
+
+    // Clear it out. The well known lists stay, but start over.
+    for (list, _) in current_lists {
+        GLOBALS.db().clear_person_list(list, Some(&mut txn))?;
+        if matches!(list, PersonList::Custom(_)) {
+            GLOBALS.db().deallocate_person_list(list, Some(&mut txn))?;
+        } else {
+            GLOBALS.db().set_person_list_metadata(
+                list,
+                &PersonListMetadata::default(),
+                Some(&mut txn),
+            )?;
+        }
+    }
+    GLOBALS
+        .db()
+        .modify_all_relays(|r| r.clear_usage_bits(ACCOUNT_RELAY_BITS), Some(&mut txn))?;
+
+    // Bring in the state of the new account
+    let identity = match target {
+        Some(mut account) => {
+            for (list, metadata) in account.person_list_metadata.iter() {
+                GLOBALS
+                    .db()
+                    .set_person_list_metadata(*list, metadata, Some(&mut txn))?;
+            }
+            for (pubkey, lists) in account.person_lists.iter() {
+                let map: HashMap<PersonList, Private> = lists
+                    .iter()
+                    .map(|(list, private)| (*list, Private(*private)))
+                    .collect();
+                GLOBALS
+                    .db()
+                    .write_person_lists(pubkey, map, Some(&mut txn))?;
+            }
+            for (url, bits) in account.relay_usage.iter() {
+                GLOBALS.db().write_relay_if_missing(url, Some(&mut txn))?;
+                GLOBALS
+                    .db()
+                    .modify_relay(url, |r| r.set_usage_bits(*bits), Some(&mut txn))?;
+            }
+            account.last_used = Unixtime::now();
+            AccountsTable::write_record(&mut account, Some(&mut txn))?;
+            serde_json::from_str(&account.identity)?
+        }
+        None => Identity::None,
+    };
+
+    txn.commit()?;
+
+    *GLOBALS.bookmarks.write_arc() = BookmarkList::empty();
+    GLOBALS.identity.set_identity(identity)?;
+
+    // Each account has its own wallet connection, which is unlocked with its key
+    GLOBALS.wallet.load()?;
+
+    // Our bookmarks, from our bookmark list event
+    if let Some(pubkey) = to {
+        if let Some(event) =
+            GLOBALS
+                .db()
+                .get_replaceable_event(EventKind::BookmarkList, pubkey, "")?
+        {
+            // The private part is added when the key is unlocked
+            match BookmarkList::from_event(&event).await {
+                Ok(bookmarks) => *GLOBALS.bookmarks.write_arc() = bookmarks,
+                Err(e) => tracing::warn!("{}", e),
+            }
+        }
+    }
+    GLOBALS.recompute_current_bookmarks.notify_one();
+
+    // Friends of friends are computed from who we follow
+    GLOBALS.db().rebuild_fof(None)?;
+
+    Ok(())
+}
+
+// Who is in each of these lists, by person
+fn person_list_memberships(
+    lists: &[(PersonList, PersonListMetadata)],
+) -> Result<Vec<(PublicKey, Vec<(PersonList, bool)>)>, Error> {
+    let mut memberships: HashMap<PublicKey, Vec<(PersonList, bool)>> = HashMap::new();
+    for (list, _) in lists {
+        for (pubkey, private) in GLOBALS.db().get_people_in_list(*list)? {
+            memberships
+                .entry(pubkey)
+                .or_default()
+                .push((*list, private.0));
+        }
+    }
+    Ok(memberships.into_iter().collect())
+}
@@ -0,0 +1,151 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, Filter, Id, NAddr, PublicKey, UncheckedUrl, Unixtime};
+
+/// A NIP-23 long-form article, from a kind 30023 event or a kind 30024 draft
+#[derive(Debug, Clone, PartialEq)]
+pub struct Article {
+    /// The id of the event this version of the article came from
+    pub id: Id,
+
+    /// The author of the article
+    pub author: PublicKey,
+
+    /// LongFormContent, or DraftLongFormContent
+    pub kind: EventKind,
+
+    /// The identifier of the article, which stays the same across edits
+    pub d: String,
+
+    /// The title of the article
+    pub title: String,
+
+    /// A summary of the article
+    pub summary: String,
+
+    /// A header image for the article
+    pub image: Option<String>,
+
+    /// When the article was first published
+    pub published_at: Option<Unixtime>,
+
+    /// Hashtags of the article
+    pub hashtags: Vec<String>,
+
+    /// The article in markdown
+    pub content: String,
+
+    /// When this version of the article was created
+    pub created_at: Unixtime,
+}
+
+impl Article {
+    /// Parse an article from a long-form event. Returns None for other kinds of events.
+    pub fn from_event(event: &Event) -> Option<Article> {
+        if event.kind != EventKind::LongFormContent && event.kind != EventKind::DraftLongFormContent
+        {
+            return None;
+        }
+
+        let mut article = Article {
+            id: event.id,
+            author: event.pubkey,
+            kind: event.kind,
+            d: "".to_owned(),
+            title: "".to_owned(),
+            summary: "".to_owned(),
+            image: None,
+            published_at: None,
+            hashtags: vec![],
+            content: event.content.clone(),
+            created_at: event.created_at,
+        };
+
+        for tag in &event.tags {
+            match tag.tagname() {
+                "d" => article.d = tag.value().to_owned(),
+                "title" => article.title = tag.value().to_owned(),
+                "summary" => article.summary = tag.value().to_owned(),
+                "image" if !tag.value().is_empty() => article.image = Some(tag.value().to_owned()),
+                "published_at" => {
+                    article.published_at = tag.value().parse::<i64>().ok().map(Unixtime)
+                }
+                "t" if !tag.value().is_empty() => article.hashtags.push(tag.value().to_owned()),
+                _ => {}
+            }
+        }
+
+        Some(article)
+    }
+
+    /// If this is a draft
+    pub fn is_draft(&self) -> bool {
+        self.kind == EventKind::DraftLongFormContent
+    }
+
+    /// The address of the article
+    pub fn naddr(&self, relays: Vec<UncheckedUrl>) -> NAddr {
+        NAddr {
+            d: self.d.clone(),
+            relays,
+            kind: self.kind,
+            author: self.author,
+        }
+    }
+
+    /// The parts of the article that can be edited
+    pub fn fields(&self) -> ArticleFields {
+        ArticleFields {
+            d: self.d.clone(),
+            title: self.title.clone(),
+            summary: self.summary.clone(),
+            image: self.image.clone().unwrap_or_default(),
+            hashtags: self.hashtags.clone(),
+            content: self.content.clone(),
+        }
+    }
+}
+
+/// The parts of an article that the user writes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArticleFields {
+    /// The identifier of the article. Publishing again with the same identifier
+    /// replaces the earlier version.
+    pub d: String,
+
+    /// The title of the article
+    pub title: String,
+
+    /// A summary of the article
+    pub summary: String,
+
+    /// A header image url, or empty
+    pub image: String,
+
+    /// Hashtags, without the '#'
+    pub hashtags: Vec<String>,
+
+    /// The article in markdown
+    pub content: String,
+}
+
+/// The latest version of the article at an address, if we have it
+pub fn article(naddr: &NAddr) -> Result<Option<Article>, Error> {
+    Ok(GLOBALS
+        .db()
+        .get_replaceable_event(naddr.kind, naddr.author, &naddr.d)?
+        .and_then(|e| Article::from_event(&e)))
+}
+
+/// Articles and drafts written by a person, latest first
+pub fn articles_by(author: PublicKey) -> Result<Vec<Article>, Error> {
+    let mut filter = Filter::new();
+    filter.add_author(author);
+    filter.kinds = vec![EventKind::LongFormContent, EventKind::DraftLongFormContent];
+    Ok(GLOBALS
+        .db()
+        .find_events_by_filter(&filter, |_| true)?
+        .iter()
+        .filter_map(Article::from_event)
+        .collect())
+}
@@ -0,0 +1,455 @@
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::misc::tagged_address;
+use crate::relationship::RelationshipByAddr;
+use crate::storage::{HiddenBadgesTable, Table};
+use nostr_types::{
+    Event, EventKind, Filter, Id, NAddr, ParsedTag, PublicKey, Tag, UncheckedUrl, Unixtime,
+};
+use std::collections::HashSet;
+
+pub type HiddenBadge = crate::storage::types::HiddenBadge1;
+
+/// The identifier of the profile badges event (kind 30008)
+pub const PROFILE_BADGES_D: &str = "profile_badges";
+
+/// A badge definition (kind 30009)
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadgeDefinition {
+    /// Who defined the badge. Only they can award it.
+    pub author: PublicKey,
+
+    /// The identifier of the badge
+    pub d: String,
+
+    /// A human readable name
+    pub name: String,
+
+    /// What the badge is for
+    pub description: String,
+
+    /// The badge image
+    pub image: String,
+
+    /// A smaller version of the badge image
+    pub thumb: String,
+
+    /// When the definition was made
+    pub created_at: Unixtime,
+}
+
+impl BadgeDefinition {
+    /// Parse a badge definition. Returns None for other kinds of events.
+    pub fn from_event(event: &Event) -> Option<BadgeDefinition> {
+        if event.kind != EventKind::BadgeDefinition {
+            return None;
+        }
+
+        let mut badge = BadgeDefinition {
+            author: event.pubkey,
+            d: "".to_owned(),
+            name: "".to_owned(),
+            description: "".to_owned(),
+            image: "".to_owned(),
+            thumb: "".to_owned(),
+            created_at: event.created_at,
+        };
+
+        for tag in &event.tags {
+            match tag.tagname() {
+                "d" => badge.d = tag.value().to_owned(),
+                "name" => badge.name = tag.value().to_owned(),
+                "description" => badge.description = tag.value().to_owned(),
+                "image" => badge.image = tag.value().to_owned(),
+                // There may be thumbnails of several sizes. We take the first.
+                "thumb" if badge.thumb.is_empty() => badge.thumb = tag.value().to_owned(),
+                _ => {}
+            }
+        }
+
+        Some(badge)
+    }
+
+    /// The address of the badge definition
+    pub fn address(&self) -> String {
+        badge_address(self.author, &self.d)
+    }
+
+    /// The name of the badge for display
+    pub fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            &self.d
+        } else {
+            &self.name
+        }
+    }
+
+    /// The image to show the badge small with: the thumbnail, if there is one
+    pub fn small_image(&self) -> &str {
+        if self.thumb.is_empty() {
+            &self.image
+        } else {
+            &self.thumb
+        }
+    }
+
+    /// The parts of the definition that can be edited
+    pub fn fields(&self) -> BadgeFields {
+        BadgeFields {
+            d: self.d.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            image: self.image.clone(),
+            thumb: self.thumb.clone(),
+        }
+    }
+}
+
+/// The parts of a badge definition that the issuer writes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BadgeFields {
+    /// The identifier of the badge. Defining a badge again with the same identifier
+    /// replaces the earlier definition.
+    pub d: String,
+
+    /// A human readable name
+    pub name: String,
+
+    /// What the badge is for
+    pub description: String,
+
+    /// A badge image url, or empty
+    pub image: String,
+
+    /// A thumbnail image url, or empty
+    pub thumb: String,
+}
+
+/// A badge that was awarded to someone
+#[derive(Debug, Clone, PartialEq)]
+pub struct Badge {
+    /// The badge award event (kind 8)
+    pub award: Id,
+
+    /// Who awarded the badge, which is who defined it
+    pub issuer: PublicKey,
+
+    /// The identifier of the badge definition
+    pub d: String,
+
+    /// When it was awarded
+    pub awarded_at: Unixtime,
+
+    /// The badge definition, if we have it
+    pub definition: Option<BadgeDefinition>,
+}
+
+impl Badge {
+    /// The badge of an award. Returns None for other kinds of events, or
+    /// if the award is for a badge somebody else defined.
+    pub fn from_award(event: &Event) -> Option<Badge> {
+        if event.kind != EventKind::BadgeAward {
+            return None;
+        }
+
+        let (issuer, d) = event
+            .tags
+            .iter()
+            .filter(|t| t.tagname() == "a")
+            .filter_map(|t| tagged_address(t, EventKind::BadgeDefinition))
+            .map(|address| (address.author, address.d))
+            .find(|(issuer, _)| *issuer == event.pubkey)?;
+
+        let definition = definition(issuer, &d).ok().flatten();
+
+        Some(Badge {
+            award: event.id,
+            issuer,
+            d,
+            awarded_at: event.created_at,
+            definition,
+        })
+    }
+
+    /// The address of the badge definition
+    pub fn address(&self) -> String {
+        badge_address(self.issuer, &self.d)
+    }
+
+    /// The name of the badge for display
+    pub fn name(&self) -> String {
+        match &self.definition {
+            Some(definition) => definition.display_name().to_owned(),
+            None => self.d.clone(),
+        }
+    }
+}
+
+/// The address of a badge definition, as used in 'a' tags
+pub fn badge_address(author: PublicKey, d: &str) -> String {
+    format!("30009:{}:{}", author.as_hex_string(), d)
+}
+
+/// The latest definition of a badge, if we have it
+pub fn definition(author: PublicKey, d: &str) -> Result<Option<BadgeDefinition>, Error> {
+    Ok(GLOBALS
+        .db()
+        .get_replaceable_event(EventKind::BadgeDefinition, author, d)?
+        .and_then(|e| BadgeDefinition::from_event(&e)))
+}
+
+/// The badges a person has defined, by name
+pub fn definitions_by(author: PublicKey) -> Result<Vec<BadgeDefinition>, Error> {
+    let mut filter = Filter::new();
+    filter.add_author(author);
+    filter.add_event_kind(EventKind::BadgeDefinition);
+
+    let mut definitions: Vec<BadgeDefinition> = GLOBALS
+        .db()
+        .find_events_by_filter(&filter, |_| true)?
+        .iter()
+        .filter_map(BadgeDefinition::from_event)
+        .filter(|b| !b.d.is_empty())
+        .collect();
+    definitions.sort_by_key(|b| b.display_name().to_lowercase());
+    Ok(definitions)
+}
+
+/// The badges that have been awarded to a person, newest first
+pub fn awards_to(pubkey: PublicKey) -> Result<Vec<Badge>, Error> {
+    let mut filter = Filter::new();
+    filter.add_event_kind(EventKind::BadgeAward);
+    filter.add_tag_value('p', pubkey.as_hex_string());
+
+    let mut badges: Vec<Badge> = GLOBALS
+        .db()
+        .find_events_by_filter(&filter, |_| true)?
+        .iter()
+        .filter_map(Badge::from_award)
+        .collect();
+    badges.sort_by(|a, b| b.awarded_at.cmp(&a.awarded_at));
+    Ok(badges)
+}
+
+/// The people a badge has been awarded to
+pub fn awarded_to(author: PublicKey, d: &str) -> Result<HashSet<PublicKey>, Error> {
+    let addr = NAddr {
+        d: d.to_owned(),
+        relays: vec![],
+        kind: EventKind::BadgeDefinition,
+        author,
+    };
+
+    let mut people: HashSet<PublicKey> = HashSet::new();
+    for (id, rel) in GLOBALS.db().find_relationships_by_addr(&addr)? {
+        if rel != RelationshipByAddr::AwardsBadge {
+            continue;
+        }
+        // Only the issuer can award their badge
+        if let Some(award) = GLOBALS.db().read_event(id)? {
+            if award.pubkey == author {
+                for (pubkey, _, _) in award.people() {
+                    people.insert(pubkey);
+                }
+            }
+        }
+    }
+    Ok(people)
+}
+
+/// The badges a person has chosen to show on their profile, in their order.
+///
+/// Only badges that were really awarded to them by the badge's issuer are included.
+pub fn profile_badges(pubkey: PublicKey) -> Result<Vec<Badge>, Error> {
+    let event = match GLOBALS.db().get_replaceable_event(
+        EventKind::ProfileBadges,
+        pubkey,
+        PROFILE_BADGES_D,
+    )? {
+        Some(event) => event,
+        None => return Ok(vec![]),
+    };
+
+    let mut badges: Vec<Badge> = Vec::new();
+    for (a_tag, e_tag) in profile_badge_pairs(&event) {
+        let Some(address) = tagged_address(&a_tag, EventKind::BadgeDefinition) else {
+            continue;
+        };
+        let (issuer, d) = (address.author, address.d);
+        let Ok(ParsedTag::Event { id, .. }) = e_tag.parse() else {
+            continue;
+        };
+        let Some(award) = GLOBALS.db().read_event(id)? else {
+            continue;
+        };
+        let awarded = award
+            .tags
+            .iter()
+            .any(|t| t.tagname() == "p" && t.value() == pubkey.as_hex_string());
+        match Badge::from_award(&award) {
+            Some(badge) if awarded && badge.issuer == issuer && badge.d == d => {
+                if !badges.iter().any(|b| b.award == badge.award) {
+                    badges.push(badge);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(badges)
+}
+
+/// If the user shows this badge award on their profile
+pub fn is_accepted(award: Id) -> bool {
+    match GLOBALS.identity.public_key() {
+        Some(pubkey) => {
+            matches!(profile_badges(pubkey), Ok(b) if b.iter().any(|b| b.award == award))
+        }
+        None => false,
+    }
+}
+
+/// If the user has hidden this badge award
+pub fn is_hidden(award: Id) -> bool {
+    matches!(HiddenBadgesTable::read_record(award, None), Ok(Some(_)))
+}
+
+/// Hide a badge award, so that it isn't offered to the user again
+pub(crate) fn hide(award: Id) -> Result<(), Error> {
+    HiddenBadgesTable::write_record(
+        &mut HiddenBadge {
+            award,
+            hidden_at: Unixtime::now(),
+        },
+        None,
+    )
+}
+
+// The ('a', 'e') tag pairs of a profile badges event
+fn profile_badge_pairs(event: &Event) -> Vec<(Tag, Tag)> {
+    let mut pairs: Vec<(Tag, Tag)> = Vec::new();
+    let mut a_tag: Option<&Tag> = None;
+    for tag in &event.tags {
+        match tag.tagname() {
+            "a" => a_tag = Some(tag),
+            "e" => {
+                if let Some(a) = a_tag.take() {
+                    pairs.push((a.clone(), tag.clone()));
+                }
+            }
+            _ => a_tag = None,
+        }
+    }
+    pairs
+}
+
+/// The tags of the user's profile badges event after adding and/or removing
+/// a badge award. Other badges stay where they were; an added badge goes last.
+pub(crate) fn profile_badges_tags(
+    add: Option<&Event>,
+    remove: Option<Id>,
+) -> Result<Vec<Tag>, Error> {
+    let mut pairs: Vec<(Tag, Tag)> = match GLOBALS.identity.public_key() {
+        Some(pubkey) => GLOBALS
+            .db()
+            .get_replaceable_event(EventKind::ProfileBadges, pubkey, PROFILE_BADGES_D)?
+            .map(|e| profile_badge_pairs(&e))
+            .unwrap_or_default(),
+        None => vec![],
+    };
+
+    let award_of = |e_tag: &Tag| match e_tag.parse() {
+        Ok(ParsedTag::Event { id, .. }) => Some(id),
+        _ => None,
+    };
+
+    if let Some(remove) = remove {
+        pairs.retain(|(_, e)| award_of(e) != Some(remove));
+    }
+
+    if let Some(award) = add {
+        if let Some(badge) = Badge::from_award(award) {
+            if !pairs.iter().any(|(_, e)| award_of(e) == Some(award.id)) {
+                let mut e_tag = vec!["e".to_owned(), award.id.as_hex_string()];
+                if let Some((url, _)) = GLOBALS.db().get_event_seen_on_relay(award.id)?.first() {
+                    e_tag.push(url.as_str().to_owned());
+                }
+                let e_tag: Vec<&str> = e_tag.iter().map(|s| s.as_str()).collect();
+                pairs.push((Tag::new(&["a", &badge.address()]), Tag::new(&e_tag)));
+            }
+        }
+    }
+
+    let mut tags: Vec<Tag> = vec![Tag::new(&["d", PROFILE_BADGES_D])];
+    for (a, e) in pairs.drain(..) {
+        tags.push(a);
+        tags.push(e);
+    }
+    Ok(tags)
+}
+
+/// The tags of a badge definition
+pub(crate) fn definition_tags(fields: &BadgeFields) -> Vec<Tag> {
+    let mut tags: Vec<Tag> = vec![Tag::new(&["d", fields.d.trim()])];
+    if !fields.name.trim().is_empty() {
+        tags.push(Tag::new(&["name", fields.name.trim()]));
+    }
+    if !fields.description.trim().is_empty() {
+        tags.push(Tag::new(&["description", fields.description.trim()]));
+    }
+    if !fields.image.trim().is_empty() {
+        tags.push(Tag::new(&["image", fields.image.trim()]));
+    }
+    if !fields.thumb.trim().is_empty() {
+        tags.push(Tag::new(&["thumb", fields.thumb.trim()]));
+    }
+    tags
+}
+
+/// Fetch the definitions of the badges in profile badges, or in awards to the user,
+/// that we don't have yet
+pub(crate) fn fetch_missing_definitions(event: &Event) -> Result<(), Error> {
+    if event.kind == EventKind::BadgeAward {
+        let to_us = match GLOBALS.identity.public_key() {
+            Some(pubkey) => event
+                .tags
+                .iter()
+                .any(|t| t.tagname() == "p" && t.value() == pubkey.as_hex_string()),
+            None => false,
+        };
+        if !to_us {
+            return Ok(());
+        }
+    }
+
+    let mut fetched: Vec<(PublicKey, String)> = Vec::new();
+    for tag in &event.tags {
+        let Ok(ParsedTag::Address { address, .. }) = tag.parse() else {
+            continue;
+        };
+        if address.kind != EventKind::BadgeDefinition
+            || fetched.contains(&(address.author, address.d.clone()))
+            || definition(address.author, &address.d)?.is_some()
+        {
+            continue;
+        }
+        fetched.push((address.author, address.d.clone()));
+
+        // The issuer publishes the definition to their outboxes
+        let mut relays: Vec<UncheckedUrl> = address.relays.clone();
+        for url in crate::relay::get_some_pubkey_outboxes(address.author)?
+            .iter()
+            .map(|r| r.to_unchecked_url())
+        {
+            if !relays.contains(&url) {
+                relays.push(url);
+            }
+        }
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::FetchNAddr(NAddr { relays, ..address }));
+    }
+
+    Ok(())
+}
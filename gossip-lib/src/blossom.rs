@@ -1,9 +1,11 @@
+use crate::comms::{RelayConnectionReason, RelayJob, ToMinionPayload, ToMinionPayloadDetail};
 use crate::error::{Error, ErrorKind};
+use crate::filter_set::FilterSet;
 use crate::globals::GLOBALS;
 use base64::Engine;
 use memmap2::Mmap;
 use mime::Mime;
-use nostr_types::{EventKind, ParsedTag, PreEvent, Tag, Unixtime};
+use nostr_types::{EventKind, ParsedTag, PreEvent, PublicKey, Tag, Unixtime};
 use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE};
 use reqwest::{Body, Client, Response};
 use serde::{Deserialize, Serialize};
@@ -14,9 +16,26 @@ use std::path::Path;
 use std::time::Duration;
 
 /// A simple type for a SHA-256 hash output of 32 bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct HashOutput([u8; 32]);
 
 impl HashOutput {
+    pub fn from_hex(s: &str) -> Result<HashOutput, Error> {
+        match hex::decode(s)
+            .ok()
+            .and_then(|b| <[u8; 32]>::try_from(b).ok())
+        {
+            Some(array) => Ok(HashOutput(array)),
+            None => Err(ErrorKind::BlossomError(format!("Not a SHA-256 hash: {}", s)).into()),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> HashOutput {
+        let mut hasher = Sha256::new();
+        hasher.update(bytes);
+        HashOutput(hasher.finalize().into())
+    }
+
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<HashOutput, Error> {
         let sha256hash = {
             let file = File::open(path)?;
@@ -98,7 +117,7 @@ impl Blossom {
             Duration::new(GLOBALS.db().read_setting_fetcher_connect_timeout_sec(), 0);
         let timeout = Duration::new(GLOBALS.db().read_setting_fetcher_timeout_sec(), 0);
 
-        let client = Client::builder()
+        let client = crate::proxy::http_client_builder(crate::proxy::http_proxy().as_deref())?
             .gzip(false)
             .brotli(false)
             .deflate(false)
@@ -109,6 +128,15 @@ impl Blossom {
         Ok(Blossom { client })
     }
 
+    /// The shared Blossom client in GLOBALS, created the first time it is needed
+    pub fn global() -> Result<&'static Blossom, Error> {
+        if let Some(blossom) = GLOBALS.blossom.get() {
+            return Ok(blossom);
+        }
+        let _ = GLOBALS.blossom.set(Blossom::new()?);
+        Ok(GLOBALS.blossom.get().unwrap())
+    }
+
     /// BUD-01 HEAD /<sha256>
     /// Check if the data exists on the blossom server
     pub async fn check_exists(
@@ -222,20 +250,112 @@ impl Blossom {
         }
     }
 
-    // BUD-04  PUT /mirror
-    //pub async fn mirror() {
-    //
-    //}
+    /// BUD-04  PUT /mirror
+    /// Have the blossom server fetch a blob from another server
+    pub async fn mirror(
+        &self,
+        base_url: String,
+        from_url: String,
+        hash: HashOutput,
+    ) -> Result<BlobDescriptor, Error> {
+        let authorization = authorization(
+            BlossomVerb::Upload,
+            "Mirror".to_owned(),
+            Unixtime::now() + Duration::new(60, 0),
+            vec![hash],
+        )
+        .await?;
 
-    // BUD-02  GET /list/<pubkey>
-    //pub async fn list() {
-    //    unimplemented!()
-    //}
+        let url = format!("{}mirror", base_url);
+        let body = serde_json::json!({ "url": from_url }).to_string();
+        let response = self
+            .client
+            .put(url)
+            .header(AUTHORIZATION, format!("Nostr {}", authorization))
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?;
 
-    // BUD-02  DELETE /<sha256>
-    //pub async fn delete() {
-    //    unimplemented!()
-    //}
+        if response.status().as_u16() < 300 {
+            let full = response.bytes().await?;
+            match serde_json::from_slice::<BlobDescriptor>(&full) {
+                Ok(bd) => Ok(bd),
+                Err(e) => {
+                    let text = String::from_utf8_lossy(&full);
+                    tracing::error!("Failed to deserialize Blossom Blob Descriptor: {}", text);
+                    Err(e.into())
+                }
+            }
+        } else {
+            Err(get_error(&response))
+        }
+    }
+
+    /// BUD-02  GET /list/<pubkey>
+    /// List the blobs uploaded by a pubkey
+    pub async fn list(
+        &self,
+        base_url: String,
+        pubkey: PublicKey,
+        authorize: bool,
+    ) -> Result<Vec<BlobDescriptor>, Error> {
+        let url = format!("{}list/{}", base_url, pubkey.as_hex_string());
+        let mut req_builder = self.client.get(url);
+
+        if authorize {
+            let authorization = authorization(
+                BlossomVerb::List,
+                "List".to_owned(),
+                Unixtime::now() + Duration::new(60, 0),
+                vec![],
+            )
+            .await?;
+
+            req_builder = req_builder.header(AUTHORIZATION, format!("Nostr {}", authorization))
+        };
+
+        let response = req_builder.send().await?;
+
+        if response.status().as_u16() < 300 {
+            let full = response.bytes().await?;
+            match serde_json::from_slice::<Vec<BlobDescriptor>>(&full) {
+                Ok(bds) => Ok(bds),
+                Err(e) => {
+                    let text = String::from_utf8_lossy(&full);
+                    tracing::error!("Failed to deserialize Blossom blob list: {}", text);
+                    Err(e.into())
+                }
+            }
+        } else {
+            Err(get_error(&response))
+        }
+    }
+
+    /// BUD-02  DELETE /<sha256>
+    pub async fn delete(&self, base_url: String, hash: HashOutput) -> Result<(), Error> {
+        let authorization = authorization(
+            BlossomVerb::Delete,
+            "Delete".to_owned(),
+            Unixtime::now() + Duration::new(60, 0),
+            vec![hash],
+        )
+        .await?;
+
+        let url = format!("{}{}", base_url, hash);
+        let response = self
+            .client
+            .delete(url)
+            .header(AUTHORIZATION, format!("Nostr {}", authorization))
+            .send()
+            .await?;
+
+        if response.status().as_u16() < 300 {
+            Ok(())
+        } else {
+            Err(get_error(&response))
+        }
+    }
 }
 
 // This returns the base64 encoded authorization event
@@ -285,6 +405,84 @@ fn get_error(response: &Response) -> Error {
     }
 }
 
+/// The blossom servers from our settings
+pub fn blossom_servers() -> Vec<String> {
+    GLOBALS
+        .db()
+        .read_setting_blossom_servers()
+        .split_whitespace()
+        .map(|s| s.to_owned())
+        .collect()
+}
+
+/// The blossom servers that a person lists in their User Server List (BUD-03)
+pub fn user_servers(pubkey: PublicKey) -> Vec<String> {
+    match GLOBALS
+        .db()
+        .get_replaceable_event(EventKind::UserServerList, pubkey, "")
+    {
+        Ok(Some(event)) => event
+            .tags
+            .iter()
+            .filter(|t| t.tagname() == "server")
+            .map(|t| t.value().to_owned())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Ask a person's outbox relays for their User Server List (BUD-03). It is
+/// processed when it comes in, after which `user_servers()` has it.
+pub(crate) fn fetch_user_servers(pubkey: PublicKey) -> Result<(), Error> {
+    crate::manager::run_jobs_on_all_relays(
+        crate::relay::get_some_pubkey_outboxes(pubkey)?,
+        vec![RelayJob {
+            reason: RelayConnectionReason::FetchBlossomServers,
+            payload: ToMinionPayload {
+                job_id: rand::random::<u64>(),
+                detail: ToMinionPayloadDetail::Subscribe(FilterSet::BlossomServers(pubkey)),
+            },
+        }],
+    );
+    Ok(())
+}
+
+/// The hash of the blob at a URL, if it looks like a blossom blob URL
+/// (the last path segment is a SHA-256 hash, with or without a file extension)
+pub fn blob_hash(url: &str) -> Option<HashOutput> {
+    let url = url::Url::parse(url).ok()?;
+    let last = url.path_segments()?.next_back()?;
+    let name = last.split('.').next()?;
+    if name.len() != 64 {
+        return None;
+    }
+    HashOutput::from_hex(name).ok()
+}
+
+/// Our blob with this hash on a blossom server, as of its last listing
+pub fn blob_on_server(server: &str, hash: &str) -> Option<BlobDescriptor> {
+    match GLOBALS.blossom_blobs.get(server)?.value() {
+        Ok(blobs) => blobs.iter().find(|bd| bd.sha256 == hash).cloned(),
+        Err(_) => None,
+    }
+}
+
+/// The base URL of a blossom server as the user wrote it, ending in a slash
+pub fn base_url(server: &str) -> Result<String, Error> {
+    use http::uri::{Parts, PathAndQuery, Scheme};
+    use http::Uri;
+
+    let uri = server.parse::<Uri>()?;
+    let mut parts: Parts = uri.into_parts();
+    parts.path_and_query = Some(PathAndQuery::from_static("/")); // Force no path
+    if parts.scheme.is_none() {
+        // Default to https
+        parts.scheme = Some(Scheme::HTTPS);
+    }
+    let uri = Uri::from_parts(parts)?;
+    Ok(format!("{}", uri))
+}
+
 /// This first infers the content-type by the magic number of the content
 /// Then it uses the file extension
 /// It falls back to application/octet-stream
@@ -0,0 +1,642 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use nostr_types::{
+    Event, EventKind, Filter, Id, NAddr, ParsedTag, PublicKey, Tag, UncheckedUrl, Unixtime,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A calendar date, which is in no particular time zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CalendarDate(NaiveDate);
+
+impl CalendarDate {
+    /// Parse a date written as YYYY-MM-DD
+    pub fn parse(s: &str) -> Option<CalendarDate> {
+        NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+            .ok()
+            .map(CalendarDate)
+    }
+
+    /// The date (in UTC) of a moment in time
+    pub fn from_unixtime(time: Unixtime) -> CalendarDate {
+        CalendarDate(utc_time(time).date_naive())
+    }
+
+    /// Midnight at the start of this date, in UTC
+    pub fn to_unixtime(&self) -> Unixtime {
+        Unixtime(self.0.and_time(NaiveTime::MIN).and_utc().timestamp())
+    }
+
+    /// The date after this one
+    pub fn next(&self) -> CalendarDate {
+        CalendarDate(self.0.succ_opt().unwrap_or(self.0))
+    }
+
+    /// The date before this one
+    pub fn previous(&self) -> CalendarDate {
+        CalendarDate(self.0.pred_opt().unwrap_or(self.0))
+    }
+
+    /// The date, for formatting
+    pub fn naive_date(&self) -> NaiveDate {
+        self.0
+    }
+}
+
+impl fmt::Display for CalendarDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.format("%Y-%m-%d"))
+    }
+}
+
+// A moment in time, in UTC. Times too far away for chrono are taken to be 1970.
+fn utc_time(time: Unixtime) -> DateTime<Utc> {
+    DateTime::from_timestamp(time.0, 0).unwrap_or_default()
+}
+
+/// When a calendar event happens
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventTime {
+    /// All day, from the start date up to (but not including) the end date.
+    /// Dates are the same in every time zone.
+    Dates {
+        start: CalendarDate,
+        end: Option<CalendarDate>,
+    },
+
+    /// From the start time to the end time, with the IANA time zones (such as
+    /// `America/Costa_Rica`) the event was planned in, if given
+    Times {
+        start: Unixtime,
+        end: Option<Unixtime>,
+        start_tzid: Option<String>,
+        end_tzid: Option<String>,
+    },
+}
+
+/// A NIP-52 calendar event, from a date-based (kind 31922) or time-based
+/// (kind 31923) event
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    /// The id of the event this version of the calendar event came from
+    pub id: Id,
+
+    /// Who put the event on the calendar
+    pub author: PublicKey,
+
+    /// DateBasedCalendarEvent, or TimeBasedCalendarEvent
+    pub kind: EventKind,
+
+    /// The identifier of the calendar event, which stays the same across edits
+    pub d: String,
+
+    /// The title of the event
+    pub title: String,
+
+    /// A short summary of the event
+    pub summary: String,
+
+    /// An image for the event
+    pub image: Option<String>,
+
+    /// When the event happens
+    pub time: EventTime,
+
+    /// Where the event happens. This may be an address, a place name, or a link.
+    pub locations: Vec<String>,
+
+    /// People taking part, with their role (such as 'speaker') if given
+    pub participants: Vec<(PublicKey, String)>,
+
+    /// Hashtags of the event
+    pub hashtags: Vec<String>,
+
+    /// Links about the event
+    pub links: Vec<String>,
+
+    /// A description of the event
+    pub content: String,
+
+    /// When this version of the calendar event was created
+    pub created_at: Unixtime,
+}
+
+impl CalendarEvent {
+    /// Parse a calendar event. Returns None for other kinds of events, or if the
+    /// event doesn't say when it starts.
+    pub fn from_event(event: &Event) -> Option<CalendarEvent> {
+        let date_based = match event.kind {
+            EventKind::DateBasedCalendarEvent => true,
+            EventKind::TimeBasedCalendarEvent => false,
+            _ => return None,
+        };
+
+        let mut d = "".to_owned();
+        let mut title = "".to_owned();
+        let mut name = "".to_owned();
+        let mut summary = "".to_owned();
+        let mut image = None;
+        let mut start = "".to_owned();
+        let mut end = "".to_owned();
+        let mut start_tzid = None;
+        let mut end_tzid = None;
+        let mut locations = vec![];
+        let mut participants = vec![];
+        let mut hashtags = vec![];
+        let mut links = vec![];
+
+        for tag in &event.tags {
+            match tag.tagname() {
+                "d" => d = tag.value().to_owned(),
+                "title" => title = tag.value().to_owned(),
+                // Older events are named rather than titled
+                "name" => name = tag.value().to_owned(),
+                "summary" => summary = tag.value().to_owned(),
+                "image" if !tag.value().is_empty() => image = Some(tag.value().to_owned()),
+                "start" => start = tag.value().to_owned(),
+                "end" => end = tag.value().to_owned(),
+                "start_tzid" if !tag.value().is_empty() => {
+                    start_tzid = Some(tag.value().to_owned())
+                }
+                "end_tzid" if !tag.value().is_empty() => end_tzid = Some(tag.value().to_owned()),
+                "location" if !tag.value().is_empty() => locations.push(tag.value().to_owned()),
+                "p" => {
+                    if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.value(), true) {
+                        participants.push((pubkey, tag.get_index(3).to_owned()));
+                    }
+                }
+                "t" if !tag.value().is_empty() => hashtags.push(tag.value().to_owned()),
+                "r" if !tag.value().is_empty() => links.push(tag.value().to_owned()),
+                _ => {}
+            }
+        }
+
+        let time = if date_based {
+            let start = CalendarDate::parse(&start)?;
+            // An end that isn't after the start is ignored
+            let end = CalendarDate::parse(&end).filter(|end| *end > start);
+            EventTime::Dates { start, end }
+        } else {
+            let start = Unixtime(start.trim().parse::<i64>().ok()?);
+            let end = end
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .map(Unixtime)
+                .filter(|end| *end > start);
+            EventTime::Times {
+                start,
+                end,
+                start_tzid,
+                end_tzid,
+            }
+        };
+
+        if title.is_empty() {
+            title = name;
+        }
+
+        Some(CalendarEvent {
+            id: event.id,
+            author: event.pubkey,
+            kind: event.kind,
+            d,
+            title,
+            summary,
+            image,
+            time,
+            locations,
+            participants,
+            hashtags,
+            links,
+            content: event.content.clone(),
+            created_at: event.created_at,
+        })
+    }
+
+    /// The address of the calendar event
+    pub fn naddr(&self, relays: Vec<UncheckedUrl>) -> NAddr {
+        NAddr {
+            d: self.d.clone(),
+            relays,
+            kind: self.kind,
+            author: self.author,
+        }
+    }
+
+    /// The address of the calendar event, as used in 'a' tags
+    pub fn address(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            u32::from(self.kind),
+            self.author.as_hex_string(),
+            self.d
+        )
+    }
+
+    /// When the event starts. All-day events start at midnight UTC.
+    pub fn starts_at(&self) -> Unixtime {
+        match &self.time {
+            EventTime::Dates { start, .. } => start.to_unixtime(),
+            EventTime::Times { start, .. } => *start,
+        }
+    }
+
+    /// When the event is over. All-day events are over at midnight UTC after their
+    /// last day, and events without an end time are taken to be over when they start.
+    pub fn ends_at(&self) -> Unixtime {
+        match &self.time {
+            EventTime::Dates { start, end } => end.unwrap_or_else(|| start.next()).to_unixtime(),
+            EventTime::Times { start, end, .. } => end.unwrap_or(*start),
+        }
+    }
+
+    /// Export the event as an iCalendar (.ics) file, for other calendar apps.
+    /// Times are written in UTC, so they are right wherever the file is opened.
+    pub fn to_ics(&self) -> String {
+        let mut lines: Vec<String> = vec![
+            "BEGIN:VCALENDAR".to_owned(),
+            "VERSION:2.0".to_owned(),
+            "PRODID:-//gossip//NIP-52//EN".to_owned(),
+            "BEGIN:VEVENT".to_owned(),
+            format!("UID:{}", ics_escape(&self.address())),
+            format!("DTSTAMP:{}", ics_time(self.created_at)),
+        ];
+
+        match &self.time {
+            EventTime::Dates { start, end } => {
+                let end = end.unwrap_or_else(|| start.next());
+                lines.push(format!("DTSTART;VALUE=DATE:{}", ics_date(*start)));
+                lines.push(format!("DTEND;VALUE=DATE:{}", ics_date(end)));
+            }
+            EventTime::Times { start, end, .. } => {
+                lines.push(format!("DTSTART:{}", ics_time(*start)));
+                if let Some(end) = end {
+                    lines.push(format!("DTEND:{}", ics_time(*end)));
+                }
+            }
+        }
+
+        if !self.title.is_empty() {
+            lines.push(format!("SUMMARY:{}", ics_escape(&self.title)));
+        }
+
+        let description = match (self.summary.is_empty(), self.content.is_empty()) {
+            (false, false) => format!("{}\n\n{}", self.summary, self.content),
+            (false, true) => self.summary.clone(),
+            _ => self.content.clone(),
+        };
+        if !description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", ics_escape(&description)));
+        }
+
+        if !self.locations.is_empty() {
+            lines.push(format!(
+                "LOCATION:{}",
+                ics_escape(&self.locations.join(", "))
+            ));
+        }
+
+        if let Some(link) = self.links.first() {
+            lines.push(format!("URL:{}", ics_escape(link)));
+        }
+
+        if !self.hashtags.is_empty() {
+            let categories: Vec<String> = self.hashtags.iter().map(|t| ics_escape(t)).collect();
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+
+        lines.push("END:VEVENT".to_owned());
+        lines.push("END:VCALENDAR".to_owned());
+
+        let mut ics = String::new();
+        for line in lines.iter() {
+            ics.push_str(&ics_fold(line));
+        }
+        ics
+    }
+}
+
+// A date as iCalendar writes it
+fn ics_date(date: CalendarDate) -> String {
+    date.0.format("%Y%m%d").to_string()
+}
+
+// A UTC time as iCalendar writes it
+fn ics_time(time: Unixtime) -> String {
+    utc_time(time).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Escape text for an iCalendar property value
+fn ics_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// iCalendar lines should be no longer than 75 octets, continuing on lines
+// that start with a space
+fn ics_fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// An answer to an invitation to a calendar event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RsvpStatus {
+    Accepted,
+    Declined,
+    Tentative,
+}
+
+impl RsvpStatus {
+    /// All of the answers
+    pub const ALL: [RsvpStatus; 3] = [
+        RsvpStatus::Accepted,
+        RsvpStatus::Tentative,
+        RsvpStatus::Declined,
+    ];
+
+    /// The status as written in the 'status' tag
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RsvpStatus::Accepted => "accepted",
+            RsvpStatus::Declined => "declined",
+            RsvpStatus::Tentative => "tentative",
+        }
+    }
+
+    /// Parse the value of a 'status' tag
+    pub fn parse(s: &str) -> Option<RsvpStatus> {
+        match s {
+            "accepted" => Some(RsvpStatus::Accepted),
+            "declined" => Some(RsvpStatus::Declined),
+            "tentative" => Some(RsvpStatus::Tentative),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RsvpStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RsvpStatus::Accepted => write!(f, "Going"),
+            RsvpStatus::Declined => write!(f, "Not going"),
+            RsvpStatus::Tentative => write!(f, "Maybe"),
+        }
+    }
+}
+
+/// An RSVP to a calendar event (kind 31925)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rsvp {
+    /// Who answered
+    pub pubkey: PublicKey,
+
+    /// The identifier of the RSVP. Answering again under the same identifier
+    /// replaces the earlier answer.
+    pub d: String,
+
+    /// The answer
+    pub status: RsvpStatus,
+
+    /// A note with the answer
+    pub content: String,
+
+    /// When they answered
+    pub created_at: Unixtime,
+}
+
+impl Rsvp {
+    /// Parse an RSVP. Returns None for other kinds of events, or if it has no
+    /// status we know.
+    pub fn from_event(event: &Event) -> Option<Rsvp> {
+        if event.kind != EventKind::CalendarEventRsvp {
+            return None;
+        }
+
+        let mut d = "".to_owned();
+        let mut status = None;
+        for tag in &event.tags {
+            match tag.tagname() {
+                "d" => d = tag.value().to_owned(),
+                "status" => status = RsvpStatus::parse(tag.value()),
+                // Older RSVPs put the status in an 'l' tag
+                "l" if status.is_none() && tag.get_index(2) == "status" => {
+                    status = RsvpStatus::parse(tag.value())
+                }
+                _ => {}
+            }
+        }
+
+        Some(Rsvp {
+            pubkey: event.pubkey,
+            d,
+            status: status?,
+            content: event.content.clone(),
+            created_at: event.created_at,
+        })
+    }
+}
+
+/// The latest version of the calendar event at an address, if we have it
+pub fn calendar_event(naddr: &NAddr) -> Result<Option<CalendarEvent>, Error> {
+    Ok(GLOBALS
+        .db()
+        .get_replaceable_event(naddr.kind, naddr.author, &naddr.d)?
+        .and_then(|e| CalendarEvent::from_event(&e)))
+}
+
+/// Calendar events put on the calendar by the people the user follows (and by
+/// the user), by when they start
+pub fn calendar_events() -> Result<Vec<CalendarEvent>, Error> {
+    let mut filter = Filter::new();
+    filter.kinds = vec![
+        EventKind::DateBasedCalendarEvent,
+        EventKind::TimeBasedCalendarEvent,
+    ];
+    for (pubkey, _) in GLOBALS.db().get_people_in_list(PersonList::Followed)? {
+        filter.add_author(pubkey);
+    }
+    if let Some(pubkey) = GLOBALS.identity.public_key() {
+        filter.add_author(pubkey);
+    }
+    if filter.authors.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut events: Vec<CalendarEvent> = GLOBALS
+        .db()
+        .find_events_by_filter(&filter, |_| true)?
+        .iter()
+        .filter_map(CalendarEvent::from_event)
+        .collect();
+    events.sort_by_key(|e| e.starts_at());
+    Ok(events)
+}
+
+/// The latest RSVP of each person to a calendar event
+pub fn rsvps(event: &CalendarEvent) -> Result<Vec<Rsvp>, Error> {
+    let mut filter = Filter::new();
+    filter.kinds = vec![EventKind::CalendarEventRsvp];
+    filter.add_tag_value('a', event.address());
+
+    let mut latest: HashMap<PublicKey, Rsvp> = HashMap::new();
+    for rsvp in GLOBALS
+        .db()
+        .find_events_by_filter(&filter, |_| true)?
+        .iter()
+        .filter_map(Rsvp::from_event)
+    {
+        match latest.get(&rsvp.pubkey) {
+            Some(existing) if existing.created_at >= rsvp.created_at => {}
+            _ => {
+                latest.insert(rsvp.pubkey, rsvp);
+            }
+        }
+    }
+    Ok(latest.into_values().collect())
+}
+
+/// The user's RSVP to a calendar event, if they answered
+pub fn my_rsvp(event: &CalendarEvent) -> Result<Option<Rsvp>, Error> {
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pubkey) => pubkey,
+        None => return Ok(None),
+    };
+    Ok(rsvps(event)?.into_iter().find(|r| r.pubkey == pubkey))
+}
+
+/// The tags of an RSVP to a calendar event. Answering again replaces the earlier answer.
+pub(crate) fn rsvp_tags(event: &CalendarEvent, status: RsvpStatus) -> Result<Vec<Tag>, Error> {
+    let d = match my_rsvp(event)? {
+        Some(rsvp) if !rsvp.d.is_empty() => rsvp.d,
+        _ => crate::misc::new_identifier(&event.title),
+    };
+
+    let hint = crate::relay::recommended_relay_hint(event.id)
+        .ok()
+        .flatten()
+        .map(|rr| rr.to_unchecked_url());
+
+    let mut tags: Vec<Tag> = vec![
+        Tag::new(&["d", &d]),
+        ParsedTag::Address {
+            address: event.naddr(hint.clone().into_iter().collect()),
+            marker: None,
+        }
+        .into_tag(),
+        ParsedTag::Event {
+            id: event.id,
+            recommended_relay_url: hint.clone(),
+            marker: None,
+            author_pubkey: None,
+        }
+        .into_tag(),
+        Tag::new(&["status", status.as_str()]),
+    ];
+
+    // Only tell people we are busy if we may go
+    if status != RsvpStatus::Declined {
+        tags.push(Tag::new(&["fb", "busy"]));
+    }
+
+    tags.push(
+        ParsedTag::Pubkey {
+            pubkey: event.author,
+            recommended_relay_url: hint,
+            petname: None,
+        }
+        .into_tag(),
+    );
+
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn date(s: &str) -> CalendarDate {
+        CalendarDate::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(date("2024-02-29").to_string(), "2024-02-29");
+        assert_eq!(date(" 2024-2-9 ").to_string(), "2024-02-09");
+        assert_eq!(CalendarDate::parse("2023-02-29"), None);
+        assert_eq!(CalendarDate::parse("2024-13-01"), None);
+        assert_eq!(CalendarDate::parse("2024-12"), None);
+        assert_eq!(CalendarDate::parse("tomorrow"), None);
+    }
+
+    #[test]
+    fn test_date_conversion() {
+        assert_eq!(CalendarDate::from_unixtime(Unixtime(0)), date("1970-01-01"));
+        assert_eq!(
+            CalendarDate::from_unixtime(Unixtime(-1)),
+            date("1969-12-31")
+        );
+        assert_eq!(
+            CalendarDate::from_unixtime(Unixtime(951782400 + 86399)),
+            date("2000-02-29")
+        );
+        assert_eq!(date("2000-02-29").to_unixtime(), Unixtime(951782400));
+        assert_eq!(date("1969-12-31").to_unixtime(), Unixtime(-86400));
+
+        assert_eq!(date("2000-02-28").next(), date("2000-02-29"));
+        assert_eq!(date("1900-02-28").next(), date("1900-03-01"));
+        assert_eq!(date("2023-12-31").next(), date("2024-01-01"));
+        assert_eq!(date("2024-03-01").previous(), date("2024-02-29"));
+        assert_eq!(date("2024-01-01").previous(), date("2023-12-31"));
+    }
+
+    #[test]
+    fn test_ics_times() {
+        assert_eq!(ics_date(date("2024-02-09")), "20240209");
+        assert_eq!(ics_time(Unixtime(1700000000)), "20231114T221320Z");
+        assert_eq!(ics_time(Unixtime(0)), "19700101T000000Z");
+    }
+
+    #[test]
+    fn test_ics_escape() {
+        assert_eq!(ics_escape("a;b,c\\d\ne\r\nf"), r"a\;b\,c\\d\ne\nf");
+        assert_eq!(ics_escape("plain text"), "plain text");
+    }
+
+    #[test]
+    fn test_ics_fold() {
+        assert_eq!(ics_fold("SUMMARY:short"), "SUMMARY:short\r\n");
+
+        let line = "x".repeat(80);
+        assert_eq!(
+            ics_fold(&line),
+            format!("{}\r\n {}\r\n", "x".repeat(75), "x".repeat(5))
+        );
+
+        // Characters are not split, and no line is longer than 75 octets
+        let line = format!("{}é{}", "x".repeat(74), "y".repeat(200));
+        let folded = ics_fold(&line);
+        assert!(folded.starts_with(&format!("{}\r\n é", "x".repeat(74))));
+        for part in folded.split("\r\n") {
+            assert!(part.len() <= 75);
+        }
+        assert_eq!(folded.replace("\r\n ", ""), format!("{}\r\n", line));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::feed::FeedKind;
+use crate::globals::GLOBALS;
+use crate::relay;
+use crate::storage::{ChannelsTable, Table};
+use nostr_types::{Event, EventKind, Filter, Id, ParsedTag, PublicKey, RelayUrl};
+use std::collections::HashSet;
+
+pub type Channel = crate::storage::types::Channel1;
+
+/// All channels we know about, joined channels first, then by name
+pub fn channels() -> Result<Vec<Channel>, Error> {
+    let mut channels = ChannelsTable::filter_records(|_| true)?;
+    channels.sort_by(|a, b| {
+        b.joined
+            .cmp(&a.joined)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    Ok(channels)
+}
+
+/// A channel, if we know about it
+pub fn channel(id: Id) -> Result<Option<Channel>, Error> {
+    ChannelsTable::read_record(id, None)
+}
+
+/// The name of a channel for display
+pub fn channel_name(id: Id) -> String {
+    match channel(id) {
+        Ok(Some(channel)) if !channel.name.is_empty() => channel.name,
+        _ => crate::names::hex_id_short(&id.into()),
+    }
+}
+
+/// The channel that a channel metadata, message, or reply event belongs to.
+///
+/// This is the 'e' tag marked 'root', or the first 'e' tag if none is marked.
+pub fn channel_of(event: &Event) -> Option<Id> {
+    let mut first: Option<Id> = None;
+    for tag in &event.tags {
+        if let Ok(ParsedTag::Event { id, marker, .. }) = tag.parse() {
+            if marker.as_deref() == Some("root") {
+                return Some(id);
+            }
+            if first.is_none() {
+                first = Some(id);
+            }
+        }
+    }
+    first
+}
+
+/// Relays to look for the events of a channel on: the relays of the channel,
+/// where we saw the channel, and our read relays
+pub fn channel_relays(channel: &Channel) -> Result<Vec<RelayUrl>, Error> {
+    let mut relays = channel.relays.clone();
+    for (url, _) in GLOBALS.db().get_event_seen_on_relay(channel.id)? {
+        relays.push(url);
+    }
+    relay::with_read_relays(relays)
+}
+
+/// The messages hidden and the users muted by the creator of the channel
+pub fn channel_moderation(channel: &Channel) -> Result<(HashSet<Id>, HashSet<PublicKey>), Error> {
+    let mut hidden: HashSet<Id> = HashSet::new();
+    let mut muted: HashSet<PublicKey> = HashSet::new();
+
+    let creator = match channel.creator {
+        Some(pk) => pk,
+        None => return Ok((hidden, muted)),
+    };
+
+    let mut filter = Filter::new();
+    filter.add_author(creator);
+    filter.kinds = vec![EventKind::ChannelHideMessage, EventKind::ChannelMuteUser];
+    for event in GLOBALS.db().find_events_by_filter(&filter, |_| true)? {
+        for tag in &event.tags {
+            match (event.kind, tag.parse()) {
+                (EventKind::ChannelHideMessage, Ok(ParsedTag::Event { id, .. })) => {
+                    hidden.insert(id);
+                }
+                (EventKind::ChannelMuteUser, Ok(ParsedTag::Pubkey { pubkey, .. })) => {
+                    muted.insert(pubkey);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok((hidden, muted))
+}
+
+/// Join a channel. Relay hints are where we may find it.
+pub(crate) fn join_channel(id: Id, relays: Vec<RelayUrl>) -> Result<(), Error> {
+    ChannelsTable::modify(
+        id,
+        |channel| {
+            channel.joined = true;
+            for url in relays.iter() {
+                if !channel.relays.contains(url) {
+                    channel.relays.push(url.clone());
+                }
+            }
+        },
+        None,
+    )
+}
+
+/// Leave a channel
+pub(crate) fn leave_channel(id: Id) -> Result<(), Error> {
+    ChannelsTable::modify_if_exists(id, |channel| channel.joined = false, None)?;
+    Ok(())
+}
+
+/// Record a channel from its creation event, along with any metadata updates
+/// that arrived before it did
+pub(crate) fn process_channel_creation(event: &Event) -> Result<(), Error> {
+    let mut channel = ChannelsTable::read_or_create_record(event.id, None)?;
+    if channel.creator.is_some() {
+        return Ok(());
+    }
+
+    channel.creator = Some(event.pubkey);
+    apply_metadata(&mut channel, event);
+
+    let mut filter = Filter::new();
+    filter.add_author(event.pubkey);
+    filter.kinds = vec![EventKind::ChannelMetadata];
+    filter.add_tag_value('e', event.id.as_hex_string());
+    for update in GLOBALS.db().find_events_by_filter(&filter, |_| true)? {
+        if channel_of(&update) == Some(event.id) && update.created_at > channel.metadata_at {
+            apply_metadata(&mut channel, &update);
+        }
+    }
+
+    ChannelsTable::write_record(&mut channel, None)?;
+
+    // If we are in this channel, we can now follow the moderation of its creator
+    if GLOBALS.feed.get_feed_kind() == FeedKind::Channel(event.id) {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::SetChannelFeed(event.id));
+    }
+
+    Ok(())
+}
+
+/// Update a channel from a metadata event. Only the creator of the channel may do this.
+pub(crate) fn process_channel_metadata(event: &Event) -> Result<(), Error> {
+    let id = match channel_of(event) {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    // If we don't have the creation event yet, this is picked up when it arrives
+    let mut channel = match ChannelsTable::read_record(id, None)? {
+        Some(channel) => channel,
+        None => return Ok(()),
+    };
+
+    if channel.creator != Some(event.pubkey) || event.created_at <= channel.metadata_at {
+        return Ok(());
+    }
+
+    apply_metadata(&mut channel, event);
+    ChannelsTable::write_record(&mut channel, None)?;
+    Ok(())
+}
+
+// Channel metadata is JSON with name, about, picture and relays
+fn apply_metadata(channel: &mut Channel, event: &Event) {
+    let value: serde_json::Value = match serde_json::from_str(&event.content) {
+        Ok(value) => value,
+        Err(_) => return,
+    };
+
+    let field = |name: &str| -> String {
+        value
+            .get(name)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_owned()
+    };
+    channel.name = field("name");
+    channel.about = field("about");
+    channel.picture = field("picture");
+
+    if let Some(relays) = value.get("relays").and_then(|v| v.as_array()) {
+        for url in relays
+            .iter()
+            .filter_map(|r| r.as_str())
+            .filter_map(|r| RelayUrl::try_from_str(r).ok())
+        {
+            if !channel.relays.contains(&url) {
+                channel.relays.push(url);
+            }
+        }
+    }
+
+    channel.metadata_at = event.created_at;
+}
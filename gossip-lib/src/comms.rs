@@ -1,5 +1,11 @@
+use crate::article::ArticleFields;
+use crate::badges::BadgeFields;
+use crate::calendar::RsvpStatus;
+use crate::counts::CountKind;
 use crate::dm_channel::DmChannel;
+use crate::dvm::JobRequest;
 use crate::filter_set::FilterSet;
+use crate::git::GitStatus;
 use crate::misc::Private;
 use crate::nostr_connect_server::{Approval, ParsedCommand};
 use crate::people::PersonList;
@@ -19,6 +25,12 @@ use std::path::PathBuf;
 /// renderer.
 #[derive(Debug, Clone)]
 pub enum ToOverlordMessage {
+    /// Calls [accept_badge](crate::Overlord::accept_badge)
+    AcceptBadge(Id),
+
+    /// Calls [add_account](crate::Overlord::add_account)
+    AddAccount,
+
     /// Calls [add_relay](crate::Overlord::add_relay)
     AddRelay(RelayUrl),
 
@@ -28,6 +40,9 @@ pub enum ToOverlordMessage {
     /// Calls [advertise_relay_list_one](crate::Overlord::advertise_relay_list)
     AdvertiseRelayListOne(RelayUrl, Box<Event>, Box<Event>),
 
+    /// Calls [approve_community_post](crate::Overlord::approve_community_post)
+    ApproveCommunityPost(Id, PublicKey, String),
+
     /// Calls [auth_approved](crate::Overlord::auth_approved)
     /// pass 'true' as the second parameter for a permanent approval
     AuthApproved(RelayUrl, bool),
@@ -36,6 +51,21 @@ pub enum ToOverlordMessage {
     /// pass 'true' as the second parameter for a permanent approval
     AuthDeclined(RelayUrl, bool),
 
+    /// Calls [award_badge](crate::Overlord::award_badge)
+    AwardBadge(String, Vec<PublicKey>),
+
+    /// Calls [blossom_delete](crate::Overlord::blossom_delete)
+    /// Deletes the blob with this SHA-256 hash from our blossom servers
+    BlossomDelete(String),
+
+    /// Calls [blossom_list](crate::Overlord::blossom_list)
+    /// Lists our blobs on each of our blossom servers
+    BlossomList,
+
+    /// Calls [blossom_mirror](crate::Overlord::blossom_mirror)
+    /// Copies the blob with this SHA-256 hash to all of our blossom servers
+    BlossomMirror(String),
+
     /// Calls [blossom_upload](crate::Overlord::blossom_upload)
     /// Uploads the local file to a blossom server
     BlossomUpload(PathBuf),
@@ -48,6 +78,9 @@ pub enum ToOverlordMessage {
     /// Removes a bookmark, and publishes new bookmarks list
     BookmarkRm(EventReference),
 
+    /// Calls [cancel_scheduled_post](crate::Overlord::cancel_scheduled_post)
+    CancelScheduledPost(u64),
+
     /// Calls [change_passphrase](crate::Overlord::change_passphrase)
     ChangePassphrase { old: String, new: String },
 
@@ -62,6 +95,16 @@ pub enum ToOverlordMessage {
     /// pass 'true' as the second parameter for a permanent approval
     ConnectDeclined(RelayUrl, bool),
 
+    /// Calls [connect_wallet](crate::Overlord::connect_wallet)
+    ConnectWallet {
+        // nostr+walletconnect:// connection string
+        uri: String,
+        password: String,
+    },
+
+    /// Calls [define_badge](crate::Overlord::define_badge)
+    DefineBadge(BadgeFields),
+
     /// Calls [delegation_reset](crate::Overlord::delegation_reset)
     DelegationReset,
 
@@ -77,9 +120,28 @@ pub enum ToOverlordMessage {
     /// Calls [delete_pub](crate::Overlord::delete_pub)
     DeletePub,
 
+    /// Calls [disconnect_wallet](crate::Overlord::disconnect_wallet)
+    DisconnectWallet,
+
+    /// Calls [discover_communities](crate::Overlord::discover_communities)
+    DiscoverCommunities,
+
     /// Calls [drop_relay](crate::Overlord::drop_relay)
     DropRelay(RelayUrl),
 
+    /// Calls [edit_scheduled_post](crate::Overlord::edit_scheduled_post)
+    EditScheduledPost {
+        key: u64,
+        content: String,
+        publish_at: Unixtime,
+    },
+
+    /// Calls [export_calendar_event](crate::Overlord::export_calendar_event)
+    ExportCalendarEvent(NAddr, PathBuf),
+
+    /// Calls [fetch_badges](crate::Overlord::fetch_badges)
+    FetchBadges(PublicKey),
+
     /// Calls [fetch_event](crate::Overlord::fetch_event)
     FetchEvent(Id, Vec<RelayUrl>),
 
@@ -95,9 +157,15 @@ pub enum ToOverlordMessage {
     /// Calls [follow_nprofile](crate::Overlord::follow_nprofile)
     FollowNprofile(Profile, PersonList, Private),
 
+    /// Calls [follow_repository](crate::Overlord::follow_repository)
+    FollowRepository(PublicKey, String, Vec<RelayUrl>),
+
     /// Calls [generate_private_key](crate::Overlord::generate_private_key)
     GeneratePrivateKey(String),
 
+    /// Calls [hide_badge](crate::Overlord::hide_badge)
+    HideBadge(Id),
+
     /// Calls [hide_or_show_relay](crate::Overlord::hide_or_show_relay)
     HideOrShowRelay(RelayUrl, bool),
 
@@ -111,6 +179,24 @@ pub enum ToOverlordMessage {
     /// Calls [import_pub](crate::Overlord::import_pub)
     ImportPub(String),
 
+    /// Calls [join_channel](crate::Overlord::join_channel)
+    JoinChannel(Id, Vec<RelayUrl>),
+
+    /// Calls [join_community](crate::Overlord::join_community)
+    JoinCommunity(PublicKey, String, Vec<RelayUrl>),
+
+    /// Calls [join_group](crate::Overlord::join_group)
+    JoinGroup(RelayUrl, String),
+
+    /// Calls [leave_channel](crate::Overlord::leave_channel)
+    LeaveChannel(Id),
+
+    /// Calls [leave_community](crate::Overlord::leave_community)
+    LeaveCommunity(PublicKey, String),
+
+    /// Calls [leave_group](crate::Overlord::leave_group)
+    LeaveGroup(RelayUrl, String),
+
     /// Calls [load_image_to_copy](crate::Overlord::load_image_to_copy)
     LoadImageToCopy(Url),
 
@@ -126,6 +212,10 @@ pub enum ToOverlordMessage {
     /// Calls [nip46_server_op_approval_response](crate::Overlord::nip46_server_op_approval_response)
     Nip46ServerOpApprovalResponse(PublicKey, ParsedCommand, Approval),
 
+    /// Calls [nip96_upload](crate::Overlord::nip96_upload)
+    /// Uploads the local file to a NIP-96 server
+    Nip96Upload(PathBuf),
+
     /// Calls [post](crate::Overlord::post)
     Post {
         content: String,
@@ -138,9 +228,27 @@ pub enum ToOverlordMessage {
     /// Calls [post_again](crate::Overlord::post_again)
     PostAgain(Event),
 
+    /// Calls [post_article](crate::Overlord::post_article)
+    PostArticle { fields: ArticleFields, draft: bool },
+
     /// Calls [post_cancel](crate::Overlord::post_cancel)
     PostCancel,
 
+    /// Calls [post_channel_message](crate::Overlord::post_channel_message)
+    PostChannelMessage {
+        channel: Id,
+        content: String,
+        tags: Vec<Tag>,
+    },
+
+    /// Calls [post_group_message](crate::Overlord::post_group_message)
+    PostGroupMessage {
+        relay: RelayUrl,
+        group_id: String,
+        content: String,
+        tags: Vec<Tag>,
+    },
+
     /// Calls [post_nip46_event](crate::Overlord::post_nip46_event)
     PostNip46Event(Event, Vec<RelayUrl>),
 
@@ -168,9 +276,36 @@ pub enum ToOverlordMessage {
     /// Calls [reresh_subscribed_metadata](crate::Overlord::refresh_subscribed_metadata)
     RefreshSubscribedMetadata,
 
+    /// Calls [refresh_wallet](crate::Overlord::refresh_wallet)
+    RefreshWallet,
+
+    /// Calls [remove_account](crate::Overlord::remove_account)
+    RemoveAccount(PublicKey),
+
+    /// Calls [report](crate::Overlord::report)
+    Report(Id, String),
+
     /// Calls [repost](crate::Overlord::repost)
     Repost(Id),
 
+    /// Calls [request_counts](crate::Overlord::request_counts)
+    RequestCounts(Vec<CountKind>),
+
+    /// Calls [request_timestamp](crate::Overlord::request_timestamp)
+    RequestTimestamp(Id),
+
+    /// Calls [rsvp_calendar_event](crate::Overlord::rsvp_calendar_event)
+    RsvpCalendarEvent(NAddr, RsvpStatus),
+
+    /// Calls [schedule_post](crate::Overlord::schedule_post)
+    SchedulePost {
+        content: String,
+        tags: Vec<Tag>,
+        in_reply_to: Option<Id>,
+        annotation: bool,
+        publish_at: Unixtime,
+    },
+
     /// Calls [search](crate::Overlord::search_locally)
     SearchLocally(String),
 
@@ -180,12 +315,27 @@ pub enum ToOverlordMessage {
     /// Calls [set_active_person](crate::Overlord::set_active_person)
     SetActivePerson(PublicKey),
 
+    /// internal
+    SetChannelFeed(Id),
+
+    /// internal
+    SetCommunityFeed(PublicKey, String),
+
     /// internal
     SetDmChannel(DmChannel),
 
+    /// Calls [set_git_status](crate::Overlord::set_git_status)
+    SetGitStatus(Id, GitStatus),
+
+    /// internal
+    SetDvmFeed(PublicKey),
+
     /// internal
     SetGlobalFeed(Unixtime),
 
+    /// internal
+    SetGroupFeed(RelayUrl, String),
+
     /// internal
     SetPersonFeed(PublicKey, Unixtime),
 
@@ -208,9 +358,18 @@ pub enum ToOverlordMessage {
     /// Calls [start_long_lived_subscriptions](crate::Overlord::start_long_lived_subscriptions)
     StartLongLivedSubscriptions,
 
+    /// Calls [submit_dvm_job](crate::Overlord::submit_dvm_job)
+    SubmitDvmJob(JobRequest),
+
+    /// Calls [subscribe_calendar](crate::Overlord::subscribe_calendar)
+    SubscribeCalendar,
+
     /// Calls [subscribe_config](crate::Overlord::subscribe_config)
     SubscribeConfig(Option<Vec<RelayUrl>>),
 
+    /// Calls [subscribe_dvms](crate::Overlord::subscribe_dvms)
+    SubscribeDvms,
+
     /// Calls [subscribe_discover](crate::Overlord::subscribe_discover)
     SubscribeDiscover(Vec<PublicKey>, Option<Vec<RelayUrl>>),
 
@@ -220,6 +379,12 @@ pub enum ToOverlordMessage {
     /// Calls [subscribe_nip46](crate::Overlord::subscribe_nip46)
     SubscribeNip46(Vec<RelayUrl>),
 
+    /// Calls [subscribe_repository](crate::Overlord::subscribe_repository)
+    SubscribeRepository(PublicKey, String),
+
+    /// Calls [switch_account](crate::Overlord::switch_account)
+    SwitchAccount(PublicKey),
+
     /// Calls [test_relay](crate::Overlord::test_relay)
     TestRelay(RelayUrl),
 
@@ -229,6 +394,9 @@ pub enum ToOverlordMessage {
     /// Calls [track_follows](crate::Overlord::track_follows)
     TrackFollows(PublicKey),
 
+    /// Calls [unfollow_repository](crate::Overlord::unfollow_repository)
+    UnfollowRepository(PublicKey, String),
+
     /// Calls [unlock_key](crate::Overlord::unlock_key)
     UnlockKey(String),
 
@@ -247,6 +415,9 @@ pub enum ToOverlordMessage {
     /// Calls [update_relay](crate::Overlord::update_relay)
     UpdateRelay(Relay, Relay),
 
+    /// internal
+    VerifyTimestamps(Id),
+
     /// Calls [visible_notes_changed](crate::Overlord::visible_notes_changed)
     VisibleNotesChanged(Vec<Id>),
 
@@ -254,7 +425,8 @@ pub enum ToOverlordMessage {
     ZapStart(Id, PublicKey, UncheckedUrl),
 
     /// Calls [zap](crate::Overlord::zap)
-    Zap(Id, PublicKey, MilliSatoshi, String),
+    /// pass 'true' as the last parameter for an anonymous zap
+    Zap(Id, PublicKey, MilliSatoshi, String, bool),
 }
 
 /// Internal to gossip-lib.
@@ -288,6 +460,7 @@ pub(crate) enum ToMinionPayloadDetail {
     AdvertiseRelayList(Box<Event>, Box<Event>),
     AuthApproved,
     AuthDeclined,
+    Count(Vec<CountKind>),
     FetchEvent(Id),
     FetchNAddr(NAddr),
     PostEvents(Vec<Event>),
@@ -304,6 +477,8 @@ pub enum RelayConnectionReason {
     Counting,
     Discovery,
     FetchAugments,
+    FetchBadges,
+    FetchBlossomServers,
     FetchDirectMessages,
     FetchContacts,
     FetchEvent,
@@ -321,8 +496,16 @@ pub enum RelayConnectionReason {
     PostNostrConnect,
     ReadThread,
     Search,
+    SubscribeCalendar,
+    SubscribeChannel,
+    SubscribeCommunity,
+    SubscribeDvm,
+    SubscribeGitRepository,
+    SubscribeGroup,
+    SubscribeModeration,
     SubscribePerson,
     SubscribeGlobal,
+    Wallet,
 }
 
 impl fmt::Display for RelayConnectionReason {
@@ -338,9 +521,11 @@ impl RelayConnectionReason {
         match *self {
             Discovery => "Searching for other people's Relay Lists",
             Config => "Reading our client configuration",
-            Counting => "Counting",
+            Counting => "Counting events (NIP-45)",
             FetchInbox => "Searching for inbox of us",
             FetchAugments => "Fetching events that augment other events (likes, zaps, deletions)",
+            FetchBadges => "Fetching the badges of a person",
+            FetchBlossomServers => "Fetching the blossom servers of a person",
             FetchDirectMessages => "Fetching direct messages",
             FetchEvent => "Fetching a particular event",
             FetchMetadata => "Fetching metadata for a person",
@@ -358,8 +543,16 @@ impl RelayConnectionReason {
             PostNostrConnect => "Posting nostrconnect",
             ReadThread => "Reading ancestors to build a thread",
             Search => "Search",
+            SubscribeCalendar => "Fetch calendar events from the people we follow",
+            SubscribeChannel => "Subscribe to a public chat channel",
+            SubscribeCommunity => "Look for moderated communities, or subscribe to one",
+            SubscribeDvm => "Look for data vending machines, or follow the jobs we gave them",
+            SubscribeGitRepository => "Subscribe to the patches and issues of git repositories",
+            SubscribeGroup => "Subscribe to a relay-based group",
+            SubscribeModeration => "Subscribe to labels and reports by moderators",
             SubscribePerson => "Subscribe to the events of a person",
             SubscribeGlobal => "Subscribe to the global feed on a relay",
+            Wallet => "Talking to our wallet (NIP-47)",
         }
     }
 
@@ -371,6 +564,8 @@ impl RelayConnectionReason {
             Counting => false,
             FetchInbox => true,
             FetchAugments => false,
+            FetchBadges => false,
+            FetchBlossomServers => false,
             FetchDirectMessages => true,
             FetchEvent => false,
             FetchMetadata => false,
@@ -388,8 +583,16 @@ impl RelayConnectionReason {
             PostNostrConnect => false,
             ReadThread => true,
             Search => false,
+            SubscribeCalendar => false,
+            SubscribeChannel => false,
+            SubscribeCommunity => false,
+            SubscribeDvm => false,
+            SubscribeGitRepository => false,
+            SubscribeGroup => false,
+            SubscribeModeration => false,
             SubscribePerson => false,
             SubscribeGlobal => false,
+            Wallet => true,
         }
     }
 }
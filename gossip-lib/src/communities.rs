@@ -0,0 +1,287 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::misc::tagged_address;
+use crate::relay;
+use crate::storage::{CommunitiesTable, Table};
+use nostr_types::{
+    Event, EventKind, Filter, Id, NostrBech32, ParsedTag, PublicKey, RelayUrl, Tag, Unixtime,
+};
+use std::collections::HashSet;
+
+pub type Community = crate::storage::types::Community1;
+
+/// A community definition (kind 34550)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommunityDefinition {
+    /// Who defined the community. They moderate it too.
+    pub author: PublicKey,
+
+    /// The identifier of the community
+    pub d: String,
+
+    /// A human readable name
+    pub name: String,
+
+    /// What the community is about
+    pub description: String,
+
+    /// An image for the community
+    pub image: String,
+
+    /// The rules of the community
+    pub rules: String,
+
+    /// The people who approve posts, besides the author
+    pub moderators: Vec<PublicKey>,
+
+    /// Relays the community uses
+    pub relays: Vec<RelayUrl>,
+
+    /// When the definition was made
+    pub created_at: Unixtime,
+}
+
+impl CommunityDefinition {
+    /// Parse a community definition. Returns None for other kinds of events.
+    pub fn from_event(event: &Event) -> Option<CommunityDefinition> {
+        if event.kind != EventKind::CommunityDefinition {
+            return None;
+        }
+
+        let mut community = CommunityDefinition {
+            author: event.pubkey,
+            d: "".to_owned(),
+            name: "".to_owned(),
+            description: "".to_owned(),
+            image: "".to_owned(),
+            rules: "".to_owned(),
+            moderators: vec![],
+            relays: vec![],
+            created_at: event.created_at,
+        };
+
+        for tag in &event.tags {
+            match tag.tagname() {
+                "d" => community.d = tag.value().to_owned(),
+                "name" => community.name = tag.value().to_owned(),
+                "description" => community.description = tag.value().to_owned(),
+                "image" => community.image = tag.value().to_owned(),
+                "rules" => community.rules = tag.value().to_owned(),
+                "p" if tag.get_index(3) == "moderator" => {
+                    if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.value(), true) {
+                        if !community.moderators.contains(&pubkey) {
+                            community.moderators.push(pubkey);
+                        }
+                    }
+                }
+                "relay" => {
+                    if let Ok(url) = RelayUrl::try_from_str(tag.value()) {
+                        if !community.relays.contains(&url) {
+                            community.relays.push(url);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Older communities put the description in the content
+        if community.description.is_empty() {
+            community.description = event.content.clone();
+        }
+
+        Some(community)
+    }
+
+    /// The address of the community
+    pub fn address(&self) -> String {
+        community_address(self.author, &self.d)
+    }
+
+    /// If this person may approve posts
+    pub fn is_moderator(&self, pubkey: PublicKey) -> bool {
+        self.author == pubkey || self.moderators.contains(&pubkey)
+    }
+}
+
+/// The address of a community, as used in 'a' tags
+pub fn community_address(author: PublicKey, d: &str) -> String {
+    format!("34550:{}:{}", author.as_hex_string(), d)
+}
+
+/// The community an event was posted in or approves a post for, from its 'a' tag
+/// (or its 'A' tag, for comments)
+pub fn community_of(event: &Event) -> Option<(PublicKey, String)> {
+    event
+        .tags
+        .iter()
+        .filter(|t| t.tagname() == "a" || t.tagname() == "A")
+        .find_map(|t| tagged_address(t, EventKind::CommunityDefinition))
+        .map(|address| (address.author, address.d))
+}
+
+/// The latest definition of a community, if we have it
+pub fn definition(author: PublicKey, d: &str) -> Result<Option<CommunityDefinition>, Error> {
+    Ok(GLOBALS
+        .db()
+        .get_replaceable_event(EventKind::CommunityDefinition, author, d)?
+        .and_then(|e| CommunityDefinition::from_event(&e)))
+}
+
+/// A community, if we know about it
+pub fn community(author: PublicKey, d: &str) -> Result<Option<Community>, Error> {
+    CommunitiesTable::read_record(community_address(author, d), None)
+}
+
+/// All communities we know about, joined communities first, then by name
+pub fn communities() -> Result<Vec<Community>, Error> {
+    let mut communities: Vec<(String, Community)> = CommunitiesTable::filter_records(|_| true)?
+        .drain(..)
+        .map(|c| (community_name(c.author, &c.d).to_lowercase(), c))
+        .collect();
+    communities.sort_by(|(a_name, a), (b_name, b)| {
+        b.joined.cmp(&a.joined).then_with(|| a_name.cmp(b_name))
+    });
+    Ok(communities.drain(..).map(|(_, c)| c).collect())
+}
+
+/// If the user has joined a community
+pub fn is_joined(author: PublicKey, d: &str) -> bool {
+    matches!(community(author, d), Ok(Some(community)) if community.joined)
+}
+
+/// The name of a community for display
+pub fn community_name(author: PublicKey, d: &str) -> String {
+    match definition(author, d) {
+        Ok(Some(community)) if !community.name.is_empty() => community.name,
+        _ => d.to_owned(),
+    }
+}
+
+/// The people whose approvals count: the author of the community and its moderators
+pub fn moderators(author: PublicKey, d: &str) -> Result<Vec<PublicKey>, Error> {
+    let mut moderators = vec![author];
+    if let Some(community) = definition(author, d)? {
+        for pubkey in community.moderators {
+            if !moderators.contains(&pubkey) {
+                moderators.push(pubkey);
+            }
+        }
+    }
+    Ok(moderators)
+}
+
+/// If the user moderates a community
+pub fn am_moderator(author: PublicKey, d: &str) -> bool {
+    match GLOBALS.identity.public_key() {
+        Some(pubkey) => matches!(moderators(author, d), Ok(m) if m.contains(&pubkey)),
+        None => false,
+    }
+}
+
+/// Relays to look for the posts of a community on: the relays it defined,
+/// the relays we were told about, and our read relays
+pub fn community_relays(author: PublicKey, d: &str) -> Result<Vec<RelayUrl>, Error> {
+    let mut relays: Vec<RelayUrl> = Vec::new();
+    if let Some(community) = definition(author, d)? {
+        relays.extend(community.relays);
+    }
+    if let Some(community) = community(author, d)? {
+        relays.extend(community.relays);
+    }
+    relay::with_read_relays(relays)
+}
+
+/// Parse a community identifier: an naddr of the community definition, or its
+/// address (`34550:<pubkey-hex>:<identifier>`). Also returns any relay hints.
+pub fn parse_community_identifier(input: &str) -> Option<(PublicKey, String, Vec<RelayUrl>)> {
+    let input = input.trim();
+    let input = input.strip_prefix("nostr:").unwrap_or(input);
+
+    if let Some(NostrBech32::NAddr(ea)) = NostrBech32::try_from_string(input) {
+        if ea.kind != EventKind::CommunityDefinition {
+            return None;
+        }
+        let relays = ea
+            .relays
+            .iter()
+            .filter_map(|r| RelayUrl::try_from_unchecked_url(r).ok())
+            .collect();
+        return Some((ea.author, ea.d, relays));
+    }
+
+    tagged_address(&Tag::new(&["a", input]), EventKind::CommunityDefinition)
+        .map(|address| (address.author, address.d, vec![]))
+}
+
+/// If a post starts a thread in a community, rather than replying to another post.
+///
+/// Posts are text notes tagging the community, or comments scoped to it.
+pub fn is_top_level(event: &Event) -> bool {
+    match event.kind {
+        EventKind::TextNote => !event.tags.iter().any(|t| t.tagname() == "e"),
+        EventKind::Comment => event
+            .tags
+            .iter()
+            .any(|t| t.tagname() == "k" && t.value() == "34550"),
+        _ => false,
+    }
+}
+
+/// The posts of a community that have been approved by its moderators
+pub fn approved_posts(author: PublicKey, d: &str) -> Result<HashSet<Id>, Error> {
+    let mut filter = Filter::new();
+    filter.authors = moderators(author, d)?;
+    filter.kinds = vec![EventKind::CommunityPostApproval];
+    filter.add_tag_value('a', community_address(author, d));
+
+    let mut approved: HashSet<Id> = HashSet::new();
+    for approval in GLOBALS.db().find_events_by_filter(&filter, |_| true)? {
+        for tag in &approval.tags {
+            if let Ok(ParsedTag::Event { id, .. }) = tag.parse() {
+                approved.insert(id);
+            }
+        }
+    }
+    Ok(approved)
+}
+
+/// Join a community. Relay hints are where we may find it.
+pub(crate) fn join_community(
+    author: PublicKey,
+    d: &str,
+    relays: Vec<RelayUrl>,
+) -> Result<(), Error> {
+    CommunitiesTable::modify(
+        community_address(author, d),
+        |community| {
+            community.joined = true;
+            for url in relays.iter() {
+                if !community.relays.contains(url) {
+                    community.relays.push(url.clone());
+                }
+            }
+        },
+        None,
+    )
+}
+
+/// Leave a community
+pub(crate) fn leave_community(author: PublicKey, d: &str) -> Result<(), Error> {
+    CommunitiesTable::modify_if_exists(
+        community_address(author, d),
+        |community| community.joined = false,
+        None,
+    )?;
+    Ok(())
+}
+
+/// Record a community from its definition, so that it can be discovered
+pub(crate) fn process_community_definition(event: &Event) -> Result<(), Error> {
+    let community = match CommunityDefinition::from_event(event) {
+        Some(community) if !community.d.is_empty() => community,
+        _ => return Ok(()),
+    };
+    CommunitiesTable::read_or_create_record(community.address(), None)?;
+    Ok(())
+}
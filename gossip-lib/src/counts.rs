@@ -0,0 +1,102 @@
+use nostr_types::{EventKind, Filter, Id, PublicKey, RelayUrl, Unixtime};
+use std::collections::HashMap;
+
+/// How long before we will ask relays to count the same thing again
+pub const COUNT_REFRESH_SECS: i64 = 60 * 10;
+
+/// How long we keep counts around after we last asked for them
+pub const COUNT_EXPIRE_SECS: i64 = 60 * 60;
+
+/// The most notes we ask relays to count things about at once. Each note costs
+/// a COUNT request per `CountKind` on every counting relay.
+pub const MAX_COUNTED_NOTES: usize = 10;
+
+/// Something we can ask relays to count for us (NIP-45)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CountKind {
+    /// People who have the pubkey in their contact list
+    Followers(PublicKey),
+
+    /// Text notes that refer to the event. Relays can't tell replies from
+    /// mentions and quotes, so this counts all of them.
+    References(Id),
+
+    /// Reactions to the event
+    Reactions(Id),
+}
+
+impl CountKind {
+    /// The filter to send in the COUNT request
+    pub fn filter(&self) -> Filter {
+        match self {
+            CountKind::Followers(pubkey) => {
+                let mut filter = Filter {
+                    kinds: vec![EventKind::ContactList],
+                    ..Default::default()
+                };
+                filter.set_tag_values('p', vec![pubkey.as_hex_string()]);
+                filter
+            }
+            CountKind::References(id) => {
+                let mut filter = Filter {
+                    kinds: vec![EventKind::TextNote],
+                    ..Default::default()
+                };
+                filter.set_tag_values('e', vec![id.as_hex_string()]);
+                filter
+            }
+            CountKind::Reactions(id) => {
+                let mut filter = Filter {
+                    kinds: vec![EventKind::Reaction],
+                    ..Default::default()
+                };
+                filter.set_tag_values('e', vec![id.as_hex_string()]);
+                filter
+            }
+        }
+    }
+}
+
+/// The counts relays have returned for a `CountKind`
+#[derive(Debug, Clone)]
+pub struct RelayCounts {
+    /// When we last asked relays for this count
+    pub requested_at: Unixtime,
+
+    /// The count each relay returned
+    pub per_relay: HashMap<RelayUrl, u64>,
+}
+
+impl RelayCounts {
+    pub fn new() -> RelayCounts {
+        RelayCounts {
+            requested_at: Unixtime::now(),
+            per_relay: HashMap::new(),
+        }
+    }
+
+    /// Our best estimate of the count.
+    ///
+    /// Relays mostly hold copies of the same events, so summing would count most
+    /// events many times over. We take the largest count reported by any relay
+    /// instead, which is a lower bound on the true count.
+    pub fn count(&self) -> Option<u64> {
+        self.per_relay.values().max().copied()
+    }
+
+    /// Whether we asked recently enough that we shouldn't ask again yet
+    pub fn is_fresh(&self) -> bool {
+        Unixtime::now().0 - self.requested_at.0 < COUNT_REFRESH_SECS
+    }
+
+    /// Whether we asked so long ago that these counts should be dropped
+    pub fn is_expired(&self) -> bool {
+        Unixtime::now().0 - self.requested_at.0 >= COUNT_EXPIRE_SECS
+    }
+}
+
+impl Default for RelayCounts {
+    fn default() -> RelayCounts {
+        RelayCounts::new()
+    }
+}
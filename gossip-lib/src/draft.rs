@@ -0,0 +1,133 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::storage::{DraftsTable, Table};
+use nostr_types::{ContentEncryptionAlgorithm, PublicKey, Tag, Unixtime};
+use serde::{Deserialize, Serialize};
+
+pub type Draft = crate::storage::types::Draft1;
+pub type DraftTarget = crate::storage::types::DraftTarget1;
+
+// What is encrypted in an encrypted draft
+#[derive(Serialize, Deserialize)]
+struct SealedDraft {
+    content: String,
+    tags: Vec<Tag>,
+    mentions: Vec<(String, String)>,
+}
+
+/// All saved drafts of the current account, most recently saved first.
+///
+/// Encrypted drafts are decrypted if the private key is unlocked, otherwise they
+/// are returned still `encrypted`.
+pub async fn drafts() -> Result<Vec<Draft>, Error> {
+    let public_key = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(vec![]),
+    };
+    let prefix = format!("{}:", public_key.as_hex_string());
+
+    let mut drafts = DraftsTable::filter_records(|d| d.key.starts_with(&prefix))?;
+    if can_encrypt() {
+        for draft in drafts.iter_mut().filter(|d| d.encrypted) {
+            if let Err(e) = unseal(draft, public_key).await {
+                tracing::error!("{}", e);
+            }
+        }
+    }
+    drafts.sort_by_key(|d| std::cmp::Reverse(d.updated_at.0));
+    Ok(drafts)
+}
+
+/// The saved draft of the current account for this target, if there is one we can read
+pub async fn load_draft(target: &DraftTarget) -> Result<Option<Draft>, Error> {
+    let public_key = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(None),
+    };
+
+    let mut draft = match DraftsTable::read_record(key(public_key, target), None)? {
+        Some(draft) => draft,
+        None => return Ok(None),
+    };
+
+    if draft.encrypted {
+        if !can_encrypt() {
+            return Ok(None);
+        }
+        unseal(&mut draft, public_key).await?;
+    }
+
+    Ok(Some(draft))
+}
+
+/// Save a draft of the current account, replacing the draft for the same target.
+/// An empty draft is deleted instead.
+///
+/// Direct message drafts are encrypted to ourself, so they are only saved while
+/// the private key is unlocked.
+pub async fn save_draft(mut draft: Draft) -> Result<(), Error> {
+    let public_key = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(()),
+    };
+
+    let encrypt = matches!(draft.target, DraftTarget::DirectMessage(_));
+    if encrypt && !can_encrypt() {
+        return Ok(());
+    }
+
+    draft.key = key(public_key, &draft.target);
+    if draft.content.trim().is_empty() {
+        return DraftsTable::delete_record(draft.key, None);
+    }
+    draft.updated_at = Unixtime::now();
+    if encrypt {
+        seal(&mut draft, public_key).await?;
+    }
+    DraftsTable::write_record(&mut draft, None)
+}
+
+/// Delete the saved draft of the current account for this target
+pub fn delete_draft(target: &DraftTarget) -> Result<(), Error> {
+    match GLOBALS.identity.public_key() {
+        Some(pk) => DraftsTable::delete_record(key(pk, target), None),
+        None => Ok(()),
+    }
+}
+
+fn key(public_key: PublicKey, target: &DraftTarget) -> String {
+    format!("{}:{}", public_key.as_hex_string(), target.key())
+}
+
+// If we can encrypt and decrypt drafts without asking a remote signer
+fn can_encrypt() -> bool {
+    GLOBALS.identity.has_private_key() && GLOBALS.identity.is_unlocked()
+}
+
+async fn seal(draft: &mut Draft, public_key: PublicKey) -> Result<(), Error> {
+    let sealed = SealedDraft {
+        content: std::mem::take(&mut draft.content),
+        tags: std::mem::take(&mut draft.tags),
+        mentions: std::mem::take(&mut draft.mentions),
+    };
+    let json = serde_json::to_string(&sealed)?;
+    draft.content = GLOBALS
+        .identity
+        .encrypt(&public_key, &json, ContentEncryptionAlgorithm::Nip44v2)
+        .await?;
+    draft.encrypted = true;
+    Ok(())
+}
+
+async fn unseal(draft: &mut Draft, public_key: PublicKey) -> Result<(), Error> {
+    let json = GLOBALS
+        .identity
+        .decrypt(&public_key, &draft.content)
+        .await?;
+    let sealed: SealedDraft = serde_json::from_str(&json)?;
+    draft.content = sealed.content;
+    draft.tags = sealed.tags;
+    draft.mentions = sealed.mentions;
+    draft.encrypted = false;
+    Ok(())
+}
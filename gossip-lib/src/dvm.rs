@@ -0,0 +1,453 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::relationship::RelationshipById;
+use crate::storage::{PersonTable, Table};
+use nostr_types::{Event, EventKind, Filter, Id, Metadata, ParsedTag, PublicKey, RelayUrl, Tag};
+use std::collections::HashMap;
+
+/// The kinds of jobs that can be requested, with what they do
+pub const JOB_KINDS: [(u32, &str); 7] = [
+    (5000, "Extract text"),
+    (5001, "Summarize"),
+    (5002, "Translate"),
+    (5050, "Generate text"),
+    (5100, "Generate images"),
+    (5300, "Discover content"),
+    (5301, "Discover people"),
+];
+
+/// The kind of job that asks for a feed of notes
+pub const CONTENT_DISCOVERY: u32 = 5300;
+
+/// What a kind of job does, for display
+pub fn job_kind_name(kind: u32) -> String {
+    match JOB_KINDS.iter().find(|(k, _)| *k == kind) {
+        Some((_, name)) => (*name).to_owned(),
+        None => format!("Job kind {}", kind),
+    }
+}
+
+/// The event kinds of the job requests we can make
+pub fn job_request_kinds() -> Vec<EventKind> {
+    JOB_KINDS.iter().map(|(k, _)| EventKind::from(*k)).collect()
+}
+
+/// The event kinds of the results of the job requests we can make
+pub fn job_result_kinds() -> Vec<EventKind> {
+    JOB_KINDS
+        .iter()
+        .map(|(k, _)| EventKind::from(*k + 1000))
+        .collect()
+}
+
+/// A data vending machine, from its NIP-89 handler announcement
+#[derive(Debug, Clone)]
+pub struct Dvm {
+    /// The key the DVM works under
+    pub pubkey: PublicKey,
+
+    /// The identifier of the announcement
+    pub d: String,
+
+    /// The metadata in the announcement, if any
+    pub metadata: Option<Metadata>,
+
+    /// The kinds of jobs it takes
+    pub kinds: Vec<u32>,
+}
+
+impl Dvm {
+    /// Parse a handler announcement. Returns None unless it announces job kinds.
+    pub fn from_event(event: &Event) -> Option<Dvm> {
+        if event.kind != EventKind::HandlerInformation {
+            return None;
+        }
+
+        let mut d = "".to_owned();
+        let mut kinds: Vec<u32> = Vec::new();
+        for tag in &event.tags {
+            match tag.tagname() {
+                "d" => d = tag.value().to_owned(),
+                "k" => {
+                    if let Ok(kind) = tag.value().parse::<u32>() {
+                        if (5000..6000).contains(&kind) && !kinds.contains(&kind) {
+                            kinds.push(kind);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if kinds.is_empty() {
+            return None;
+        }
+
+        Some(Dvm {
+            pubkey: event.pubkey,
+            d,
+            metadata: serde_json::from_str::<Metadata>(&event.content).ok(),
+            kinds,
+        })
+    }
+
+    /// The name of the DVM for display
+    pub fn name(&self) -> String {
+        match self.metadata.as_ref().and_then(|m| m.name.clone()) {
+            Some(name) if !name.is_empty() => name,
+            _ => crate::names::best_name_from_pubkey_lookup(&self.pubkey),
+        }
+    }
+
+    /// What the DVM says about itself
+    pub fn about(&self) -> String {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.about.clone())
+            .unwrap_or_default()
+    }
+
+    /// If it takes this kind of job
+    pub fn takes(&self, kind: u32) -> bool {
+        self.kinds.contains(&kind)
+    }
+}
+
+/// The DVMs we know of, from the handler announcements we have, by name
+pub fn dvms() -> Result<Vec<Dvm>, Error> {
+    let mut filter = Filter::new();
+    filter.kinds = vec![EventKind::HandlerInformation];
+    latest_dvms(&filter)
+}
+
+// The DVMs announced by this pubkey, by name
+fn dvms_of(pubkey: PublicKey) -> Result<Vec<Dvm>, Error> {
+    let mut filter = Filter::new();
+    filter.add_author(pubkey);
+    filter.kinds = vec![EventKind::HandlerInformation];
+    latest_dvms(&filter)
+}
+
+// The DVMs in the handler announcements matching the filter, by name
+fn latest_dvms(filter: &Filter) -> Result<Vec<Dvm>, Error> {
+    // Keep only the latest announcement under each identifier
+    let mut dvms: HashMap<(PublicKey, String), (Event, Dvm)> = HashMap::new();
+    for event in GLOBALS.db().find_events_by_filter(filter, |_| true)? {
+        let Some(dvm) = Dvm::from_event(&event) else {
+            continue;
+        };
+        let key = (dvm.pubkey, dvm.d.clone());
+        match dvms.get(&key) {
+            Some((existing, _)) if existing.created_at >= event.created_at => {}
+            _ => {
+                dvms.insert(key, (event, dvm));
+            }
+        }
+    }
+
+    let mut dvms: Vec<Dvm> = dvms.into_values().map(|(_, dvm)| dvm).collect();
+    dvms.sort_by_key(|dvm| dvm.name().to_lowercase());
+    Ok(dvms)
+}
+
+/// The name of a DVM for display, from its announcement if we have it
+pub fn dvm_name(pubkey: PublicKey) -> String {
+    match dvms_of(pubkey) {
+        Ok(dvms) if !dvms.is_empty() => dvms[0].name(),
+        _ => crate::names::best_name_from_pubkey_lookup(&pubkey),
+    }
+}
+
+/// The lnurl to zap a DVM at, from its profile or its announcement
+pub fn dvm_lnurl(pubkey: PublicKey) -> Option<String> {
+    if let Ok(Some(person)) = PersonTable::read_record(pubkey, None) {
+        if let Some(lnurl) = person.metadata().as_ref().and_then(|m| m.lnurl()) {
+            return Some(lnurl);
+        }
+    }
+    dvms_of(pubkey)
+        .ok()?
+        .iter()
+        .find_map(|dvm| dvm.metadata.as_ref().and_then(|m| m.lnurl()))
+}
+
+/// A job to ask the DVMs for
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobRequest {
+    /// The kind of job
+    pub kind: u32,
+
+    /// The input, if the job takes one
+    pub input: String,
+
+    /// What the input is: `text`, `url`, `event` or `job`
+    pub input_type: String,
+
+    /// Parameters of the job, such as the language to translate to
+    pub params: Vec<(String, String)>,
+
+    /// The DVM to ask. If None, any DVM may take the job.
+    pub dvm: Option<PublicKey>,
+}
+
+impl JobRequest {
+    /// The tags of the job request event. DVMs send feedback and results to `relays`.
+    pub(crate) fn tags(&self, relays: &[RelayUrl]) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = Vec::new();
+        if !self.input.is_empty() {
+            tags.push(Tag::new(&["i", &self.input, &self.input_type]));
+        }
+        for (name, value) in &self.params {
+            tags.push(Tag::new(&["param", name, value]));
+        }
+        if let Some(dvm) = self.dvm {
+            tags.push(Tag::new(&["p", &dvm.as_hex_string()]));
+        }
+        if !relays.is_empty() {
+            let mut relays_tag: Vec<&str> = vec!["relays"];
+            relays_tag.extend(relays.iter().map(|r| r.as_str()));
+            tags.push(Tag::new(&relays_tag));
+        }
+        tags
+    }
+}
+
+/// Feedback from a DVM on a job
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobFeedback {
+    /// The feedback event. Zaps paying for a job go to this event.
+    pub event: Event,
+
+    /// `payment-required`, `processing`, `error`, `success` or `partial`
+    pub status: String,
+
+    /// More about the status, from the DVM
+    pub extra: String,
+
+    /// How much the DVM wants, in millisatoshis
+    pub amount: Option<u64>,
+}
+
+impl JobFeedback {
+    /// Parse a job feedback event. Returns None for other kinds of events.
+    pub fn from_event(event: &Event) -> Option<JobFeedback> {
+        if event.kind != EventKind::JobFeedback {
+            return None;
+        }
+
+        let mut feedback = JobFeedback {
+            event: event.clone(),
+            status: "".to_owned(),
+            extra: event.content.clone(),
+            amount: None,
+        };
+        for tag in &event.tags {
+            match tag.tagname() {
+                "status" => {
+                    feedback.status = tag.value().to_owned();
+                    if feedback.extra.is_empty() {
+                        feedback.extra = tag.get_index(2).to_owned();
+                    }
+                }
+                "amount" => feedback.amount = tag.value().parse::<u64>().ok(),
+                _ => {}
+            }
+        }
+        Some(feedback)
+    }
+
+    /// If the DVM wants to be paid before (or after) doing the job
+    pub fn payment_required(&self) -> bool {
+        self.status == "payment-required"
+    }
+}
+
+/// A job we asked for, with what the DVMs made of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    /// The job request event
+    pub request: Event,
+
+    /// The latest feedback from each DVM
+    pub feedback: Vec<JobFeedback>,
+
+    /// The results, oldest first
+    pub results: Vec<Event>,
+}
+
+impl Job {
+    /// The kind of job
+    pub fn kind(&self) -> u32 {
+        u32::from(self.request.kind)
+    }
+
+    /// The input of the job
+    pub fn input(&self) -> String {
+        self.request
+            .tags
+            .iter()
+            .find(|t| t.tagname() == "i")
+            .map(|t| t.value().to_owned())
+            .unwrap_or_default()
+    }
+
+    /// The DVM the job was asked of, if just one
+    pub fn dvm(&self) -> Option<PublicKey> {
+        job_dvm(&self.request)
+    }
+}
+
+// The DVM a job request is addressed to
+fn job_dvm(request: &Event) -> Option<PublicKey> {
+    request.tags.iter().find_map(|t| match t.parse() {
+        Ok(ParsedTag::Pubkey { pubkey, .. }) => Some(pubkey),
+        _ => None,
+    })
+}
+
+// The job request that a feedback or result event is about
+fn request_of(event: &Event) -> Option<Id> {
+    event.tags.iter().find_map(|t| match t.parse() {
+        Ok(ParsedTag::Event { id, .. }) => Some(id),
+        _ => None,
+    })
+}
+
+/// The jobs the user has asked for, latest first
+pub fn jobs() -> Result<Vec<Job>, Error> {
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pubkey) => pubkey,
+        None => return Ok(vec![]),
+    };
+
+    let mut filter = Filter::new();
+    filter.add_author(pubkey);
+    filter.kinds = job_request_kinds();
+    filter.limit = Some(50);
+    let mut requests = GLOBALS.db().find_events_by_filter(&filter, |_| true)?;
+    requests.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    let mut jobs: Vec<Job> = Vec::new();
+    for request in requests.drain(..) {
+        jobs.push(Job {
+            feedback: feedback(&request)?,
+            results: results(request.id)?,
+            request,
+        });
+    }
+    Ok(jobs)
+}
+
+/// The latest feedback from each DVM on a job request
+pub fn feedback(request: &Event) -> Result<Vec<JobFeedback>, Error> {
+    let mut filter = Filter::new();
+    filter.kinds = vec![EventKind::JobFeedback];
+    filter.add_tag_value('e', request.id.as_hex_string());
+    filter.since = Some(request.created_at);
+    let screen = |e: &Event| request_of(e) == Some(request.id);
+
+    let mut latest: HashMap<PublicKey, JobFeedback> = HashMap::new();
+    for event in GLOBALS.db().find_events_by_filter(&filter, screen)? {
+        let Some(feedback) = JobFeedback::from_event(&event) else {
+            continue;
+        };
+        match latest.get(&event.pubkey) {
+            Some(existing) if existing.event.created_at >= event.created_at => {}
+            _ => {
+                latest.insert(event.pubkey, feedback);
+            }
+        }
+    }
+    Ok(latest.into_values().collect())
+}
+
+/// The results of a job request, oldest first
+pub fn results(id: Id) -> Result<Vec<Event>, Error> {
+    let mut results: Vec<Event> = Vec::new();
+    for (result_id, rel) in GLOBALS.db().find_relationships_by_id(id)? {
+        if matches!(rel, RelationshipById::SuppliesJobResult) {
+            if let Some(event) = GLOBALS.db().read_event(result_id)? {
+                results.push(event);
+            }
+        }
+    }
+    results.sort_by_key(|e| e.created_at);
+    Ok(results)
+}
+
+/// The notes a content discovery result lists, with any relay hints
+pub fn result_ids(result: &Event) -> Vec<(Id, Vec<RelayUrl>)> {
+    // The content is a JSON array of tags
+    let tags: Vec<Tag> = match serde_json::from_str(&result.content) {
+        Ok(tags) => tags,
+        Err(_) => return vec![],
+    };
+    tags.iter()
+        .filter_map(|tag| match tag.parse() {
+            Ok(ParsedTag::Event {
+                id,
+                recommended_relay_url,
+                ..
+            }) => {
+                let relays = recommended_relay_url
+                    .and_then(|r| RelayUrl::try_from_unchecked_url(&r).ok())
+                    .into_iter()
+                    .collect();
+                Some((id, relays))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The latest content discovery job the user asked of a DVM
+pub fn discovery_request(dvm: PublicKey) -> Result<Option<Event>, Error> {
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pubkey) => pubkey,
+        None => return Ok(None),
+    };
+
+    let mut filter = Filter::new();
+    filter.add_author(pubkey);
+    filter.kinds = vec![EventKind::from(CONTENT_DISCOVERY)];
+    let requests = GLOBALS
+        .db()
+        .find_events_by_filter(&filter, |e| job_dvm(e) == Some(dvm))?;
+    Ok(requests.into_iter().max_by_key(|e| e.created_at))
+}
+
+/// The notes of a DVM feed: those in the latest result of the latest content
+/// discovery job asked of the DVM
+pub fn discovery_feed(dvm: PublicKey) -> Result<Vec<Id>, Error> {
+    let request = match discovery_request(dvm)? {
+        Some(request) => request,
+        None => return Ok(vec![]),
+    };
+    let result = results(request.id)?
+        .into_iter()
+        .filter(|e| e.pubkey == dvm)
+        .max_by_key(|e| e.created_at);
+    Ok(match result {
+        Some(result) => result_ids(&result).into_iter().map(|(id, _)| id).collect(),
+        None => vec![],
+    })
+}
+
+/// Seek the notes listed in a content discovery result for one of our jobs
+pub(crate) fn process_job_result(event: &Event) -> Result<(), Error> {
+    if u32::from(event.kind) != CONTENT_DISCOVERY + 1000 {
+        return Ok(());
+    }
+    let request = match request_of(event) {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    if !GLOBALS.db().is_my_event(request)? {
+        return Ok(());
+    }
+    for (id, relays) in result_ids(event) {
+        if GLOBALS.db().read_event(id)?.is_none() {
+            GLOBALS.seeker.seek_id(id, relays, false)?;
+        }
+    }
+    Ok(())
+}
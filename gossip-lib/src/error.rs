@@ -29,6 +29,7 @@ pub enum ErrorKind {
     Lmdb(heed::Error),
     MaxRelaysReached,
     MpscSend(Box<tokio::sync::mpsc::error::SendError<ToOverlordMessage>>),
+    Negentropy(String),
     Nip05KeyNotFound,
     Nip46CommandMissingId,
     Nip46CommandNotJsonObject,
@@ -36,6 +37,7 @@ pub enum ErrorKind {
     Nip46NeedApproval,
     Nip46ParsingError(String, String),
     Nip46RelayNeeded,
+    Nip96Error(String),
     Nostr(nostr_types::Error),
     NoPrivateKey,
     NoPublicKey,
@@ -67,6 +69,7 @@ pub enum ErrorKind {
     Offline,
     ParseInt(std::num::ParseIntError),
     ParseBool(std::str::ParseBoolError),
+    Proxy(String),
     RecordIsNotNewable,
     Regex(regex::Error),
     RelayRejectedUs,
@@ -87,6 +90,7 @@ pub enum ErrorKind {
     Usage(String, String), // error, usage line
     UsersCantUseNip17,
     Utf8Error(std::str::Utf8Error),
+    Wallet(String),
     Websocket(tungstenite::Error),
     WrongEventKind,
 }
@@ -137,6 +141,7 @@ impl std::fmt::Display for ErrorKind {
                 "Maximum relay connections reached, will not connect to another"
             ),
             MpscSend(e) => write!(f, "Error sending mpsc: {e}"),
+            Negentropy(s) => write!(f, "Negentropy error: {s}"),
             Nip05KeyNotFound => write!(f, "NIP-05 public key not found"),
             Nip46CommandMissingId => write!(f, "NIP-46 command missing ID"),
             Nip46CommandNotJsonObject => write!(f, "NIP-46 command not a json object"),
@@ -144,6 +149,7 @@ impl std::fmt::Display for ErrorKind {
             Nip46NeedApproval => write!(f, "NIP-46 approval needed"),
             Nip46ParsingError(_id, e) => write!(f, "NIP-46 parse error: {e}"),
             Nip46RelayNeeded => write!(f, "NIP-46 relay needed to respond."),
+            Nip96Error(s) => write!(f, "NIP-96 error: {s}"),
             Nostr(e) => write!(f, "Nostr: {e}"),
             NoPrivateKey => write!(f, "No private key is available."),
             NoPublicKey => write!(f, "No public key identity available."),
@@ -178,6 +184,7 @@ impl std::fmt::Display for ErrorKind {
             Offline => write!(f, "Offline"),
             ParseInt(e) => write!(f, "Bad integer: {e}"),
             ParseBool(e) => write!(f, "Bad bool: {e}"),
+            Proxy(s) => write!(f, "Proxy error: {s}"),
             RecordIsNotNewable => write!(f, "Record is not newable"),
             Regex(e) => write!(f, "Regex: {e}"),
             RelayRejectedUs => write!(f, "Relay rejected us."),
@@ -198,6 +205,7 @@ impl std::fmt::Display for ErrorKind {
             Usage(e, u) => write!(f, "{}\n\nUsage: {}", e, u),
             UsersCantUseNip17 => write!(f, "User(s) can't use NIP-17 DMs"),
             Utf8Error(e) => write!(f, "UTF-8 error: {e}"),
+            Wallet(s) => write!(f, "Wallet: {s}"),
             Websocket(e) => write!(f, "Websocket: {e}"),
             WrongEventKind => write!(f, "Wrong event kind"),
         }
@@ -18,6 +18,10 @@ pub enum FeedKind {
     },
     Person(PublicKey),
     DmChat(DmChannel),
+    Channel(Id),
+    Group(RelayUrl, String),
+    Community(PublicKey, String),
+    DvmFeed(PublicKey),
     Global,
     Relay(RelayUrl),
 }
@@ -38,6 +42,14 @@ impl std::fmt::Display for FeedKind {
             } => write!(f, "Thread {}", crate::names::hex_id_short(&(*id).into())),
             FeedKind::Person(pk) => write!(f, "{}", crate::names::best_name_from_pubkey_lookup(pk)),
             FeedKind::DmChat(channel) => write!(f, "{}", channel.name()),
+            FeedKind::Channel(id) => write!(f, "{}", crate::channels::channel_name(*id)),
+            FeedKind::Group(relay, group_id) => {
+                write!(f, "{}", crate::groups::group_name(relay, group_id))
+            }
+            FeedKind::Community(author, d) => {
+                write!(f, "{}", crate::communities::community_name(*author, d))
+            }
+            FeedKind::DvmFeed(pubkey) => write!(f, "{}", crate::dvm::dvm_name(*pubkey)),
             FeedKind::Global => write!(f, "Global"),
             FeedKind::Relay(relayurl) => write!(f, "{}", relayurl),
         }
@@ -54,6 +66,10 @@ impl FeedKind {
             Self::Thread { .. } => "thread".to_owned(),
             Self::Person(pubkey) => format!("person{}", pubkey.as_hex_string()),
             Self::DmChat(_) => "dmchat".to_owned(),
+            Self::Channel(id) => format!("channel{}", id.as_hex_string()),
+            Self::Group(relay, group_id) => format!("group {}'{}", relay, group_id),
+            Self::Community(author, d) => format!("community {}'{}", author.as_hex_string(), d),
+            Self::DvmFeed(pubkey) => format!("dvm{}", pubkey.as_hex_string()),
             Self::Global => "global".to_owned(),
             Self::Relay(relayurl) => format!("relay {}", relayurl),
         }
@@ -66,7 +82,11 @@ impl FeedKind {
             Self::Inbox(_) => true,
             Self::Thread { .. } => false, // always full
             Self::Person(_) => true,
-            Self::DmChat(_) => false, // always full
+            Self::DmChat(_) => false,       // always full
+            Self::Channel(_) => false,      // always full
+            Self::Group(_, _) => false,     // always full
+            Self::Community(_, _) => false, // always full
+            Self::DvmFeed(_) => false,      // the DVM picks what is in it
             Self::Global => true,
             Self::Relay(_) => true,
         }
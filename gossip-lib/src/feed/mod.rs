@@ -43,6 +43,9 @@ pub struct Feed {
 
     thread_parent: Arc<RwLock<Option<Id>>>,
 
+    // Posts in the current community feed that await approval (only for moderators)
+    community_pending: Arc<RwLock<HashSet<Id>>>,
+
     last_volatile_feed: Arc<RwLock<Option<FeedKind>>>,
 }
 
@@ -65,6 +68,7 @@ impl Feed {
             interval_ms: Arc::new(RwLock::new(10000)), // Every 10 seconds, until we load from settings
             last_computed: Arc::new(RwLock::new(None)),
             thread_parent: Arc::new(RwLock::new(None)),
+            community_pending: Arc::new(RwLock::new(HashSet::new())),
             last_volatile_feed: Arc::new(RwLock::new(None)),
         }
     }
@@ -153,6 +157,64 @@ impl Feed {
             });
         }
 
+        // If not in a Channel feed
+        if !matches!(feed_kind, FeedKind::Channel(_)) {
+            // Stop listening to Channel events
+            for filter_set in [
+                FilterSet::Channel(Id([0; 32])),
+                FilterSet::ChannelModeration(*DUMMY_PUBKEY),
+            ] {
+                let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                    target: "all".to_string(),
+                    payload: ToMinionPayload {
+                        job_id: 0,
+                        detail: ToMinionPayloadDetail::Unsubscribe(filter_set),
+                    },
+                });
+            }
+        }
+
+        // If not in a Group feed
+        if !matches!(feed_kind, FeedKind::Group(_, _)) {
+            // Stop listening to Group events
+            for filter_set in [
+                FilterSet::Group("".to_owned()),
+                FilterSet::GroupMetadata("".to_owned()),
+            ] {
+                let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                    target: "all".to_string(),
+                    payload: ToMinionPayload {
+                        job_id: 0,
+                        detail: ToMinionPayloadDetail::Unsubscribe(filter_set),
+                    },
+                });
+            }
+        }
+
+        // If not in a Community feed
+        if !matches!(feed_kind, FeedKind::Community(_, _)) {
+            // Stop listening to Community events
+            let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                target: "all".to_string(),
+                payload: ToMinionPayload {
+                    job_id: 0,
+                    detail: ToMinionPayloadDetail::Unsubscribe(FilterSet::Community("".to_owned())),
+                },
+            });
+        }
+
+        // If not in a DVM feed
+        if !matches!(feed_kind, FeedKind::DvmFeed(_)) {
+            // Stop listening for DVM results
+            let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                target: "all".to_string(),
+                payload: ToMinionPayload {
+                    job_id: 0,
+                    detail: ToMinionPayloadDetail::Unsubscribe(FilterSet::DvmJobs(vec![])),
+                },
+            });
+        }
+
         // If not in a Relay feed
         if let FeedKind::Relay(relay_url) = feed_kind {
             // Stop listening to Global events
@@ -251,6 +313,31 @@ impl Feed {
                     .to_overlord
                     .send(ToOverlordMessage::SetDmChannel(dm_channel.clone()));
             }
+            FeedKind::Channel(id) => {
+                // Listen for Channel events
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::SetChannelFeed(*id));
+            }
+            FeedKind::Group(relay, group_id) => {
+                // Listen for Group events
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SetGroupFeed(
+                    relay.clone(),
+                    group_id.clone(),
+                ));
+            }
+            FeedKind::Community(author, d) => {
+                // Listen for Community events
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::SetCommunityFeed(*author, d.clone()));
+            }
+            FeedKind::DvmFeed(pubkey) => {
+                // Ask the DVM for a feed, and listen for it
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::SetDvmFeed(*pubkey));
+            }
             FeedKind::Global => {
                 let _ = GLOBALS
                     .to_overlord
@@ -328,6 +415,13 @@ impl Feed {
         *self.thread_parent.read_arc()
     }
 
+    /// If a post in the current community feed awaits approval by the moderators.
+    /// Such posts are only in the feed if the user is one of them.
+    pub fn is_pending_approval(&self, id: Id) -> bool {
+        matches!(self.get_feed_kind(), FeedKind::Community(_, _))
+            && self.community_pending.read_arc().contains(&id)
+    }
+
     /// When initially changing to the thread feed, the Overlord sets the thread
     /// parent to the highest locally available one (or the event if it is not local)
     pub(crate) fn set_thread_parent(&self, id: Id) {
@@ -459,6 +553,105 @@ impl Feed {
                 let ids = GLOBALS.db().dm_events(&channel).await?;
                 *self.current_feed_events.write_arc() = ids;
             }
+            FeedKind::Channel(id) => {
+                // Honor messages hidden and users muted by the channel creator
+                let (hidden, muted) = match crate::channels::channel(id)? {
+                    Some(channel) => crate::channels::channel_moderation(&channel)?,
+                    None => Default::default(),
+                };
+                let dismissed = GLOBALS.dismissed.read().await.clone();
+                let now = Unixtime::now();
+
+                let mut filter = Filter::new();
+                filter.kinds = vec![EventKind::ChannelMessage];
+                filter.add_tag_value('e', id.as_hex_string());
+
+                let screen = |e: &Event| {
+                    e.created_at <= now
+                        && crate::channels::channel_of(e) == Some(id)
+                        && !hidden.contains(&e.id)
+                        && !muted.contains(&e.pubkey)
+                        && !dismissed.contains(&e.id)
+                };
+
+                let events = GLOBALS.db().find_events_by_filter(&filter, screen)?;
+                *self.current_feed_events.write_arc() = events.iter().map(|e| e.id).collect();
+            }
+            FeedKind::Group(relay, group_id) => {
+                let dismissed = GLOBALS.dismissed.read().await.clone();
+                let my_pubkey = GLOBALS.identity.public_key();
+                let now = Unixtime::now();
+
+                let mut filter = Filter::new();
+                filter.kinds = crate::groups::GROUP_CHAT_KINDS.to_vec();
+                filter.add_tag_value('h', group_id.clone());
+
+                // Group ids are only unique within a relay, so only take events
+                // that came from the group relay (or that we posted ourselves)
+                let screen = |e: &Event| {
+                    e.created_at <= now
+                        && crate::groups::group_of(e).as_deref() == Some(group_id.as_str())
+                        && !dismissed.contains(&e.id)
+                        && (Some(e.pubkey) == my_pubkey
+                            || GLOBALS
+                                .db()
+                                .get_event_seen_on_relay(e.id)
+                                .map(|seen| seen.iter().any(|(url, _)| *url == relay))
+                                .unwrap_or(false))
+                };
+
+                let events = GLOBALS.db().find_events_by_filter(&filter, screen)?;
+                *self.current_feed_events.write_arc() = events.iter().map(|e| e.id).collect();
+            }
+            FeedKind::Community(author, d) => {
+                // Only show posts approved by the moderators, and their own posts.
+                // Moderators see posts awaiting approval too, so they can approve them.
+                let moderators = crate::communities::moderators(author, &d)?;
+                let approved = crate::communities::approved_posts(author, &d)?;
+                let am_moderator = GLOBALS
+                    .identity
+                    .public_key()
+                    .is_some_and(|pk| moderators.contains(&pk));
+                let dismissed = GLOBALS.dismissed.read().await.clone();
+                let now = Unixtime::now();
+
+                let mut filter = Filter::new();
+                filter.kinds = vec![EventKind::TextNote, EventKind::Comment];
+                filter.add_tag_value('a', crate::communities::community_address(author, &d));
+
+                let is_approved =
+                    |e: &Event| approved.contains(&e.id) || moderators.contains(&e.pubkey);
+
+                let screen = |e: &Event| {
+                    e.created_at <= now
+                        && crate::communities::is_top_level(e)
+                        && crate::communities::community_of(e) == Some((author, d.clone()))
+                        && !dismissed.contains(&e.id)
+                        && (am_moderator || is_approved(e))
+                };
+
+                let events = GLOBALS.db().find_events_by_filter(&filter, screen)?;
+                *self.community_pending.write_arc() = events
+                    .iter()
+                    .filter(|e| !is_approved(e))
+                    .map(|e| e.id)
+                    .collect();
+                *self.current_feed_events.write_arc() = events.iter().map(|e| e.id).collect();
+            }
+            FeedKind::DvmFeed(pubkey) => {
+                // The DVM lists the notes in its own order. Show the ones we have.
+                let dismissed = GLOBALS.dismissed.read().await.clone();
+                let mut ids: Vec<Id> = Vec::new();
+                for id in crate::dvm::discovery_feed(pubkey)? {
+                    if !dismissed.contains(&id)
+                        && !ids.contains(&id)
+                        && GLOBALS.db().read_event(id)?.is_some()
+                    {
+                        ids.push(id);
+                    }
+                }
+                *self.current_feed_events.write_arc() = ids;
+            }
             FeedKind::Global | FeedKind::Relay(_) => {
                 let dismissed = GLOBALS.dismissed.read().await.clone();
 
@@ -622,8 +815,11 @@ fn basic_screen(e: &Event, include_replies: bool, dismissed: &[Id]) -> bool {
         && e.kind != EventKind::EncryptedDirectMessage
         && e.kind != EventKind::DmChat
         && e.kind != EventKind::GiftWrap
+        && e.kind != EventKind::ChannelMessage
+        && !crate::groups::GROUP_CHAT_KINDS.contains(&e.kind)
         && !dismissed.contains(&e.id)
         && !e.is_annotation()
+        && !crate::moderation::is_hidden(e.id)
 }
 
 pub fn enabled_event_kinds() -> Vec<EventKind> {
@@ -634,6 +830,15 @@ pub fn enabled_event_kinds() -> Vec<EventKind> {
     let enable_zap_receipts = GLOBALS.db().read_setting_enable_zap_receipts();
     let enable_picture_events = GLOBALS.db().read_setting_enable_picture_events();
     let comments = GLOBALS.db().read_setting_enable_comments();
+    let calendar = GLOBALS.db().read_setting_enable_calendar();
+    let public_channels = GLOBALS.db().read_setting_enable_public_channels();
+    let relay_groups = GLOBALS.db().read_setting_enable_relay_groups();
+    let git = GLOBALS.db().read_setting_enable_git_repositories();
+    let moderation = GLOBALS.db().read_setting_enable_moderation();
+    let communities = GLOBALS.db().read_setting_enable_communities();
+    let dvms = GLOBALS.db().read_setting_enable_dvms();
+    let timestamps = GLOBALS.db().read_setting_enable_timestamps();
+    let wallet = GLOBALS.db().read_setting_enable_wallet();
 
     EventKind::iter()
         .filter(|k| {
@@ -646,48 +851,48 @@ pub fn enabled_event_kinds() -> Vec<EventKind> {
                 || ((*k == EventKind::Repost) && reposts)
                 || ((*k == EventKind::Reaction) && reactions)
             //|| *k == EventKind::BadgeAward
-            // EventKind::GroupChatMessage
-            // EventKind::GroupChatThreadedReply
-            // EventKind::GroupChatThread
-            // EventKind::GroupChatReply
+                || ((*k == EventKind::GroupChatMessage) && relay_groups)
+                || ((*k == EventKind::GroupChatThreadedReply) && relay_groups)
+                || ((*k == EventKind::GroupChatThread) && relay_groups)
+                || ((*k == EventKind::GroupChatReply) && relay_groups)
             //|| *k == EventKind::Seal // -- never subscribed to
                 || ((*k == EventKind::DmChat) && direct_messages)
                 || ((*k == EventKind::GenericRepost) && reposts)
             // EventKind::ReactionToWebsite
                 || ((*k == EventKind::Picture) && enable_picture_events)
-            //|| *k == EventKind::ChannelCreation
-            //|| *k == EventKind::ChannelMetadata
-            //|| *k == EventKind::ChannelMessage
-            //|| *k == EventKind::ChannelHideMessage
-            //|| *k == EventKind::ChannelMuteUser
+                || ((*k == EventKind::ChannelCreation) && public_channels)
+                || ((*k == EventKind::ChannelMetadata) && public_channels)
+                || ((*k == EventKind::ChannelMessage) && public_channels)
+                || ((*k == EventKind::ChannelHideMessage) && public_channels)
+                || ((*k == EventKind::ChannelMuteUser) && public_channels)
             // EventKind::ChessPgn
             // EventKind::WikiMergeRequest
             // EventKind::Bid
             // EventKind::BidConfirmation
-            // || *k == EventKind::Timestamp
+                || ((*k == EventKind::Timestamp) && timestamps)
                 || ((*k == EventKind::GiftWrap) && direct_messages)
             // || *k == EventKind::FileMetadata
                 || ((*k == EventKind::Comment) && comments)
             // || *k == EventKind::LiveChatMessage
-            // || *k == EventKind::Patches
-            // || *k == EventKind::GitIssue
-            // || *k == EventKind::GitReply
-            // || *k == EventKind::GitStatusOpen
-            // || *k == EventKind::GitStatusApproved
-            // || *k == EventKind::GitStatusClosed
-            // || *k == EventKind::GitStatusDraft
+                || ((*k == EventKind::Patches) && git)
+                || ((*k == EventKind::GitIssue) && git)
+                || ((*k == EventKind::GitReply) && git)
+                || ((*k == EventKind::GitStatusOpen) && git)
+                || ((*k == EventKind::GitStatusApproved) && git)
+                || ((*k == EventKind::GitStatusClosed) && git)
+                || ((*k == EventKind::GitStatusDraft) && git)
             // || *k == EventKind::ProblemTracker
-            // || *k == EventKind::Reporting
-            // || *k == EventKind::Label
+                || ((*k == EventKind::Reporting) && moderation)
+                || ((*k == EventKind::Label) && moderation)
             // EventKind::RelayReviews
             // EventKind::AiEmbeddings
             // EventKind::Torrent
             // EventKind::TorrentComment
             // EventKind::CoinjoinPool
             // || *k == EventKind::CommunityPost
-            // || *k == EventKind::CommunityPostApproval
-            // || *k == EventKind::JobFeedback
-            // || *k == EventKind::ZapGoal
+                || ((*k == EventKind::CommunityPostApproval) && communities)
+                || ((*k == EventKind::JobFeedback) && dvms)
+                || ((*k == EventKind::ZapGoal) && enable_zap_receipts)
             // EventKind::TidalLogin
                 || *k == EventKind::ZapRequest
                 || ((*k == EventKind::Zap) && enable_zap_receipts)
@@ -705,11 +910,11 @@ pub fn enabled_event_kinds() -> Vec<EventKind> {
             // || *k == EventKind::UserEmojiList
                 || (*k == EventKind::DmRelayList && direct_messages)
             // || *k == EventKind::FileStorageServerList
-            // || *k == EventKind::WalletInfo
+                || ((*k == EventKind::WalletInfo) && wallet)
             // || *k == EventKind::LightningPubRpc
             // || *k == EventKind::Auth -- never subscribed to <------
             // || *k == EventKind::WalletRequest
-            // || *k == EventKind::WalletResponse
+                || ((*k == EventKind::WalletResponse) && wallet)
                 || *k == EventKind::NostrConnect
             // || *k == EventKind::Blossom (auth)
             // || *k == EventKind::HttpAuth
@@ -739,22 +944,22 @@ pub fn enabled_event_kinds() -> Vec<EventKind> {
             // || *k == EventKind::UserStatus
             // || *k == EventKind::ClassifiedListing
             // || *k == EventKind::DraftClassifiedListing
-            // || *k == EventKind::RepositoryAnnouncement
+                || ((*k == EventKind::RepositoryAnnouncement) && git)
             // EventKind::RepositoryStateAnnouncement
             // || *k == EventKind::WikiArticle
             // EventKind::Redirects
             // EventKind::LinkSet
             // EventKind::Feed
-            // || *k == EventKind::DateBasedCalendarEvent
-            // || *k == EventKind::TimeBasedCalendarEvent
+                || ((*k == EventKind::DateBasedCalendarEvent) && calendar)
+                || ((*k == EventKind::TimeBasedCalendarEvent) && calendar)
             // || *k == EventKind::Calendar
-            // || *k == EventKind::CalendarEventRsvp
+                || ((*k == EventKind::CalendarEventRsvp) && calendar)
                 || *k == EventKind::HandlerRecommendation
                 || *k == EventKind::HandlerInformation
             // EventKind::VideoEvent
             // EventKind::ShortFormPortraitVideoEvent
             // EventKind::VideoViewEvent
-            // || *k == EventKind::CommunityDefinition
+                || ((*k == EventKind::CommunityDefinition) && communities)
         })
         .collect()
 }
@@ -773,6 +978,9 @@ pub fn feed_related_event_kinds(mut dms: bool) -> Vec<EventKind> {
                     || (*k != EventKind::EncryptedDirectMessage
                         && *k != EventKind::DmChat
                         && *k != EventKind::GiftWrap))
+                // Channel and group messages are only shown in their channel or group
+                && *k != EventKind::ChannelMessage
+                && !crate::groups::GROUP_CHAT_KINDS.contains(k)
         })
         .collect()
 }
@@ -790,6 +998,9 @@ pub fn feed_displayable_event_kinds(mut dms: bool) -> Vec<EventKind> {
                     || (*k != EventKind::EncryptedDirectMessage
                         && *k != EventKind::DmChat
                         && *k != EventKind::GiftWrap))
+                // Channel and group messages are only shown in their channel or group
+                && *k != EventKind::ChannelMessage
+                && !crate::groups::GROUP_CHAT_KINDS.contains(k)
         })
         .collect()
 }
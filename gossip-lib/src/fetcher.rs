@@ -1,9 +1,10 @@
+use crate::blossom::{self, Blossom, HashOutput};
 use crate::error::Error;
 use crate::globals::GLOBALS;
 use crate::profile::Profile;
 use crate::USER_AGENT;
 use dashmap::DashMap;
-use nostr_types::{Unixtime, Url};
+use nostr_types::{PublicKey, Unixtime, Url};
 use reqwest::header::ETAG;
 use reqwest::{Client, StatusCode};
 use sha2::Digest;
@@ -13,6 +14,12 @@ use std::sync::{Arc, RwLock};
 use std::time::{Duration, SystemTime};
 use tokio::sync::Semaphore;
 
+// The most blob URLs we remember the authors of at once
+const MAX_BLOB_AUTHORS: usize = 10_000;
+
+// How long we wait for a person's blossom server list to come in from their outboxes
+const USER_SERVERS_WAIT: Duration = Duration::from_secs(10);
+
 impl Fetcher {
     /// This creates a new fetcher with lazy initialization
     pub(crate) fn new() -> Fetcher {
@@ -120,7 +127,7 @@ impl Fetcher {
                 // Note: This state only occurs if we just created this entry newly, it
                 //       should not persist after this function call completes
                 std::mem::drop(tokio::spawn(Box::pin(async move {
-                    GLOBALS.fetcher.process(url).await;
+                    GLOBALS.fetcher.process_and_fallback(url).await;
 
                     // Notify the UI to redraw now that the image loading is complete
                     GLOBALS.notify_ui_redraw.notify_waiters();
@@ -138,7 +145,7 @@ impl Fetcher {
                 // So start the fetch over (this time probably cached)
                 refmut.value_mut().state = FetchState::Starting;
                 std::mem::drop(tokio::spawn(Box::pin(async move {
-                    GLOBALS.fetcher.process(url).await;
+                    GLOBALS.fetcher.process_and_fallback(url).await;
 
                     // Notify the UI to redraw now that the image loading is complete
                     GLOBALS.notify_ui_redraw.notify_waiters();
@@ -189,7 +196,7 @@ impl Fetcher {
         }
         if start {
             // Run the fetch
-            GLOBALS.fetcher.process(url.clone()).await;
+            GLOBALS.fetcher.process_and_fallback(url.clone()).await;
 
             // Notify the UI to redraw now that the image loading is complete
             GLOBALS.notify_ui_redraw.notify_waiters();
@@ -226,6 +233,18 @@ impl Fetcher {
         }
     }
 
+    /// Remember who posted a URL. If it is a blossom blob and it fails to load, we
+    /// will look for it on their blossom servers.
+    pub fn set_blob_author(&self, url: &Url, author: PublicKey) {
+        if !self.blob_authors.contains_key(url) && blossom::blob_hash(url.as_str()).is_some() {
+            // Don't let this grow without bound. URLs still on screen are set again.
+            if self.blob_authors.len() >= MAX_BLOB_AUTHORS {
+                self.blob_authors.clear();
+            }
+            self.blob_authors.insert(url.to_owned(), author);
+        }
+    }
+
     /// If a resource has failed and you want to retry, clear the failure first
     pub fn clear_for_retry(&self, url: Url) {
         // Must be in Failed state
@@ -393,6 +412,11 @@ pub struct Fetcher {
 
     // Warned about lack of modification time
     warned_already: AtomicBool,
+
+    /// Who posted blossom blob URLs, so we can look for the blob on their other
+    /// blossom servers (BUD-03) if it fails to load. Entries are removed once the
+    /// fetch is done, and it is cleared if it gets too big.
+    blob_authors: DashMap<Url, PublicKey>,
 }
 
 impl Fetcher {
@@ -413,7 +437,7 @@ impl Fetcher {
         let timeout = std::time::Duration::new(GLOBALS.db().read_setting_fetcher_timeout_sec(), 0);
 
         *self.client.write().unwrap() = Some(
-            Client::builder()
+            crate::proxy::http_client_builder(crate::proxy::http_proxy().as_deref())?
                 .gzip(true)
                 .brotli(true)
                 .deflate(true)
@@ -690,6 +714,109 @@ impl Fetcher {
         }
     }
 
+    async fn process_and_fallback(&self, url: Url) {
+        self.process(url.clone()).await;
+
+        let failed = self
+            .url_data
+            .get(&url)
+            .is_some_and(|r| r.value().state != FetchState::Ready);
+        if failed && !self.blob_fallback(&url).await {
+            self.set_state(&url, FetchState::Failed);
+        }
+
+        // We are done with it
+        self.blob_authors.remove(&url);
+    }
+
+    // The URL names a blossom blob but we could not fetch it. Look for the same
+    // blob on the blossom servers of the person who posted it. If it isn't on any
+    // we know of, get their latest server list from their outboxes and try again.
+    async fn blob_fallback(&self, url: &Url) -> bool {
+        let Some(author) = self.blob_authors.get(url).map(|r| *r.value()) else {
+            return false;
+        };
+        let Some(hash) = blossom::blob_hash(url.as_str()) else {
+            return false;
+        };
+        if GLOBALS.db().read_setting_offline() {
+            return false;
+        }
+        let Ok(client) = Blossom::global() else {
+            return false;
+        };
+
+        let known = blossom::user_servers(author);
+        if self.blob_from_servers(client, url, hash, &known).await {
+            return true;
+        }
+
+        if let Err(e) = blossom::fetch_user_servers(author) {
+            tracing::debug!("FETCH {url}: {e}");
+            return false;
+        }
+        let mut waited = Duration::ZERO;
+        let mut servers = known.clone();
+        while servers == known && waited < USER_SERVERS_WAIT {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            waited += Duration::from_millis(500);
+            servers = blossom::user_servers(author);
+        }
+        servers.retain(|s| !known.contains(s));
+
+        self.blob_from_servers(client, url, hash, &servers).await
+    }
+
+    // Look for a blob on these blossom servers
+    async fn blob_from_servers(
+        &self,
+        client: &Blossom,
+        url: &Url,
+        hash: HashOutput,
+        servers: &[String],
+    ) -> bool {
+        let host = self.host(url);
+        for server in servers {
+            let Ok(base_url) = blossom::base_url(server) else {
+                continue;
+            };
+
+            // Don't ask the server that just failed us
+            if url::Url::parse(&base_url)
+                .ok()
+                .and_then(|u| u.host_str().map(|h| h.to_owned()))
+                == host
+            {
+                continue;
+            }
+
+            let bytes = match client.download(base_url, hash, false).await {
+                Ok(response) => match response.bytes().await {
+                    Ok(bytes) => bytes,
+                    Err(_) => continue,
+                },
+                Err(_) => continue,
+            };
+
+            // Servers can be wrong or lie, so make sure it is the blob we asked for
+            if HashOutput::from_bytes(&bytes) != hash {
+                tracing::debug!("FETCH {url}: {server} returned the wrong blob");
+                continue;
+            }
+
+            tracing::debug!("FETCH {url}: found at {server}");
+            GLOBALS.bytes_read.fetch_add(bytes.len(), Ordering::Relaxed);
+
+            // Cache it under the original URL
+            let _ = tokio::fs::write(self.cache_file(url).as_path(), &bytes).await;
+
+            self.finish(url, bytes.to_vec());
+            return true;
+        }
+
+        false
+    }
+
     fn set_state(&self, url: &Url, state: FetchState) {
         if let Some(mut refmut) = self.url_data.get_mut(url) {
             refmut.value_mut().state = state;
@@ -699,7 +826,11 @@ impl Fetcher {
     fn failed(&self, url: &Url, error: String) {
         if let Some(mut refmut) = self.url_data.get_mut(url) {
             refmut.value_mut().error = Some(error);
-            refmut.value_mut().state = FetchState::Failed;
+            // Blobs are not failed until we have looked for them elsewhere, which
+            // happens in process_and_fallback()
+            if !self.blob_authors.contains_key(url) {
+                refmut.value_mut().state = FetchState::Failed;
+            }
         }
     }
 
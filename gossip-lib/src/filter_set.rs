@@ -3,6 +3,7 @@
 use crate::dm_channel::DmChannel;
 use crate::globals::GLOBALS;
 use nostr_types::{EventKind, Filter, Id, NAddr, ParsedTag, PublicKey, Unixtime};
+use std::time::Duration;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum FeedRange {
@@ -25,9 +26,19 @@ impl FeedRange {
 #[derive(Debug, Clone, PartialEq)]
 pub enum FilterSet {
     Augments(Vec<Id>),
+    BadgeAwards(PublicKey),
+    Badges(PublicKey),
+    BlossomServers(PublicKey),
+    Calendar(Vec<PublicKey>),
+    Channel(Id),
+    ChannelModeration(PublicKey),
+    Community(String),
+    CommunityDiscovery,
     Config,
     Discover(Vec<PublicKey>),
     DmChannel(DmChannel),
+    DvmDiscovery,
+    DvmJobs(Vec<Id>),
     FollowersOf(PublicKey),
     GeneralFeedFuture {
         pubkeys: Vec<PublicKey>,
@@ -38,11 +49,16 @@ pub enum FilterSet {
         anchor: Unixtime,
     },
     Giftwraps(FeedRange),
+    GitRepositories(Vec<String>),
+    GitRepository(String),
     GlobalFeedFuture(Unixtime),
     GlobalFeedChunk(Unixtime),
+    Group(String),
+    GroupMetadata(String),
     InboxFeedFuture(Unixtime),
     InboxFeedChunk(Unixtime),
     Metadata(Vec<PublicKey>),
+    Moderation(Vec<PublicKey>),
     Nip46,
     PersonFeedFuture {
         pubkey: PublicKey,
@@ -55,30 +71,49 @@ pub enum FilterSet {
     RepliesToId(Id),
     RepliesToAddr(NAddr),
     Search(String),
+    WalletInfo(PublicKey),
+    WalletResponses,
 }
 
 impl FilterSet {
     pub fn temporary(&self) -> bool {
         match self {
             FilterSet::Augments(_) => true,
+            FilterSet::BadgeAwards(_) => true,
+            FilterSet::Badges(_) => true,
+            FilterSet::BlossomServers(_) => true,
+            FilterSet::Calendar(_) => true,
+            FilterSet::Channel(_) => false,
+            FilterSet::ChannelModeration(_) => false,
+            FilterSet::Community(_) => false,
+            FilterSet::CommunityDiscovery => true,
             FilterSet::Config => false,
             FilterSet::Discover(_) => true,
             FilterSet::DmChannel(_) => false,
+            FilterSet::DvmDiscovery => true,
+            FilterSet::DvmJobs(_) => false,
             FilterSet::FollowersOf(_) => true,
             FilterSet::GeneralFeedFuture { .. } => false,
             FilterSet::GeneralFeedChunk { .. } => true,
             FilterSet::Giftwraps(_) => false,
+            FilterSet::GitRepositories(_) => false,
+            FilterSet::GitRepository(_) => true,
             FilterSet::GlobalFeedFuture(_) => false,
             FilterSet::GlobalFeedChunk(_) => true,
+            FilterSet::Group(_) => false,
+            FilterSet::GroupMetadata(_) => false,
             FilterSet::InboxFeedFuture(_) => false,
             FilterSet::InboxFeedChunk(_) => true,
             FilterSet::Metadata(_) => true,
+            FilterSet::Moderation(_) => false,
             FilterSet::Nip46 => false,
             FilterSet::PersonFeedFuture { .. } => false,
             FilterSet::PersonFeedChunk { .. } => true,
             FilterSet::RepliesToId(_) => false,
             FilterSet::RepliesToAddr(_) => false,
             FilterSet::Search(_) => true,
+            FilterSet::WalletInfo(_) => true,
+            FilterSet::WalletResponses => false,
         }
     }
 
@@ -102,27 +137,53 @@ impl FilterSet {
         }
     }
 
+    /// Whether we can fetch this with NIP-77 negentropy sync instead of a plain REQ,
+    /// skipping the events we already have
+    pub fn can_use_negentropy(&self) -> bool {
+        matches!(
+            self,
+            FilterSet::GeneralFeedChunk { .. } | FilterSet::PersonFeedChunk { .. }
+        )
+    }
+
     pub fn inner_handle(&self) -> &'static str {
         match self {
             FilterSet::Augments(_) => "augments",
+            FilterSet::BadgeAwards(_) => "badge_awards",
+            FilterSet::Badges(_) => "badges",
+            FilterSet::BlossomServers(_) => "blossom_servers",
+            FilterSet::Calendar(_) => "calendar",
+            FilterSet::Channel(_) => "channel_feed",
+            FilterSet::ChannelModeration(_) => "channel_moderation",
+            FilterSet::Community(_) => "community_feed",
+            FilterSet::CommunityDiscovery => "community_discovery",
             FilterSet::Config => "config_feed",
             FilterSet::Discover(_) => "discover_feed",
             FilterSet::DmChannel(_) => "dm_channel",
+            FilterSet::DvmDiscovery => "dvm_discovery",
+            FilterSet::DvmJobs(_) => "dvm_jobs",
             FilterSet::FollowersOf(_) => "followers_of",
             FilterSet::GeneralFeedFuture { .. } => "general_feed",
             FilterSet::GeneralFeedChunk { .. } => "general_feed_chunk",
             FilterSet::Giftwraps(_) => "giftwraps",
+            FilterSet::GitRepositories(_) => "git_repositories",
+            FilterSet::GitRepository(_) => "git_repository",
             FilterSet::GlobalFeedFuture(_) => "global_feed",
             FilterSet::GlobalFeedChunk(_) => "global_feed_chunk",
+            FilterSet::Group(_) => "group_feed",
+            FilterSet::GroupMetadata(_) => "group_metadata",
             FilterSet::InboxFeedFuture(_) => "inbox_feed",
             FilterSet::InboxFeedChunk(_) => "inbox_feed_chunk",
             FilterSet::Metadata(_) => "subscribe_metadata",
+            FilterSet::Moderation(_) => "moderation",
             FilterSet::Nip46 => "nip46",
             FilterSet::PersonFeedFuture { .. } => "person_feed",
             FilterSet::PersonFeedChunk { .. } => "person_feed_chunk",
             FilterSet::RepliesToId(_) => "id_replies",
             FilterSet::RepliesToAddr(_) => "addr_replies",
             FilterSet::Search(_) => "relay_search",
+            FilterSet::WalletInfo(_) => "wallet_info",
+            FilterSet::WalletResponses => "wallet_responses",
         }
     }
 
@@ -149,6 +210,85 @@ impl FilterSet {
                 filter.set_tag_values('e', ids.iter().map(|id| id.as_hex_string()).collect());
                 Some(filter)
             }
+            FilterSet::BadgeAwards(pubkey) => {
+                let mut filter = Filter {
+                    kinds: vec![EventKind::BadgeAward],
+                    ..Default::default()
+                };
+                filter.set_tag_values('p', vec![pubkey.as_hex_string()]);
+                Some(filter)
+            }
+            FilterSet::Badges(pubkey) => {
+                // The badges they show, and the badges they have defined
+                Some(Filter {
+                    authors: vec![*pubkey],
+                    kinds: vec![EventKind::ProfileBadges, EventKind::BadgeDefinition],
+                    // these are all replaceable, no since required
+                    ..Default::default()
+                })
+            }
+            FilterSet::BlossomServers(pubkey) => {
+                Some(Filter {
+                    authors: vec![*pubkey],
+                    kinds: vec![EventKind::UserServerList],
+                    // this is replaceable, no since required
+                    ..Default::default()
+                })
+            }
+            FilterSet::Calendar(pubkeys) => {
+                if pubkeys.is_empty() {
+                    return None;
+                }
+                // Their calendar events, and their RSVPs to calendar events
+                Some(Filter {
+                    authors: pubkeys.to_vec(),
+                    kinds: vec![
+                        EventKind::DateBasedCalendarEvent,
+                        EventKind::TimeBasedCalendarEvent,
+                        EventKind::CalendarEventRsvp,
+                    ],
+                    // these are all replaceable, no since required
+                    ..Default::default()
+                })
+            }
+            FilterSet::Channel(id) => {
+                // Metadata updates and messages in the channel.
+                // Only metadata from the channel creator is used, see process.
+                let mut filter = Filter {
+                    kinds: vec![EventKind::ChannelMetadata, EventKind::ChannelMessage],
+                    ..Default::default()
+                };
+                filter.set_tag_values('e', vec![id.as_hex_string()]);
+                Some(filter)
+            }
+            FilterSet::ChannelModeration(creator) => {
+                // Messages hidden and users muted by the channel creator. These reference
+                // the messages and users, not the channel, so we take them all.
+                Some(Filter {
+                    authors: vec![*creator],
+                    kinds: vec![EventKind::ChannelHideMessage, EventKind::ChannelMuteUser],
+                    ..Default::default()
+                })
+            }
+            FilterSet::Community(address) => {
+                // Posts in the community, and their approvals.
+                // Only approvals by the community moderators are used, see feed.
+                let mut filter = Filter {
+                    kinds: vec![
+                        EventKind::TextNote,
+                        EventKind::Comment,
+                        EventKind::CommunityPostApproval,
+                    ],
+                    ..Default::default()
+                };
+                filter.set_tag_values('a', vec![address.clone()]);
+                Some(filter)
+            }
+            FilterSet::CommunityDiscovery => Some(Filter {
+                kinds: vec![EventKind::CommunityDefinition],
+                limit: Some(100),
+                ..Default::default()
+            }),
             FilterSet::Config => {
                 let pubkey = GLOBALS.identity.public_key()?;
 
@@ -195,6 +335,33 @@ impl FilterSet {
                 filter.set_tag_values('p', authors.iter().map(|x| x.as_hex_string()).collect());
                 Some(filter)
             }
+            FilterSet::DvmDiscovery => {
+                let mut filter = Filter {
+                    kinds: vec![EventKind::HandlerInformation],
+                    limit: Some(200),
+                    ..Default::default()
+                };
+                let values = crate::dvm::JOB_KINDS
+                    .iter()
+                    .map(|(kind, _)| format!("{}", kind))
+                    .collect();
+                filter.set_tag_values('k', values);
+                Some(filter)
+            }
+            FilterSet::DvmJobs(ids) => {
+                if ids.is_empty() {
+                    return None;
+                }
+                let mut kinds = vec![EventKind::JobFeedback];
+                kinds.extend(crate::dvm::job_result_kinds());
+                let mut filter = Filter {
+                    kinds,
+                    ..Default::default()
+                };
+                let values = ids.iter().map(|id| id.as_hex_string()).collect();
+                filter.set_tag_values('e', values);
+                Some(filter)
+            }
             FilterSet::FollowersOf(pubkey) => {
                 let mut filter = Filter {
                     kinds: vec![EventKind::ContactList],
@@ -269,6 +436,23 @@ impl FilterSet {
                 };
                 Some(filter)
             }
+            FilterSet::GitRepositories(addresses) => {
+                // Patches, issues, replies and statuses of the repositories we follow
+                let mut filter = Filter {
+                    kinds: crate::git::GIT_KINDS.to_vec(),
+                    ..Default::default()
+                };
+                filter.set_tag_values('a', addresses.clone());
+                Some(filter)
+            }
+            FilterSet::GitRepository(address) => {
+                let mut filter = Filter {
+                    kinds: crate::git::GIT_KINDS.to_vec(),
+                    ..Default::default()
+                };
+                filter.set_tag_values('a', vec![address.clone()]);
+                Some(filter)
+            }
             FilterSet::GlobalFeedFuture(anchor) => {
                 // Allow all feed related event kinds (excluding DMs)
                 // Do not load feed related or the limit will be wrong
@@ -303,6 +487,24 @@ impl FilterSet {
                     ..Default::default()
                 })
             }
+            FilterSet::Group(group_id) => {
+                // Chat in the group. Group ids are per-relay, so this is only
+                // subscribed on the relay that manages the group.
+                let mut filter = Filter {
+                    kinds: crate::groups::GROUP_CHAT_KINDS.to_vec(),
+                    ..Default::default()
+                };
+                filter.set_tag_values('h', vec![group_id.clone()]);
+                Some(filter)
+            }
+            FilterSet::GroupMetadata(group_id) => {
+                let mut filter = Filter {
+                    kinds: vec![crate::groups::group_metadata_kind()],
+                    ..Default::default()
+                };
+                filter.set_tag_values('d', vec![group_id.clone()]);
+                Some(filter)
+            }
             FilterSet::InboxFeedFuture(anchor) => {
                 let pubkey = GLOBALS.identity.public_key()?;
 
@@ -347,6 +549,16 @@ impl FilterSet {
                     ..Default::default()
                 })
             }
+            FilterSet::Moderation(moderators) => {
+                // Labels and reports by the moderators. Older ones are unlikely to
+                // matter to the notes we will look at.
+                Some(Filter {
+                    authors: moderators.to_vec(),
+                    kinds: vec![EventKind::Label, EventKind::Reporting],
+                    since: Some(Unixtime::now() - Duration::from_secs(60 * 60 * 24 * 30)),
+                    ..Default::default()
+                })
+            }
             FilterSet::Nip46 => {
                 let pubkey = GLOBALS.identity.public_key()?;
 
@@ -454,6 +666,29 @@ impl FilterSet {
                 };
                 Some(filter)
             }
+            FilterSet::WalletInfo(wallet) => {
+                Some(Filter {
+                    authors: vec![*wallet],
+                    kinds: vec![EventKind::WalletInfo],
+                    // this is replaceable, no since required
+                    ..Default::default()
+                })
+            }
+            FilterSet::WalletResponses => {
+                let wallet = GLOBALS.wallet.wallet_pubkey()?;
+                let client = GLOBALS.wallet.client_pubkey()?;
+
+                // Responses are ephemeral, we only want those to requests we
+                // are still waiting on
+                let mut filter = Filter {
+                    authors: vec![wallet],
+                    kinds: vec![EventKind::WalletResponse],
+                    since: Some(Unixtime::now() - Duration::from_secs(60)),
+                    ..Default::default()
+                };
+                filter.set_tag_values('p', vec![client.as_hex_string()]);
+                Some(filter)
+            }
         }
     }
 
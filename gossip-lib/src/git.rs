@@ -0,0 +1,415 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::misc::tagged_address;
+use crate::relay;
+use crate::storage::{RepositoriesTable, Table};
+use nostr_types::{
+    Event, EventKind, Filter, Id, NostrBech32, ParsedTag, PublicKey, RelayUrl, Tag, Unixtime,
+};
+use std::collections::HashMap;
+
+pub type Repository = crate::storage::types::Repository1;
+
+/// The kinds of events posted against a repository
+pub const GIT_KINDS: [EventKind; 7] = [
+    EventKind::Patches,
+    EventKind::GitIssue,
+    EventKind::GitReply,
+    EventKind::GitStatusOpen,
+    EventKind::GitStatusApproved,
+    EventKind::GitStatusClosed,
+    EventKind::GitStatusDraft,
+];
+
+/// The status of a patch or an issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GitStatus {
+    Open,
+    Applied,
+    Closed,
+    Draft,
+}
+
+impl GitStatus {
+    /// The status set by a status event
+    pub fn from_kind(kind: EventKind) -> Option<GitStatus> {
+        match kind {
+            EventKind::GitStatusOpen => Some(GitStatus::Open),
+            EventKind::GitStatusApproved => Some(GitStatus::Applied),
+            EventKind::GitStatusClosed => Some(GitStatus::Closed),
+            EventKind::GitStatusDraft => Some(GitStatus::Draft),
+            _ => None,
+        }
+    }
+
+    /// The kind of event that sets this status
+    pub fn kind(&self) -> EventKind {
+        match self {
+            GitStatus::Open => EventKind::GitStatusOpen,
+            GitStatus::Applied => EventKind::GitStatusApproved,
+            GitStatus::Closed => EventKind::GitStatusClosed,
+            GitStatus::Draft => EventKind::GitStatusDraft,
+        }
+    }
+
+    /// The status for display. Issues are resolved rather than applied.
+    pub fn label(&self, issue: bool) -> &'static str {
+        match self {
+            GitStatus::Open => "open",
+            GitStatus::Applied if issue => "resolved",
+            GitStatus::Applied => "applied",
+            GitStatus::Closed => "closed",
+            GitStatus::Draft => "draft",
+        }
+    }
+}
+
+/// A repository announcement (kind 30617)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoAnnouncement {
+    /// Who announced the repository
+    pub author: PublicKey,
+
+    /// The identifier of the repository, usually its short name
+    pub d: String,
+
+    /// A human readable name
+    pub name: String,
+
+    /// What the repository is
+    pub description: String,
+
+    /// Urls for browsing the repository
+    pub web: Vec<String>,
+
+    /// Urls for cloning the repository
+    pub clone: Vec<String>,
+
+    /// Relays the repository monitors for patches and issues
+    pub relays: Vec<RelayUrl>,
+
+    /// Other people who may apply patches and set statuses
+    pub maintainers: Vec<PublicKey>,
+
+    /// When the announcement was made
+    pub created_at: Unixtime,
+}
+
+impl RepoAnnouncement {
+    /// Parse a repository announcement. Returns None for other kinds of events.
+    pub fn from_event(event: &Event) -> Option<RepoAnnouncement> {
+        if event.kind != EventKind::RepositoryAnnouncement {
+            return None;
+        }
+
+        let mut repo = RepoAnnouncement {
+            author: event.pubkey,
+            d: "".to_owned(),
+            name: "".to_owned(),
+            description: "".to_owned(),
+            web: vec![],
+            clone: vec![],
+            relays: vec![],
+            maintainers: vec![],
+            created_at: event.created_at,
+        };
+
+        for tag in &event.tags {
+            // Tags that hold lists have their values in every field after the name
+            let values = || -> Vec<String> {
+                tag.clone()
+                    .into_inner()
+                    .into_iter()
+                    .skip(1)
+                    .filter(|v| !v.is_empty())
+                    .collect()
+            };
+            match tag.tagname() {
+                "d" => repo.d = tag.value().to_owned(),
+                "name" => repo.name = tag.value().to_owned(),
+                "description" => repo.description = tag.value().to_owned(),
+                "web" => repo.web.extend(values()),
+                "clone" => repo.clone.extend(values()),
+                "relays" => repo.relays.extend(
+                    values()
+                        .iter()
+                        .filter_map(|r| RelayUrl::try_from_str(r).ok()),
+                ),
+                "maintainers" => repo.maintainers.extend(
+                    values()
+                        .iter()
+                        .filter_map(|pk| PublicKey::try_from_hex_string(pk, true).ok()),
+                ),
+                _ => {}
+            }
+        }
+
+        Some(repo)
+    }
+
+    /// The address of the repository
+    pub fn address(&self) -> String {
+        repo_address(self.author, &self.d)
+    }
+
+    /// If this person may apply patches and set statuses
+    pub fn is_maintainer(&self, pubkey: PublicKey) -> bool {
+        self.author == pubkey || self.maintainers.contains(&pubkey)
+    }
+}
+
+/// A patch or an issue, with its current status
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitItem {
+    /// The root patch, or the issue
+    pub event: Event,
+
+    /// The current status
+    pub status: GitStatus,
+
+    /// When the status was set, or when the item was posted if it was never set
+    pub status_at: Unixtime,
+}
+
+impl GitItem {
+    /// If this is an issue rather than a patch
+    pub fn is_issue(&self) -> bool {
+        self.event.kind == EventKind::GitIssue
+    }
+}
+
+/// The address of a repository, as used in 'a' tags
+pub fn repo_address(author: PublicKey, d: &str) -> String {
+    format!("30617:{}:{}", author.as_hex_string(), d)
+}
+
+/// The repository an event was posted against, from its 'a' tag
+pub fn repo_of(event: &Event) -> Option<(PublicKey, String)> {
+    event
+        .tags
+        .iter()
+        .filter(|t| t.tagname() == "a")
+        .find_map(|t| tagged_address(t, EventKind::RepositoryAnnouncement))
+        .map(|address| (address.author, address.d))
+}
+
+/// The latest announcement of a repository, if we have it
+pub fn announcement(author: PublicKey, d: &str) -> Result<Option<RepoAnnouncement>, Error> {
+    Ok(GLOBALS
+        .db()
+        .get_replaceable_event(EventKind::RepositoryAnnouncement, author, d)?
+        .and_then(|e| RepoAnnouncement::from_event(&e)))
+}
+
+/// A repository, if we know about it
+pub fn repository(author: PublicKey, d: &str) -> Result<Option<Repository>, Error> {
+    RepositoriesTable::read_record(repo_address(author, d), None)
+}
+
+/// The repositories the user follows
+pub fn followed_repositories() -> Result<Vec<Repository>, Error> {
+    let mut repos = RepositoriesTable::filter_records(|r| r.followed)?;
+    repos.sort_by(|a, b| a.d.to_lowercase().cmp(&b.d.to_lowercase()));
+    Ok(repos)
+}
+
+/// If the user follows a repository
+pub fn is_followed(author: PublicKey, d: &str) -> bool {
+    matches!(repository(author, d), Ok(Some(repo)) if repo.followed)
+}
+
+/// The name of a repository for display
+pub fn repo_name(author: PublicKey, d: &str) -> String {
+    match announcement(author, d) {
+        Ok(Some(repo)) if !repo.name.is_empty() => repo.name,
+        _ => d.to_owned(),
+    }
+}
+
+/// Relays to look for the events of a repository on: the relays it announced,
+/// the relays we were told about, and our read relays
+pub fn repo_relays(author: PublicKey, d: &str) -> Result<Vec<RelayUrl>, Error> {
+    let mut relays: Vec<RelayUrl> = Vec::new();
+    if let Some(repo) = announcement(author, d)? {
+        relays.extend(repo.relays);
+    }
+    if let Some(repo) = repository(author, d)? {
+        relays.extend(repo.relays);
+    }
+    relay::with_read_relays(relays)
+}
+
+/// Parse a repository identifier: an naddr of the repository announcement, or its
+/// address (`30617:<pubkey-hex>:<identifier>`). Also returns any relay hints.
+pub fn parse_repo_identifier(input: &str) -> Option<(PublicKey, String, Vec<RelayUrl>)> {
+    let input = input.trim();
+    let input = input.strip_prefix("nostr:").unwrap_or(input);
+
+    if let Some(NostrBech32::NAddr(ea)) = NostrBech32::try_from_string(input) {
+        if ea.kind != EventKind::RepositoryAnnouncement {
+            return None;
+        }
+        let relays = ea
+            .relays
+            .iter()
+            .filter_map(|r| RelayUrl::try_from_unchecked_url(r).ok())
+            .collect();
+        return Some((ea.author, ea.d, relays));
+    }
+
+    tagged_address(&Tag::new(&["a", input]), EventKind::RepositoryAnnouncement)
+        .map(|address| (address.author, address.d, vec![]))
+}
+
+/// If a patch or issue starts a thread of its own, rather than revising or replying
+/// to another one
+pub fn is_root(event: &Event) -> bool {
+    match event.kind {
+        EventKind::GitIssue => true,
+        EventKind::Patches => {
+            event
+                .tags
+                .iter()
+                .any(|t| t.tagname() == "t" && t.value() == "root")
+                || !event.tags.iter().any(|t| t.tagname() == "e")
+        }
+        _ => false,
+    }
+}
+
+/// The patch or issue at the root of the thread a git event belongs to
+pub fn root_of(event: &Event) -> Option<Id> {
+    if is_root(event) {
+        return Some(event.id);
+    }
+    let mut first: Option<Id> = None;
+    for tag in &event.tags {
+        if let Ok(ParsedTag::Event { id, marker, .. }) = tag.parse() {
+            if marker.as_deref() == Some("root") {
+                return Some(id);
+            }
+            if first.is_none() {
+                first = Some(id);
+            }
+        }
+    }
+    first
+}
+
+/// A one line description of a patch or an issue
+pub fn subject(event: &Event) -> String {
+    if let Some(tag) = event.tags.iter().find(|t| t.tagname() == "subject") {
+        return tag.value().to_owned();
+    }
+
+    // Patches are the output of `git format-patch`
+    if event.kind == EventKind::Patches {
+        for line in event.content.lines() {
+            if let Some(subject) = line.strip_prefix("Subject: ") {
+                let subject = subject.trim();
+                return match subject.strip_prefix('[') {
+                    Some(rest) => match rest.split_once(']') {
+                        Some((_, title)) => title.trim().to_owned(),
+                        None => subject.to_owned(),
+                    },
+                    None => subject.to_owned(),
+                };
+            }
+        }
+    }
+
+    event.content.lines().next().unwrap_or_default().to_owned()
+}
+
+/// The patches and issues of a repository with their current status, latest first.
+///
+/// A status counts if it was set by a maintainer of the repository, or by the
+/// author of the patch or issue.
+pub fn items(author: PublicKey, d: &str) -> Result<Vec<GitItem>, Error> {
+    let address = repo_address(author, d);
+    let maintainers: Vec<PublicKey> = match announcement(author, d)? {
+        Some(repo) => repo.maintainers,
+        None => vec![],
+    };
+
+    let mut filter = Filter::new();
+    filter.kinds = vec![EventKind::Patches, EventKind::GitIssue];
+    filter.add_tag_value('a', address.clone());
+    let mut items: HashMap<Id, GitItem> = GLOBALS
+        .db()
+        .find_events_by_filter(&filter, is_root)?
+        .drain(..)
+        .map(|event| {
+            let item = GitItem {
+                status: GitStatus::Open,
+                status_at: event.created_at,
+                event,
+            };
+            (item.event.id, item)
+        })
+        .collect();
+
+    let mut filter = Filter::new();
+    filter.kinds = GIT_KINDS
+        .iter()
+        .filter(|k| GitStatus::from_kind(**k).is_some())
+        .copied()
+        .collect();
+    filter.add_tag_value('a', address);
+    let mut statuses = GLOBALS.db().find_events_by_filter(&filter, |_| true)?;
+    statuses.sort_by_key(|e| e.created_at);
+
+    for status_event in statuses.iter() {
+        let status = match GitStatus::from_kind(status_event.kind) {
+            Some(status) => status,
+            None => continue,
+        };
+        for tag in &status_event.tags {
+            if let Ok(ParsedTag::Event { id, .. }) = tag.parse() {
+                if let Some(item) = items.get_mut(&id) {
+                    let authorized = status_event.pubkey == author
+                        || status_event.pubkey == item.event.pubkey
+                        || maintainers.contains(&status_event.pubkey);
+                    if authorized {
+                        item.status = status;
+                        item.status_at = status_event.created_at;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut items: Vec<GitItem> = items.into_values().collect();
+    items.sort_by(|a, b| b.event.created_at.cmp(&a.event.created_at));
+    Ok(items)
+}
+
+/// Follow a repository. Relay hints are where we may find it.
+pub(crate) fn follow_repository(
+    author: PublicKey,
+    d: &str,
+    relays: Vec<RelayUrl>,
+) -> Result<(), Error> {
+    RepositoriesTable::modify(
+        repo_address(author, d),
+        |repo| {
+            repo.followed = true;
+            for url in relays.iter() {
+                if !repo.relays.contains(url) {
+                    repo.relays.push(url.clone());
+                }
+            }
+        },
+        None,
+    )
+}
+
+/// Stop following a repository
+pub(crate) fn unfollow_repository(author: PublicKey, d: &str) -> Result<(), Error> {
+    RepositoriesTable::modify_if_exists(
+        repo_address(author, d),
+        |repo| repo.followed = false,
+        None,
+    )?;
+    Ok(())
+}
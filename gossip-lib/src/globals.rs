@@ -2,6 +2,7 @@ use crate::blossom::{BlobDescriptor, Blossom};
 use crate::bookmarks::BookmarkList;
 use crate::client_identity::ClientIdentity;
 use crate::comms::{RelayJob, ToMinionMessage, ToOverlordMessage};
+use crate::counts::{CountKind, RelayCounts};
 use crate::delegation::Delegation;
 use crate::error::Error;
 use crate::feed::Feed;
@@ -9,15 +10,19 @@ use crate::fetcher::Fetcher;
 use crate::media::Media;
 use crate::minion::MinionExitReason;
 use crate::misc::ZapState;
+use crate::nip96::Nip96Upload;
+use crate::ots::TimestampStatus;
 use crate::pending::Pending;
 use crate::people::{FollowList, People, Person};
 use crate::relay::Relay;
 use crate::relay_picker::RelayPicker;
 use crate::relay_test_results::RelayTestResults;
 use crate::seeker::Seeker;
+use crate::spam_filter::SpamFilterStatus;
 use crate::status::StatusQueue;
 use crate::storage::{HandlersTable, Storage, Table};
 use crate::user_identity::UserIdentity;
+use crate::wallet::Wallet;
 use crate::RunState;
 use dashmap::{DashMap, DashSet};
 use nostr_types::{Event, EventKind, Id, Profile, PublicKey, RelayUrl, UncheckedUrl};
@@ -86,6 +91,9 @@ pub struct Globals {
     /// Client identity wrapping a Signer
     pub client_identity: ClientIdentity,
 
+    /// NIP-47 wallet connection
+    pub wallet: Wallet,
+
     /// Dismissed Events
     pub dismissed: RwLock<Vec<Id>>,
 
@@ -165,7 +173,13 @@ pub struct Globals {
 
     /// Filter
     pub(crate) spam_filter_engine: Engine,
-    pub(crate) spam_filter: Option<AST>,
+    pub(crate) spam_filter: PRwLock<Option<AST>>,
+
+    /// Whether the spam filter script loaded, for the UI
+    pub spam_filter_status: PRwLock<SpamFilterStatus>,
+
+    /// How many times each named spam filter rule has fired since the script loaded
+    pub spam_filter_hits: DashMap<String, u64>,
 
     // Wait for login
     pub wait_for_login: AtomicBool,
@@ -205,6 +219,12 @@ pub struct Globals {
     /// Blossom Uploads (Path to Url)
     pub blossom_uploads: DashMap<PathBuf, Result<BlobDescriptor, Error>>,
 
+    /// Our blobs on each of our blossom servers (Server to Blobs)
+    pub blossom_blobs: DashMap<String, Result<Vec<BlobDescriptor>, Error>>,
+
+    /// NIP-96 Uploads (Path to Upload)
+    pub nip96_uploads: DashMap<PathBuf, Result<Nip96Upload, Error>>,
+
     /// Followers (we keep it in memory only, for just one person)
     pub followers: PRwLock<FollowList>,
 
@@ -214,6 +234,12 @@ pub struct Globals {
     /// Delayed posts
     pub delayed_posts: DashSet<Id>,
 
+    /// Counts returned by relays (NIP-45), per thing counted
+    pub relay_counts: DashMap<CountKind, RelayCounts>,
+
+    /// What we found checking the NIP-03 timestamps of events (None if they have none)
+    pub timestamp_status: DashMap<Id, Option<TimestampStatus>>,
+
     /// Notify the UI to redraw.
     pub notify_ui_redraw: Notify,
 }
@@ -233,8 +259,9 @@ lazy_static! {
         // We start in the Offline state
         let (write_runstate, read_runstate) = watcher::channel(RunState::Initializing);
 
-        let spam_filter_engine = Engine::new();
-        let spam_filter = crate::spam_filter::load_script(&spam_filter_engine);
+        let spam_filter_engine = crate::spam_filter::build_engine();
+        let (spam_filter, spam_filter_status) =
+            crate::spam_filter::load_script(&spam_filter_engine);
 
         Globals {
             runtime: Arc::new(runtime),
@@ -250,6 +277,7 @@ lazy_static! {
             relay_picker: Default::default(),
             identity: UserIdentity::default(),
             client_identity: ClientIdentity::default(),
+            wallet: Wallet::default(),
             dismissed: RwLock::new(Vec::new()),
             feed: Feed::new(),
             fetcher: Fetcher::new(),
@@ -281,7 +309,9 @@ lazy_static! {
             storage: OnceLock::new(),
             events_processed: AtomicU32::new(0),
             spam_filter_engine,
-            spam_filter,
+            spam_filter: PRwLock::new(spam_filter),
+            spam_filter_status: PRwLock::new(spam_filter_status),
+            spam_filter_hits: DashMap::new(),
             wait_for_login: AtomicBool::new(false),
             wait_for_login_notify: Notify::new(),
             wait_for_data_migration: AtomicBool::new(false),
@@ -296,9 +326,13 @@ lazy_static! {
             handlers: DashMap::new(),
             blossom: OnceLock::new(),
             blossom_uploads: DashMap::new(),
+            blossom_blobs: DashMap::new(),
+            nip96_uploads: DashMap::new(),
             followers: PRwLock::new(FollowList::default()),
             follows: PRwLock::new(FollowList::default()),
             delayed_posts: DashSet::new(),
+            relay_counts: DashMap::new(),
+            timestamp_status: DashMap::new(),
             notify_ui_redraw: Notify::new(),
         }
     };
@@ -0,0 +1,245 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::storage::{GroupsTable, Table};
+use nostr_types::{Event, EventKind, Filter, NostrBech32, PublicKey, RelayUrl};
+
+pub type Group = crate::storage::types::Group1;
+
+/// The kinds of chat events posted into a group
+pub const GROUP_CHAT_KINDS: [EventKind; 4] = [
+    EventKind::GroupChatMessage,
+    EventKind::GroupChatThreadedReply,
+    EventKind::GroupChatThread,
+    EventKind::GroupChatReply,
+];
+
+/// Group metadata, signed by the relay
+pub fn group_metadata_kind() -> EventKind {
+    EventKind::from(39000)
+}
+
+/// A request to join a group
+pub fn join_request_kind() -> EventKind {
+    EventKind::from(9021)
+}
+
+/// A request to leave a group
+pub fn leave_request_kind() -> EventKind {
+    EventKind::from(9022)
+}
+
+/// The key of a group in storage
+pub fn group_key(relay: &RelayUrl, group_id: &str) -> String {
+    format!("{}'{}", relay, group_id)
+}
+
+/// All groups we know about, joined groups first, then by name
+pub fn groups() -> Result<Vec<Group>, Error> {
+    let mut groups = GroupsTable::filter_records(|_| true)?;
+    let sort_name = |g: &Group| {
+        if g.name.is_empty() {
+            g.group_id.to_lowercase()
+        } else {
+            g.name.to_lowercase()
+        }
+    };
+    groups.sort_by(|a, b| {
+        b.joined
+            .cmp(&a.joined)
+            .then_with(|| sort_name(a).cmp(&sort_name(b)))
+    });
+    Ok(groups)
+}
+
+/// The groups the user has joined
+pub fn joined_groups() -> Result<Vec<Group>, Error> {
+    let mut groups = groups()?;
+    groups.retain(|g| g.joined);
+    Ok(groups)
+}
+
+/// A group, if we know about it
+pub fn group(relay: &RelayUrl, group_id: &str) -> Result<Option<Group>, Error> {
+    GroupsTable::read_record(group_key(relay, group_id), None)
+}
+
+/// The name of a group for display
+pub fn group_name(relay: &RelayUrl, group_id: &str) -> String {
+    match group(relay, group_id) {
+        Ok(Some(group)) if !group.name.is_empty() => group.name,
+        _ => group_id.to_owned(),
+    }
+}
+
+/// The group a chat event was posted into, from its 'h' tag
+pub fn group_of(event: &Event) -> Option<String> {
+    event
+        .tags
+        .iter()
+        .find(|t| t.tagname() == "h" && !t.value().is_empty())
+        .map(|t| t.value().to_owned())
+}
+
+/// The group a chat event was posted into, along with the relay that manages
+/// it, if we know about that group
+pub fn group_and_relay_of(event: &Event) -> Result<Option<(RelayUrl, String)>, Error> {
+    if !GROUP_CHAT_KINDS.contains(&event.kind) {
+        return Ok(None);
+    }
+    let group_id = match group_of(event) {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+    for (url, _) in GLOBALS.db().get_event_seen_on_relay(event.id)? {
+        if group(&url, &group_id)?.is_some() {
+            return Ok(Some((url, group_id)));
+        }
+    }
+    Ok(None)
+}
+
+/// If the user has joined any group on this relay. We authenticate to such
+/// relays without asking, since group relays need to know who is reading.
+pub fn relay_has_joined_group(url: &RelayUrl) -> bool {
+    match GroupsTable::filter_records(|g| g.joined && g.relay == *url) {
+        Ok(groups) => !groups.is_empty(),
+        Err(_) => false,
+    }
+}
+
+/// Parse a group identifier: `host'group-id`, a relay url followed by `'group-id`,
+/// or an naddr of the group metadata
+pub fn parse_group_identifier(input: &str) -> Option<(RelayUrl, String)> {
+    let input = input.trim();
+    let input = input.strip_prefix("nostr:").unwrap_or(input);
+
+    if let Some(NostrBech32::NAddr(ea)) = NostrBech32::try_from_string(input) {
+        if ea.kind != group_metadata_kind() {
+            return None;
+        }
+        let relay = RelayUrl::try_from_unchecked_url(ea.relays.first()?).ok()?;
+        return Some((relay, ea.d));
+    }
+
+    let (host, group_id) = input.rsplit_once('\'')?;
+    if group_id.is_empty() {
+        return None;
+    }
+    let relay = if host.starts_with("wss://") || host.starts_with("ws://") {
+        RelayUrl::try_from_str(host).ok()?
+    } else {
+        RelayUrl::try_from_str(&format!("wss://{}", host)).ok()?
+    };
+    Some((relay, group_id.to_owned()))
+}
+
+/// Mark a group as joined
+pub(crate) fn join_group(relay: &RelayUrl, group_id: &str) -> Result<(), Error> {
+    GroupsTable::modify(
+        group_key(relay, group_id),
+        |group| group.joined = true,
+        None,
+    )
+}
+
+/// Mark a group as left
+pub(crate) fn leave_group(relay: &RelayUrl, group_id: &str) -> Result<(), Error> {
+    GroupsTable::modify_if_exists(
+        group_key(relay, group_id),
+        |group| group.joined = false,
+        None,
+    )?;
+    Ok(())
+}
+
+/// The key a relay signs its group events with, the `self` of its NIP-11 document
+pub fn relay_self_pubkey(relay: &RelayUrl) -> Option<PublicKey> {
+    let nip11 = GLOBALS.db().read_relay(relay).ok()??.nip11?;
+    let hex = nip11.other.get("self")?.as_str()?.to_owned();
+    PublicKey::try_from_hex_string(&hex, true).ok()
+}
+
+/// Update a group from its metadata event.
+///
+/// Group ids are only unique within a relay, and only the relay may sign the
+/// metadata, so we only take it from the relay it was seen on, and only if the
+/// relay's own key signed it.
+pub(crate) fn process_group_metadata(
+    event: &Event,
+    seen_on: Option<&RelayUrl>,
+) -> Result<(), Error> {
+    let relay = match seen_on {
+        Some(url) => url,
+        None => return Ok(()),
+    };
+
+    if relay_self_pubkey(relay) != Some(event.pubkey) {
+        tracing::debug!("Group metadata from {} is not signed by the relay", relay);
+        return Ok(());
+    }
+
+    let mut group_id: Option<&str> = None;
+    for tag in &event.tags {
+        if tag.tagname() == "d" {
+            group_id = Some(tag.value());
+        }
+    }
+    let group_id = match group_id {
+        Some(id) if !id.is_empty() => id,
+        _ => return Ok(()),
+    };
+
+    let mut group = GroupsTable::read_or_create_record(group_key(relay, group_id), None)?;
+    if event.created_at <= group.metadata_at {
+        return Ok(());
+    }
+
+    group.name = "".to_owned();
+    group.about = "".to_owned();
+    group.picture = "".to_owned();
+    group.private = false;
+    group.closed = false;
+    for tag in &event.tags {
+        match tag.tagname() {
+            "name" => group.name = tag.value().to_owned(),
+            "about" => group.about = tag.value().to_owned(),
+            "picture" => group.picture = tag.value().to_owned(),
+            "private" => group.private = true,
+            "closed" => group.closed = true,
+            _ => {}
+        }
+    }
+    group.metadata_at = event.created_at;
+
+    GroupsTable::write_record(&mut group, None)?;
+    Ok(())
+}
+
+/// Take any group metadata we already have from the relay that we could not
+/// check before, because we did not yet know the relay's key. Call this after
+/// its NIP-11 document arrives.
+pub(crate) fn recheck_group_metadata(relay: &RelayUrl) -> Result<(), Error> {
+    let pubkey = match relay_self_pubkey(relay) {
+        Some(pubkey) => pubkey,
+        None => return Ok(()),
+    };
+
+    let mut filter = Filter::new();
+    filter.add_author(pubkey);
+    filter.kinds = vec![group_metadata_kind()];
+    let events = GLOBALS.db().find_events_by_filter(&filter, |event| {
+        GLOBALS
+            .db()
+            .get_event_seen_on_relay(event.id)
+            .unwrap_or_default()
+            .iter()
+            .any(|(url, _)| url == relay)
+    })?;
+
+    // Newest first, so the older ones are skipped
+    for event in events.iter() {
+        process_group_metadata(event, Some(relay))?;
+    }
+
+    Ok(())
+}
@@ -64,24 +64,59 @@
 //! with the storage engine. In some cases, the `Overlord` has more complex code for doing this,
 //! but in many cases, you can interact with `GLOBALS.db()` directly.
 
+/// Multiple accounts in one profile
+pub mod accounts;
+pub use accounts::Account;
+
+/// NIP-23 long-form articles
+pub mod article;
+pub use article::{Article, ArticleFields};
+
+/// NIP-58 badges
+pub mod badges;
+pub use badges::{Badge, BadgeDefinition, BadgeFields};
+
 pub mod blossom;
 pub use blossom::Blossom;
 
 pub mod bookmarks;
 pub use bookmarks::BookmarkList;
 
+/// NIP-52 calendar events
+pub mod calendar;
+pub use calendar::{CalendarEvent, Rsvp, RsvpStatus};
+
+/// NIP-28 public chat channels
+pub mod channels;
+pub use channels::Channel;
+
 mod client_identity;
 pub use client_identity::ClientIdentity;
 
 /// Defines messages sent to the overlord
 pub mod comms;
 
+/// NIP-72 moderated communities
+pub mod communities;
+pub use communities::{Community, CommunityDefinition};
+
+mod counts;
+pub use counts::{CountKind, RelayCounts};
+
 mod delegation;
 pub use delegation::Delegation;
 
+/// Drafts of posts being written
+pub mod draft;
+pub use draft::{Draft, DraftTarget};
+
 mod dm_channel;
 pub use dm_channel::{DmChannel, DmChannelData};
 
+/// NIP-90 data vending machines
+pub mod dvm;
+pub use dvm::{Dvm, Job, JobFeedback, JobRequest};
+
 mod error;
 pub use error::{Error, ErrorKind};
 
@@ -96,9 +131,17 @@ pub use fetcher::{FetchResult, Fetcher};
 
 mod filter_set;
 
+/// NIP-34 git collaboration
+pub mod git;
+pub use git::{GitItem, GitStatus, RepoAnnouncement, Repository};
+
 mod globals;
 pub use globals::{Globals, GLOBALS};
 
+/// NIP-29 relay-based groups
+pub mod groups;
+pub use groups::Group;
+
 pub mod manager;
 
 mod media;
@@ -107,7 +150,13 @@ pub use media::{media_url_mimetype, Media, MediaLoadingResult};
 mod minion;
 
 mod misc;
-pub use misc::{Freshness, Private, ZapState};
+pub use misc::{new_identifier, Freshness, Private, ZapState};
+
+/// NIP-32 labels and NIP-56 reports from trusted moderators
+pub mod moderation;
+pub use moderation::Moderation;
+
+mod negentropy;
 
 /// Rendering various names of users
 pub mod names;
@@ -115,10 +164,22 @@ pub mod names;
 /// nip05 handling
 pub mod nip05;
 
+/// NIP-96 HTTP file storage uploads
+pub mod nip96;
+pub use nip96::Nip96Upload;
+
 #[allow(dead_code)]
 pub mod nostr_connect_server;
 pub use nostr_connect_server::{Nip46Server, Nip46UnconnectedServer};
 
+/// NIP-03 OpenTimestamps attestations
+pub mod ots;
+pub use ots::TimestampStatus;
+
+/// Tracking delivery of our events to relays
+pub mod outbox;
+pub use outbox::{DeliveryState, OutboxDelivery, OutboxEntry};
+
 mod overlord;
 pub use overlord::Overlord;
 
@@ -142,6 +203,9 @@ pub mod process;
 mod profile;
 pub use profile::Profile;
 
+/// SOCKS5 proxy support
+pub mod proxy;
+
 mod relationship;
 
 pub mod relay;
@@ -153,10 +217,15 @@ pub use relay_picker::RelayPicker;
 mod relay_test_results;
 pub use relay_test_results::{RelayTestResult, RelayTestResults};
 
+/// Posts to be published later
+pub mod scheduled_post;
+pub use scheduled_post::ScheduledPost;
+
 mod seeker;
 pub use seeker::Seeker;
 
 mod spam_filter;
+pub use spam_filter::SpamFilterStatus;
 
 mod status;
 pub use status::StatusQueue;
@@ -170,6 +239,14 @@ mod tasks;
 mod user_identity;
 pub use user_identity::UserIdentity;
 
+/// NIP-47 Nostr Wallet Connect
+pub mod wallet;
+pub use wallet::{Transaction, Wallet, WalletConnection};
+
+/// NIP-57 zap splits and NIP-75 zap goals
+pub mod zaps;
+pub use zaps::{ZapGoal, ZapSplit};
+
 #[macro_use]
 extern crate lazy_static;
 
@@ -234,9 +311,15 @@ pub async fn init(rapid: bool, command_mode: bool) -> Result<(), Error> {
     // Load user identity
     GLOBALS.identity.load()?;
 
+    // Make sure it is among our accounts
+    accounts::remember_active_account()?;
+
     // Load client identity
     GLOBALS.client_identity.load()?;
 
+    // Load wallet connection
+    GLOBALS.wallet.load()?;
+
     // Load delegation tag
     GLOBALS.delegation.load()?;
 
@@ -1,5 +1,6 @@
 use super::{AuthState, Minion};
 use crate::comms::ToOverlordMessage;
+use crate::counts::CountKind;
 use crate::error::Error;
 use crate::globals::GLOBALS;
 use nostr_types::{RelayMessage, Unixtime};
@@ -9,6 +10,14 @@ impl Minion {
         // TODO: pull out the raw event without any deserialization to be sure we don't mangle
         //       it.
 
+        // NIP-77 negentropy messages are handled separately
+        if ws_message
+            .trim_start_matches(|c: char| c == '[' || c.is_whitespace())
+            .starts_with("\"NEG-")
+        {
+            return self.handle_negentropy_message(&ws_message).await;
+        }
+
         let relay_message: RelayMessage = match serde_json::from_str(&ws_message) {
             Ok(rm) => rm,
             Err(e) => {
@@ -219,6 +228,9 @@ impl Minion {
                         }
                     }
 
+                    // Record the result in the outbox
+                    crate::outbox::record_ok(id, &self.url, ok, &ok_message)?;
+
                     let mut job_is_done: bool = false;
                     {
                         // Take it out of the posting_jobs
@@ -252,6 +264,12 @@ impl Minion {
                 self.maybe_authenticate().await?;
             }
             RelayMessage::Closed(subid, message) => {
+                // The relay may refuse COUNT requests with a CLOSED
+                if self.finish_count(&subid.0)?.is_some() {
+                    tracing::debug!("{}: COUNT refused: {}", &self.url, message);
+                    return Ok(());
+                }
+
                 let handle = self
                     .subscription_map
                     .get_handle_by_id(&subid.0)
@@ -360,12 +378,28 @@ impl Minion {
                 tracing::debug!("{}: removed subscription {}", &self.url, handle);
                 self.subscription_map.remove(&handle);
             }
-            RelayMessage::Count(_subid, _count_result) => {
-                tracing::info!(
-                    "{} Relay returned count results that we do not yet support",
-                    &self.url
-                );
-            }
+            RelayMessage::Count(subid, count_result) => match self.finish_count(&subid.0)? {
+                Some(kind) => {
+                    tracing::debug!("{}: COUNT {:?} = {}", &self.url, kind, count_result.count);
+
+                    GLOBALS
+                        .relay_counts
+                        .entry(kind)
+                        .or_default()
+                        .per_relay
+                        .insert(self.url.clone(), count_result.count as u64);
+
+                    match kind {
+                        CountKind::Followers(_) => GLOBALS.notify_ui_redraw.notify_waiters(),
+                        CountKind::References(id) | CountKind::Reactions(id) => {
+                            GLOBALS.ui_invalidate_note(id)
+                        }
+                    }
+                }
+                None => {
+                    tracing::debug!("{}: COUNT for unknown request {:?}", &self.url, subid);
+                }
+            },
         }
 
         Ok(())
@@ -1,8 +1,10 @@
 mod handle_websocket;
+mod negentropy_sync;
 mod subscription;
 mod subscription_map;
 
 use crate::comms::{ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail, ToOverlordMessage};
+use crate::counts::CountKind;
 use crate::error::{Error, ErrorKind};
 use crate::filter_set::FilterSet;
 use crate::globals::GLOBALS;
@@ -15,9 +17,10 @@ use futures_util::stream::{FusedStream, StreamExt};
 use http::uri::{Parts, Scheme};
 use http::Uri;
 use mime::Mime;
+use negentropy_sync::NegentropySync;
 use nostr_types::{
     ClientMessage, EventKind, Filter, Id, KeySigner, NAddr, PreEvent, PublicKey,
-    RelayInformationDocument, RelayUrl, Signer, Tag, Unixtime,
+    RelayInformationDocument, RelayUrl, Signer, SubscriptionId, Tag, Unixtime,
 };
 use reqwest::Response;
 use std::borrow::Cow;
@@ -60,6 +63,15 @@ pub struct EventSeekState {
     pub asked: bool,
 }
 
+pub struct PendingCount {
+    pub job_id: u64,
+    pub kind: CountKind,
+    pub asked_at: Unixtime,
+}
+
+// How long we wait for a relay to answer a COUNT before giving up on it
+const COUNT_TIMEOUT_SECS: u64 = 30;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MinionExitReason {
     GotDisconnected,
@@ -95,6 +107,8 @@ pub struct Minion {
     repost_ids_after_auth: HashSet<Id>,
     sought_events: HashMap<Id, EventSeekState>,
     sought_naddrs: HashMap<NAddr, EventSeekState>,
+    pending_counts: HashMap<String, PendingCount>,
+    negentropy_syncs: HashMap<String, NegentropySync>,
     last_message_sent: String,
     auth_challenge: String,
     subscriptions_waiting_for_auth: HashMap<String, Unixtime>,
@@ -143,6 +157,8 @@ impl Minion {
             repost_ids_after_auth: HashSet::new(),
             sought_events: HashMap::new(),
             sought_naddrs: HashMap::new(),
+            pending_counts: HashMap::new(),
+            negentropy_syncs: HashMap::new(),
             last_message_sent: String::new(),
             auth_challenge: "".to_string(),
             subscriptions_waiting_for_auth: HashMap::new(),
@@ -267,9 +283,10 @@ impl Minion {
                 GLOBALS.db().read_setting_websocket_connect_timeout_sec()
             };
 
+            let proxy = crate::proxy::relay_proxy(&self.url);
             let connect_future = tokio::time::timeout(
                 std::time::Duration::new(connect_timeout_secs, 0),
-                tokio_tungstenite::connect_async_with_config(req, Some(config), false),
+                crate::proxy::connect_websocket(req, config, proxy.as_deref()),
             );
 
             let websocket_stream;
@@ -381,7 +398,8 @@ impl Minion {
         };
         let uri = http::Uri::from_parts(parts)?;
 
-        let request_nip11_future = reqwest::Client::builder()
+        let proxy = crate::proxy::relay_proxy(&self.url);
+        let request_nip11_future = crate::proxy::http_client_builder(proxy.as_deref())?
             .timeout(fetcher_timeout)
             .redirect(reqwest::redirect::Policy::none())
             .gzip(true)
@@ -429,6 +447,11 @@ impl Minion {
         // Save updated NIP-11 data (even if it failed)
         GLOBALS.db().write_relay(&self.dbrelay, None)?;
 
+        // Group metadata that came before we knew the relay's key can be checked now
+        if self.nip11.is_some() {
+            crate::groups::recheck_group_metadata(&self.url)?;
+        }
+
         Ok(())
     }
 
@@ -455,6 +478,12 @@ impl Minion {
                 self.get_events().await?;
                 self.get_naddrs().await?;
 
+                // Give up on counts the relay never answered
+                self.expire_counts()?;
+
+                // Fall back to REQs for negentropy syncs that are taking too long
+                self.expire_negentropy().await?;
+
                 // Try to subscribe to subscriptions waiting for something
                 self.try_subscribe_waiting().await?;
             },
@@ -511,6 +540,8 @@ impl Minion {
             && self.subscriptions_waiting_for_auth.is_empty()
             && self.subscriptions_waiting_for_metadata.is_empty()
             && self.posting_jobs.is_empty()
+            && self.pending_counts.is_empty()
+            && self.negentropy_syncs.is_empty()
         {
             let now = Unixtime::now();
             if let Some(when) = self.subscriptions_empty_asof {
@@ -581,6 +612,31 @@ impl Minion {
                     );
                 }
             }
+            ToMinionPayloadDetail::Count(kinds) => {
+                // Reset timing of empty subscription period
+                self.subscriptions_empty_asof = None;
+
+                for kind in kinds {
+                    let subid = format!("count_{}", self.next_events_subscription_id);
+                    self.next_events_subscription_id += 1;
+
+                    let msg = ClientMessage::Count(SubscriptionId(subid.clone()), kind.filter());
+                    let wire = serde_json::to_string(&msg)?;
+                    let ws_stream = self.stream.as_mut().unwrap();
+                    tracing::trace!("{}: Sending {}", &self.url, &wire);
+                    self.last_message_sent = wire.clone();
+                    ws_stream.send(WsMessage::Text(wire)).await?;
+
+                    self.pending_counts.insert(
+                        subid,
+                        PendingCount {
+                            job_id: message.job_id,
+                            kind,
+                            asked_at: Unixtime::now(),
+                        },
+                    );
+                }
+            }
             ToMinionPayloadDetail::FetchEvent(id) => {
                 // We don't ask the relay immediately. See task_timer.
                 self.sought_events
@@ -636,7 +692,14 @@ impl Minion {
                 if !self.subscription_map.has(&handle) || filter_set.can_have_duplicates() {
                     let spamsafe = self.dbrelay.has_usage_bits(Relay::SPAMSAFE);
                     if let Some(filter) = filter_set.filter(spamsafe) {
-                        self.subscribe(filter, &handle, message.job_id).await?;
+                        // Sync what we can instead of downloading events we already have
+                        if !(filter_set.can_use_negentropy()
+                            && self
+                                .negentropy_subscribe(&filter, &handle, message.job_id)
+                                .await?)
+                        {
+                            self.subscribe(filter, &handle, message.job_id).await?;
+                        }
                     }
                 } else {
                     // It does not allow duplicates and we are already running it,
@@ -650,6 +713,7 @@ impl Minion {
                 }
             }
             ToMinionPayloadDetail::Unsubscribe(filter_set) => {
+                self.cancel_negentropy(filter_set.inner_handle()).await?;
                 let handles = self
                     .subscription_map
                     .get_all_handles_matching(filter_set.inner_handle());
@@ -736,6 +800,42 @@ impl Minion {
         Ok(())
     }
 
+    // Remove a pending count, and tell the overlord if that completes its job
+    fn finish_count(&mut self, subid: &str) -> Result<Option<CountKind>, Error> {
+        let pending = match self.pending_counts.remove(subid) {
+            Some(pending) => pending,
+            None => return Ok(None),
+        };
+
+        if !self
+            .pending_counts
+            .values()
+            .any(|p| p.job_id == pending.job_id)
+        {
+            self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
+                self.url.clone(),
+                pending.job_id,
+            ))?;
+        }
+
+        Ok(Some(pending.kind))
+    }
+
+    fn expire_counts(&mut self) -> Result<(), Error> {
+        let now = Unixtime::now();
+        let expired: Vec<String> = self
+            .pending_counts
+            .iter()
+            .filter(|(_, p)| now - p.asked_at > Duration::from_secs(COUNT_TIMEOUT_SECS))
+            .map(|(subid, _)| subid.to_owned())
+            .collect();
+        for subid in expired.iter() {
+            tracing::debug!("{}: COUNT {} timed out", &self.url, subid);
+            self.finish_count(subid)?;
+        }
+        Ok(())
+    }
+
     // After AUTH, resend events that failed due to "auth-required"
     async fn resend_post_auth(&mut self) -> Result<(), Error> {
         if !self.repost_ids_after_auth.is_empty() {
@@ -939,6 +1039,11 @@ impl Minion {
             match self.dbrelay.allow_auth {
                 Some(true) => self.real_authenticate().await?,
                 Some(false) => self.fake_authenticate().await?,
+                // Group relays need to know who we are. Joining a group there
+                // is approval enough.
+                None if crate::groups::relay_has_joined_group(&self.url) => {
+                    self.real_authenticate().await?
+                }
                 None => {
                     if let Some(pubkey) = GLOBALS.identity.public_key() {
                         GLOBALS.pending.insert(
@@ -0,0 +1,277 @@
+use super::Minion;
+use crate::comms::ToOverlordMessage;
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::negentropy::Negentropy;
+use futures_util::sink::SinkExt;
+use nostr_types::{Filter, Unixtime};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tungstenite::protocol::Message as WsMessage;
+
+// How long we wait for a negentropy sync to finish before falling back to a REQ
+const NEGENTROPY_TIMEOUT_SECS: u64 = 60;
+
+// How many missing events we ask for per REQ once a sync finishes
+const NEGENTROPY_FETCH_BATCH: usize = 250;
+
+// If a sync finds more missing events than this, we fall back to the plain REQ,
+// which is bounded by its limit
+const NEGENTROPY_MAX_NEED: usize = 2000;
+
+pub struct NegentropySync {
+    pub handle: String,
+    pub job_id: u64,
+    pub filter: Filter, // The plain REQ filter, in case the sync fails
+    pub negentropy: Negentropy,
+    pub started_at: Unixtime,
+}
+
+impl Minion {
+    // Fetch a feed chunk with NIP-77 negentropy sync, so that we only download
+    // the events we are missing.
+    //
+    // Returns false if the relay or the chunk is not suitable, in which case the
+    // caller should subscribe with a plain REQ. We only sync by authors and kinds,
+    // so filters with ids or tags are not suitable.
+    pub(super) async fn negentropy_subscribe(
+        &mut self,
+        filter: &Filter,
+        handle: &str,
+        job_id: u64,
+    ) -> Result<bool, Error> {
+        if !GLOBALS.db().read_setting_negentropy_sync()
+            || !self.dbrelay.supports_nip(77)
+            || self.auth_state.is_waiting()
+            || self.failed_subs.contains(handle)
+            || filter.authors.is_empty()
+            || filter.kinds.is_empty()
+            || !filter.ids.is_empty()
+            || !filter.tags.is_empty()
+        {
+            return Ok(false);
+        }
+
+        let until = filter.until.unwrap_or_else(Unixtime::now);
+        let limit = filter
+            .limit
+            .unwrap_or(GLOBALS.db().read_setting_load_more_count() as usize);
+
+        // Build our set from the index. Taking `limit` events per author-kind pair
+        // covers every event we have since the `limit`'th newest overall, so we sync
+        // from there. After a long time offline this range spans the whole gap.
+        let mut items =
+            GLOBALS
+                .db()
+                .get_recent_event_items(&filter.authors, &filter.kinds, until, limit)?;
+        if items.is_empty() {
+            // Nothing to gain over a plain REQ
+            return Ok(false);
+        }
+        items.sort_by_key(|(created_at, _)| std::cmp::Reverse(created_at.0));
+        let since = items[items.len().min(limit) - 1].0;
+        items.retain(|(created_at, _)| created_at.0 >= since.0);
+
+        let sync_filter = Filter {
+            authors: filter.authors.clone(),
+            kinds: filter.kinds.clone(),
+            since: Some(since),
+            until: Some(until),
+            ..Default::default()
+        };
+
+        let mut negentropy = Negentropy::new(items);
+        let initial_message = hex::encode(negentropy.initiate());
+
+        let subid = format!("neg_{}", self.next_events_subscription_id);
+        self.next_events_subscription_id += 1;
+
+        let wire = serde_json::to_string(&("NEG-OPEN", &subid, &sync_filter, initial_message))?;
+        self.send_negentropy_message(wire).await?;
+
+        tracing::debug!(
+            "NEW NEGENTROPY SYNC on {} handle={}, id={}",
+            &self.url,
+            handle,
+            &subid
+        );
+
+        // Reset timing of empty subscription period
+        self.subscriptions_empty_asof = None;
+
+        self.negentropy_syncs.insert(
+            subid,
+            NegentropySync {
+                handle: handle.to_owned(),
+                job_id,
+                filter: filter.clone(),
+                negentropy,
+                started_at: Unixtime::now(),
+            },
+        );
+
+        Ok(true)
+    }
+
+    // Handle NEG-MSG and NEG-ERR from the relay
+    pub(super) async fn handle_negentropy_message(
+        &mut self,
+        ws_message: &str,
+    ) -> Result<(), Error> {
+        let message: Vec<serde_json::Value> = serde_json::from_str(ws_message)?;
+        let verb = message.first().and_then(|v| v.as_str()).unwrap_or_default();
+        let subid = message.get(1).and_then(|v| v.as_str()).unwrap_or_default();
+        let payload = message.get(2).and_then(|v| v.as_str()).unwrap_or_default();
+
+        let mut sync = match self.negentropy_syncs.remove(subid) {
+            Some(sync) => sync,
+            None => {
+                tracing::debug!(
+                    "{}: {} for unknown negentropy sync {}",
+                    &self.url,
+                    verb,
+                    subid
+                );
+                return Ok(());
+            }
+        };
+
+        match verb {
+            "NEG-MSG" => {
+                let result = hex::decode(payload)
+                    .map_err(|e| Error::from(ErrorKind::Negentropy(e.to_string())))
+                    .and_then(|bytes| sync.negentropy.reconcile(&bytes));
+                match result {
+                    Ok(Some(reply)) => {
+                        let wire = serde_json::to_string(&("NEG-MSG", subid, hex::encode(reply)))?;
+                        self.send_negentropy_message(wire).await?;
+                        self.negentropy_syncs.insert(subid.to_owned(), sync);
+                    }
+                    Ok(None) => {
+                        self.close_negentropy(subid).await?;
+                        self.fetch_negentropy_needs(sync).await?;
+                    }
+                    Err(e) => {
+                        tracing::warn!("{}: negentropy sync {} failed: {}", &self.url, subid, e);
+                        self.close_negentropy(subid).await?;
+                        self.subscribe(sync.filter, &sync.handle, sync.job_id)
+                            .await?;
+                    }
+                }
+            }
+            "NEG-ERR" => {
+                tracing::info!("{}: NEG-ERR {}: {}", &self.url, subid, payload);
+                self.subscribe(sync.filter, &sync.handle, sync.job_id)
+                    .await?;
+            }
+            _ => {
+                self.negentropy_syncs.insert(subid.to_owned(), sync);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Fall back to a plain REQ for syncs the relay has not finished in time
+    pub(super) async fn expire_negentropy(&mut self) -> Result<(), Error> {
+        let now = Unixtime::now();
+        let expired: Vec<String> = self
+            .negentropy_syncs
+            .iter()
+            .filter(|(_, s)| now - s.started_at > Duration::from_secs(NEGENTROPY_TIMEOUT_SECS))
+            .map(|(subid, _)| subid.to_owned())
+            .collect();
+        for subid in expired.iter() {
+            tracing::info!("{}: negentropy sync {} timed out", &self.url, subid);
+            self.close_negentropy(subid).await?;
+            if let Some(sync) = self.negentropy_syncs.remove(subid) {
+                self.subscribe(sync.filter, &sync.handle, sync.job_id)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Abandon syncs for handles we are unsubscribing from
+    pub(super) async fn cancel_negentropy(&mut self, inner_handle: &str) -> Result<(), Error> {
+        let subids: Vec<String> = self
+            .negentropy_syncs
+            .iter()
+            .filter(|(_, s)| s.handle.contains(inner_handle))
+            .map(|(subid, _)| subid.to_owned())
+            .collect();
+        for subid in subids.iter() {
+            self.close_negentropy(subid).await?;
+            if let Some(sync) = self.negentropy_syncs.remove(subid) {
+                self.complete_negentropy_job(&sync)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn fetch_negentropy_needs(&mut self, sync: NegentropySync) -> Result<(), Error> {
+        tracing::debug!(
+            "{}: negentropy sync for {} is missing {} events (we have {} the relay lacks)",
+            &self.url,
+            &sync.handle,
+            sync.negentropy.need_ids.len(),
+            sync.negentropy.have_ids.len()
+        );
+
+        if sync.negentropy.need_ids.is_empty() {
+            return self.complete_negentropy_job(&sync);
+        }
+
+        if sync.negentropy.need_ids.len() > NEGENTROPY_MAX_NEED {
+            return self.subscribe(sync.filter, &sync.handle, sync.job_id).await;
+        }
+
+        for (i, batch) in sync
+            .negentropy
+            .need_ids
+            .chunks(NEGENTROPY_FETCH_BATCH)
+            .enumerate()
+        {
+            let mut filter = Filter::new();
+            filter.ids = batch.to_vec();
+
+            if i == 0 {
+                // The first batch takes over the chunk's handle and job, so the
+                // chunk completes when it reaches EOSE like a plain REQ would.
+                self.subscribe(filter, &sync.handle, sync.job_id).await?;
+            } else {
+                let handle = format!("temp_events_{}", self.next_events_subscription_id);
+                self.next_events_subscription_id += 1;
+                self.subscribe(filter, &handle, u64::MAX).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Complete the chunk's job without subscribing, the same way unsubscribe() would
+    fn complete_negentropy_job(&mut self, sync: &NegentropySync) -> Result<(), Error> {
+        if sync.handle.contains("_feed_chunk") {
+            self.loading_more -= 1;
+            GLOBALS.loading_more.fetch_sub(1, Ordering::SeqCst);
+        }
+        self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
+            self.url.clone(),
+            sync.job_id,
+        ))?;
+        Ok(())
+    }
+
+    async fn close_negentropy(&mut self, subid: &str) -> Result<(), Error> {
+        let wire = serde_json::to_string(&("NEG-CLOSE", subid))?;
+        self.send_negentropy_message(wire).await
+    }
+
+    async fn send_negentropy_message(&mut self, wire: String) -> Result<(), Error> {
+        let ws_stream = self.stream.as_mut().unwrap();
+        tracing::trace!("{}: Sending {}", &self.url, &wire);
+        self.last_message_sent = wire.clone();
+        ws_stream.send(WsMessage::Text(wire)).await?;
+        Ok(())
+    }
+}
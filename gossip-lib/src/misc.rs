@@ -1,6 +1,9 @@
 use crate::error::Error;
 use crate::globals::GLOBALS;
-use nostr_types::{Event, EventReference, Id, PayRequestData, PublicKey, UncheckedUrl};
+use nostr_types::{
+    Event, EventKind, EventReference, Id, NAddr, ParsedTag, PayRequestData, PublicKey, Tag,
+    UncheckedUrl,
+};
 use std::ops::Deref;
 
 /// The state that a Zap is in (it moves through 5 states before it is complete)
@@ -11,6 +14,8 @@ pub enum ZapState {
     SeekingAmount(Id, PublicKey, PayRequestData, UncheckedUrl),
     LoadingInvoice(Id, PublicKey),
     ReadyToPay(Id, String), // String is the Zap Invoice as a string, to be shown as a QR code
+    PayingWithWallet(Id),   // Instead of showing the invoice, our NIP-47 wallet is paying it
+    ReadyToPaySplit(Id, Vec<(PublicKey, String)>), // An invoice for each recipient of a zap split
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -101,8 +106,37 @@ impl<C: speedy::Context> speedy::Writable<C> for Private {
     }
 }
 
+/// A new identifier (the 'd' tag) for an addressable event, made from its title
+pub fn new_identifier(title: &str) -> String {
+    let slug: String = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-");
+    format!("{}-{:08x}", slug, rand::random::<u32>())
+        .trim_start_matches('-')
+        .to_owned()
+}
+
 pub fn exponential_decay(base: f32, halflife_seconds: u64, elapsed_seconds: u64) -> f32 {
     use std::f32::consts::E;
     let decay_constant = 2.0_f32.ln() / halflife_seconds as f32;
     base * E.powf(-decay_constant * elapsed_seconds as f32)
 }
+
+/// The address in an 'a' tag (or an 'A' tag, which comments use for their root),
+/// if it is the address of an event of this kind
+pub(crate) fn tagged_address(tag: &Tag, kind: EventKind) -> Option<NAddr> {
+    let parsed = if tag.tagname() == "A" {
+        Tag::new(&["a", tag.value()]).parse()
+    } else {
+        tag.parse()
+    };
+    match parsed {
+        Ok(ParsedTag::Address { address, .. }) if address.kind == kind && !address.d.is_empty() => {
+            Some(address)
+        }
+        _ => None,
+    }
+}
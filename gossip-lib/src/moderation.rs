@@ -0,0 +1,194 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::people::{PersonList, PersonListMetadata};
+use crate::relationship::RelationshipById;
+use nostr_types::{Id, PublicKey};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The d-tag of the NIP-51 follow set holding the moderators we trust
+pub const MODERATORS_DTAG: &str = "moderators";
+
+// Feeds check every event they show, every time they are built, so the moderators
+// and what they said about each event are cached. The cache is cleared when a label
+// or report arrives, and rebuilt now and then in case the moderators changed.
+struct ModerationCache {
+    built: Instant,
+    moderators: Vec<PublicKey>,
+    moderations: HashMap<Id, Option<Moderation>>,
+}
+
+static CACHE: Mutex<Option<ModerationCache>> = Mutex::new(None);
+
+// How long the moderators are cached for
+const CACHE_SECS: u64 = 60;
+
+// How many events we remember the moderation of
+const CACHE_MAX_EVENTS: usize = 100_000;
+
+/// The report types of NIP-56
+pub const REPORT_TYPES: [&str; 7] = [
+    "nudity",
+    "malware",
+    "profanity",
+    "illegal",
+    "spam",
+    "impersonation",
+    "other",
+];
+
+/// What the moderators said about an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Moderation {
+    /// Show it behind a content warning, for this reason
+    Warn(String),
+
+    /// Hide it, for this reason
+    Hide(String),
+}
+
+impl Moderation {
+    /// The reason given
+    pub fn reason(&self) -> &str {
+        match self {
+            Moderation::Warn(reason) => reason,
+            Moderation::Hide(reason) => reason,
+        }
+    }
+
+    // What a report type (or a label using one) calls for
+    fn from_reason(reason: &str) -> Option<Moderation> {
+        match reason.to_lowercase().as_str() {
+            "malware" | "illegal" | "spam" | "impersonation" => {
+                Some(Moderation::Hide(reason.to_owned()))
+            }
+            "nudity" | "profanity" | "other" => Some(Moderation::Warn(reason.to_owned())),
+            _ => None,
+        }
+    }
+}
+
+/// The moderator list, if there is one
+pub fn moderator_list() -> Result<Option<PersonList>, Error> {
+    Ok(GLOBALS
+        .db()
+        .find_person_list_by_dtag(MODERATORS_DTAG)?
+        .map(|(list, _)| list))
+}
+
+/// The moderator list, creating it if there isn't one yet
+pub(crate) fn ensure_moderator_list() -> Result<PersonList, Error> {
+    if let Some(list) = moderator_list()? {
+        return Ok(list);
+    }
+    let metadata = PersonListMetadata {
+        dtag: MODERATORS_DTAG.to_owned(),
+        title: "Moderators".to_owned(),
+        ..Default::default()
+    };
+    GLOBALS.db().allocate_person_list(&metadata, None)
+}
+
+/// The people whose labels and reports we act on: the moderators, and the user
+pub fn moderators() -> Result<Vec<PublicKey>, Error> {
+    let mut moderators: Vec<PublicKey> = match moderator_list()? {
+        Some(list) => GLOBALS
+            .db()
+            .get_people_in_list(list)?
+            .drain(..)
+            .map(|(pk, _)| pk)
+            .collect(),
+        None => vec![],
+    };
+    if let Some(pk) = GLOBALS.identity.public_key() {
+        if !moderators.contains(&pk) {
+            moderators.push(pk);
+        }
+    }
+    Ok(moderators)
+}
+
+/// Forget the cached moderation of events
+pub(crate) fn clear_cache() {
+    *CACHE.lock().unwrap() = None;
+}
+
+/// What the moderators said about an event, if anything. A hide outweighs a warning.
+///
+/// Labels in the `content-warning` namespace warn. Otherwise reports, and labels
+/// using a NIP-56 report type, hide or warn depending on the type.
+pub fn moderation(id: Id) -> Result<Option<Moderation>, Error> {
+    if !GLOBALS.db().read_setting_enable_moderation() {
+        return Ok(None);
+    }
+
+    let mut cache = CACHE.lock().unwrap();
+    let stale = match *cache {
+        Some(ref c) => {
+            c.built.elapsed() > Duration::from_secs(CACHE_SECS)
+                || c.moderations.len() >= CACHE_MAX_EVENTS
+        }
+        None => true,
+    };
+    if stale {
+        *cache = Some(ModerationCache {
+            built: Instant::now(),
+            moderators: moderators()?,
+            moderations: HashMap::new(),
+        });
+    }
+    let cache = cache.as_mut().unwrap();
+
+    if let Some(moderation) = cache.moderations.get(&id) {
+        return Ok(moderation.clone());
+    }
+    let moderation = moderation_by(id, &cache.moderators)?;
+    cache.moderations.insert(id, moderation.clone());
+    Ok(moderation)
+}
+
+// What these moderators said about an event
+fn moderation_by(id: Id, moderators: &[PublicKey]) -> Result<Option<Moderation>, Error> {
+    let mut result: Option<Moderation> = None;
+
+    for (by_id, rel) in GLOBALS.db().find_relationships_by_id(id)? {
+        let moderation = match rel {
+            RelationshipById::Labels { label, namespace } if namespace == "content-warning" => {
+                Some(Moderation::Warn(if label.is_empty() {
+                    namespace
+                } else {
+                    label
+                }))
+            }
+            RelationshipById::Labels { label, .. } => Moderation::from_reason(&label),
+            RelationshipById::Reports(report) => Moderation::from_reason(&report),
+            _ => None,
+        };
+        let Some(moderation) = moderation else {
+            continue;
+        };
+
+        // Only moderators count
+        let by = match GLOBALS.db().read_event(by_id)? {
+            Some(event) => event.pubkey,
+            None => continue,
+        };
+        if !moderators.contains(&by) {
+            continue;
+        }
+
+        match moderation {
+            Moderation::Hide(_) => return Ok(Some(moderation)),
+            Moderation::Warn(_) if result.is_none() => result = Some(moderation),
+            Moderation::Warn(_) => {}
+        }
+    }
+
+    Ok(result)
+}
+
+/// If the moderators want an event hidden
+pub fn is_hidden(id: Id) -> bool {
+    matches!(moderation(id), Ok(Some(Moderation::Hide(_))))
+}
@@ -0,0 +1,438 @@
+//! NIP-77 negentropy set reconciliation (protocol version 1)
+//!
+//! See <https://github.com/hoytech/negentropy> for the protocol specification.
+//! We only hold a small local set at a time (the events of one feed chunk) so
+//! everything is kept in a sorted Vec and no frame size limit is applied.
+
+use crate::error::{Error, ErrorKind};
+use nostr_types::{Id, Unixtime};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+const PROTOCOL_VERSION: u8 = 0x61;
+const ID_SIZE: usize = 32;
+const FINGERPRINT_SIZE: usize = 16;
+const BUCKETS: usize = 16;
+
+// Timestamps are encoded as 0 for infinity
+const INFINITY: u64 = u64::MAX;
+
+const MODE_SKIP: u64 = 0;
+const MODE_FINGERPRINT: u64 = 1;
+const MODE_ID_LIST: u64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Item {
+    timestamp: u64,
+    id: [u8; ID_SIZE],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bound {
+    timestamp: u64,
+    id_prefix: Vec<u8>,
+}
+
+impl Bound {
+    fn infinite() -> Bound {
+        Bound {
+            timestamp: INFINITY,
+            id_prefix: vec![],
+        }
+    }
+
+    fn is_at_or_below(&self, item: &Item) -> bool {
+        if self.timestamp != item.timestamp {
+            return self.timestamp < item.timestamp;
+        }
+        self.id_prefix.as_slice() <= &item.id[..]
+    }
+}
+
+/// One side of a negentropy reconciliation
+pub struct Negentropy {
+    items: Vec<Item>,
+    is_initiator: bool,
+    last_timestamp_in: u64,
+    last_timestamp_out: u64,
+
+    /// Ids we have that the other side does not (only tracked by the initiator)
+    pub have_ids: Vec<Id>,
+
+    /// Ids the other side has that we do not (only tracked by the initiator)
+    pub need_ids: Vec<Id>,
+}
+
+impl Negentropy {
+    /// Create from our local set of events
+    pub fn new(items: impl IntoIterator<Item = (Unixtime, Id)>) -> Negentropy {
+        let mut items: Vec<Item> = items
+            .into_iter()
+            .map(|(created_at, id)| Item {
+                timestamp: created_at.0.max(0) as u64,
+                id: id.0,
+            })
+            .collect();
+        items.sort();
+        items.dedup();
+
+        Negentropy {
+            items,
+            is_initiator: false,
+            last_timestamp_in: 0,
+            last_timestamp_out: 0,
+            have_ids: vec![],
+            need_ids: vec![],
+        }
+    }
+
+    /// Create the initial message (client side)
+    pub fn initiate(&mut self) -> Vec<u8> {
+        self.is_initiator = true;
+        self.last_timestamp_out = 0;
+
+        let mut output = vec![PROTOCOL_VERSION];
+        output.extend(self.split_range(0, self.items.len(), &Bound::infinite()));
+        output
+    }
+
+    /// Process a message from the other side, returning the reply to send.
+    ///
+    /// On the initiating side, `None` means reconciliation is complete and
+    /// `need_ids` holds everything the other side has that we lack.
+    pub fn reconcile(&mut self, query: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        self.last_timestamp_in = 0;
+        self.last_timestamp_out = 0;
+
+        let mut query = query;
+        let mut full_output = vec![PROTOCOL_VERSION];
+
+        let protocol_version = take_byte(&mut query)?;
+        if !(0x60..=0x6f).contains(&protocol_version) {
+            return Err(negentropy_error("invalid protocol version byte"));
+        }
+        if protocol_version != PROTOCOL_VERSION {
+            if self.is_initiator {
+                return Err(negentropy_error("unsupported protocol version"));
+            }
+            return Ok(Some(full_output));
+        }
+
+        let mut prev_bound = Bound {
+            timestamp: 0,
+            id_prefix: vec![],
+        };
+        let mut prev_index: usize = 0;
+        let mut skip = false;
+
+        while !query.is_empty() {
+            let mut output: Vec<u8> = Vec::new();
+
+            let curr_bound = self.decode_bound(&mut query)?;
+            let mode = decode_varint(&mut query)?;
+
+            let lower = prev_index;
+            let upper = self.find_lower_bound(prev_index, &curr_bound);
+
+            match mode {
+                MODE_SKIP => skip = true,
+                MODE_FINGERPRINT => {
+                    let their_fingerprint = take_bytes(&mut query, FINGERPRINT_SIZE)?;
+                    let our_fingerprint = self.fingerprint(lower, upper);
+                    if their_fingerprint != our_fingerprint.as_slice() {
+                        if skip {
+                            skip = false;
+                            output.extend(self.encode_bound(&prev_bound));
+                            output.extend(encode_varint(MODE_SKIP));
+                        }
+                        output.extend(self.split_range(lower, upper, &curr_bound));
+                    } else {
+                        skip = true;
+                    }
+                }
+                MODE_ID_LIST => {
+                    let num_ids = decode_varint(&mut query)?;
+                    let mut their_ids: HashSet<[u8; ID_SIZE]> = HashSet::new();
+                    for _ in 0..num_ids {
+                        let id: [u8; ID_SIZE] = take_bytes(&mut query, ID_SIZE)?.try_into()?;
+                        their_ids.insert(id);
+                    }
+
+                    if self.is_initiator {
+                        for item in &self.items[lower..upper] {
+                            if !their_ids.remove(&item.id) {
+                                self.have_ids.push(Id(item.id));
+                            }
+                        }
+                        self.need_ids.extend(their_ids.drain().map(Id));
+                        skip = true;
+                    } else {
+                        if skip {
+                            skip = false;
+                            output.extend(self.encode_bound(&prev_bound));
+                            output.extend(encode_varint(MODE_SKIP));
+                        }
+                        output.extend(self.encode_bound(&curr_bound));
+                        output.extend(encode_varint(MODE_ID_LIST));
+                        output.extend(encode_varint((upper - lower) as u64));
+                        for item in &self.items[lower..upper] {
+                            output.extend(item.id);
+                        }
+                    }
+                }
+                _ => return Err(negentropy_error("unexpected mode")),
+            }
+
+            full_output.extend(output);
+            prev_index = upper;
+            prev_bound = curr_bound;
+        }
+
+        if self.is_initiator && full_output.len() == 1 {
+            Ok(None)
+        } else {
+            Ok(Some(full_output))
+        }
+    }
+
+    fn split_range(&mut self, lower: usize, upper: usize, upper_bound: &Bound) -> Vec<u8> {
+        let mut output: Vec<u8> = Vec::new();
+        let num_items = upper - lower;
+
+        if num_items < BUCKETS * 2 {
+            output.extend(self.encode_bound(upper_bound));
+            output.extend(encode_varint(MODE_ID_LIST));
+            output.extend(encode_varint(num_items as u64));
+            for item in &self.items[lower..upper] {
+                output.extend(item.id);
+            }
+        } else {
+            let items_per_bucket = num_items / BUCKETS;
+            let buckets_with_extra = num_items % BUCKETS;
+            let mut curr = lower;
+
+            for i in 0..BUCKETS {
+                let bucket_size = items_per_bucket + if i < buckets_with_extra { 1 } else { 0 };
+                let fingerprint = self.fingerprint(curr, curr + bucket_size);
+                curr += bucket_size;
+
+                let next_bound = if curr == upper {
+                    upper_bound.clone()
+                } else {
+                    minimal_bound(&self.items[curr - 1], &self.items[curr])
+                };
+
+                output.extend(self.encode_bound(&next_bound));
+                output.extend(encode_varint(MODE_FINGERPRINT));
+                output.extend(fingerprint);
+            }
+        }
+
+        output
+    }
+
+    // The index of the first item at or after `bound`, searching from `begin`
+    fn find_lower_bound(&self, begin: usize, bound: &Bound) -> usize {
+        let begin = begin.min(self.items.len());
+        begin + self.items[begin..].partition_point(|item| !bound.is_at_or_below(item))
+    }
+
+    fn fingerprint(&self, lower: usize, upper: usize) -> [u8; FINGERPRINT_SIZE] {
+        // Sum of the ids as little-endian 256-bit integers, mod 2^256
+        let mut sum = [0u8; ID_SIZE];
+        for item in &self.items[lower..upper] {
+            let mut carry: u16 = 0;
+            for (s, b) in sum.iter_mut().zip(item.id.iter()) {
+                let total = *s as u16 + *b as u16 + carry;
+                *s = total as u8;
+                carry = total >> 8;
+            }
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(sum);
+        hasher.update(encode_varint((upper - lower) as u64));
+        let hash = hasher.finalize();
+
+        let mut fingerprint = [0u8; FINGERPRINT_SIZE];
+        fingerprint.copy_from_slice(&hash[..FINGERPRINT_SIZE]);
+        fingerprint
+    }
+
+    fn encode_bound(&mut self, bound: &Bound) -> Vec<u8> {
+        let mut output = self.encode_timestamp_out(bound.timestamp);
+        output.extend(encode_varint(bound.id_prefix.len() as u64));
+        output.extend(&bound.id_prefix);
+        output
+    }
+
+    fn decode_bound(&mut self, input: &mut &[u8]) -> Result<Bound, Error> {
+        let timestamp = self.decode_timestamp_in(input)?;
+        let len = decode_varint(input)? as usize;
+        if len > ID_SIZE {
+            return Err(negentropy_error("bound id prefix too long"));
+        }
+        let id_prefix = take_bytes(input, len)?.to_vec();
+        Ok(Bound {
+            timestamp,
+            id_prefix,
+        })
+    }
+
+    fn encode_timestamp_out(&mut self, timestamp: u64) -> Vec<u8> {
+        if timestamp == INFINITY {
+            self.last_timestamp_out = INFINITY;
+            return encode_varint(0);
+        }
+        let delta = timestamp - self.last_timestamp_out;
+        self.last_timestamp_out = timestamp;
+        encode_varint(delta + 1)
+    }
+
+    fn decode_timestamp_in(&mut self, input: &mut &[u8]) -> Result<u64, Error> {
+        let encoded = decode_varint(input)?;
+        if encoded == 0 || self.last_timestamp_in == INFINITY {
+            self.last_timestamp_in = INFINITY;
+            return Ok(INFINITY);
+        }
+        let timestamp = self.last_timestamp_in.saturating_add(encoded - 1);
+        self.last_timestamp_in = timestamp;
+        Ok(timestamp)
+    }
+}
+
+// The shortest bound that sorts after `prev` and at or before `curr`
+fn minimal_bound(prev: &Item, curr: &Item) -> Bound {
+    if curr.timestamp != prev.timestamp {
+        Bound {
+            timestamp: curr.timestamp,
+            id_prefix: vec![],
+        }
+    } else {
+        let shared = prev
+            .id
+            .iter()
+            .zip(curr.id.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        Bound {
+            timestamp: curr.timestamp,
+            id_prefix: curr.id[..shared + 1].to_vec(),
+        }
+    }
+}
+
+fn encode_varint(mut n: u64) -> Vec<u8> {
+    if n == 0 {
+        return vec![0];
+    }
+    let mut output: Vec<u8> = Vec::new();
+    while n != 0 {
+        output.push((n & 0x7f) as u8);
+        n >>= 7;
+    }
+    output.reverse();
+    let last = output.len() - 1;
+    for byte in &mut output[..last] {
+        *byte |= 0x80;
+    }
+    output
+}
+
+fn decode_varint(input: &mut &[u8]) -> Result<u64, Error> {
+    let mut n: u64 = 0;
+    loop {
+        let byte = take_byte(input)?;
+        n = (n << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+}
+
+fn take_byte(input: &mut &[u8]) -> Result<u8, Error> {
+    Ok(take_bytes(input, 1)?[0])
+}
+
+fn take_bytes<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if input.len() < len {
+        return Err(negentropy_error("message ended early"));
+    }
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
+
+fn negentropy_error(s: &str) -> Error {
+    ErrorKind::Negentropy(s.to_owned()).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn make_items(range: std::ops::Range<u32>) -> Vec<(Unixtime, Id)> {
+        range
+            .map(|n| {
+                let mut id = [0u8; ID_SIZE];
+                id[..4].copy_from_slice(&n.to_be_bytes());
+                id[31] = 0xaa;
+                (Unixtime(1_700_000_000 + (n / 3) as i64), Id(id))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_varint() {
+        for n in [0, 1, 127, 128, 255, 16384, u32::MAX as u64, u64::MAX] {
+            let encoded = encode_varint(n);
+            let mut input = encoded.as_slice();
+            assert_eq!(decode_varint(&mut input).unwrap(), n);
+            assert!(input.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_reconcile() {
+        // The client is missing 100..150 and 400..420, and has 1000..1005
+        // which the relay does not
+        let mut client_items = make_items(0..100);
+        client_items.extend(make_items(150..400));
+        client_items.extend(make_items(420..500));
+        client_items.extend(make_items(1000..1005));
+        let relay_items = make_items(0..500);
+
+        let mut client = Negentropy::new(client_items);
+        let mut relay = Negentropy::new(relay_items);
+
+        let mut msg = client.initiate();
+        let mut rounds = 0;
+        loop {
+            let reply = relay.reconcile(&msg).unwrap().unwrap();
+            match client.reconcile(&reply).unwrap() {
+                Some(next) => msg = next,
+                None => break,
+            }
+            rounds += 1;
+            assert!(rounds < 20);
+        }
+
+        let mut need: Vec<Id> = client.need_ids.clone();
+        need.sort_by_key(|id| id.0);
+        let mut expected: Vec<Id> = make_items(100..150)
+            .into_iter()
+            .chain(make_items(400..420))
+            .map(|(_, id)| id)
+            .collect();
+        expected.sort_by_key(|id| id.0);
+        assert_eq!(need, expected);
+
+        let mut have: Vec<Id> = client.have_ids.clone();
+        have.sort_by_key(|id| id.0);
+        let expected: Vec<Id> = make_items(1000..1005)
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect();
+        assert_eq!(have, expected);
+    }
+}
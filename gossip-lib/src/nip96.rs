@@ -0,0 +1,434 @@
+use crate::blossom::{self, HashOutput};
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use base64::Engine;
+use mime::Mime;
+use nostr_types::{EventKind, PreEvent, Tag, Unixtime};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{Client, Response, StatusCode};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+
+// How long we wait between asking if the server is done processing an upload,
+// and how many times we ask before giving up
+const PROCESSING_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const PROCESSING_POLL_LIMIT: usize = 90;
+
+/// A NIP-96 server's `/.well-known/nostr/nip96.json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Nip96Config {
+    /// Where to upload to
+    #[serde(default)]
+    pub api_url: String,
+
+    /// Where files can be downloaded from, if not the `api_url`
+    #[serde(default)]
+    pub download_url: Option<String>,
+
+    /// Another server that handles this server's uploads
+    #[serde(default)]
+    pub delegated_to_url: Option<String>,
+
+    /// The content types the server accepts (empty if it accepts anything)
+    #[serde(default)]
+    pub content_types: Vec<String>,
+}
+
+/// A file that was uploaded to a NIP-96 server
+#[derive(Debug, Clone)]
+pub struct Nip96Upload {
+    /// Where it can be downloaded from
+    pub url: String,
+
+    /// The tags of the server's `nip94_event` (NIP-94 file metadata)
+    pub tags: Vec<Tag>,
+}
+
+impl Nip96Upload {
+    /// The file metadata as an `imeta` tag (NIP-92), to attach to a note that
+    /// links to the file
+    pub fn imeta_tag(&self) -> Tag {
+        let mut fields: Vec<String> = vec![format!("url {}", self.url)];
+        for tag in self.tags.iter() {
+            if tag.tagname() == "url" || tag.value().is_empty() {
+                continue;
+            }
+            fields.push(format!("{} {}", tag.tagname(), tag.value()));
+        }
+        let mut imeta = Tag::new(&["imeta"]);
+        imeta.push_values(fields);
+        imeta
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+    #[serde(default)]
+    status: String,
+
+    #[serde(default)]
+    message: Option<String>,
+
+    #[serde(default)]
+    processing_url: Option<String>,
+
+    #[serde(default)]
+    nip94_event: Option<Nip94Event>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Nip94Event {
+    #[serde(default)]
+    tags: Vec<Tag>,
+}
+
+pub struct Nip96 {
+    client: Client,
+
+    // The whole-request timeout for everything but the upload itself, which
+    // can take as long as the file needs
+    timeout: Duration,
+}
+
+impl Nip96 {
+    pub fn new() -> Result<Nip96, Error> {
+        let connect_timeout =
+            Duration::new(GLOBALS.db().read_setting_fetcher_connect_timeout_sec(), 0);
+        let timeout = Duration::new(GLOBALS.db().read_setting_fetcher_timeout_sec(), 0);
+
+        let client = crate::proxy::http_client_builder(crate::proxy::http_proxy().as_deref())?
+            .connect_timeout(connect_timeout)
+            .build()?;
+
+        Ok(Nip96 { client, timeout })
+    }
+
+    /// GET /.well-known/nostr/nip96.json
+    /// If the server delegates to another server, this gets that server's configuration
+    pub async fn server_config(&self, server: &str) -> Result<Nip96Config, Error> {
+        let mut config = self.fetch_config(server).await?;
+        if config.api_url.is_empty() {
+            if let Some(delegate) = config.delegated_to_url.take() {
+                config = self.fetch_config(&delegate).await?;
+            }
+        }
+        if config.api_url.is_empty() {
+            return Err(ErrorKind::Nip96Error(format!("{} has no api_url", server)).into());
+        }
+        Ok(config)
+    }
+
+    async fn fetch_config(&self, server: &str) -> Result<Nip96Config, Error> {
+        let url = format!("{}.well-known/nostr/nip96.json", blossom::base_url(server)?);
+        let response = self.client.get(url).timeout(self.timeout).send().await?;
+        if response.status().as_u16() >= 300 {
+            return Err(ErrorKind::Nip96Error(format!(
+                "{} has no NIP-96 configuration: {}",
+                server,
+                response.status()
+            ))
+            .into());
+        }
+        Ok(response.json::<Nip96Config>().await?)
+    }
+
+    /// POST $api_url
+    /// Upload a file, waiting for the server to finish processing it
+    pub async fn upload(&self, server: &str, path: &Path) -> Result<Nip96Upload, Error> {
+        let config = self.server_config(server).await?;
+
+        let content_type = blossom::get_content_type(path)?;
+        if !config.content_types.is_empty()
+            && !config
+                .content_types
+                .iter()
+                .any(|ct| content_type_matches(ct, &content_type))
+        {
+            return Err(ErrorKind::Nip96Error(format!(
+                "{} does not accept {} files",
+                server, content_type
+            ))
+            .into());
+        }
+
+        let data = tokio::fs::read(path).await?;
+        let hash = HashOutput::from_bytes(&data);
+        let filename = path
+            .file_name()
+            .map(|n| n.to_string_lossy().replace(['"', '\r', '\n'], "_"))
+            .unwrap_or_else(|| "file".to_owned());
+
+        // Build the multipart/form-data body ourselves
+        let boundary = format!("gossip-{}", hash);
+        let mut body: Vec<u8> = Vec::with_capacity(data.len() + 512);
+        for (name, value) in [
+            ("size", format!("{}", data.len())),
+            ("content_type", format!("{}", content_type)),
+        ] {
+            body.extend_from_slice(
+                format!(
+                    "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+                )
+                .as_bytes(),
+            );
+        }
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{filename}\"\r\nContent-Type: {content_type}\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&data);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+        let authorization = authorization(&config.api_url, "POST", Some(hash)).await?;
+
+        let response = self
+            .client
+            .post(&config.api_url)
+            .header(AUTHORIZATION, format!("Nostr {}", authorization))
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .body(body)
+            .send()
+            .await?;
+
+        let mut upload_response = read_response(response).await?;
+
+        // The server may still be processing the file (e.g. transcoding a video)
+        let mut polls: usize = 0;
+        while upload_response.nip94_event.is_none() {
+            let processing_url = match upload_response.processing_url.take() {
+                Some(url) => url,
+                None => {
+                    return Err(ErrorKind::Nip96Error(
+                        upload_response
+                            .message
+                            .unwrap_or_else(|| "The server did not return the file".to_owned()),
+                    )
+                    .into())
+                }
+            };
+
+            polls += 1;
+            if polls > PROCESSING_POLL_LIMIT {
+                return Err(ErrorKind::Nip96Error(
+                    "The server took too long to process the file".to_owned(),
+                )
+                .into());
+            }
+            tokio::time::sleep(PROCESSING_POLL_INTERVAL).await;
+
+            let response = self
+                .client
+                .get(&processing_url)
+                .timeout(self.timeout)
+                .send()
+                .await?;
+            upload_response = read_response(response).await?;
+            if upload_response.nip94_event.is_none() && upload_response.processing_url.is_none() {
+                // Still processing, ask the same place again
+                upload_response.processing_url = Some(processing_url);
+            }
+        }
+
+        let tags = upload_response
+            .nip94_event
+            .map(|e| e.tags)
+            .unwrap_or_default();
+        let url = match tags.iter().find(|t| t.tagname() == "url") {
+            Some(tag) => tag.value().to_owned(),
+            None => {
+                return Err(ErrorKind::Nip96Error(
+                    "The server did not say where the file is".to_owned(),
+                )
+                .into())
+            }
+        };
+
+        Ok(Nip96Upload { url, tags })
+    }
+}
+
+async fn read_response(response: Response) -> Result<UploadResponse, Error> {
+    let status = response.status();
+    let full = response.bytes().await?;
+    parse_response(status, &full)
+}
+
+// What the server said, or why it failed
+fn parse_response(status: StatusCode, full: &[u8]) -> Result<UploadResponse, Error> {
+    let upload_response = match serde_json::from_slice::<UploadResponse>(full) {
+        Ok(ur) => ur,
+        Err(e) => {
+            if status.as_u16() >= 300 {
+                return Err(ErrorKind::Nip96Error(format!("{}", status)).into());
+            }
+            let text = String::from_utf8_lossy(full);
+            tracing::error!("Failed to deserialize NIP-96 upload response: {}", text);
+            return Err(e.into());
+        }
+    };
+    if status.as_u16() >= 300 || upload_response.status == "error" {
+        return Err(ErrorKind::Nip96Error(
+            upload_response
+                .message
+                .unwrap_or_else(|| format!("{}", status)),
+        )
+        .into());
+    }
+    Ok(upload_response)
+}
+
+// This returns the base64 encoded NIP-98 HTTP auth event
+async fn authorization(
+    url: &str,
+    method: &str,
+    payload: Option<HashOutput>,
+) -> Result<String, Error> {
+    let public_key = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Err(ErrorKind::NoPublicKey.into()),
+    };
+
+    let mut tags: Vec<Tag> = vec![Tag::new(&["u", url]), Tag::new(&["method", method])];
+    if let Some(hash) = payload {
+        tags.push(Tag::new(&["payload", &format!("{}", hash)]));
+    }
+
+    let pre_event = PreEvent {
+        pubkey: public_key,
+        created_at: Unixtime::now(),
+        kind: EventKind::from(27235), // NIP-98 HTTP Auth
+        tags,
+        content: "".to_owned(),
+    };
+
+    let event = GLOBALS.identity.sign_event(pre_event).await?;
+    let event_json = serde_json::to_string(&event)?;
+    let base64 = base64::engine::general_purpose::STANDARD.encode(&event_json);
+
+    Ok(base64)
+}
+
+// Content types in the server configuration may end with a wildcard, like "image/*"
+fn content_type_matches(accepted: &str, content_type: &Mime) -> bool {
+    match accepted.strip_suffix("/*") {
+        Some(prefix) => content_type.type_().as_str() == prefix,
+        None => content_type.essence_str() == accepted,
+    }
+}
+
+/// The NIP-96 servers from our settings
+pub fn nip96_servers() -> Vec<String> {
+    GLOBALS
+        .db()
+        .read_setting_nip96_servers()
+        .split_whitespace()
+        .map(|s| s.to_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn message(result: Result<UploadResponse, Error>) -> String {
+        match result {
+            Err(Error {
+                kind: ErrorKind::Nip96Error(s),
+                ..
+            }) => s,
+            other => panic!("expected a NIP-96 error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_content_type_matches() {
+        let png: Mime = "image/png".parse().unwrap();
+        let svg: Mime = "image/svg+xml; charset=utf-8".parse().unwrap();
+        assert!(content_type_matches("image/png", &png));
+        assert!(content_type_matches("image/*", &png));
+        assert!(content_type_matches("image/*", &svg));
+        assert!(content_type_matches("image/svg+xml", &svg));
+        assert!(!content_type_matches("image/jpeg", &png));
+        assert!(!content_type_matches("video/*", &png));
+        assert!(!content_type_matches("image", &png));
+    }
+
+    #[test]
+    fn test_imeta_tag() {
+        let upload = Nip96Upload {
+            url: "https://example.com/abc.png".to_owned(),
+            tags: vec![
+                Tag::new(&["url", "https://example.com/abc.png"]),
+                Tag::new(&["m", "image/png"]),
+                Tag::new(&["x", "abc"]),
+                Tag::new(&["dim", ""]),
+                Tag::new(&["blurhash", "LEHV6nWB2yk8"]),
+            ],
+        };
+        assert_eq!(
+            upload.imeta_tag(),
+            Tag::new(&[
+                "imeta",
+                "url https://example.com/abc.png",
+                "m image/png",
+                "x abc",
+                "blurhash LEHV6nWB2yk8",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        // Done
+        let body = br#"{"status":"success","nip94_event":{"tags":[["url","https://example.com/abc.png"]]}}"#;
+        let response = parse_response(StatusCode::CREATED, body).unwrap();
+        assert_eq!(response.nip94_event.unwrap().tags.len(), 1);
+
+        // Still processing
+        let body = br#"{"status":"processing","processing_url":"https://example.com/p/1"}"#;
+        let response = parse_response(StatusCode::ACCEPTED, body).unwrap();
+        assert!(response.nip94_event.is_none());
+        assert_eq!(
+            response.processing_url.as_deref(),
+            Some("https://example.com/p/1")
+        );
+
+        // Errors carry the server's message, else the HTTP status
+        let body = br#"{"status":"error","message":"File too large"}"#;
+        assert_eq!(
+            message(parse_response(StatusCode::OK, body)),
+            "File too large"
+        );
+        assert_eq!(
+            message(parse_response(StatusCode::PAYLOAD_TOO_LARGE, body)),
+            "File too large"
+        );
+        let body = br#"{"status":"error"}"#;
+        assert_eq!(
+            message(parse_response(StatusCode::INTERNAL_SERVER_ERROR, body)),
+            "500 Internal Server Error"
+        );
+        assert_eq!(
+            message(parse_response(
+                StatusCode::BAD_GATEWAY,
+                b"<html>Bad gateway</html>"
+            )),
+            "502 Bad Gateway"
+        );
+
+        // A successful status with something that isn't an upload response
+        assert!(matches!(
+            parse_response(StatusCode::OK, b"<html></html>"),
+            Err(Error {
+                kind: ErrorKind::SerdeJson(_),
+                ..
+            })
+        ));
+    }
+}
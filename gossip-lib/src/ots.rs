@@ -0,0 +1,1189 @@
+use crate::comms::{RelayConnectionReason, ToOverlordMessage};
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::relationship::RelationshipById;
+use crate::storage::types::BlockHeader1;
+use crate::storage::{BlockHeadersTable, Table, TimestampRequestsTable};
+use base64::Engine;
+use nostr_types::{Event, EventKind, Id, PreEvent, Tag, Unixtime};
+use reqwest::Client;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::io::SeekFrom;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+pub type TimestampRequest = crate::storage::types::TimestampRequest1;
+
+// The start of every detached timestamp file
+const MAGIC: &[u8] = b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94";
+
+const BITCOIN_TAG: [u8; 8] = [0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01];
+const PENDING_TAG: [u8; 8] = [0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e];
+
+// Limits from the reference implementation, so that a bad proof can't run away with us
+const MAX_MSG_LENGTH: usize = 4096;
+const MAX_PAYLOAD_LENGTH: usize = 8192;
+const MAX_DEPTH: usize = 256;
+
+// The easiest target a Bitcoin block can have, in compact form
+const POW_LIMIT_BITS: u32 = 0x1d00ffff;
+
+// Bitcoin blocks we know the hashes of, which block headers are checked against
+const CHECKPOINTS: &str = include_str!("bitcoin_checkpoints.txt");
+
+/// Something that attests that a message existed at some time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attestation {
+    /// The message is the merkle root of the Bitcoin block at this height
+    Bitcoin(u64),
+
+    /// A calendar server has the message, and will get it into the blockchain
+    Pending(String),
+
+    /// Something we don't understand (another blockchain, probably)
+    Unknown([u8; 8], Vec<u8>),
+}
+
+/// An operation leading from one message to the next
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    Sha1,
+    Ripemd160,
+    Sha256,
+    Keccak256,
+    Append(Vec<u8>),
+    Prepend(Vec<u8>),
+    Reverse,
+    Hexlify,
+}
+
+impl Op {
+    fn tag(&self) -> u8 {
+        match self {
+            Op::Sha1 => 0x02,
+            Op::Ripemd160 => 0x03,
+            Op::Sha256 => 0x08,
+            Op::Keccak256 => 0x67,
+            Op::Append(_) => 0xf0,
+            Op::Prepend(_) => 0xf1,
+            Op::Reverse => 0xf2,
+            Op::Hexlify => 0xf3,
+        }
+    }
+
+    /// Apply the operation. Returns None for hash functions we don't have.
+    fn apply(&self, msg: &[u8]) -> Option<Vec<u8>> {
+        match self {
+            Op::Sha256 => Some(Sha256::digest(msg).to_vec()),
+            Op::Append(arg) => Some([msg, arg].concat()),
+            Op::Prepend(arg) => Some([arg, msg].concat()),
+            Op::Reverse => Some(msg.iter().rev().copied().collect()),
+            Op::Hexlify => Some(hex::encode(msg).into_bytes()),
+            Op::Sha1 | Op::Ripemd160 | Op::Keccak256 => None,
+        }
+    }
+}
+
+/// A tree of operations from a message to the attestations of it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timestamp {
+    pub attestations: Vec<Attestation>,
+    pub ops: Vec<(Op, Timestamp)>,
+}
+
+impl Timestamp {
+    /// Parse a timestamp, as serialized without the file header
+    pub fn from_bytes(bytes: &[u8]) -> Result<Timestamp, Error> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let timestamp = Timestamp::read(&mut reader, 0)?;
+        if reader.pos != bytes.len() {
+            return Err(ots_error("trailing data"));
+        }
+        Ok(timestamp)
+    }
+
+    /// Serialize a timestamp, without the file header
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        self.write(&mut out);
+        out
+    }
+
+    fn read(reader: &mut Reader<'_>, depth: usize) -> Result<Timestamp, Error> {
+        if depth > MAX_DEPTH {
+            return Err(ots_error("too deep"));
+        }
+        let mut timestamp = Timestamp::default();
+        loop {
+            let mut tag = reader.byte()?;
+            let more = tag == 0xff;
+            if more {
+                tag = reader.byte()?;
+            }
+            if tag == 0x00 {
+                timestamp.attestations.push(read_attestation(reader)?);
+            } else {
+                let op = read_op(reader, tag)?;
+                let stamp = Timestamp::read(reader, depth + 1)?;
+                timestamp.ops.push((op, stamp));
+            }
+            if !more {
+                return Ok(timestamp);
+            }
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        let count = self.attestations.len() + self.ops.len();
+        let mut n = 0;
+        for attestation in &self.attestations {
+            n += 1;
+            if n < count {
+                out.push(0xff);
+            }
+            out.push(0x00);
+            write_attestation(attestation, out);
+        }
+        for (op, stamp) in &self.ops {
+            n += 1;
+            if n < count {
+                out.push(0xff);
+            }
+            write_op(op, out);
+            stamp.write(out);
+        }
+    }
+
+    /// All the attestations in the tree, each with the message it attests to.
+    /// Branches using hash functions we don't have are skipped.
+    pub fn all_attestations(&self, msg: &[u8]) -> Vec<(Vec<u8>, Attestation)> {
+        let mut found: Vec<(Vec<u8>, Attestation)> = Vec::new();
+        for attestation in &self.attestations {
+            found.push((msg.to_vec(), attestation.clone()));
+        }
+        for (op, stamp) in &self.ops {
+            if let Some(next) = op.apply(msg) {
+                found.extend(stamp.all_attestations(&next));
+            }
+        }
+        found
+    }
+
+    /// A copy of the tree with just the path to the first Bitcoin attestation,
+    /// or None if there isn't one
+    pub fn bitcoin_only(&self) -> Option<Timestamp> {
+        if let Some(attestation) = self
+            .attestations
+            .iter()
+            .find(|a| matches!(a, Attestation::Bitcoin(_)))
+        {
+            return Some(Timestamp {
+                attestations: vec![attestation.clone()],
+                ops: vec![],
+            });
+        }
+        self.ops.iter().find_map(|(op, stamp)| {
+            stamp.bitcoin_only().map(|stamp| Timestamp {
+                attestations: vec![],
+                ops: vec![(op.clone(), stamp)],
+            })
+        })
+    }
+
+    // Replace pending attestations with what the calendar has since found
+    fn merge(&mut self, msg: &[u8], upgrades: &[(Vec<u8>, Timestamp)]) {
+        let pending = self
+            .attestations
+            .iter()
+            .any(|a| matches!(a, Attestation::Pending(_)));
+        if pending {
+            if let Some((_, stamp)) = upgrades.iter().find(|(m, _)| m == msg) {
+                self.attestations
+                    .retain(|a| !matches!(a, Attestation::Pending(_)));
+                self.attestations.extend(stamp.attestations.iter().cloned());
+                self.ops.extend(stamp.ops.iter().cloned());
+            }
+        }
+        for (op, stamp) in self.ops.iter_mut() {
+            if let Some(next) = op.apply(msg) {
+                stamp.merge(&next, upgrades);
+            }
+        }
+    }
+}
+
+/// A timestamp file: the digest of the thing timestamped, and its timestamp
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetachedTimestamp {
+    /// The SHA-256 digest. For NIP-03 this is the event id.
+    pub digest: Vec<u8>,
+
+    pub timestamp: Timestamp,
+}
+
+impl DetachedTimestamp {
+    /// Parse a timestamp file
+    pub fn from_bytes(bytes: &[u8]) -> Result<DetachedTimestamp, Error> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(ots_error("not an OpenTimestamps proof"));
+        }
+        let mut reader = Reader {
+            bytes,
+            pos: MAGIC.len(),
+        };
+        if reader.varuint()? != 1 {
+            return Err(ots_error("unsupported version"));
+        }
+        if reader.byte()? != Op::Sha256.tag() {
+            return Err(ots_error("only SHA-256 digests are supported"));
+        }
+        let digest = reader.take(32)?.to_vec();
+        let timestamp = Timestamp::read(&mut reader, 0)?;
+        if reader.pos != bytes.len() {
+            return Err(ots_error("trailing data"));
+        }
+        Ok(DetachedTimestamp { digest, timestamp })
+    }
+
+    /// Serialize as a timestamp file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out: Vec<u8> = MAGIC.to_vec();
+        write_varuint(1, &mut out);
+        out.push(Op::Sha256.tag());
+        out.extend(&self.digest);
+        self.timestamp.write(&mut out);
+        out
+    }
+}
+
+/// What we know about the timestamps of an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimestampStatus {
+    /// We are checking the timestamps
+    Checking,
+
+    /// It has a timestamp, but it isn't in the blockchain yet
+    Pending,
+
+    /// It existed by the time of this Bitcoin block
+    Verified { height: u64, time: Unixtime },
+
+    /// The timestamps don't check out
+    Invalid(String),
+}
+
+/// What we know about the timestamps of an event, or None if it has no timestamps.
+///
+/// This starts checking timestamps we haven't checked yet.
+pub fn timestamp_status(id: Id) -> Option<TimestampStatus> {
+    if let Some(status) = GLOBALS.timestamp_status.get(&id) {
+        return status.clone();
+    }
+
+    let has_timestamps = match GLOBALS.db().find_relationships_by_id(id) {
+        Ok(rels) => rels
+            .iter()
+            .any(|(_, rel)| matches!(rel, RelationshipById::Timestamps)),
+        Err(_) => false,
+    };
+    if !has_timestamps {
+        GLOBALS.timestamp_status.insert(id, None);
+        return None;
+    }
+
+    GLOBALS
+        .timestamp_status
+        .insert(id, Some(TimestampStatus::Checking));
+    let _ = GLOBALS
+        .to_overlord
+        .send(ToOverlordMessage::VerifyTimestamps(id));
+    Some(TimestampStatus::Checking)
+}
+
+/// If we have asked a calendar to timestamp this event, and are waiting on it
+pub fn is_timestamp_requested(id: Id) -> bool {
+    matches!(TimestampRequestsTable::read_record(id, None), Ok(Some(_)))
+}
+
+/// Check the timestamps of an event, and remember what we found
+pub(crate) async fn verify_timestamps(id: Id) -> Result<(), Error> {
+    let status = match check_timestamps(id).await {
+        Ok(status) => status,
+        Err(e) => Some(TimestampStatus::Invalid(format!("{}", e))),
+    };
+    GLOBALS.timestamp_status.insert(id, status);
+    GLOBALS.ui_invalidate_note(id);
+    Ok(())
+}
+
+// The best thing any of the timestamps of an event says about it: the earliest
+// verified Bitcoin attestation, else pending, else why they failed.
+async fn check_timestamps(id: Id) -> Result<Option<TimestampStatus>, Error> {
+    let mut best: Option<TimestampStatus> = None;
+
+    for (timestamp_id, rel) in GLOBALS.db().find_relationships_by_id(id)? {
+        if !matches!(rel, RelationshipById::Timestamps) {
+            continue;
+        }
+        let Some(event) = GLOBALS.db().read_event(timestamp_id)? else {
+            continue;
+        };
+        let status = match check_timestamp_event(id, &event).await {
+            Ok(status) => status,
+            Err(e) => TimestampStatus::Invalid(format!("{}", e)),
+        };
+        best = Some(match (best, status) {
+            (
+                Some(TimestampStatus::Verified { height, time }),
+                TimestampStatus::Verified {
+                    height: h2,
+                    time: t2,
+                },
+            ) => {
+                if h2 < height {
+                    TimestampStatus::Verified {
+                        height: h2,
+                        time: t2,
+                    }
+                } else {
+                    TimestampStatus::Verified { height, time }
+                }
+            }
+            (Some(verified @ TimestampStatus::Verified { .. }), _) => verified,
+            (_, verified @ TimestampStatus::Verified { .. }) => verified,
+            (Some(TimestampStatus::Pending), _) => TimestampStatus::Pending,
+            (_, status) => status,
+        });
+    }
+
+    Ok(best)
+}
+
+async fn check_timestamp_event(id: Id, event: &Event) -> Result<TimestampStatus, Error> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(event.content.trim())
+        .map_err(|_| ots_error("the proof is not base64"))?;
+    let detached = DetachedTimestamp::from_bytes(&bytes)?;
+    if detached.digest != id.0 {
+        return Err(ots_error("the proof is for something else"));
+    }
+
+    let mut pending = false;
+    let mut verified: Option<(u64, Unixtime)> = None;
+    let mut header_error: Option<Error> = None;
+    for (msg, attestation) in detached.timestamp.all_attestations(&detached.digest) {
+        match attestation {
+            Attestation::Bitcoin(height) => {
+                if verified.is_some_and(|(h, _)| h <= height) {
+                    continue;
+                }
+                // Another attestation may still check out without this header
+                let header = match block_header(height).await {
+                    Ok(header) => header,
+                    Err(e) => {
+                        tracing::warn!("Bitcoin block {}: {}", height, e);
+                        header_error = Some(e);
+                        continue;
+                    }
+                };
+                if msg.len() != 32 || msg[..] != header[36..68] {
+                    return Err(ots_error(&format!(
+                        "the proof does not match Bitcoin block {}",
+                        height
+                    )));
+                }
+                let time = u32::from_le_bytes([header[68], header[69], header[70], header[71]]);
+                verified = Some((height, Unixtime(time as i64)));
+            }
+            Attestation::Pending(_) => pending = true,
+            Attestation::Unknown(_, _) => {}
+        }
+    }
+
+    match (verified, header_error) {
+        (Some((height, time)), _) => Ok(TimestampStatus::Verified { height, time }),
+        (None, _) if pending => Ok(TimestampStatus::Pending),
+        (None, Some(e)) => Err(e),
+        (None, None) => Err(ots_error("no attestation we can check")),
+    }
+}
+
+/// The header of the Bitcoin block at a height.
+///
+/// Headers come from those we have stored, else from the configured header
+/// source: a local file of headers for checking offline, or an Esplora API. The
+/// source is not trusted. A header is only accepted with the headers after it,
+/// each following on from the one before, up to a header we already have or
+/// one of the checkpoints that come with gossip.
+pub(crate) async fn block_header(height: u64) -> Result<Vec<u8>, Error> {
+    if let Some(stored) = BlockHeadersTable::read_record(height, None)? {
+        return Ok(stored.header);
+    }
+
+    let Some((checkpoint, checkpoint_hash)) = next_checkpoint(CHECKPOINTS, height) else {
+        return Err(ots_error(&format!(
+            "Bitcoin block {} is newer than the checkpoints in this version of gossip",
+            height
+        )));
+    };
+
+    // Headers we have were checked already, so we can stop at one of those
+    let mut top = checkpoint;
+    let mut top_hash = checkpoint_hash;
+    for h in height + 1..=checkpoint {
+        if let Some(stored) = BlockHeadersTable::read_record(h, None)? {
+            top = h - 1;
+            top_hash.copy_from_slice(&stored.header[4..36]);
+            top_hash.reverse();
+            break;
+        }
+    }
+
+    let source = GLOBALS.db().read_setting_ots_header_source();
+    let source = source.trim();
+    if source.is_empty() {
+        return Err(ots_error(&format!(
+            "we don't have the header of Bitcoin block {}, and no header source is set",
+            height
+        )));
+    }
+
+    let headers = if source.starts_with("http://") || source.starts_with("https://") {
+        esplora_headers(source, height, top).await?
+    } else {
+        file_headers(source, height, top).await?
+    };
+
+    if !check_chain(&headers, &top_hash) {
+        return Err(ots_error(
+            "the header source sent headers that don't lead to a known block",
+        ));
+    }
+
+    let mut txn = GLOBALS.db().get_write_txn()?;
+    for (h, header) in (height..).zip(headers.iter()) {
+        BlockHeadersTable::write_record(
+            &mut BlockHeader1 {
+                height: h,
+                header: header.clone(),
+            },
+            Some(&mut txn),
+        )?;
+    }
+    txn.commit()?;
+
+    Ok(headers[0].clone())
+}
+
+// The first checkpoint at or above a height
+fn next_checkpoint(checkpoints: &str, height: u64) -> Option<(u64, [u8; 32])> {
+    checkpoints
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (h, hash) = line.split_once(' ')?;
+            let hash: [u8; 32] = hex::decode(hash.trim()).ok()?.try_into().ok()?;
+            Some((h.parse::<u64>().ok()?, hash))
+        })
+        .find(|(h, _)| *h >= height)
+}
+
+// If the headers, one for each height from the first, each follow on from the
+// one before and have the proof of work they claim, and the last has the hash
+fn check_chain(headers: &[Vec<u8>], last_hash: &[u8; 32]) -> bool {
+    let Some(last) = headers.last() else {
+        return false;
+    };
+    if !headers.iter().all(|header| check_proof_of_work(header)) {
+        return false;
+    }
+    for pair in headers.windows(2) {
+        let mut prev_hash = block_hash(&pair[0]);
+        prev_hash.reverse();
+        if pair[1][4..36] != prev_hash {
+            return false;
+        }
+    }
+    block_hash(last) == *last_hash
+}
+
+// Headers from a file of 80 byte headers, one after another from the genesis block
+async fn file_headers(path: &str, from: u64, to: u64) -> Result<Vec<Vec<u8>>, Error> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut bytes = vec![0; ((to - from + 1) * 80) as usize];
+    file.seek(SeekFrom::Start(from * 80)).await?;
+    file.read_exact(&mut bytes).await?;
+    Ok(bytes.chunks(80).map(|header| header.to_vec()).collect())
+}
+
+// A block as an Esplora API lists it
+#[derive(Debug, Deserialize)]
+struct EsploraBlock {
+    height: u64,
+    version: i64,
+    #[serde(default)]
+    previousblockhash: Option<String>,
+    merkle_root: String,
+    timestamp: u32,
+    bits: u32,
+    nonce: u32,
+}
+
+impl EsploraBlock {
+    // Put the header back together from its fields
+    fn header(&self) -> Option<Vec<u8>> {
+        let hash = |display: &str| -> Option<Vec<u8>> {
+            let mut bytes = hex::decode(display).ok()?;
+            if bytes.len() != 32 {
+                return None;
+            }
+            bytes.reverse();
+            Some(bytes)
+        };
+
+        let mut header = Vec::with_capacity(80);
+        header.extend((self.version as u32).to_le_bytes());
+        match &self.previousblockhash {
+            Some(prev) => header.extend(hash(prev)?),
+            None => header.extend([0; 32]),
+        }
+        header.extend(hash(&self.merkle_root)?);
+        header.extend(self.timestamp.to_le_bytes());
+        header.extend(self.bits.to_le_bytes());
+        header.extend(self.nonce.to_le_bytes());
+        Some(header)
+    }
+}
+
+// Headers from an Esplora API, which lists blocks ten at a time going down
+async fn esplora_headers(base: &str, from: u64, to: u64) -> Result<Vec<Vec<u8>>, Error> {
+    let client = http_client()?;
+    let base = base.trim_end_matches('/');
+
+    let mut headers: Vec<Vec<u8>> = Vec::new();
+    let mut next = to;
+    loop {
+        let blocks: Vec<EsploraBlock> = client
+            .get(format!("{}/blocks/{}", base, next))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if blocks.is_empty() {
+            return Err(ots_error("the header source sent no blocks"));
+        }
+        for block in blocks {
+            if block.height != next {
+                return Err(ots_error("the header source sent the wrong block"));
+            }
+            let header = block
+                .header()
+                .ok_or_else(|| ots_error("the header source sent a bad block"))?;
+            headers.push(header);
+            if next == from {
+                headers.reverse();
+                return Ok(headers);
+            }
+            next -= 1;
+        }
+    }
+}
+
+// The hash of a block header, in the byte order it is usually displayed in
+fn block_hash(header: &[u8]) -> [u8; 32] {
+    let mut hash = [0; 32];
+    hash.copy_from_slice(&Sha256::digest(Sha256::digest(header)));
+    hash.reverse();
+    hash
+}
+
+// If the header hashes to no more than the target in its nBits field, and that
+// target is no easier than Bitcoin has ever allowed
+fn check_proof_of_work(header: &[u8]) -> bool {
+    if header.len() != 80 {
+        return false;
+    }
+    let bits = u32::from_le_bytes([header[72], header[73], header[74], header[75]]);
+    let Some(target) = bits_to_target(bits) else {
+        return false;
+    };
+    let Some(limit) = bits_to_target(POW_LIMIT_BITS) else {
+        return false;
+    };
+
+    // Big-endian arrays of the same length compare as numbers do
+    target <= limit && block_hash(header) <= target
+}
+
+// Expand the compact form of a target into a big-endian 256-bit number.
+// None if it is negative, zero or too big.
+fn bits_to_target(bits: u32) -> Option<[u8; 32]> {
+    let exponent = (bits >> 24) as usize;
+    let mantissa = bits & 0x007f_ffff;
+    if bits & 0x0080_0000 != 0 || mantissa == 0 {
+        return None;
+    }
+
+    // The mantissa is three bytes, the first of which is worth 256^(exponent-1)
+    let mut target = [0; 32];
+    for (i, byte) in mantissa.to_be_bytes()[1..].iter().enumerate() {
+        let Some(power) = exponent.checked_sub(i + 1) else {
+            continue; // shifted out
+        };
+        if power > 31 {
+            if *byte != 0 {
+                return None;
+            }
+            continue;
+        }
+        target[31 - power] = *byte;
+    }
+    Some(target)
+}
+
+/// Ask the configured calendar to timestamp one of our events. It takes a few
+/// hours to get into a Bitcoin block, after which we publish the timestamp.
+pub(crate) async fn request_timestamp(id: Id) -> Result<(), Error> {
+    let calendar = GLOBALS.db().read_setting_ots_calendar();
+    let calendar = calendar.trim().trim_end_matches('/').to_owned();
+    if calendar.is_empty() {
+        return Err(ots_error("no calendar server is configured"));
+    }
+
+    // Calendars timestamp a digest of what we send, so we add a random nonce
+    // first, as the reference client does, to not reveal the event id
+    let nonce: [u8; 16] = rand::random();
+    let nonce_op = Op::Append(nonce.to_vec());
+    let msg = Op::Sha256
+        .apply(&nonce_op.apply(&id.0).unwrap_or_default())
+        .unwrap_or_default();
+
+    let response = http_client()?
+        .post(format!("{}/digest", calendar))
+        .header("Accept", "application/vnd.opentimestamps.v1")
+        .body(msg)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let calendar_stamp = Timestamp::from_bytes(&response)?;
+
+    let timestamp = Timestamp {
+        attestations: vec![],
+        ops: vec![(
+            nonce_op,
+            Timestamp {
+                attestations: vec![],
+                ops: vec![(Op::Sha256, calendar_stamp)],
+            },
+        )],
+    };
+
+    TimestampRequestsTable::write_record(
+        &mut TimestampRequest {
+            id,
+            calendar,
+            proof: timestamp.to_bytes(),
+            requested_at: Unixtime::now(),
+        },
+        None,
+    )?;
+
+    Ok(())
+}
+
+/// Ask the calendars if our requested timestamps have made it into the blockchain,
+/// and publish those that have
+pub(crate) async fn upgrade_timestamps() -> Result<(), Error> {
+    let client = http_client()?;
+
+    for mut request in TimestampRequestsTable::filter_records(|_| true)? {
+        let mut timestamp = Timestamp::from_bytes(&request.proof)?;
+
+        if timestamp.bitcoin_only().is_none() {
+            // Ask about each pending attestation
+            let mut upgrades: Vec<(Vec<u8>, Timestamp)> = Vec::new();
+            for (msg, attestation) in timestamp.all_attestations(&request.id.0) {
+                let Attestation::Pending(url) = attestation else {
+                    continue;
+                };
+                let url = format!(
+                    "{}/timestamp/{}",
+                    url.trim_end_matches('/'),
+                    hex::encode(&msg)
+                );
+                let response = match client
+                    .get(url)
+                    .header("Accept", "application/vnd.opentimestamps.v1")
+                    .send()
+                    .await
+                {
+                    Ok(response) if response.status().is_success() => response,
+                    _ => continue, // not yet
+                };
+                if let Ok(stamp) = Timestamp::from_bytes(&response.bytes().await?) {
+                    upgrades.push((msg, stamp));
+                }
+            }
+            if upgrades.is_empty() {
+                continue;
+            }
+            timestamp.merge(&request.id.0, &upgrades);
+            request.proof = timestamp.to_bytes();
+            TimestampRequestsTable::write_record(&mut request, None)?;
+        }
+
+        if let Some(bitcoin) = timestamp.bitcoin_only() {
+            // A local key has to be unlocked first. We try again next time.
+            if GLOBALS.identity.has_private_key() && !GLOBALS.identity.is_unlocked() {
+                continue;
+            }
+            publish_timestamp(request.id, bitcoin).await?;
+            TimestampRequestsTable::delete_record(request.id, None)?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn publish_timestamp(id: Id, timestamp: Timestamp) -> Result<(), Error> {
+    let public_key = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Err(ErrorKind::NoPublicKey.into()),
+    };
+    let target = match GLOBALS.db().read_event(id)? {
+        Some(event) => event,
+        None => return Ok(()),
+    };
+
+    let detached = DetachedTimestamp {
+        digest: id.0.to_vec(),
+        timestamp,
+    };
+
+    let mut e_tag = vec!["e".to_owned(), id.as_hex_string()];
+    if let Some((url, _)) = GLOBALS.db().get_event_seen_on_relay(id)?.first() {
+        e_tag.push(url.as_str().to_owned());
+    }
+    let e_tag: Vec<&str> = e_tag.iter().map(|s| s.as_str()).collect();
+
+    let event = {
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now(),
+            kind: EventKind::Timestamp,
+            tags: vec![
+                Tag::new(&e_tag),
+                Tag::new(&["k", &format!("{}", u32::from(target.kind))]),
+            ],
+            content: base64::engine::general_purpose::STANDARD.encode(detached.to_bytes()),
+        };
+        GLOBALS.identity.sign_event(pre_event).await?
+    };
+
+    // Process the event locally. Check the timestamp again when it is next shown.
+    crate::process::process_new_event(&event, None, None, false, false).await?;
+    GLOBALS.timestamp_status.remove(&id);
+
+    let relay_urls = crate::relay::relays_to_post_to(&event)?;
+    crate::outbox::post_events(vec![event], relay_urls, RelayConnectionReason::PostEvent)?;
+
+    GLOBALS
+        .status_queue
+        .write()
+        .write("Published a timestamp of your note.".to_owned());
+
+    Ok(())
+}
+
+fn http_client() -> Result<Client, Error> {
+    let connect_timeout = Duration::new(GLOBALS.db().read_setting_fetcher_connect_timeout_sec(), 0);
+    let timeout = Duration::new(GLOBALS.db().read_setting_fetcher_timeout_sec(), 0);
+    Ok(
+        crate::proxy::http_client_builder(crate::proxy::http_proxy().as_deref())?
+            .connect_timeout(connect_timeout)
+            .timeout(timeout)
+            .build()?,
+    )
+}
+
+fn ots_error(what: &str) -> Error {
+    ErrorKind::General(format!("OpenTimestamps: {}", what)).into()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn take(&mut self, len: usize) -> Result<&[u8], Error> {
+        if self.pos + len > self.bytes.len() {
+            return Err(ots_error("the proof is truncated"));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn varuint(&mut self) -> Result<u64, Error> {
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            if shift > 63 {
+                return Err(ots_error("bad number"));
+            }
+            value |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn varbytes(&mut self, max: usize) -> Result<Vec<u8>, Error> {
+        let len = self.varuint()? as usize;
+        if len > max {
+            return Err(ots_error("too long"));
+        }
+        Ok(self.take(len)?.to_vec())
+    }
+}
+
+fn read_attestation(reader: &mut Reader<'_>) -> Result<Attestation, Error> {
+    let mut tag = [0; 8];
+    tag.copy_from_slice(reader.take(8)?);
+    let payload = reader.varbytes(MAX_PAYLOAD_LENGTH)?;
+    let mut inner = Reader {
+        bytes: &payload,
+        pos: 0,
+    };
+    Ok(match tag {
+        BITCOIN_TAG => Attestation::Bitcoin(inner.varuint()?),
+        PENDING_TAG => {
+            let url = inner.varbytes(1000)?;
+            Attestation::Pending(String::from_utf8(url).map_err(|_| ots_error("bad calendar url"))?)
+        }
+        _ => Attestation::Unknown(tag, payload),
+    })
+}
+
+fn write_attestation(attestation: &Attestation, out: &mut Vec<u8>) {
+    let (tag, payload) = match attestation {
+        Attestation::Bitcoin(height) => {
+            let mut payload = Vec::new();
+            write_varuint(*height, &mut payload);
+            (BITCOIN_TAG, payload)
+        }
+        Attestation::Pending(url) => {
+            let mut payload = Vec::new();
+            write_varbytes(url.as_bytes(), &mut payload);
+            (PENDING_TAG, payload)
+        }
+        Attestation::Unknown(tag, payload) => (*tag, payload.clone()),
+    };
+    out.extend(tag);
+    write_varbytes(&payload, out);
+}
+
+fn read_op(reader: &mut Reader<'_>, tag: u8) -> Result<Op, Error> {
+    Ok(match tag {
+        0x02 => Op::Sha1,
+        0x03 => Op::Ripemd160,
+        0x08 => Op::Sha256,
+        0x67 => Op::Keccak256,
+        0xf0 => Op::Append(reader.varbytes(MAX_MSG_LENGTH)?),
+        0xf1 => Op::Prepend(reader.varbytes(MAX_MSG_LENGTH)?),
+        0xf2 => Op::Reverse,
+        0xf3 => Op::Hexlify,
+        _ => return Err(ots_error("unknown operation")),
+    })
+}
+
+fn write_op(op: &Op, out: &mut Vec<u8>) {
+    out.push(op.tag());
+    match op {
+        Op::Append(arg) | Op::Prepend(arg) => write_varbytes(arg, out),
+        _ => {}
+    }
+}
+
+fn write_varuint(mut value: u64, out: &mut Vec<u8>) {
+    loop {
+        let b = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(b);
+            return;
+        }
+        out.push(b | 0x80);
+    }
+}
+
+fn write_varbytes(bytes: &[u8], out: &mut Vec<u8>) {
+    write_varuint(bytes.len() as u64, out);
+    out.extend(bytes);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+    const BLOCK_1_HEADER: &str = "010000006fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000982051fd1e4ba744bbbe680e1fee14677ba1a3c3540bf7b1cdb606e857233e0e61bc6649ffff001d01e36299";
+
+    fn sample() -> DetachedTimestamp {
+        DetachedTimestamp {
+            digest: vec![1; 32],
+            timestamp: Timestamp {
+                attestations: vec![Attestation::Pending("https://a".to_owned())],
+                ops: vec![(
+                    Op::Append(vec![0xaa]),
+                    Timestamp {
+                        attestations: vec![Attestation::Bitcoin(100)],
+                        ops: vec![],
+                    },
+                )],
+            },
+        }
+    }
+
+    #[test]
+    fn test_varuint() {
+        for n in [0, 1, 127, 128, 300, 16384, u32::MAX as u64, u64::MAX] {
+            let mut encoded = Vec::new();
+            write_varuint(n, &mut encoded);
+            let mut reader = Reader {
+                bytes: &encoded,
+                pos: 0,
+            };
+            assert_eq!(reader.varuint().unwrap(), n);
+            assert_eq!(reader.pos, encoded.len());
+        }
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut expected: Vec<u8> = MAGIC.to_vec();
+        expected.extend([0x01, 0x08]);
+        expected.extend([1; 32]);
+        // The pending attestation, with more to follow
+        expected.extend([0xff, 0x00]);
+        expected.extend(PENDING_TAG);
+        expected.extend([0x0a, 0x09]);
+        expected.extend(b"https://a");
+        // The append operation, then the timestamp it leads to
+        expected.extend([0xf0, 0x01, 0xaa, 0x00]);
+        expected.extend(BITCOIN_TAG);
+        expected.extend([0x01, 100]);
+
+        assert_eq!(sample().to_bytes(), expected);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let detached = sample();
+        let bytes = detached.to_bytes();
+        assert_eq!(DetachedTimestamp::from_bytes(&bytes).unwrap(), detached);
+
+        let timestamp = detached.timestamp;
+        let bytes = timestamp.to_bytes();
+        assert_eq!(Timestamp::from_bytes(&bytes).unwrap(), timestamp);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let bytes = sample().to_bytes();
+
+        // Truncated
+        assert!(DetachedTimestamp::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        // Trailing data
+        let mut long = bytes.clone();
+        long.push(0);
+        assert!(DetachedTimestamp::from_bytes(&long).is_err());
+
+        // Not a timestamp file
+        assert!(DetachedTimestamp::from_bytes(&bytes[1..]).is_err());
+
+        // An unknown operation
+        let mut bad = bytes.clone();
+        let op = MAGIC.len() + 2 + 32 + 2 + 8 + 11;
+        assert_eq!(bad[op], 0xf0);
+        bad[op] = 0x55;
+        assert!(DetachedTimestamp::from_bytes(&bad).is_err());
+    }
+
+    #[test]
+    fn test_attestations() {
+        let detached = sample();
+        let found = detached.timestamp.all_attestations(&detached.digest);
+        assert_eq!(found.len(), 2);
+        assert_eq!(
+            found[0],
+            (vec![1; 32], Attestation::Pending("https://a".to_owned()))
+        );
+        let mut appended = vec![1; 32];
+        appended.push(0xaa);
+        assert_eq!(found[1], (appended, Attestation::Bitcoin(100)));
+
+        let bitcoin = detached.timestamp.bitcoin_only().unwrap();
+        assert!(bitcoin.attestations.is_empty());
+        assert_eq!(bitcoin.ops.len(), 1);
+        assert_eq!(
+            bitcoin.ops[0].1.attestations,
+            vec![Attestation::Bitcoin(100)]
+        );
+    }
+
+    #[test]
+    fn test_proof_of_work() {
+        let genesis = hex::decode(GENESIS_HEADER).unwrap();
+        assert_eq!(
+            hex::encode(block_hash(&genesis)),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        assert!(check_proof_of_work(&genesis));
+
+        // Block 1 follows on from the genesis block
+        let block_1 = hex::decode(BLOCK_1_HEADER).unwrap();
+        assert!(check_proof_of_work(&block_1));
+        let mut prev_hash = block_hash(&genesis);
+        prev_hash.reverse();
+        assert_eq!(block_1[4..36], prev_hash);
+
+        // A different nonce doesn't have the work
+        let mut tampered = genesis.clone();
+        tampered[79] ^= 1;
+        assert!(!check_proof_of_work(&tampered));
+
+        // An easier target than Bitcoin allows
+        let mut easy = genesis.clone();
+        easy[75] = 0x20;
+        assert!(!check_proof_of_work(&easy));
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let genesis_hash = block_hash(&hex::decode(GENESIS_HEADER).unwrap());
+        assert_eq!(next_checkpoint(CHECKPOINTS, 0), Some((0, genesis_hash)));
+
+        // Every line that isn't a comment is a checkpoint, in height order
+        let lines = CHECKPOINTS
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.trim().starts_with('#'));
+        let mut last: Option<u64> = None;
+        for line in lines {
+            let height: u64 = line.split_once(' ').unwrap().0.parse().unwrap();
+            if let Some(last) = last {
+                assert!(last < height);
+            }
+            assert_eq!(next_checkpoint(CHECKPOINTS, height).unwrap().0, height);
+            last = Some(height);
+        }
+
+        let text = "# comment\n10 00000000000000000000000000000000000000000000000000000000000000aa\n\n20 00000000000000000000000000000000000000000000000000000000000000bb\n";
+        assert_eq!(next_checkpoint(text, 5).unwrap().0, 10);
+        assert_eq!(next_checkpoint(text, 10).unwrap().0, 10);
+        assert_eq!(next_checkpoint(text, 11).unwrap().0, 20);
+        assert_eq!(next_checkpoint(text, 21), None);
+    }
+
+    #[test]
+    fn test_check_chain() {
+        let genesis = hex::decode(GENESIS_HEADER).unwrap();
+        let block_1 = hex::decode(BLOCK_1_HEADER).unwrap();
+        let block_1_hash = block_hash(&block_1);
+
+        assert!(check_chain(
+            &[genesis.clone(), block_1.clone()],
+            &block_1_hash
+        ));
+        assert!(check_chain(&[block_1.clone()], &block_1_hash));
+
+        // Not leading to the block we know
+        assert!(!check_chain(&[genesis.clone()], &block_1_hash));
+
+        // Out of order
+        assert!(!check_chain(
+            &[block_1.clone(), genesis.clone()],
+            &block_hash(&genesis)
+        ));
+
+        // A header that doesn't have its proof of work
+        let mut tampered = genesis.clone();
+        tampered[79] ^= 1;
+        assert!(!check_chain(&[tampered, block_1], &block_1_hash));
+
+        assert!(!check_chain(&[], &block_1_hash));
+    }
+
+    #[test]
+    fn test_esplora_header() {
+        let block = EsploraBlock {
+            height: 1,
+            version: 1,
+            previousblockhash: Some(
+                "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f".to_owned(),
+            ),
+            merkle_root: "0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098"
+                .to_owned(),
+            timestamp: 1231469665,
+            bits: 486604799,
+            nonce: 2573394689,
+        };
+        assert_eq!(
+            block.header().unwrap(),
+            hex::decode(BLOCK_1_HEADER).unwrap()
+        );
+
+        let genesis = EsploraBlock {
+            height: 0,
+            version: 1,
+            previousblockhash: None,
+            merkle_root: "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+                .to_owned(),
+            timestamp: 1231006505,
+            bits: 486604799,
+            nonce: 2083236893,
+        };
+        assert_eq!(
+            genesis.header().unwrap(),
+            hex::decode(GENESIS_HEADER).unwrap()
+        );
+
+        let bad = EsploraBlock {
+            merkle_root: "abcd".to_owned(),
+            ..genesis
+        };
+        assert!(bad.header().is_none());
+    }
+
+    #[test]
+    fn test_bits_to_target() {
+        let mut limit = [0; 32];
+        limit[4] = 0xff;
+        limit[5] = 0xff;
+        assert_eq!(bits_to_target(0x1d00ffff), Some(limit));
+
+        let mut small = [0; 32];
+        small[30] = 0x12;
+        small[31] = 0x34;
+        assert_eq!(bits_to_target(0x03001234), Some(small));
+        assert_eq!(bits_to_target(0x02123456), Some(small));
+
+        // Negative, zero and too big
+        assert_eq!(bits_to_target(0x1d800000), None);
+        assert_eq!(bits_to_target(0x1d000000), None);
+        assert_eq!(bits_to_target(0x22123456), None);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::comms::{RelayConnectionReason, RelayJob, ToMinionPayload, ToMinionPayloadDetail};
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::manager;
+use crate::storage::{OutboxTable, Table};
+use nostr_types::{Event, EventKind, Id, RelayUrl, Unixtime};
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub type OutboxEntry = crate::storage::types::OutboxEntry1;
+pub type OutboxDelivery = crate::storage::types::OutboxDelivery1;
+pub type DeliveryState = crate::storage::types::DeliveryState1;
+
+// How long we wait for an OK before we count the delivery as failed
+const OK_TIMEOUT_SECS: u64 = 120;
+
+// The first retry waits this long, and each one after waits twice as long as the last
+const RETRY_BASE_SECS: u64 = 60;
+
+// No retry waits longer than this
+const RETRY_MAX_SECS: u64 = 6 * 60 * 60;
+
+// How many times we send an event to a relay before giving up
+const MAX_ATTEMPTS: u32 = 8;
+
+// How long we keep the delivery report of an event
+const KEEP_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Send our events to relays, recording each delivery in the outbox so that failed
+/// deliveries are retried.
+pub(crate) fn post_events(
+    events: Vec<Event>,
+    relay_urls: Vec<RelayUrl>,
+    reason: RelayConnectionReason,
+) -> Result<(), Error> {
+    for event in &events {
+        record_attempt(event, &relay_urls)?;
+    }
+
+    manager::run_jobs_on_all_relays(
+        relay_urls,
+        vec![RelayJob {
+            reason,
+            payload: ToMinionPayload {
+                job_id: rand::random::<u64>(),
+                detail: ToMinionPayloadDetail::PostEvents(events),
+            },
+        }],
+    );
+
+    Ok(())
+}
+
+/// Record that we are sending our event to these relays.
+///
+/// Events by other people, and ephemeral events, are not tracked. Gift wraps are
+/// signed by a random key, but any gift wrap we send is one we wrapped.
+pub(crate) fn record_attempt(event: &Event, relay_urls: &[RelayUrl]) -> Result<(), Error> {
+    if event.kind.is_ephemeral() {
+        return Ok(());
+    }
+    if event.kind != EventKind::GiftWrap && Some(event.pubkey) != GLOBALS.identity.public_key() {
+        return Ok(());
+    }
+
+    let account = GLOBALS.identity.public_key();
+    let now = Unixtime::now();
+    OutboxTable::modify(
+        event.id,
+        |entry| {
+            if entry.account.is_none() {
+                entry.account = account;
+            }
+            for url in relay_urls {
+                let delivery = match entry.deliveries.iter_mut().position(|d| d.relay == *url) {
+                    Some(i) => &mut entry.deliveries[i],
+                    None => {
+                        entry.deliveries.push(OutboxDelivery {
+                            relay: url.to_owned(),
+                            state: DeliveryState::Pending,
+                            attempts: 0,
+                            last_attempt: None,
+                            message: "".to_owned(),
+                        });
+                        entry.deliveries.last_mut().unwrap()
+                    }
+                };
+                delivery.state = DeliveryState::Pending;
+                delivery.attempts += 1;
+                delivery.last_attempt = Some(now);
+                delivery.message = "".to_owned();
+            }
+        },
+        None,
+    )
+}
+
+/// Record the OK a relay gave in response to one of our events
+pub(crate) fn record_ok(id: Id, relay: &RelayUrl, ok: bool, message: &str) -> Result<(), Error> {
+    let state = if ok || message.starts_with("duplicate:") {
+        DeliveryState::Accepted
+    } else if message.starts_with("rate-limited:") || message.starts_with("error:") {
+        // These might pass if we try again later
+        DeliveryState::Failed
+    } else {
+        // blocked, invalid, pow, restricted, etc.
+        DeliveryState::Rejected
+    };
+
+    OutboxTable::modify_if_exists(
+        id,
+        |entry| {
+            if let Some(delivery) = entry.deliveries.iter_mut().find(|d| d.relay == *relay) {
+                delivery.state = state;
+                delivery.message = message.to_owned();
+            }
+        },
+        None,
+    )?;
+
+    GLOBALS.ui_invalidate_note(id);
+
+    Ok(())
+}
+
+/// Get the delivery report for one of our events, if we have one
+pub fn delivery_report(id: Id) -> Result<Option<OutboxEntry>, Error> {
+    OutboxTable::read_record(id, None)
+}
+
+/// Retry deliveries of the current account's events that failed and are due, give up
+/// on those that have failed too often, and forget old reports.
+pub(crate) fn retry_failed_deliveries() -> Result<(), Error> {
+    let account = GLOBALS.identity.public_key();
+    let now = Unixtime::now();
+
+    let mut to_send: HashMap<RelayUrl, Vec<Event>> = HashMap::new();
+    let mut expired: Vec<Id> = Vec::new();
+
+    let entries = OutboxTable::filter_records(|entry| {
+        !entry.is_finished() || now - entry.queued_at > Duration::from_secs(KEEP_SECS)
+    })?;
+
+    let mut changed_entries: Vec<OutboxEntry> = Vec::new();
+    for mut entry in entries {
+        if now - entry.queued_at > Duration::from_secs(KEEP_SECS) {
+            expired.push(entry.id);
+            continue;
+        }
+
+        // Another account's events wait until it is the current one again
+        if entry.account != account {
+            continue;
+        }
+
+        let event = match GLOBALS.db().read_event(entry.id)? {
+            Some(event) => event,
+            None => {
+                // Deleted (or never sent, e.g. an undone post)
+                expired.push(entry.id);
+                continue;
+            }
+        };
+
+        let mut changed = false;
+        for delivery in entry.deliveries.iter_mut() {
+            let since_last = match delivery.last_attempt {
+                Some(when) => now - when,
+                None => Duration::MAX,
+            };
+
+            if delivery.state == DeliveryState::Pending
+                && since_last > Duration::from_secs(OK_TIMEOUT_SECS)
+            {
+                delivery.state = DeliveryState::Failed;
+                if delivery.message.is_empty() {
+                    delivery.message = "no response from relay".to_owned();
+                }
+                changed = true;
+            }
+
+            if delivery.state != DeliveryState::Failed {
+                continue;
+            }
+
+            if delivery.attempts >= MAX_ATTEMPTS {
+                delivery.state = DeliveryState::GaveUp;
+                changed = true;
+                tracing::info!(
+                    "Gave up posting {} to {}: {}",
+                    entry.id.as_hex_string(),
+                    &delivery.relay,
+                    &delivery.message
+                );
+                continue;
+            }
+
+            let backoff = RETRY_BASE_SECS
+                .saturating_mul(1 << delivery.attempts.saturating_sub(1).min(16))
+                .min(RETRY_MAX_SECS);
+            if since_last > Duration::from_secs(backoff) {
+                delivery.state = DeliveryState::Pending;
+                delivery.attempts += 1;
+                delivery.last_attempt = Some(now);
+                changed = true;
+                to_send
+                    .entry(delivery.relay.clone())
+                    .or_default()
+                    .push(event.clone());
+            }
+        }
+
+        if changed {
+            changed_entries.push(entry);
+        }
+    }
+
+    let mut txn = GLOBALS.db().get_write_txn()?;
+    for mut entry in changed_entries {
+        OutboxTable::write_record(&mut entry, Some(&mut txn))?;
+        GLOBALS.ui_invalidate_note(entry.id);
+    }
+    for id in expired {
+        OutboxTable::delete_record(id, Some(&mut txn))?;
+    }
+    txn.commit()?;
+
+    for (url, events) in to_send.drain() {
+        tracing::debug!("Retrying {} event(s) on {}", events.len(), &url);
+        manager::engage_minion(
+            url,
+            vec![RelayJob {
+                reason: RelayConnectionReason::PostEvent,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::PostEvents(events),
+                },
+            }],
+        );
+    }
+
+    Ok(())
+}
@@ -1,25 +1,41 @@
-use crate::blossom::{Blossom, HashOutput};
+use crate::accounts;
+use crate::article::ArticleFields;
+use crate::badges::{self, BadgeFields};
+use crate::blossom::{self, BlobDescriptor, Blossom, HashOutput};
+use crate::calendar::{self, RsvpStatus};
+use crate::channels;
 use crate::comms::{
     RelayConnectionReason, RelayJob, ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail,
     ToOverlordMessage,
 };
+use crate::communities;
+use crate::counts::CountKind;
 use crate::dm_channel::DmChannel;
+use crate::dvm::{self, JobRequest};
 use crate::error::{Error, ErrorKind};
 use crate::feed::FeedKind;
 use crate::filter_set::{FeedRange, FilterSet};
+use crate::git::{self, GitStatus};
 use crate::globals::GLOBALS;
+use crate::groups;
 use crate::manager;
 use crate::minion::MinionExitReason;
 use crate::misc::{Private, ZapState};
+use crate::moderation;
+use crate::nip96::{self, Nip96, Nip96Upload};
 use crate::nostr_connect_server::{Approval, ParsedCommand};
+use crate::ots;
+use crate::outbox;
 use crate::pending::PendingItem;
 use crate::people::{Person, PersonList};
 use crate::relay;
 use crate::relay::Relay;
 use crate::relay_picker::RelayAssignment;
 use crate::relay_test_results::{RelayTestResult, RelayTestResults};
+use crate::scheduled_post::{self, ScheduledPost};
 use crate::storage::types::{HandlerKey, ScoreFactors};
-use crate::storage::{PersonTable, Table};
+use crate::storage::{PersonTable, ScheduledPostsTable, Table};
+use crate::zaps::{self, ZapGoal, ZapSplit};
 use crate::RunState;
 use heed::RwTxn;
 use http::StatusCode;
@@ -28,7 +44,7 @@ use nostr_types::{
     NAddr, NostrBech32, ParsedTag, PayRequestData, PreEvent, PrivateKey, Profile, PublicKey,
     RelayUrl, Tag, UncheckedUrl, Unixtime, Url,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::atomic::Ordering;
 use std::sync::mpsc;
@@ -588,6 +604,12 @@ impl Overlord {
 
     async fn handle_message(&mut self, message: ToOverlordMessage) -> Result<(), Error> {
         match message {
+            ToOverlordMessage::AcceptBadge(award) => {
+                self.accept_badge(award).await?;
+            }
+            ToOverlordMessage::AddAccount => {
+                self.add_account().await?;
+            }
             ToOverlordMessage::AddRelay(relay_url) => {
                 self.add_relay(relay_url).await?;
             }
@@ -597,12 +619,27 @@ impl Overlord {
             ToOverlordMessage::AdvertiseRelayListOne(relay_url, event, dmevent) => {
                 self.advertise_relay_list_one(relay_url, event, dmevent)?;
             }
+            ToOverlordMessage::ApproveCommunityPost(id, author, d) => {
+                self.approve_community_post(id, author, d).await?;
+            }
             ToOverlordMessage::AuthApproved(relay_url, permanent) => {
                 self.auth_approved(relay_url, permanent)?;
             }
             ToOverlordMessage::AuthDeclined(relay_url, permanent) => {
                 self.auth_declined(relay_url, permanent)?;
             }
+            ToOverlordMessage::AwardBadge(d, pubkeys) => {
+                self.award_badge(d, pubkeys).await?;
+            }
+            ToOverlordMessage::BlossomDelete(hash) => {
+                self.blossom_delete(hash).await?;
+            }
+            ToOverlordMessage::BlossomList => {
+                self.blossom_list().await?;
+            }
+            ToOverlordMessage::BlossomMirror(hash) => {
+                self.blossom_mirror(hash).await?;
+            }
             ToOverlordMessage::BlossomUpload(pathbuf) => {
                 self.blossom_upload(pathbuf).await?;
             }
@@ -612,6 +649,9 @@ impl Overlord {
             ToOverlordMessage::BookmarkRm(er) => {
                 self.bookmark_rm(er).await?;
             }
+            ToOverlordMessage::CancelScheduledPost(key) => {
+                self.cancel_scheduled_post(key)?;
+            }
             ToOverlordMessage::ChangePassphrase { old, new } => {
                 Self::change_passphrase(old, new).await?;
             }
@@ -624,6 +664,12 @@ impl Overlord {
             ToOverlordMessage::ConnectDeclined(relay_url, permanent) => {
                 self.connect_declined(relay_url, permanent)?;
             }
+            ToOverlordMessage::ConnectWallet { uri, password } => {
+                Self::connect_wallet(uri, password)?;
+            }
+            ToOverlordMessage::DefineBadge(fields) => {
+                self.define_badge(fields).await?;
+            }
             ToOverlordMessage::DelegationReset => {
                 Self::delegation_reset().await?;
             }
@@ -639,9 +685,28 @@ impl Overlord {
             ToOverlordMessage::DeletePub => {
                 Self::delete_pub().await?;
             }
+            ToOverlordMessage::DisconnectWallet => {
+                Self::disconnect_wallet()?;
+            }
+            ToOverlordMessage::DiscoverCommunities => {
+                self.discover_communities()?;
+            }
             ToOverlordMessage::DropRelay(relay_url) => {
                 self.drop_relay(relay_url)?;
             }
+            ToOverlordMessage::EditScheduledPost {
+                key,
+                content,
+                publish_at,
+            } => {
+                self.edit_scheduled_post(key, content, publish_at).await?;
+            }
+            ToOverlordMessage::ExportCalendarEvent(naddr, path) => {
+                self.export_calendar_event(naddr, path)?;
+            }
+            ToOverlordMessage::FetchBadges(pubkey) => {
+                self.fetch_badges(pubkey)?;
+            }
             ToOverlordMessage::FetchEvent(id, relay_urls) => {
                 self.fetch_event(id, relay_urls)?;
             }
@@ -657,6 +722,9 @@ impl Overlord {
             ToOverlordMessage::FollowNprofile(nprofile, list, private) => {
                 self.follow_nprofile(nprofile, list, private)?;
             }
+            ToOverlordMessage::FollowRepository(author, d, relays) => {
+                self.follow_repository(author, d, relays)?;
+            }
             ToOverlordMessage::GeneratePrivateKey(password) => {
                 if let Err(e) = Self::generate_private_key(password) {
                     if let Err(e2) = GLOBALS.identity.delete_identity() {
@@ -669,6 +737,9 @@ impl Overlord {
                     GLOBALS.status_queue.write().write(format!("{}", e));
                 }
             }
+            ToOverlordMessage::HideBadge(award) => {
+                self.hide_badge(award).await?;
+            }
             ToOverlordMessage::HideOrShowRelay(relay_url, hidden) => {
                 Self::hide_or_show_relay(relay_url, hidden)?;
             }
@@ -678,6 +749,24 @@ impl Overlord {
             ToOverlordMessage::ImportPub(pubstr) => {
                 Self::import_pub(pubstr)?;
             }
+            ToOverlordMessage::JoinChannel(id, relays) => {
+                self.join_channel(id, relays)?;
+            }
+            ToOverlordMessage::JoinCommunity(author, d, relays) => {
+                self.join_community(author, d, relays)?;
+            }
+            ToOverlordMessage::JoinGroup(relay, group_id) => {
+                Self::join_group(relay, group_id).await?;
+            }
+            ToOverlordMessage::LeaveChannel(id) => {
+                Self::leave_channel(id)?;
+            }
+            ToOverlordMessage::LeaveCommunity(author, d) => {
+                Self::leave_community(author, d)?;
+            }
+            ToOverlordMessage::LeaveGroup(relay, group_id) => {
+                Self::leave_group(relay, group_id).await?;
+            }
             ToOverlordMessage::LoadImageToCopy(url) => {
                 self.load_image_to_copy(url).await?;
             }
@@ -708,6 +797,9 @@ impl Overlord {
                 self.nip46_server_op_approval_response(pubkey, parsed_command, approval)
                     .await?;
             }
+            ToOverlordMessage::Nip96Upload(pathbuf) => {
+                self.nip96_upload(pathbuf).await?;
+            }
             ToOverlordMessage::RefreshScoresAndPickRelays => {
                 self.refresh_scores_and_pick_relays().await?;
             }
@@ -724,9 +816,28 @@ impl Overlord {
             ToOverlordMessage::PostAgain(event) => {
                 self.post_again(event)?;
             }
+            ToOverlordMessage::PostArticle { fields, draft } => {
+                self.post_article(fields, draft).await?;
+            }
             ToOverlordMessage::PostCancel => {
                 self.post_cancel();
             }
+            ToOverlordMessage::PostChannelMessage {
+                channel,
+                content,
+                tags,
+            } => {
+                self.post_channel_message(channel, content, tags).await?;
+            }
+            ToOverlordMessage::PostGroupMessage {
+                relay,
+                group_id,
+                content,
+                tags,
+            } => {
+                self.post_group_message(relay, group_id, content, tags)
+                    .await?;
+            }
             ToOverlordMessage::PostNip46Event(event, relays) => {
                 self.post_nip46_event(event, relays)?;
             }
@@ -751,9 +862,37 @@ impl Overlord {
             ToOverlordMessage::RefreshSubscribedMetadata => {
                 self.refresh_subscribed_metadata()?;
             }
+            ToOverlordMessage::RefreshWallet => {
+                Self::refresh_wallet();
+            }
+            ToOverlordMessage::RemoveAccount(pubkey) => {
+                Self::remove_account(pubkey)?;
+            }
+            ToOverlordMessage::Report(id, reason) => {
+                self.report(id, reason).await?;
+            }
             ToOverlordMessage::Repost(id) => {
                 self.repost(id).await?;
             }
+            ToOverlordMessage::RequestCounts(kinds) => {
+                self.request_counts(kinds)?;
+            }
+            ToOverlordMessage::RequestTimestamp(id) => {
+                self.request_timestamp(id)?;
+            }
+            ToOverlordMessage::RsvpCalendarEvent(naddr, status) => {
+                self.rsvp_calendar_event(naddr, status).await?;
+            }
+            ToOverlordMessage::SchedulePost {
+                content,
+                tags,
+                in_reply_to,
+                annotation,
+                publish_at,
+            } => {
+                self.schedule_post(content, tags, in_reply_to, annotation, publish_at)
+                    .await?;
+            }
             ToOverlordMessage::SearchLocally(text) => {
                 Self::search_locally(text)?;
             }
@@ -763,12 +902,27 @@ impl Overlord {
             ToOverlordMessage::SetActivePerson(pubkey) => {
                 Self::set_active_person(pubkey).await?;
             }
+            ToOverlordMessage::SetChannelFeed(id) => {
+                self.set_channel_feed(id)?;
+            }
+            ToOverlordMessage::SetCommunityFeed(author, d) => {
+                self.set_community_feed(author, d)?;
+            }
             ToOverlordMessage::SetDmChannel(dmchannel) => {
                 self.set_dm_channel(dmchannel)?;
             }
+            ToOverlordMessage::SetGitStatus(id, status) => {
+                self.set_git_status(id, status).await?;
+            }
+            ToOverlordMessage::SetDvmFeed(dvm) => {
+                self.set_dvm_feed(dvm).await?;
+            }
             ToOverlordMessage::SetGlobalFeed(anchor) => {
                 self.set_global_feed(anchor)?;
             }
+            ToOverlordMessage::SetGroupFeed(relay, group_id) => {
+                self.set_group_feed(relay, group_id)?;
+            }
             ToOverlordMessage::SetPersonFeed(pubkey, anchor) => {
                 self.set_person_feed(pubkey, anchor)?;
             }
@@ -791,9 +945,18 @@ impl Overlord {
             ToOverlordMessage::StartLongLivedSubscriptions => {
                 self.start_long_lived_subscriptions().await?;
             }
+            ToOverlordMessage::SubmitDvmJob(request) => {
+                self.submit_dvm_job(request).await?;
+            }
+            ToOverlordMessage::SubscribeCalendar => {
+                self.subscribe_calendar()?;
+            }
             ToOverlordMessage::SubscribeConfig(opt_relays) => {
                 self.subscribe_config(opt_relays)?;
             }
+            ToOverlordMessage::SubscribeDvms => {
+                self.subscribe_dvms()?;
+            }
             ToOverlordMessage::SubscribeDiscover(pubkeys, opt_relays) => {
                 self.subscribe_discover(pubkeys, opt_relays)?;
             }
@@ -803,6 +966,12 @@ impl Overlord {
             ToOverlordMessage::SubscribeNip46(relays) => {
                 self.subscribe_nip46(relays)?;
             }
+            ToOverlordMessage::SubscribeRepository(author, d) => {
+                self.subscribe_repository(author, d)?;
+            }
+            ToOverlordMessage::SwitchAccount(pubkey) => {
+                self.switch_account(pubkey).await?;
+            }
             ToOverlordMessage::TestRelay(relay_url) => {
                 Self::test_relay(relay_url);
             }
@@ -812,6 +981,9 @@ impl Overlord {
             ToOverlordMessage::TrackFollows(pubkey) => {
                 self.track_follows(pubkey).await?;
             }
+            ToOverlordMessage::UnfollowRepository(author, d) => {
+                self.unfollow_repository(author, d)?;
+            }
             ToOverlordMessage::UnlockKey(password) => {
                 Self::unlock_key(password).await?;
             }
@@ -827,20 +999,59 @@ impl Overlord {
             ToOverlordMessage::UpdateRelay(old, new) => {
                 self.update_relay(old, new)?;
             }
+            ToOverlordMessage::VerifyTimestamps(id) => {
+                self.verify_timestamps(id)?;
+            }
             ToOverlordMessage::VisibleNotesChanged(visible) => {
                 self.visible_notes_changed(visible)?;
             }
             ToOverlordMessage::ZapStart(id, pubkey, lnurl) => {
                 self.zap_start(id, pubkey, lnurl).await?;
             }
-            ToOverlordMessage::Zap(id, pubkey, msats, comment) => {
-                self.zap(id, pubkey, msats, comment).await?;
+            ToOverlordMessage::Zap(id, pubkey, msats, comment, anonymous) => {
+                self.zap(id, pubkey, msats, comment, anonymous).await?;
             }
         }
 
         Ok(())
     }
 
+    /// Show a badge that was awarded to us on our profile (NIP-58)
+    pub async fn accept_badge(&mut self, award: Id) -> Result<(), Error> {
+        let award = match GLOBALS.db().read_event(award)? {
+            Some(event) => event,
+            None => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Cannot accept - cannot find the badge award.".to_owned());
+                return Ok(());
+            }
+        };
+
+        let tags = badges::profile_badges_tags(Some(&award), None)?;
+        Self::post_profile_badges(tags).await?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Your profile badges were updated.".to_owned());
+
+        Ok(())
+    }
+
+    /// Switch to a new account with no identity. Setting up its keys adds it to the accounts.
+    pub async fn add_account(&mut self) -> Result<(), Error> {
+        accounts::switch_account(None).await?;
+        self.start_long_lived_subscriptions().await?;
+        GLOBALS.feed.sync_recompute();
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Set up the keys for the new account.".to_owned());
+        Ok(())
+    }
+
     /// Add a new relay to gossip
     pub async fn add_relay(&mut self, relay_url: RelayUrl) -> Result<(), Error> {
         // Create relay if missing
@@ -855,12 +1066,9 @@ impl Overlord {
 
     /// Advertise the user's current relay list
     pub async fn advertise_relay_list(&mut self) -> Result<(), Error> {
-        let public_key = match GLOBALS.identity.public_key() {
+        let public_key = match posting_public_key() {
             Some(pk) => pk,
-            None => {
-                tracing::warn!("No public key! Not posting");
-                return Ok(());
-            }
+            None => return Ok(()),
         };
 
         let event = {
@@ -953,6 +1161,13 @@ impl Overlord {
     ) -> Result<(), Error> {
         let job_id = rand::random::<u64>();
 
+        // Record it in the outbox, so it is retried if it doesn't get there
+        for ev in [&event, &dmevent] {
+            if let Err(e) = outbox::record_attempt(ev, &[relay_url.clone()]) {
+                tracing::error!("{}", e);
+            }
+        }
+
         // Send it the event to post
         tracing::debug!("Asking {} to advertise relay list", &relay_url);
         manager::engage_minion(
@@ -973,6 +1188,70 @@ impl Overlord {
         Ok(())
     }
 
+    /// Approve a post in a NIP-72 community that we moderate
+    pub async fn approve_community_post(
+        &mut self,
+        id: Id,
+        author: PublicKey,
+        d: String,
+    ) -> Result<(), Error> {
+        let public_key = match posting_public_key() {
+            Some(pk) => pk,
+            None => return Ok(()),
+        };
+
+        let post = match GLOBALS.db().read_event(id)? {
+            Some(event) => event,
+            None => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Cannot approve - cannot find post.".to_owned());
+                return Ok(());
+            }
+        };
+
+        let event = {
+            let pre_event = PreEvent {
+                pubkey: public_key,
+                created_at: Unixtime::now(),
+                kind: EventKind::CommunityPostApproval,
+                tags: vec![
+                    Tag::new(&["a", &communities::community_address(author, &d)]),
+                    Tag::new(&["e", &id.as_hex_string()]),
+                    Tag::new(&["p", &post.pubkey.as_hex_string()]),
+                    Tag::new(&["k", &format!("{}", u32::from(post.kind))]),
+                ],
+                // The approved post goes along with the approval
+                content: serde_json::to_string(&post)?,
+            };
+
+            GLOBALS.identity.sign_event(pre_event).await?
+        };
+
+        // Process this event locally
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        // Post to our write relays, and the relays of the community
+        let mut relay_urls: Vec<RelayUrl> = relay::relays_to_post_to(&event)?;
+        if let Some(community) = communities::definition(author, &d)? {
+            relay_urls.extend(community.relays);
+        }
+        relay_urls.sort();
+        relay_urls.dedup();
+
+        outbox::post_events(vec![event], relay_urls, RelayConnectionReason::PostEvent)?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Approved post in {}.",
+            communities::community_name(author, &d)
+        ));
+
+        GLOBALS.feed.sync_recompute();
+
+        Ok(())
+    }
+
     /// User has approved authentication on this relay. Save this result for later
     /// and inform the minion.
     pub fn auth_approved(&mut self, relay_url: RelayUrl, permanent: bool) -> Result<(), Error> {
@@ -1043,6 +1322,157 @@ impl Overlord {
         Ok(())
     }
 
+    /// Award a badge that we defined to some people (NIP-58)
+    pub async fn award_badge(&mut self, d: String, pubkeys: Vec<PublicKey>) -> Result<(), Error> {
+        let public_key = match posting_public_key() {
+            Some(pk) => pk,
+            None => return Ok(()),
+        };
+
+        if pubkeys.is_empty() {
+            return Ok(());
+        }
+
+        let badge = match badges::definition(public_key, &d)? {
+            Some(badge) => badge,
+            None => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Cannot award - cannot find the badge definition.".to_owned());
+                return Ok(());
+            }
+        };
+
+        let event = {
+            let mut tags: Vec<Tag> = vec![Tag::new(&["a", &badge.address()])];
+            for pubkey in pubkeys.iter() {
+                tags.push(Tag::new(&["p", &pubkey.as_hex_string()]));
+            }
+
+            let pre_event = PreEvent {
+                pubkey: public_key,
+                created_at: Unixtime::now(),
+                kind: EventKind::BadgeAward,
+                tags,
+                content: "".to_owned(),
+            };
+
+            GLOBALS.identity.sign_event(pre_event).await?
+        };
+
+        // Process this event locally
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        // Post to our write relays, and the inboxes of the people awarded
+        let relay_urls = relay::relays_to_post_to(&event)?;
+        outbox::post_events(vec![event], relay_urls, RelayConnectionReason::PostEvent)?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Awarded the badge {} to {} people.",
+            badge.display_name(),
+            pubkeys.len()
+        ));
+
+        Ok(())
+    }
+
+    /// Delete a blob from each of our blossom servers that has it
+    pub async fn blossom_delete(&mut self, hash: String) -> Result<(), Error> {
+        let hash_output = HashOutput::from_hex(&hash)?;
+        for server in blossom::blossom_servers() {
+            if blossom::blob_on_server(&server, &hash).is_none() {
+                continue;
+            }
+            std::mem::drop(tokio::spawn(Box::pin(async move {
+                let result: Result<(), Error> = async {
+                    let base_url = blossom::base_url(&server)?;
+                    Blossom::global()?.delete(base_url, hash_output).await
+                }
+                .await;
+                if let Err(e) = result {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("Could not delete from {}: {}", server, e));
+                }
+                Overlord::blossom_list_server(server).await;
+            })));
+        }
+
+        Ok(())
+    }
+
+    /// List our blobs on each of our blossom servers into `GLOBALS.blossom_blobs`
+    pub async fn blossom_list(&mut self) -> Result<(), Error> {
+        GLOBALS.blossom_blobs.clear();
+        for server in blossom::blossom_servers() {
+            std::mem::drop(tokio::spawn(Box::pin(async move {
+                Overlord::blossom_list_server(server).await;
+            })));
+        }
+
+        Ok(())
+    }
+
+    async fn blossom_list_server(server: String) {
+        let result: Result<Vec<BlobDescriptor>, Error> = async {
+            let pubkey = GLOBALS
+                .identity
+                .public_key()
+                .ok_or(ErrorKind::NoPublicKey)?;
+            let base_url = blossom::base_url(&server)?;
+            // Some servers only list blobs to their owner
+            let authorize = GLOBALS.identity.is_unlocked();
+            Blossom::global()?.list(base_url, pubkey, authorize).await
+        }
+        .await;
+        GLOBALS.blossom_blobs.insert(server, result);
+    }
+
+    /// Copy a blob to each of our blossom servers that doesn't have it yet, from one that does
+    pub async fn blossom_mirror(&mut self, hash: String) -> Result<(), Error> {
+        let hash_output = HashOutput::from_hex(&hash)?;
+        let servers = blossom::blossom_servers();
+        let from_url = match servers
+            .iter()
+            .find_map(|server| blossom::blob_on_server(server, &hash))
+        {
+            Some(bd) => bd.url,
+            None => {
+                return Err(ErrorKind::BlossomError(
+                    "None of your servers have that blob".to_owned(),
+                )
+                .into())
+            }
+        };
+
+        for server in servers {
+            if blossom::blob_on_server(&server, &hash).is_some() {
+                continue;
+            }
+            let from_url = from_url.clone();
+            std::mem::drop(tokio::spawn(Box::pin(async move {
+                let result: Result<BlobDescriptor, Error> = async {
+                    let base_url = blossom::base_url(&server)?;
+                    Blossom::global()?
+                        .mirror(base_url, from_url, hash_output)
+                        .await
+                }
+                .await;
+                if let Err(e) = result {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("Could not mirror to {}: {}", server, e));
+                }
+                Overlord::blossom_list_server(server).await;
+            })));
+        }
+
+        Ok(())
+    }
+
     pub async fn blossom_upload(&mut self, pathbuf: PathBuf) -> Result<(), Error> {
         std::mem::drop(tokio::spawn(Box::pin(async move {
             if let Err(e) = Overlord::inner_blossom_upload(pathbuf.clone()).await {
@@ -1054,35 +1484,11 @@ impl Overlord {
     }
 
     async fn inner_blossom_upload(pathbuf: PathBuf) -> Result<(), Error> {
-        let blossom = match GLOBALS.blossom.get() {
-            Some(b) => b,
-            None => {
-                let blossom = Blossom::new()?;
-                let _ = GLOBALS.blossom.set(blossom);
-                GLOBALS.blossom.get().unwrap()
-            }
-        };
+        let blossom = Blossom::global()?;
 
-        let base_url = {
-            let blossom_servers = GLOBALS.db().read_setting_blossom_servers();
-            let first = blossom_servers.split_whitespace().next();
-            match first {
-                Some(bs) => {
-                    use http::uri::{Parts, PathAndQuery, Scheme};
-                    use http::Uri;
-
-                    let uri = bs.parse::<Uri>()?;
-                    let mut parts: Parts = uri.into_parts();
-                    parts.path_and_query = Some(PathAndQuery::from_static("/")); // Force no path
-                    if parts.scheme.is_none() {
-                        // Default to https
-                        parts.scheme = Some(Scheme::HTTPS);
-                    }
-                    let uri = Uri::from_parts(parts)?;
-                    format!("{}", uri)
-                }
-                None => return Err(ErrorKind::General("Blossom not configured".to_owned()).into()),
-            }
+        let base_url = match blossom::blossom_servers().first() {
+            Some(bs) => blossom::base_url(bs)?,
+            None => return Err(ErrorKind::General("Blossom not configured".to_owned()).into()),
         };
 
         // metadata
@@ -1092,7 +1498,7 @@ impl Overlord {
         let hash = HashOutput::from_file(&pathbuf)?;
 
         // mime type
-        let mime = crate::blossom::get_content_type(&pathbuf)?;
+        let mime = blossom::get_content_type(&pathbuf)?;
 
         // open
         let file = tokio::fs::File::open(&pathbuf).await?;
@@ -1115,16 +1521,11 @@ impl Overlord {
 
         let config_relays: Vec<RelayUrl> = Relay::choose_relay_urls(Relay::WRITE, |_| true)?;
 
-        manager::run_jobs_on_all_relays(
+        outbox::post_events(
+            vec![event.clone()],
             config_relays,
-            vec![RelayJob {
-                reason: RelayConnectionReason::PostEvent,
-                payload: ToMinionPayload {
-                    job_id: rand::random::<u64>(),
-                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                },
-            }],
-        );
+            RelayConnectionReason::PostEvent,
+        )?;
 
         Ok(())
     }
@@ -1175,6 +1576,12 @@ impl Overlord {
         Ok(())
     }
 
+    /// Cancel a scheduled post
+    pub fn cancel_scheduled_post(&mut self, key: u64) -> Result<(), Error> {
+        ScheduledPostsTable::delete_record(key, None)?;
+        Ok(())
+    }
+
     /// Change the user's passphrase.
     pub async fn change_passphrase(mut old: String, mut new: String) -> Result<(), Error> {
         GLOBALS.identity.change_passphrase(&old, &new).await?;
@@ -1182,6 +1589,7 @@ impl Overlord {
             .client_identity
             .change_passphrase(&old, &new)
             .await?;
+        GLOBALS.wallet.change_passphrase(&old, &new)?;
         old.zeroize();
         new.zeroize();
         Ok(())
@@ -1236,24 +1644,96 @@ impl Overlord {
         Ok(())
     }
 
-    /// Remove any key delegation setup
-    pub async fn delegation_reset() -> Result<(), Error> {
-        if GLOBALS.delegation.reset() {
-            // save and statusmsg
-            GLOBALS.delegation.save().await?;
-            GLOBALS
-                .status_queue
-                .write()
-                .write("Delegation tag removed".to_string());
+    /// Connect to the user's wallet with a NIP-47 `nostr+walletconnect://` connection
+    /// string. Its secret is encrypted with the passphrase.
+    pub fn connect_wallet(mut uri: String, mut password: String) -> Result<(), Error> {
+        // Unlock it right away, so the user doesn't have to log in again
+        let result = GLOBALS
+            .wallet
+            .connect(&uri, &password)
+            .and_then(|_| GLOBALS.wallet.unlock(&password));
+        uri.zeroize();
+        password.zeroize();
+
+        match result {
+            Ok(()) => {
+                GLOBALS.wallet.fetch_info();
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Wallet connected.".to_owned());
+                Self::refresh_wallet();
+            }
+            Err(e) => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write(format!("Could not connect the wallet: {}", e));
+            }
         }
+
         Ok(())
     }
 
-    /// Delete a person list
-    pub async fn delete_person_list(&mut self, list: PersonList) -> Result<(), Error> {
-        // Get the metadata first, we need it to delete events
-        let metadata = match GLOBALS.db().get_person_list_metadata(list)? {
-            Some(m) => m,
+    /// Define a badge (NIP-58), or change the definition of a badge we defined before
+    pub async fn define_badge(&mut self, fields: BadgeFields) -> Result<(), Error> {
+        let public_key = match posting_public_key() {
+            Some(pk) => pk,
+            None => return Ok(()),
+        };
+
+        if fields.d.trim().is_empty() {
+            GLOBALS
+                .status_queue
+                .write()
+                .write("A badge needs an identifier.".to_owned());
+            return Ok(());
+        }
+
+        let event = {
+            let pre_event = PreEvent {
+                pubkey: public_key,
+                created_at: Unixtime::now(),
+                kind: EventKind::BadgeDefinition,
+                tags: badges::definition_tags(&fields),
+                content: "".to_owned(),
+            };
+
+            GLOBALS.identity.sign_event(pre_event).await?
+        };
+
+        // Process this event locally
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        let relay_urls = relay::relays_to_post_to(&event)?;
+        outbox::post_events(vec![event], relay_urls, RelayConnectionReason::PostEvent)?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Your badge was published.".to_owned());
+
+        Ok(())
+    }
+
+    /// Remove any key delegation setup
+    pub async fn delegation_reset() -> Result<(), Error> {
+        if GLOBALS.delegation.reset() {
+            // save and statusmsg
+            GLOBALS.delegation.save().await?;
+            GLOBALS
+                .status_queue
+                .write()
+                .write("Delegation tag removed".to_string());
+        }
+        Ok(())
+    }
+
+    /// Delete a person list
+    pub async fn delete_person_list(&mut self, list: PersonList) -> Result<(), Error> {
+        // Get the metadata first, we need it to delete events
+        let metadata = match GLOBALS.db().get_person_list_metadata(list)? {
+            Some(m) => m,
             None => return Ok(()),
         };
 
@@ -1387,28 +1867,20 @@ impl Overlord {
         }
 
         // Send event to all these relays
-        manager::run_jobs_on_all_relays(
+        outbox::post_events(
+            vec![event.clone()],
             relay_urls,
-            vec![RelayJob {
-                reason: RelayConnectionReason::PostEvent,
-                payload: ToMinionPayload {
-                    job_id: rand::random::<u64>(),
-                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                },
-            }],
-        );
+            RelayConnectionReason::PostEvent,
+        )?;
 
         Ok(())
     }
 
     /// Delete a post
     pub async fn delete_post(&mut self, id: Id) -> Result<(), Error> {
-        let public_key = match GLOBALS.identity.public_key() {
+        let public_key = match posting_public_key() {
             Some(pk) => pk,
-            None => {
-                tracing::warn!("No public key! Not posting");
-                return Ok(());
-            }
+            None => return Ok(()),
         };
 
         let mut tags: Vec<Tag> = vec![ParsedTag::Event {
@@ -1459,22 +1931,19 @@ impl Overlord {
             relay_urls.dedup();
         }
 
-        manager::run_jobs_on_all_relays(
+        outbox::post_events(
+            vec![event.clone()],
             relay_urls,
-            vec![RelayJob {
-                reason: RelayConnectionReason::PostEvent,
-                payload: ToMinionPayload {
-                    job_id: rand::random::<u64>(),
-                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                },
-            }],
-        );
+            RelayConnectionReason::PostEvent,
+        )?;
 
         Ok(())
     }
 
     /// Delete private key and any delegation setup
     pub async fn delete_priv() -> Result<(), Error> {
+        // The wallet connection is under the identity's passphrase
+        GLOBALS.wallet.disconnect()?;
         GLOBALS.identity.delete_identity()?;
         GLOBALS.client_identity.delete_identity()?;
         Self::delegation_reset().await?;
@@ -1493,6 +1962,37 @@ impl Overlord {
         Ok(())
     }
 
+    /// Forget the user's NIP-47 wallet connection
+    pub fn disconnect_wallet() -> Result<(), Error> {
+        GLOBALS.wallet.disconnect()?;
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Wallet disconnected.".to_owned());
+        Ok(())
+    }
+
+    /// Look for NIP-72 community definitions on our read relays
+    pub fn discover_communities(&mut self) -> Result<(), Error> {
+        manager::run_jobs_on_all_relays(
+            Relay::choose_relay_urls(Relay::READ, |_| true)?,
+            vec![RelayJob {
+                reason: RelayConnectionReason::SubscribeCommunity,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::CommunityDiscovery),
+                },
+            }],
+        );
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Looking for communities...".to_owned());
+
+        Ok(())
+    }
+
     /// Disconnect from the specified relay. This may not happen immediately if the minion
     /// handling that relay is stuck waiting for a timeout.
     pub fn drop_relay(&mut self, relay_url: RelayUrl) -> Result<(), Error> {
@@ -1507,6 +2007,84 @@ impl Overlord {
         Ok(())
     }
 
+    /// Change the content or publish time of a scheduled post
+    pub async fn edit_scheduled_post(
+        &mut self,
+        key: u64,
+        content: String,
+        publish_at: Unixtime,
+    ) -> Result<(), Error> {
+        let mut post = match ScheduledPostsTable::read_record(key, None)? {
+            Some(post) => post,
+            None => return Err(ErrorKind::General("Scheduled post not found".to_owned()).into()),
+        };
+
+        post.content = content;
+        post.publish_at = publish_at;
+
+        // Any event signed in advance is now out of date
+        post.event = None;
+        if scheduled_post::can_sign_in_advance() {
+            post.event = Some(scheduled_post::sign_scheduled_post(&post).await?);
+        }
+
+        ScheduledPostsTable::write_record(&mut post, None)?;
+
+        Ok(())
+    }
+
+    /// Save a calendar event (NIP-52) as an iCalendar file, for other calendar apps
+    pub fn export_calendar_event(&mut self, naddr: NAddr, path: PathBuf) -> Result<(), Error> {
+        let event = match calendar::calendar_event(&naddr)? {
+            Some(event) => event,
+            None => return Err(ErrorKind::General("Calendar event not found".to_owned()).into()),
+        };
+
+        std::fs::write(&path, event.to_ics())?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("Saved {}", path.display()));
+
+        Ok(())
+    }
+
+    /// Fetch the badges a person shows and has defined, and the badges awarded to them (NIP-58)
+    pub fn fetch_badges(&mut self, pubkey: PublicKey) -> Result<(), Error> {
+        // They publish their profile badges and badge definitions to their outboxes
+        manager::run_jobs_on_all_relays(
+            relay::get_some_pubkey_outboxes(pubkey)?,
+            vec![RelayJob {
+                reason: RelayConnectionReason::FetchBadges,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::Badges(pubkey)),
+                },
+            }],
+        );
+
+        // Issuers send awards to the inboxes of the people they award
+        let mut relay_urls = relay::get_all_pubkey_inboxes(pubkey)?;
+        if GLOBALS.identity.public_key() == Some(pubkey) {
+            relay_urls.extend(Relay::choose_relay_urls(Relay::INBOX, |_| true)?);
+            relay_urls.sort();
+            relay_urls.dedup();
+        }
+        manager::run_jobs_on_all_relays(
+            relay_urls,
+            vec![RelayJob {
+                reason: RelayConnectionReason::FetchBadges,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::BadgeAwards(pubkey)),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
     /// Fetch an event from specific relays by event `Id`
     pub fn fetch_event(&mut self, id: Id, mut relay_urls: Vec<RelayUrl>) -> Result<(), Error> {
         // Use READ relays if relays are unknown
@@ -1618,6 +2196,24 @@ impl Overlord {
         Ok(())
     }
 
+    /// Follow a NIP-34 git repository. Relay hints are where we may find it.
+    pub fn follow_repository(
+        &mut self,
+        author: PublicKey,
+        d: String,
+        relays: Vec<RelayUrl>,
+    ) -> Result<(), Error> {
+        git::follow_repository(author, &d, relays)?;
+        self.subscribe_repositories()?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Following repository {}",
+            git::repo_name(author, &d)
+        ));
+
+        Ok(())
+    }
+
     /// Generate an identity (private key) and keep encrypted under the given passphrase
     pub fn generate_private_key(mut password: String) -> Result<(), Error> {
         GLOBALS.identity.generate_private_key(&password)?;
@@ -1626,6 +2222,21 @@ impl Overlord {
         Ok(())
     }
 
+    /// Hide a badge that was awarded to us (NIP-58). If it was on our profile,
+    /// it is taken off.
+    pub async fn hide_badge(&mut self, award: Id) -> Result<(), Error> {
+        let accepted = badges::is_accepted(award);
+
+        badges::hide(award)?;
+
+        if accepted {
+            let tags = badges::profile_badges_tags(None, Some(award))?;
+            Self::post_profile_badges(tags).await?;
+        }
+
+        Ok(())
+    }
+
     /// Hide or Show a relay. This adjusts the `hidden` a flag on the `Relay` record
     /// (You could easily do this yourself by talking to GLOBALS.db() directly too)
     pub fn hide_or_show_relay(relay_url: RelayUrl, hidden: bool) -> Result<(), Error> {
@@ -1693,6 +2304,115 @@ impl Overlord {
         Ok(())
     }
 
+    /// Join a NIP-28 public chat channel, fetching its creation event if we don't have it
+    pub fn join_channel(&mut self, id: Id, relays: Vec<RelayUrl>) -> Result<(), Error> {
+        channels::join_channel(id, relays.clone())?;
+        self.fetch_event(id, relays)?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("Joined channel {}", channels::channel_name(id)));
+
+        Ok(())
+    }
+
+    /// Join a NIP-72 moderated community
+    pub fn join_community(
+        &mut self,
+        author: PublicKey,
+        d: String,
+        relays: Vec<RelayUrl>,
+    ) -> Result<(), Error> {
+        communities::join_community(author, &d, relays)?;
+        self.fetch_community_definition(author, &d)?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Joined community {}",
+            communities::community_name(author, &d)
+        ));
+
+        Ok(())
+    }
+
+    /// Join a NIP-29 relay-based group, asking the group relay to add us as a member
+    pub async fn join_group(relay: RelayUrl, group_id: String) -> Result<(), Error> {
+        // Mark it joined first, so the minion will authenticate to the group relay
+        groups::join_group(&relay, &group_id)?;
+        Self::send_group_request(groups::join_request_kind(), &relay, &group_id).await?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Asked to join group {}",
+            groups::group_name(&relay, &group_id)
+        ));
+
+        Ok(())
+    }
+
+    /// Leave a NIP-28 public chat channel
+    pub fn leave_channel(id: Id) -> Result<(), Error> {
+        channels::leave_channel(id)?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("Left channel {}", channels::channel_name(id)));
+
+        Ok(())
+    }
+
+    /// Leave a NIP-72 moderated community
+    pub fn leave_community(author: PublicKey, d: String) -> Result<(), Error> {
+        communities::leave_community(author, &d)?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Left community {}",
+            communities::community_name(author, &d)
+        ));
+
+        Ok(())
+    }
+
+    /// Leave a NIP-29 relay-based group, asking the group relay to remove us
+    pub async fn leave_group(relay: RelayUrl, group_id: String) -> Result<(), Error> {
+        Self::send_group_request(groups::leave_request_kind(), &relay, &group_id).await?;
+        groups::leave_group(&relay, &group_id)?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Left group {}",
+            groups::group_name(&relay, &group_id)
+        ));
+
+        Ok(())
+    }
+
+    // Send a join or leave request to the relay that manages a group
+    async fn send_group_request(
+        kind: EventKind,
+        relay: &RelayUrl,
+        group_id: &str,
+    ) -> Result<(), Error> {
+        let pubkey = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => return Err(ErrorKind::NoPublicKey.into()),
+        };
+
+        let pre_event = PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind,
+            tags: vec![Tag::new(&["h", group_id])],
+            content: "".to_owned(),
+        };
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
+
+        outbox::post_events(
+            vec![event],
+            vec![relay.clone()],
+            RelayConnectionReason::PostEvent,
+        )
+    }
+
     /// Load an image to copy into the cut-n-paste buffer
     pub async fn load_image_to_copy(&mut self, url: Url) -> Result<(), Error> {
         let Ok(fetchresult) = GLOBALS.fetcher.get(url, true).await else {
@@ -1832,6 +2552,28 @@ impl Overlord {
         Ok(())
     }
 
+    /// Upload a file to our first NIP-96 server, into `GLOBALS.nip96_uploads`
+    pub async fn nip96_upload(&mut self, pathbuf: PathBuf) -> Result<(), Error> {
+        std::mem::drop(tokio::spawn(Box::pin(async move {
+            let result: Result<Nip96Upload, Error> = async {
+                let server = match nip96::nip96_servers().first() {
+                    Some(server) => server.to_owned(),
+                    None => {
+                        return Err(ErrorKind::General("NIP-96 not configured".to_owned()).into())
+                    }
+                };
+                Nip96::new()?.upload(&server, &pathbuf).await
+            }
+            .await;
+            if let Ok(ref upload) = result {
+                tracing::info!("Uploaded {} to {}", pathbuf.display(), upload.url);
+            }
+            GLOBALS.nip96_uploads.insert(pathbuf, result);
+        })));
+
+        Ok(())
+    }
+
     /// Trigger the relay picker to find relays for people not fully covered
     pub async fn refresh_scores_and_pick_relays(&mut self) -> Result<(), Error> {
         // When manually doing this, we refresh person_relay scores first which
@@ -1881,12 +2623,9 @@ impl Overlord {
     /// pubkey author too.
     pub async fn react(&mut self, id: Id, pubkey: PublicKey, reaction: char) -> Result<(), Error> {
         let event = {
-            let public_key = match GLOBALS.identity.public_key() {
+            let public_key = match posting_public_key() {
                 Some(pk) => pk,
-                None => {
-                    tracing::warn!("No public key! Not posting");
-                    return Ok(());
-                }
+                None => return Ok(()),
             };
 
             let mut tags: Vec<Tag> = vec![
@@ -1938,16 +2677,11 @@ impl Overlord {
             tracing::debug!("Asking {} to post", url);
         }
 
-        manager::run_jobs_on_all_relays(
+        outbox::post_events(
+            vec![event.clone()],
             relay_urls,
-            vec![RelayJob {
-                reason: RelayConnectionReason::PostLike,
-                payload: ToMinionPayload {
-                    job_id: rand::random::<u64>(),
-                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                },
-            }],
-        );
+            RelayConnectionReason::PostLike,
+        )?;
 
         // Process the message for ourself
         crate::process::process_new_event(&event, None, None, false, false).await?;
@@ -1964,16 +2698,13 @@ impl Overlord {
         annotation: bool,
         dm_channel: Option<DmChannel>,
     ) -> Result<(), Error> {
-        let author = match GLOBALS.identity.public_key() {
+        let author = match posting_public_key() {
             Some(pk) => pk,
-            None => {
-                tracing::warn!("No public key! Not posting");
-                return Ok(());
-            }
+            None => return Ok(()),
         };
 
         // Prepare events for posting
-        let mut prepared_events = match dm_channel {
+        let prepared_events = match dm_channel {
             Some(channel) => {
                 if channel.can_use_nip17() {
                     crate::post::prepare_post_nip17(author, content, tags, channel, annotation)
@@ -1983,32 +2714,139 @@ impl Overlord {
                 }
             }
             None => {
-                if let Some(parent_id) = in_reply_to {
-                    let parent = match GLOBALS.db().read_event(parent_id)? {
-                        Some(e) => e,
-                        None => return Err("Cannot find event we are replying to.".into()),
-                    };
+                crate::post::prepare_post_public(
+                    author,
+                    content,
+                    tags,
+                    in_reply_to,
+                    annotation,
+                    Unixtime::now(),
+                )
+                .await?
+            }
+        };
 
-                    if parent.kind == EventKind::TextNote {
-                        crate::post::prepare_post_normal(
-                            author,
-                            content,
-                            tags,
-                            Some(parent),
-                            annotation,
-                        )
-                        .await?
-                    } else {
-                        crate::post::prepare_post_comment(author, content, tags, parent, annotation)
-                            .await?
-                    }
-                } else {
-                    crate::post::prepare_post_normal(author, content, tags, None, annotation)
-                        .await?
+        Self::post_prepared(author, prepared_events).await
+    }
+
+    /// Publish a long-form article (kind 30023), or save a draft of one (kind 30024)
+    pub async fn post_article(&mut self, fields: ArticleFields, draft: bool) -> Result<(), Error> {
+        let author = match posting_public_key() {
+            Some(pk) => pk,
+            None => return Ok(()),
+        };
+
+        // The draft this article was written from, if any
+        let draft_event = if draft || fields.d.is_empty() {
+            None
+        } else {
+            GLOBALS.db().get_replaceable_event(
+                EventKind::DraftLongFormContent,
+                author,
+                &fields.d,
+            )?
+        };
+
+        let prepared_events =
+            crate::post::prepare_post_article(author, fields, draft, Unixtime::now()).await?;
+        let article_id = prepared_events.first().map(|(event, _)| event.id);
+
+        Self::post_prepared(author, prepared_events).await?;
+
+        // Once the article is sent (and not undone) its draft is deleted
+        if let (Some(draft_event), Some(article_id)) = (draft_event, article_id) {
+            std::mem::drop(tokio::task::spawn(Box::pin(async move {
+                let secs = GLOBALS.db().read_setting_undo_send_seconds() + 1;
+                tokio::time::sleep(Duration::new(secs, 0)).await;
+
+                let sent = !GLOBALS.delayed_posts.contains(&article_id)
+                    && matches!(GLOBALS.db().read_event(article_id), Ok(Some(_)));
+                if sent {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::DeletePost(draft_event.id));
                 }
-            }
+            })));
+        }
+
+        Ok(())
+    }
+
+    /// Post a message (kind 42) in a NIP-28 public chat channel
+    pub async fn post_channel_message(
+        &mut self,
+        channel: Id,
+        content: String,
+        tags: Vec<Tag>,
+    ) -> Result<(), Error> {
+        let author = match posting_public_key() {
+            Some(pk) => pk,
+            None => return Ok(()),
+        };
+
+        let prepared_events = crate::post::prepare_post_channel(
+            author,
+            content,
+            tags,
+            channel,
+            None,
+            Unixtime::now(),
+        )
+        .await?;
+
+        Self::post_prepared(author, prepared_events).await
+    }
+
+    /// Post a chat message (kind 9) in a NIP-29 relay-based group
+    pub async fn post_group_message(
+        &mut self,
+        relay: RelayUrl,
+        group_id: String,
+        content: String,
+        tags: Vec<Tag>,
+    ) -> Result<(), Error> {
+        let author = match posting_public_key() {
+            Some(pk) => pk,
+            None => return Ok(()),
+        };
+
+        let prepared_events = crate::post::prepare_post_group(
+            author,
+            content,
+            tags,
+            relay,
+            group_id,
+            None,
+            Unixtime::now(),
+        )
+        .await?;
+
+        Self::post_prepared(author, prepared_events).await
+    }
+
+    /// Set the status of a NIP-34 patch or issue
+    pub async fn set_git_status(&mut self, id: Id, status: GitStatus) -> Result<(), Error> {
+        let author = match posting_public_key() {
+            Some(pk) => pk,
+            None => return Ok(()),
+        };
+
+        let root = match GLOBALS.db().read_event(id)? {
+            Some(event) => event,
+            None => return Err("Cannot find the patch or issue.".into()),
         };
 
+        let prepared_events =
+            crate::post::prepare_post_git_status(author, root, status, Unixtime::now()).await?;
+
+        Self::post_prepared(author, prepared_events).await
+    }
+
+    // Process prepared events locally, then send them after the undo delay
+    async fn post_prepared(
+        author: PublicKey,
+        mut prepared_events: Vec<(Event, Vec<RelayUrl>)>,
+    ) -> Result<(), Error> {
         for (event, _) in &prepared_events {
             // Process the event locally (ignore any errors)
             crate::process::process_new_event(event, None, None, false, false).await?;
@@ -2051,16 +2889,11 @@ impl Overlord {
                         None => vec![event],
                     };
 
-                    manager::run_jobs_on_all_relays(
-                        relay_urls,
-                        vec![RelayJob {
-                            reason: RelayConnectionReason::PostEvent,
-                            payload: ToMinionPayload {
-                                job_id: rand::random::<u64>(),
-                                detail: ToMinionPayloadDetail::PostEvents(events),
-                            },
-                        }],
-                    );
+                    if let Err(e) =
+                        outbox::post_events(events, relay_urls, RelayConnectionReason::PostEvent)
+                    {
+                        tracing::error!("{}", e);
+                    }
                 }
             }
         })));
@@ -2068,6 +2901,34 @@ impl Overlord {
         Ok(())
     }
 
+    // Publish our profile badges (kind 30008)
+    async fn post_profile_badges(tags: Vec<Tag>) -> Result<(), Error> {
+        let public_key = match posting_public_key() {
+            Some(pk) => pk,
+            None => return Ok(()),
+        };
+
+        let event = {
+            let pre_event = PreEvent {
+                pubkey: public_key,
+                created_at: Unixtime::now(),
+                kind: EventKind::ProfileBadges,
+                tags,
+                content: "".to_owned(),
+            };
+
+            GLOBALS.identity.sign_event(pre_event).await?
+        };
+
+        // Process this event locally
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        let relay_urls = relay::relays_to_post_to(&event)?;
+        outbox::post_events(vec![event], relay_urls, RelayConnectionReason::PostEvent)?;
+
+        Ok(())
+    }
+
     pub fn post_again(&mut self, event: Event) -> Result<(), Error> {
         let relay_urls = relay::relays_to_post_to(&event)?;
 
@@ -2075,16 +2936,11 @@ impl Overlord {
             tracing::debug!("Asking {} to post", url);
         }
 
-        manager::run_jobs_on_all_relays(
+        outbox::post_events(
+            vec![event.clone()],
             relay_urls,
-            vec![RelayJob {
-                reason: RelayConnectionReason::PostEvent,
-                payload: ToMinionPayload {
-                    job_id: rand::random::<u64>(),
-                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                },
-            }],
-        );
+            RelayConnectionReason::PostEvent,
+        )?;
 
         Ok(())
     }
@@ -2142,16 +2998,11 @@ impl Overlord {
 
         let config_relays: Vec<RelayUrl> = Relay::choose_relay_urls(Relay::WRITE, |_| true)?;
 
-        manager::run_jobs_on_all_relays(
+        outbox::post_events(
+            vec![event.clone()],
             config_relays,
-            vec![RelayJob {
-                reason: RelayConnectionReason::PostBlossomServers,
-                payload: ToMinionPayload {
-                    job_id: rand::random::<u64>(),
-                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                },
-            }],
-        );
+            RelayConnectionReason::PostBlossomServers,
+        )?;
 
         Ok(())
     }
@@ -2174,16 +3025,11 @@ impl Overlord {
         for url in &relay_urls {
             tracing::debug!("Pushing PersonList={} to {}", metadata.title, url);
         }
-        manager::run_jobs_on_all_relays(
+        outbox::post_events(
+            vec![event.clone()],
             relay_urls,
-            vec![RelayJob {
-                reason: RelayConnectionReason::PostContacts,
-                payload: ToMinionPayload {
-                    job_id: rand::random::<u64>(),
-                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                },
-            }],
-        );
+            RelayConnectionReason::PostContacts,
+        )?;
 
         Ok(())
     }
@@ -2211,16 +3057,11 @@ impl Overlord {
         for url in &relay_urls {
             tracing::debug!("Pushing Metadata to {}", url);
         }
-        manager::run_jobs_on_all_relays(
+        outbox::post_events(
+            vec![event.clone()],
             relay_urls,
-            vec![RelayJob {
-                reason: RelayConnectionReason::PostMetadata,
-                payload: ToMinionPayload {
-                    job_id: rand::random::<u64>(),
-                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                },
-            }],
-        );
+            RelayConnectionReason::PostMetadata,
+        )?;
 
         Ok(())
     }
@@ -2273,6 +3114,89 @@ impl Overlord {
         Ok(())
     }
 
+    /// Ask our NIP-47 wallet for its balance and recent payments
+    pub fn refresh_wallet() {
+        if !GLOBALS.wallet.is_ready() {
+            return;
+        }
+
+        // The wallet may take a while to answer, so don't hold up the overlord
+        std::mem::drop(tokio::spawn(Box::pin(async move {
+            let result = match GLOBALS.wallet.get_balance().await {
+                Ok(_) => GLOBALS.wallet.list_transactions(50).await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                GLOBALS.status_queue.write().write(format!("{}", e));
+            }
+        })));
+    }
+
+    /// Forget an account. The active account cannot be removed.
+    pub fn remove_account(pubkey: PublicKey) -> Result<(), Error> {
+        accounts::remove_account(pubkey)
+    }
+
+    /// Report a post by `Id` (NIP-56), giving one of the NIP-56 report types as the reason
+    pub async fn report(&mut self, id: Id, reason: String) -> Result<(), Error> {
+        let public_key = match posting_public_key() {
+            Some(pk) => pk,
+            None => return Ok(()),
+        };
+
+        let reported_event = match GLOBALS.db().read_event(id)? {
+            Some(event) => event,
+            None => {
+                GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Cannot report - cannot find event.".to_owned());
+                return Ok(());
+            }
+        };
+
+        let event = {
+            let pre_event = PreEvent {
+                pubkey: public_key,
+                created_at: Unixtime::now(),
+                kind: EventKind::Reporting,
+                tags: vec![
+                    Tag::new(&["e", &id.as_hex_string(), &reason]),
+                    Tag::new(&["p", &reported_event.pubkey.as_hex_string(), &reason]),
+                ],
+                content: "".to_owned(),
+            };
+
+            GLOBALS.identity.sign_event(pre_event).await?
+        };
+
+        // Process this event locally. Our own reports hide what we reported.
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        // Post to our write relays, and where the reported event was seen
+        let mut relay_urls: Vec<RelayUrl> = relay::relays_to_post_to(&event)?;
+        relay_urls.extend(
+            GLOBALS
+                .db()
+                .get_event_seen_on_relay(id)?
+                .drain(..)
+                .map(|(url, _time)| url),
+        );
+        relay_urls.sort();
+        relay_urls.dedup();
+
+        outbox::post_events(vec![event], relay_urls, RelayConnectionReason::PostEvent)?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write(format!("Reported as {}.", reason));
+
+        GLOBALS.feed.sync_recompute();
+
+        Ok(())
+    }
+
     /// Repost a post by `Id`
     pub async fn repost(&mut self, id: Id) -> Result<(), Error> {
         let reposted_event = match GLOBALS.db().read_event(id)? {
@@ -2350,12 +3274,9 @@ impl Overlord {
         }
 
         let event = {
-            let public_key = match GLOBALS.identity.public_key() {
+            let public_key = match posting_public_key() {
                 Some(pk) => pk,
-                None => {
-                    tracing::warn!("No public key! Not posting");
-                    return Ok(());
-                }
+                None => return Ok(()),
             };
 
             if GLOBALS.db().read_setting_set_client_tag() {
@@ -2394,30 +3315,168 @@ impl Overlord {
         // Process this event locally
         crate::process::process_new_event(&event, None, None, false, false).await?;
 
-        // Determine which relays to post this to
-        let mut relay_urls: Vec<RelayUrl> = Vec::new();
-        {
-            // Get all of the relays that we write to
-            let write_relay_urls: Vec<RelayUrl> = relay::relays_to_post_to(&event)?;
-            relay_urls.extend(write_relay_urls);
-            relay_urls.sort();
-            relay_urls.dedup();
-        }
+        // Determine which relays to post this to
+        let mut relay_urls: Vec<RelayUrl> = Vec::new();
+        {
+            // Get all of the relays that we write to
+            let write_relay_urls: Vec<RelayUrl> = relay::relays_to_post_to(&event)?;
+            relay_urls.extend(write_relay_urls);
+            relay_urls.sort();
+            relay_urls.dedup();
+        }
+
+        // Send it the event to post
+        for url in &relay_urls {
+            tracing::debug!("Asking {} to (re)post", url);
+        }
+        outbox::post_events(
+            vec![event.clone()],
+            relay_urls,
+            RelayConnectionReason::PostEvent,
+        )?;
+
+        Ok(())
+    }
+
+    /// Ask relays that support NIP-45 to count things for us. Anything we asked about
+    /// recently is skipped. Results eventually arrive in `GLOBALS.relay_counts`
+    pub fn request_counts(&mut self, mut kinds: Vec<CountKind>) -> Result<(), Error> {
+        // Skip counts we asked for recently
+        kinds.retain(|kind| match GLOBALS.relay_counts.get(kind) {
+            Some(counts) => !counts.is_fresh(),
+            None => true,
+        });
+        if kinds.is_empty() {
+            return Ok(());
+        }
+
+        let mut relays =
+            Relay::choose_relays(0, |r| r.is_good_for_advertise() && r.supports_nip(45))?;
+        relays.sort_by(|a, b| {
+            b.adjusted_score(ScoreFactors::FULLY_ADJUSTED)
+                .total_cmp(&a.adjusted_score(ScoreFactors::FULLY_ADJUSTED))
+        });
+        relays.truncate(GLOBALS.db().read_setting_num_relays_for_counting() as usize);
+        if relays.is_empty() {
+            return Ok(());
+        }
+        let relays: Vec<RelayUrl> = relays.iter().map(|r| r.url.clone()).collect();
+
+        // Remember that we asked, keeping any earlier results until new ones come in
+        let now = Unixtime::now();
+        for kind in &kinds {
+            GLOBALS
+                .relay_counts
+                .entry(*kind)
+                .and_modify(|counts| counts.requested_at = now)
+                .or_default();
+        }
+
+        manager::run_jobs_on_all_relays(
+            relays,
+            vec![RelayJob {
+                reason: RelayConnectionReason::Counting,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Count(kinds),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
+    /// Ask the configured OpenTimestamps calendar to timestamp one of our events.
+    /// The timestamp is published (NIP-03) once it is in a Bitcoin block.
+    pub fn request_timestamp(&mut self, id: Id) -> Result<(), Error> {
+        std::mem::drop(tokio::spawn(Box::pin(async move {
+            let message = match ots::request_timestamp(id).await {
+                Ok(()) => "Timestamp requested. It will be published once it is in a Bitcoin block, which takes a few hours.".to_owned(),
+                Err(e) => format!("Could not request a timestamp: {}", e),
+            };
+            GLOBALS.status_queue.write().write(message);
+        })));
+
+        Ok(())
+    }
+
+    /// Answer an invitation to a calendar event (NIP-52). Answering again
+    /// replaces the earlier answer.
+    pub async fn rsvp_calendar_event(
+        &mut self,
+        naddr: NAddr,
+        status: RsvpStatus,
+    ) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => return Err(ErrorKind::NoPublicKey.into()),
+        };
+
+        let calendar_event = match calendar::calendar_event(&naddr)? {
+            Some(event) => event,
+            None => return Err(ErrorKind::General("Calendar event not found".to_owned()).into()),
+        };
+
+        let event = {
+            let pre_event = PreEvent {
+                pubkey: public_key,
+                created_at: Unixtime::now(),
+                kind: EventKind::CalendarEventRsvp,
+                tags: calendar::rsvp_tags(&calendar_event, status)?,
+                content: "".to_owned(),
+            };
+
+            GLOBALS.identity.sign_event(pre_event).await?
+        };
+
+        // Process this event locally
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        // This includes the inboxes of the author of the calendar event
+        let relay_urls = relay::relays_to_post_to(&event)?;
+        outbox::post_events(vec![event], relay_urls, RelayConnectionReason::PostEvent)?;
 
-        // Send it the event to post
-        for url in &relay_urls {
-            tracing::debug!("Asking {} to (re)post", url);
+        Ok(())
+    }
+
+    /// Schedule a post to be published at a later time.
+    ///
+    /// If we have an unlocked private key it is signed now, otherwise (e.g. with a
+    /// remote signer) it is signed when it is published.
+    pub async fn schedule_post(
+        &mut self,
+        content: String,
+        tags: Vec<Tag>,
+        in_reply_to: Option<Id>,
+        annotation: bool,
+        publish_at: Unixtime,
+    ) -> Result<(), Error> {
+        let author = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => return Err(ErrorKind::NoPublicKey.into()),
+        };
+
+        let mut post = ScheduledPost {
+            key: rand::random::<u64>(),
+            author,
+            publish_at,
+            content,
+            tags,
+            in_reply_to,
+            annotation,
+            event: None,
+        };
+
+        if scheduled_post::can_sign_in_advance() {
+            post.event = Some(scheduled_post::sign_scheduled_post(&post).await?);
         }
-        manager::run_jobs_on_all_relays(
-            relay_urls,
-            vec![RelayJob {
-                reason: RelayConnectionReason::PostEvent,
-                payload: ToMinionPayload {
-                    job_id: rand::random::<u64>(),
-                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                },
-            }],
-        );
+
+        ScheduledPostsTable::write_record(&mut post, None)?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Post scheduled.".to_owned());
 
         Ok(())
     }
@@ -2625,6 +3684,81 @@ impl Overlord {
         Ok(())
     }
 
+    fn set_channel_feed(&mut self, id: Id) -> Result<(), Error> {
+        let channel = channels::channel(id)?;
+        let relay_urls = match &channel {
+            Some(channel) => channels::channel_relays(channel)?,
+            None => Relay::choose_relay_urls(Relay::READ, |_| true)?,
+        };
+
+        let mut jobs = vec![RelayJob {
+            reason: RelayConnectionReason::SubscribeChannel,
+            payload: ToMinionPayload {
+                job_id: rand::random::<u64>(),
+                detail: ToMinionPayloadDetail::Subscribe(FilterSet::Channel(id)),
+            },
+        }];
+
+        // Moderation comes from the creator. If we don't know who that is yet,
+        // fetch the creation event; processing it will bring us back here.
+        match channel.and_then(|c| c.creator) {
+            Some(creator) => jobs.push(RelayJob {
+                reason: RelayConnectionReason::SubscribeChannel,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::ChannelModeration(creator)),
+                },
+            }),
+            None => self.fetch_event(id, relay_urls.clone())?,
+        }
+
+        manager::run_jobs_on_all_relays(relay_urls, jobs);
+
+        Ok(())
+    }
+
+    fn set_community_feed(&mut self, author: PublicKey, d: String) -> Result<(), Error> {
+        // The definition names the moderators, whose approvals we need
+        self.fetch_community_definition(author, &d)?;
+
+        manager::run_jobs_on_all_relays(
+            communities::community_relays(author, &d)?,
+            vec![RelayJob {
+                reason: RelayConnectionReason::SubscribeCommunity,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::Community(
+                        communities::community_address(author, &d),
+                    )),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
+    // Fetch the definition of a community. The author publishes it to their outboxes.
+    fn fetch_community_definition(&mut self, author: PublicKey, d: &str) -> Result<(), Error> {
+        let mut relays: Vec<UncheckedUrl> = communities::community_relays(author, d)?
+            .iter()
+            .map(|r| r.to_unchecked_url())
+            .collect();
+        for url in relay::get_some_pubkey_outboxes(author)?
+            .iter()
+            .map(|r| r.to_unchecked_url())
+        {
+            if !relays.contains(&url) {
+                relays.push(url);
+            }
+        }
+        self.fetch_naddr(NAddr {
+            d: d.to_owned(),
+            relays,
+            kind: EventKind::CommunityDefinition,
+            author,
+        })
+    }
+
     fn set_dm_channel(&mut self, dmchannel: DmChannel) -> Result<(), Error> {
         // subscribe to channel on outbox and inbox relays
         //   outbox: you may have written them there. Other clients may have too.
@@ -2649,6 +3783,25 @@ impl Overlord {
         Ok(())
     }
 
+    async fn set_dvm_feed(&mut self, dvm: PublicKey) -> Result<(), Error> {
+        // DVM feeds are made on request. Ask again unless we asked recently.
+        let recent = Unixtime::now() - Duration::from_secs(600);
+        match dvm::discovery_request(dvm)? {
+            Some(request) if request.created_at > recent => self.subscribe_dvm_jobs(),
+            _ => {
+                // This subscribes to the results too
+                self.submit_dvm_job(JobRequest {
+                    kind: dvm::CONTENT_DISCOVERY,
+                    input: "".to_owned(),
+                    input_type: "".to_owned(),
+                    params: vec![],
+                    dvm: Some(dvm),
+                })
+                .await
+            }
+        }
+    }
+
     fn set_global_feed(&mut self, anchor: Unixtime) -> Result<(), Error> {
         let relay_urls = Relay::choose_relay_urls(Relay::GLOBAL, |_| true)?;
         manager::run_jobs_on_all_relays(
@@ -2678,6 +3831,35 @@ impl Overlord {
         Ok(())
     }
 
+    fn set_group_feed(&mut self, relay: RelayUrl, group_id: String) -> Result<(), Error> {
+        // Group ids are only unique within a relay, so we only ask the group relay
+        manager::run_jobs_on_all_relays(
+            vec![relay],
+            vec![
+                RelayJob {
+                    reason: RelayConnectionReason::SubscribeGroup,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::Group(
+                            group_id.clone(),
+                        )),
+                    },
+                },
+                RelayJob {
+                    reason: RelayConnectionReason::SubscribeGroup,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::GroupMetadata(
+                            group_id,
+                        )),
+                    },
+                },
+            ],
+        );
+
+        Ok(())
+    }
+
     fn set_person_feed(&mut self, pubkey: PublicKey, anchor: Unixtime) -> Result<(), Error> {
         let relays: Vec<RelayUrl> = relay::get_some_pubkey_outboxes(pubkey)?;
         manager::run_jobs_on_all_relays(
@@ -2985,12 +4167,9 @@ impl Overlord {
     }
 
     pub async fn share_handler_recommendations(&mut self, kind: EventKind) -> Result<(), Error> {
-        let public_key = match GLOBALS.identity.public_key() {
+        let public_key = match posting_public_key() {
             Some(pk) => pk,
-            None => {
-                tracing::warn!("No public key! Not posting");
-                return Ok(());
-            }
+            None => return Ok(()),
         };
 
         // Build the recommended handlers tags
@@ -3067,16 +4246,11 @@ impl Overlord {
 
         // Post the event to our outboxes
         let write_relays = relay::relays_to_post_to(&event)?;
-        manager::run_jobs_on_all_relays(
+        outbox::post_events(
+            vec![event.clone()],
             write_relays,
-            vec![RelayJob {
-                reason: RelayConnectionReason::PostEvent,
-                payload: ToMinionPayload {
-                    job_id: rand::random::<u64>(),
-                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                },
-            }],
-        );
+            RelayConnectionReason::PostEvent,
+        )?;
 
         Ok(())
     }
@@ -3122,6 +4296,100 @@ impl Overlord {
         relays.dedup();
         self.subscribe_nip46(relays)?;
 
+        // Separately subscribe to the git repositories we follow
+        if GLOBALS.db().read_setting_enable_git_repositories() {
+            self.subscribe_repositories()?;
+        }
+
+        // Separately subscribe to labels and reports by our moderators
+        if GLOBALS.db().read_setting_enable_moderation() {
+            self.subscribe_moderation()?;
+        }
+
+        Ok(())
+    }
+
+    /// Ask a NIP-90 data vending machine to do a job
+    pub async fn submit_dvm_job(&mut self, request: JobRequest) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => return Err(ErrorKind::NoPublicKey.into()),
+        };
+
+        // DVMs send their feedback and results to our read relays
+        let read_relays = Relay::choose_relay_urls(Relay::READ, |_| true)?;
+
+        let event = {
+            let pre_event = PreEvent {
+                pubkey: public_key,
+                created_at: Unixtime::now(),
+                kind: EventKind::from(request.kind),
+                tags: request.tags(&read_relays),
+                content: "".to_owned(),
+            };
+
+            GLOBALS.identity.sign_event(pre_event).await?
+        };
+
+        // Process this event locally
+        crate::process::process_new_event(&event, None, None, false, false).await?;
+
+        // Post to our write relays and the inboxes of the DVM, which
+        // may only be listening on its own relays
+        let mut relay_urls: Vec<RelayUrl> = relay::relays_to_post_to(&event)?;
+        if let Some(dvm) = request.dvm {
+            relay_urls.extend(relay::get_some_pubkey_outboxes(dvm)?);
+        }
+        relay_urls.sort();
+        relay_urls.dedup();
+
+        outbox::post_events(vec![event], relay_urls, RelayConnectionReason::PostEvent)?;
+
+        self.subscribe_dvm_jobs()?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Asked {} to {}.",
+            match request.dvm {
+                Some(dvm) => dvm::dvm_name(dvm),
+                None => "data vending machines".to_owned(),
+            },
+            dvm::job_kind_name(request.kind).to_lowercase()
+        ));
+
+        Ok(())
+    }
+
+    /// Fetch calendar events (NIP-52), and RSVPs to them, from the people we follow
+    pub fn subscribe_calendar(&mut self) -> Result<(), Error> {
+        // People we follow are already assigned to relays they write to
+        let mut assignments: HashMap<RelayUrl, Vec<PublicKey>> = HashMap::new();
+        for relay_assignment in GLOBALS.relay_picker.relay_assignments_iter() {
+            assignments.insert(
+                relay_assignment.relay_url.clone(),
+                relay_assignment.pubkeys.clone(),
+            );
+        }
+
+        // Our own are on our outboxes
+        if let Some(pubkey) = GLOBALS.identity.public_key() {
+            for relay_url in Relay::choose_relay_urls(Relay::OUTBOX, |_| true)? {
+                assignments.entry(relay_url).or_default().push(pubkey);
+            }
+        }
+
+        for (relay_url, pubkeys) in assignments.drain() {
+            manager::run_jobs_on_all_relays(
+                vec![relay_url],
+                vec![RelayJob {
+                    reason: RelayConnectionReason::SubscribeCalendar,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::Calendar(pubkeys)),
+                    },
+                }],
+            );
+        }
+
         Ok(())
     }
 
@@ -3245,6 +4513,44 @@ impl Overlord {
         Ok(())
     }
 
+    /// Look for NIP-90 data vending machines, and follow the jobs we gave them
+    pub fn subscribe_dvms(&mut self) -> Result<(), Error> {
+        manager::run_jobs_on_all_relays(
+            Relay::choose_relay_urls(Relay::READ, |_| true)?,
+            vec![RelayJob {
+                reason: RelayConnectionReason::SubscribeDvm,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::DvmDiscovery),
+                },
+            }],
+        );
+
+        self.subscribe_dvm_jobs()
+    }
+
+    // Subscribe to the feedback and results of our recent jobs on our read relays,
+    // where we asked the DVMs to send them
+    fn subscribe_dvm_jobs(&mut self) -> Result<(), Error> {
+        let ids: Vec<Id> = dvm::jobs()?.iter().map(|job| job.request.id).collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        manager::run_jobs_on_all_relays(
+            Relay::choose_relay_urls(Relay::READ, |_| true)?,
+            vec![RelayJob {
+                reason: RelayConnectionReason::SubscribeDvm,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::DvmJobs(ids)),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
     /// Subscribe to nip46 nostr connect relays
     pub fn subscribe_nip46(&mut self, relays: Vec<RelayUrl>) -> Result<(), Error> {
         manager::run_jobs_on_all_relays(
@@ -3261,6 +4567,121 @@ impl Overlord {
         Ok(())
     }
 
+    /// Subscribe to the labels and reports of the moderators we trust
+    pub fn subscribe_moderation(&mut self) -> Result<(), Error> {
+        // Make sure there is a list to add moderators to
+        moderation::ensure_moderator_list()?;
+
+        // Moderators post to their own outboxes
+        let mut by_relay: HashMap<RelayUrl, Vec<PublicKey>> = HashMap::new();
+        for pubkey in moderation::moderators()? {
+            for url in relay::get_some_pubkey_outboxes(pubkey)? {
+                by_relay.entry(url).or_default().push(pubkey);
+            }
+        }
+
+        for (url, pubkeys) in by_relay.drain() {
+            manager::run_jobs_on_all_relays(
+                vec![url],
+                vec![RelayJob {
+                    reason: RelayConnectionReason::SubscribeModeration,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::Moderation(pubkeys)),
+                    },
+                }],
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Subscribe to the patches and issues of the git repositories we follow
+    pub fn subscribe_repositories(&mut self) -> Result<(), Error> {
+        // Repositories live on different relays, so we gather the repositories
+        // to ask each relay about
+        let mut by_relay: HashMap<RelayUrl, Vec<String>> = HashMap::new();
+        for repo in git::followed_repositories()? {
+            let address = git::repo_address(repo.author, &repo.d);
+            for url in git::repo_relays(repo.author, &repo.d)? {
+                by_relay.entry(url).or_default().push(address.clone());
+            }
+            self.fetch_repo_announcement(repo.author, &repo.d)?;
+        }
+
+        for (url, addresses) in by_relay.drain() {
+            manager::run_jobs_on_all_relays(
+                vec![url],
+                vec![RelayJob {
+                    reason: RelayConnectionReason::SubscribeGitRepository,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::GitRepositories(
+                            addresses,
+                        )),
+                    },
+                }],
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the announcement, patches and issues of a git repository
+    pub fn subscribe_repository(&mut self, author: PublicKey, d: String) -> Result<(), Error> {
+        self.fetch_repo_announcement(author, &d)?;
+
+        manager::run_jobs_on_all_relays(
+            git::repo_relays(author, &d)?,
+            vec![RelayJob {
+                reason: RelayConnectionReason::SubscribeGitRepository,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::GitRepository(
+                        git::repo_address(author, &d),
+                    )),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
+    // Fetch the announcement of a repository, which names the relays it lives on.
+    // The author publishes it to their outboxes.
+    fn fetch_repo_announcement(&mut self, author: PublicKey, d: &str) -> Result<(), Error> {
+        let mut relays: Vec<UncheckedUrl> = git::repo_relays(author, d)?
+            .iter()
+            .map(|r| r.to_unchecked_url())
+            .collect();
+        for url in relay::get_some_pubkey_outboxes(author)?
+            .iter()
+            .map(|r| r.to_unchecked_url())
+        {
+            if !relays.contains(&url) {
+                relays.push(url);
+            }
+        }
+        self.fetch_naddr(NAddr {
+            d: d.to_owned(),
+            relays,
+            kind: EventKind::RepositoryAnnouncement,
+            author,
+        })
+    }
+
+    /// Switch to another account. Its person lists, relay lists and bookmarks come with it.
+    pub async fn switch_account(&mut self, pubkey: PublicKey) -> Result<(), Error> {
+        accounts::switch_account(Some(pubkey)).await?;
+        self.start_long_lived_subscriptions().await?;
+        GLOBALS.feed.sync_recompute();
+        GLOBALS.status_queue.write().write(format!(
+            "Switched to {}",
+            crate::names::best_name_from_pubkey_lookup(&pubkey)
+        ));
+        Ok(())
+    }
+
     pub fn test_relay(relay_url: RelayUrl) {
         // Indicate that the test has started
         GLOBALS.relay_tests.insert(relay_url.clone(), None);
@@ -3554,6 +4975,37 @@ impl Overlord {
         })
     }
 
+    /// Stop following a NIP-34 git repository
+    pub fn unfollow_repository(&mut self, author: PublicKey, d: String) -> Result<(), Error> {
+        let repo_relays = git::repo_relays(author, &d)?;
+        git::unfollow_repository(author, &d)?;
+
+        // Relays that have none of our other repositories stop sending us its events
+        let mut still_used: HashSet<RelayUrl> = HashSet::new();
+        for repo in git::followed_repositories()? {
+            still_used.extend(git::repo_relays(repo.author, &repo.d)?);
+        }
+        for url in repo_relays.iter().filter(|url| !still_used.contains(*url)) {
+            let _ = self.to_minions.send(ToMinionMessage {
+                target: url.as_str().to_owned(),
+                payload: ToMinionPayload {
+                    job_id: 0,
+                    detail: ToMinionPayloadDetail::Unsubscribe(FilterSet::GitRepositories(vec![])),
+                },
+            });
+        }
+
+        // The others are asked again without it
+        self.subscribe_repositories()?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Stopped following repository {}",
+            git::repo_name(author, &d)
+        ));
+
+        Ok(())
+    }
+
     /// Unlock the private key with the given passphrase so that gossip can use it.
     /// This is akin to logging in.
     pub async fn unlock_key(mut password: String) -> Result<(), Error> {
@@ -3571,6 +5023,20 @@ impl Overlord {
         }
         GLOBALS.client_identity.unlock(&password)?;
 
+        // Unlock the wallet connection, which is under the same passphrase
+        if GLOBALS.wallet.is_connected() {
+            match GLOBALS.wallet.unlock(&password) {
+                Ok(()) => GLOBALS.wallet.fetch_info(),
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write("Could not unlock your wallet connection.".to_owned());
+                }
+            }
+        }
+
         password.zeroize();
 
         Ok(())
@@ -3931,12 +5397,45 @@ impl Overlord {
         Ok(())
     }
 
+    // Check the NIP-03 timestamps of an event in the background
+    fn verify_timestamps(&mut self, id: Id) -> Result<(), Error> {
+        std::mem::drop(tokio::spawn(Box::pin(async move {
+            if let Err(e) = ots::verify_timestamps(id).await {
+                tracing::error!("{}", e);
+            }
+        })));
+
+        Ok(())
+    }
+
     /// Set which notes are currently visible to the user. This is used to modify subscriptions
     /// that query for likes, zaps, and deletions. Such subscriptions only query for that data
     /// for events currently in view, to keep them small.
     ///
     /// WARNING: DO NOT CALL TOO OFTEN or relays will hate you.
     pub fn visible_notes_changed(&mut self, mut visible: Vec<Id>) -> Result<(), Error> {
+        // Drop counts for notes that were scrolled past long ago
+        GLOBALS
+            .relay_counts
+            .retain(|_, counts| !counts.is_expired());
+
+        // Ask counting relays how many references and reactions these have,
+        // skipping notes we counted recently
+        if GLOBALS.db().read_setting_count_replies_and_reactions() {
+            let kinds: Vec<CountKind> = visible
+                .iter()
+                .filter(
+                    |id| match GLOBALS.relay_counts.get(&CountKind::Reactions(**id)) {
+                        Some(counts) => !counts.is_fresh(),
+                        None => true,
+                    },
+                )
+                .take(crate::counts::MAX_COUNTED_NOTES)
+                .flat_map(|id| [CountKind::References(*id), CountKind::Reactions(*id)])
+                .collect();
+            self.request_counts(kinds)?;
+        }
+
         // Work out which relays to use to find augments for which ids
         let mut augment_subs: HashMap<RelayUrl, Vec<Id>> = HashMap::new();
         for id in visible.drain(..) {
@@ -3952,6 +5451,38 @@ impl Overlord {
                         })
                         .or_insert(vec![id]);
                 }
+
+                // Zap receipts for a goal (NIP-75) are posted to the relays it names,
+                // and notes raising funds for a goal need the goal and its receipts too
+                let (goal_id, goal_relays) = match zaps::goal_of(&event) {
+                    Some((goal_id, hint)) => match ZapGoal::load(goal_id) {
+                        Some(goal) => (goal_id, goal.relays),
+                        None => {
+                            let hint: Vec<RelayUrl> = hint.into_iter().collect();
+                            GLOBALS.seeker.seek_id_and_author(
+                                goal_id,
+                                event.pubkey,
+                                hint.clone(),
+                                false,
+                            )?;
+                            (goal_id, hint)
+                        }
+                    },
+                    None => match ZapGoal::from_event(&event) {
+                        Some(goal) => (id, goal.relays),
+                        None => continue,
+                    },
+                };
+                for relay_url in goal_relays {
+                    augment_subs
+                        .entry(relay_url)
+                        .and_modify(|vec| {
+                            if !vec.contains(&goal_id) {
+                                vec.push(goal_id)
+                            }
+                        })
+                        .or_insert(vec![goal_id]);
+                }
             }
         }
 
@@ -3993,27 +5524,11 @@ impl Overlord {
 
         *GLOBALS.current_zap.write() = ZapState::CheckingLnurl(id, target_pubkey, lnurl.clone());
 
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::new(15, 0))
-            .gzip(true)
-            .brotli(true)
-            .deflate(true)
-            .build()?;
-
-        // Convert the lnurl UncheckedUrl to a Url
-        let url = nostr_types::Url::try_from_unchecked_url(&lnurl)?;
-
-        // Read the PayRequestData from the lnurl
-        let response = client.get(url.as_str()).send().await?;
-        let text = response.text().await?;
-        let prd: PayRequestData = match serde_json::from_str(&text) {
+        let prd = match Self::fetch_pay_request_data(&lnurl).await {
             Ok(prd) => prd,
             Err(e) => {
-                tracing::error!("Zap pay request data invalid: {}, {}", text, e);
-                GLOBALS
-                    .status_queue
-                    .write()
-                    .write(format!("Zap pay request data invalid: {}, {}", text, e));
+                tracing::error!("{}", e);
+                GLOBALS.status_queue.write().write(format!("{}", e));
                 *GLOBALS.current_zap.write() = ZapState::None;
                 return Ok(());
             }
@@ -4033,28 +5548,51 @@ impl Overlord {
         Ok(())
     }
 
+    // Read the PayRequestData from an lnurl
+    async fn fetch_pay_request_data(lnurl: &UncheckedUrl) -> Result<PayRequestData, Error> {
+        let client = crate::proxy::http_client_builder(crate::proxy::http_proxy().as_deref())?
+            .timeout(std::time::Duration::new(15, 0))
+            .gzip(true)
+            .brotli(true)
+            .deflate(true)
+            .build()?;
+
+        // Convert the lnurl UncheckedUrl to a Url
+        let url = nostr_types::Url::try_from_unchecked_url(lnurl)?;
+
+        let response = client.get(url.as_str()).send().await?;
+        let text = response.text().await?;
+        match serde_json::from_str(&text) {
+            Ok(prd) => Ok(prd),
+            Err(e) => Err(ErrorKind::General(format!(
+                "Zap pay request data invalid: {}, {}",
+                text, e
+            ))
+            .into()),
+        }
+    }
+
     /// Complete a zap on the note with Id and author PublicKey by setting a value and a comment.
+    /// If the note splits its zaps (NIP-57 `zap` tags), the amount is shared among those
+    /// recipients, each getting their own invoice. An anonymous zap is requested under a
+    /// throwaway key.
     pub async fn zap(
         &mut self,
         id: Id,
         target_pubkey: PublicKey,
         msats: MilliSatoshi,
         comment: String,
+        anonymous: bool,
     ) -> Result<(), Error> {
-        use serde_json::Value;
-
-        let user_pubkey = match GLOBALS.identity.public_key() {
-            Some(pk) => pk,
-            None => {
-                tracing::warn!("You need to setup your identity to zap.");
-                GLOBALS
-                    .status_queue
-                    .write()
-                    .write("You need to setup your identity to zap.".to_string());
-                *GLOBALS.current_zap.write() = ZapState::None;
-                return Ok(());
-            }
-        };
+        if GLOBALS.identity.public_key().is_none() {
+            tracing::warn!("You need to setup your identity to zap.");
+            GLOBALS
+                .status_queue
+                .write()
+                .write("You need to setup your identity to zap.".to_string());
+            *GLOBALS.current_zap.write() = ZapState::None;
+            return Ok(());
+        }
 
         // Make sure we are in the right zap state, and destructure it
         let (state_id, state_pubkey, prd, lnurl) = match *GLOBALS.current_zap.read() {
@@ -4075,36 +5613,170 @@ impl Overlord {
             return Ok(());
         }
 
-        // Validate amount bounds
+        let maybe_event = GLOBALS.db().read_event(id)?;
+
+        // Work out who gets what. Split recipients we cannot zap are left out.
+        let splits: Vec<ZapSplit> = match maybe_event {
+            Some(ref event) if event.pubkey == target_pubkey => zaps::zap_splits(event)
+                .into_iter()
+                .filter(|s| s.pubkey == target_pubkey || zaps::lnurl_of(s.pubkey).is_some())
+                .collect(),
+            _ => vec![],
+        };
+        let recipients: Vec<(PublicKey, MilliSatoshi)> = if splits.is_empty() {
+            // Validate amount bounds
+            if let Some(problem) = Self::zap_amount_problem(&prd, msats) {
+                tracing::warn!("{}", problem);
+                GLOBALS.status_queue.write().write(problem);
+                // leave zap state as is.
+                return Ok(());
+            }
+            vec![(target_pubkey, msats)]
+        } else {
+            zaps::split_zap_amount(msats, &zaps::zap_shares(&splits))
+        };
+        if recipients.is_empty() {
+            GLOBALS
+                .status_queue
+                .write()
+                .write("The zap is too small to split.".to_string());
+            // leave zap state as is.
+            return Ok(());
+        }
+
+        // Bump the state
+        *GLOBALS.current_zap.write() = ZapState::LoadingInvoice(id, target_pubkey);
+
+        let mut invoices: Vec<(PublicKey, String)> = Vec::new();
+        for (recipient, amount) in recipients {
+            let result = if recipient == target_pubkey {
+                // The target's share of a split must also be within its bounds
+                match Self::zap_amount_problem(&prd, amount) {
+                    Some(problem) => Err(ErrorKind::General(problem).into()),
+                    None => {
+                        Self::zap_invoice(
+                            id,
+                            maybe_event.as_ref(),
+                            recipient,
+                            amount,
+                            &comment,
+                            &prd,
+                            &lnurl,
+                            anonymous,
+                        )
+                        .await
+                    }
+                }
+            } else {
+                Self::zap_split_invoice(
+                    id,
+                    maybe_event.as_ref(),
+                    recipient,
+                    amount,
+                    &comment,
+                    anonymous,
+                )
+                .await
+            };
+
+            match result {
+                Ok(invoice) => invoices.push((recipient, invoice)),
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    GLOBALS.status_queue.write().write(format!(
+                        "Could not zap {}: {}",
+                        crate::names::best_name_from_pubkey_lookup(&recipient),
+                        e
+                    ));
+                }
+            }
+        }
+
+        // The user may have closed this zap in the meantime
+        if !matches!(*GLOBALS.current_zap.read(), ZapState::LoadingInvoice(i, _) if i == id) {
+            return Ok(());
+        }
+
+        Self::pay_zap(id, invoices);
+
+        Ok(())
+    }
+
+    // Why a zap amount can't be sent to an lnurl, if it can't
+    fn zap_amount_problem(prd: &PayRequestData, msats: MilliSatoshi) -> Option<String> {
+        use serde_json::Value;
+
         if let Some(Value::Number(n)) = prd.other.get("minSendable") {
             if let Some(u) = n.as_u64() {
                 if msats.0 < u {
-                    tracing::warn!("Zap amount too low. Min is {}", u);
-                    GLOBALS
-                        .status_queue
-                        .write()
-                        .write("Zap amount is too low.".to_string());
-                    // leave zap state as is.
-                    return Ok(());
+                    return Some(format!("Zap amount is too low. Min is {} sats.", u / 1000));
                 }
             }
         }
         if let Some(Value::Number(n)) = prd.other.get("maxSendable") {
             if let Some(u) = n.as_u64() {
                 if msats.0 > u {
-                    tracing::warn!("Zap amount too high. Max is {}", u);
-                    GLOBALS
-                        .status_queue
-                        .write()
-                        .write("Zap amount is too high.".to_string());
-                    // leave zap state as is.
-                    return Ok(());
+                    return Some(format!("Zap amount is too high. Max is {} sats.", u / 1000));
                 }
             }
         }
+        None
+    }
 
-        // Bump the state
-        *GLOBALS.current_zap.write() = ZapState::LoadingInvoice(id, target_pubkey);
+    // Get an invoice for a zap split recipient's share of a zap
+    async fn zap_split_invoice(
+        id: Id,
+        maybe_event: Option<&Event>,
+        recipient: PublicKey,
+        msats: MilliSatoshi,
+        comment: &str,
+        anonymous: bool,
+    ) -> Result<String, Error> {
+        let lnurl = match zaps::lnurl_of(recipient) {
+            Some(lnurl) => UncheckedUrl(lnurl),
+            None => return Err(ErrorKind::General("No lightning address".to_owned()).into()),
+        };
+        let prd = Self::fetch_pay_request_data(&lnurl).await?;
+        if let Some(problem) = Self::zap_amount_problem(&prd, msats) {
+            return Err(ErrorKind::General(problem).into());
+        }
+        Self::zap_invoice(
+            id,
+            maybe_event,
+            recipient,
+            msats,
+            comment,
+            &prd,
+            &lnurl,
+            anonymous,
+        )
+        .await
+    }
+
+    // Send a zap request to a recipient's lnurl callback, getting back the invoice
+    #[allow(clippy::too_many_arguments)]
+    async fn zap_invoice(
+        id: Id,
+        maybe_event: Option<&Event>,
+        recipient: PublicKey,
+        msats: MilliSatoshi,
+        comment: &str,
+        prd: &PayRequestData,
+        lnurl: &UncheckedUrl,
+        anonymous: bool,
+    ) -> Result<String, Error> {
+        use nostr_types::{KeySigner, Signer};
+        use serde_json::Value;
+
+        // Zaps on an addressable event that raises funds for a goal go to the goal
+        // (NIP-75), as do zaps on the goal itself
+        let zapped_id = match maybe_event {
+            Some(event) if event.kind.is_parameterized_replaceable() => zaps::goal_of(event)
+                .map(|(goal_id, _)| goal_id)
+                .unwrap_or(id),
+            _ => id,
+        };
+        let goal = ZapGoal::load(zapped_id);
 
         let msats_string: String = format!("{}", msats.0);
 
@@ -4112,85 +5784,105 @@ impl Overlord {
         let callback = nostr_types::Url::try_from_unchecked_url(&prd.callback)?;
 
         // Get the relays to have the receipt posted to
-        let relays = {
-            // Start with the relays the event was seen on
-            let mut relays: Vec<RelayUrl> = GLOBALS
-                .db()
-                .get_event_seen_on_relay(id)?
-                .drain(..)
-                .map(|(url, _)| url)
-                .collect();
+        let relays = match goal {
+            // Receipts for a goal must go to the relays the goal asks for
+            Some(goal) if !goal.relays.is_empty() => {
+                let relays: Vec<String> =
+                    goal.relays.iter().map(|r| r.as_str().to_owned()).collect();
+                relays
+            }
+            _ => {
+                // Start with the relays the event was seen on
+                let mut relays: Vec<RelayUrl> = GLOBALS
+                    .db()
+                    .get_event_seen_on_relay(id)?
+                    .drain(..)
+                    .map(|(url, _)| url)
+                    .collect();
 
-            // Add the read relays of the target person
-            let target_read_relays: Vec<RelayUrl> = relay::get_all_pubkey_inboxes(target_pubkey)?;
-            relays.extend(target_read_relays);
+                // Add the read relays of the recipient
+                let recipient_read_relays: Vec<RelayUrl> =
+                    relay::get_all_pubkey_inboxes(recipient)?;
+                relays.extend(recipient_read_relays);
 
-            // Add all my write relays
-            let write_relay_urls: Vec<RelayUrl> = Relay::choose_relay_urls(Relay::WRITE, |_| true)?;
-            relays.extend(write_relay_urls);
+                // Add all my write relays
+                let write_relay_urls: Vec<RelayUrl> =
+                    Relay::choose_relay_urls(Relay::WRITE, |_| true)?;
+                relays.extend(write_relay_urls);
 
-            if relays.is_empty() {
-                *GLOBALS.current_zap.write() = ZapState::None;
-                return Err(ErrorKind::NoRelay.into());
-            }
+                if relays.is_empty() {
+                    return Err(ErrorKind::NoRelay.into());
+                }
 
-            // Deduplicate
-            relays.sort();
-            relays.dedup();
+                // Deduplicate
+                relays.sort();
+                relays.dedup();
 
-            // Turn relays into strings for the event tag
-            let relays: Vec<String> = relays.iter().map(|r| r.as_str().to_owned()).collect();
-            relays
+                // Turn relays into strings for the event tag
+                let relays: Vec<String> = relays.iter().map(|r| r.as_str().to_owned()).collect();
+                relays
+            }
         };
 
         let mut relays_tag = Tag::new(&["relays"]);
         relays_tag.push_values(relays);
 
-        // Generate the zap request event
-        let pre_event = PreEvent {
-            pubkey: user_pubkey,
-            created_at: Unixtime::now(),
-            kind: EventKind::ZapRequest,
-            tags: vec![
-                ParsedTag::Event {
-                    id,
-                    recommended_relay_url: None,
-                    marker: None,
-                    author_pubkey: None,
-                }
-                .into_tag(),
-                ParsedTag::Pubkey {
-                    pubkey: target_pubkey,
-                    recommended_relay_url: None,
-                    petname: None,
-                }
-                .into_tag(),
-                relays_tag,
-                Tag::new(&["amount", &msats_string]),
-                Tag::new(&["lnurl", lnurl.as_str()]),
-            ],
-            content: comment,
-        };
+        let mut tags = vec![
+            ParsedTag::Event {
+                id: zapped_id,
+                recommended_relay_url: None,
+                marker: None,
+                author_pubkey: None,
+            }
+            .into_tag(),
+            ParsedTag::Pubkey {
+                pubkey: recipient,
+                recommended_relay_url: None,
+                petname: None,
+            }
+            .into_tag(),
+            relays_tag,
+            Tag::new(&["amount", &msats_string]),
+            Tag::new(&["lnurl", lnurl.as_str()]),
+        ];
 
-        let event = GLOBALS.identity.sign_event(pre_event).await?;
+        // Generate the zap request event. Anonymous zaps are signed by a key
+        // made up just for this zap.
+        let event = if anonymous {
+            let stranger = KeySigner::generate("anon", 2)?;
+            tags.push(Tag::new(&["anon"]));
+            let pre_event = PreEvent {
+                pubkey: stranger.public_key(),
+                created_at: Unixtime::now(),
+                kind: EventKind::ZapRequest,
+                tags,
+                content: comment.to_owned(),
+            };
+            stranger.sign_event(pre_event).await?
+        } else {
+            let pre_event = PreEvent {
+                pubkey: GLOBALS
+                    .identity
+                    .public_key()
+                    .ok_or(ErrorKind::NoPublicKey)?,
+                created_at: Unixtime::now(),
+                kind: EventKind::ZapRequest,
+                tags,
+                content: comment.to_owned(),
+            };
+            GLOBALS.identity.sign_event(pre_event).await?
+        };
 
         let serialized_event = serde_json::to_string(&event)?;
 
-        let client = reqwest::Client::builder()
+        let client = crate::proxy::http_client_builder(crate::proxy::http_proxy().as_deref())?
             .timeout(std::time::Duration::new(15, 0))
             .gzip(true)
             .brotli(true)
             .deflate(true)
             .build()?;
 
-        let mut url = match url::Url::parse(callback.as_str()) {
-            Ok(url) => url,
-            Err(e) => {
-                tracing::error!("{}", e);
-                *GLOBALS.current_zap.write() = ZapState::None;
-                return Ok(());
-            }
-        };
+        let mut url = url::Url::parse(callback.as_str())?;
 
         url.query_pairs_mut()
             .clear()
@@ -4204,19 +5896,75 @@ impl Overlord {
         if let Value::Object(map) = value {
             if let Some(Value::String(s)) = map.get("pr") {
                 tracing::debug!("Zap Invoice = {}", s);
-                *GLOBALS.current_zap.write() = ZapState::ReadyToPay(id, s.to_owned());
-                return Ok(());
+                return Ok(s.to_owned());
             }
         }
 
-        *GLOBALS.current_zap.write() = ZapState::None;
         tracing::warn!("Zap invoice data not recognized: {}", text);
-        GLOBALS
-            .status_queue
-            .write()
-            .write("Zap invoice data not recognized.".to_string());
+        Err(ErrorKind::General("Zap invoice data not recognized.".to_owned()).into())
+    }
 
-        Ok(())
+    // Pay zap invoices with our wallet if we have one, otherwise show them
+    fn pay_zap(id: Id, mut invoices: Vec<(PublicKey, String)>) {
+        if invoices.is_empty() {
+            *GLOBALS.current_zap.write() = ZapState::None;
+        } else if GLOBALS.wallet.is_ready() {
+            Self::pay_zap_with_wallet(id, invoices);
+        } else if invoices.len() == 1 {
+            let (_, invoice) = invoices.remove(0);
+            *GLOBALS.current_zap.write() = ZapState::ReadyToPay(id, invoice);
+        } else {
+            *GLOBALS.current_zap.write() = ZapState::ReadyToPaySplit(id, invoices);
+        }
+    }
+
+    // Pay zap invoices with our NIP-47 wallet. Whatever doesn't get paid is shown
+    // so the user can pay it some other way.
+    fn pay_zap_with_wallet(id: Id, invoices: Vec<(PublicKey, String)>) {
+        *GLOBALS.current_zap.write() = ZapState::PayingWithWallet(id);
+
+        std::mem::drop(tokio::spawn(Box::pin(async move {
+            let mut unpaid: Vec<(PublicKey, String)> = Vec::new();
+            let mut paid: usize = 0;
+            let mut pending: usize = 0;
+            for (recipient, invoice) in invoices {
+                match GLOBALS.wallet.pay_invoice(&invoice).await {
+                    Ok(Some(_)) => paid += 1,
+                    // Not unpaid either, so it isn't offered to pay again
+                    Ok(None) => pending += 1,
+                    Err(e) => {
+                        tracing::warn!("{}", e);
+                        GLOBALS
+                            .status_queue
+                            .write()
+                            .write(format!("Your wallet did not pay the zap: {}", e));
+                        unpaid.push((recipient, invoice));
+                    }
+                }
+            }
+
+            if paid > 0 {
+                GLOBALS.status_queue.write().write("Zap paid.".to_owned());
+                Overlord::refresh_wallet();
+            }
+            if pending > 0 {
+                GLOBALS.status_queue.write().write(
+                    "Your wallet has not answered yet, so the zap payment is pending.".to_owned(),
+                );
+            }
+
+            // The user may have closed this zap, or started another, in the meantime
+            let mut current_zap = GLOBALS.current_zap.write();
+            if matches!(*current_zap, ZapState::PayingWithWallet(i) if i == id) {
+                *current_zap = if unpaid.is_empty() {
+                    ZapState::None
+                } else if unpaid.len() == 1 {
+                    ZapState::ReadyToPay(id, unpaid.remove(0).1)
+                } else {
+                    ZapState::ReadyToPaySplit(id, unpaid)
+                };
+            }
+        })));
     }
 }
 
@@ -4237,3 +5985,12 @@ fn work_logger(work_receiver: mpsc::Receiver<u8>, powint: u8) {
         }
     }
 }
+
+// The public key to post with. If we have none, we warn and the caller doesn't post.
+fn posting_public_key() -> Option<PublicKey> {
+    let public_key = GLOBALS.identity.public_key();
+    if public_key.is_none() {
+        tracing::warn!("No public key! Not posting");
+    }
+    public_key
+}
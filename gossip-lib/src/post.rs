@@ -1,3 +1,4 @@
+use crate::article::ArticleFields;
 use crate::dm_channel::DmChannel;
 use crate::error::{Error, ErrorKind};
 use crate::fetcher::FetchResult;
@@ -11,12 +12,58 @@ use nostr_types::{
 };
 use std::sync::mpsc;
 
+/// Prepare a public post, a TextNote or (when replying to something else) a Comment
+pub async fn prepare_post_public(
+    author: PublicKey,
+    content: String,
+    tags: Vec<Tag>,
+    in_reply_to: Option<Id>,
+    annotation: bool,
+    created_at: Unixtime,
+) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
+    if let Some(parent_id) = in_reply_to {
+        let parent = match GLOBALS.db().read_event(parent_id)? {
+            Some(e) => e,
+            None => return Err("Cannot find event we are replying to.".into()),
+        };
+
+        if parent.kind == EventKind::TextNote {
+            prepare_post_normal(author, content, tags, Some(parent), annotation, created_at).await
+        } else if let (EventKind::ChannelMessage, Some(channel)) =
+            (parent.kind, crate::channels::channel_of(&parent))
+        {
+            // Replies in a public chat channel stay in the channel
+            prepare_post_channel(author, content, tags, channel, Some(parent), created_at).await
+        } else if let Some((relay, group_id)) = crate::groups::group_and_relay_of(&parent)? {
+            // Replies in a relay-based group stay in the group
+            prepare_post_group(
+                author,
+                content,
+                tags,
+                relay,
+                group_id,
+                Some(parent),
+                created_at,
+            )
+            .await
+        } else if crate::git::GIT_KINDS.contains(&parent.kind) {
+            // Replies to patches and issues are git replies
+            prepare_post_git_reply(author, content, tags, parent, created_at).await
+        } else {
+            prepare_post_comment(author, content, tags, parent, annotation, created_at).await
+        }
+    } else {
+        prepare_post_normal(author, content, tags, None, annotation, created_at).await
+    }
+}
+
 pub async fn prepare_post_normal(
     author: PublicKey,
     content: String,
     mut tags: Vec<Tag>,
     in_reply_to: Option<Event>,
     annotation: bool,
+    created_at: Unixtime,
 ) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
     add_gossip_tag(&mut tags);
 
@@ -32,27 +79,13 @@ pub async fn prepare_post_normal(
 
     let pre_event = PreEvent {
         pubkey: author,
-        created_at: Unixtime::now(),
+        created_at,
         kind: EventKind::TextNote,
         tags,
         content,
     };
 
-    let event = {
-        let powint = GLOBALS.db().read_setting_pow();
-        if powint > 0 {
-            let (work_sender, work_receiver) = mpsc::channel();
-            std::thread::spawn(move || {
-                work_logger(work_receiver, powint);
-            });
-            GLOBALS
-                .identity
-                .sign_event_with_pow(pre_event, powint, Some(work_sender))
-                .await?
-        } else {
-            GLOBALS.identity.sign_event(pre_event).await?
-        }
-    };
+    let event = sign_with_pow(pre_event).await?;
 
     let relays = relay::relays_to_post_to(&event)?;
 
@@ -65,6 +98,7 @@ pub async fn prepare_post_comment(
     mut tags: Vec<Tag>,
     parent: Event,
     annotation: bool,
+    created_at: Unixtime,
 ) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
     add_gossip_tag(&mut tags);
 
@@ -82,33 +116,333 @@ pub async fn prepare_post_comment(
 
     let pre_event = PreEvent {
         pubkey: author,
-        created_at: Unixtime::now(),
+        created_at,
         kind: EventKind::Comment,
         tags,
         content,
     };
 
-    let event = {
-        let powint = GLOBALS.db().read_setting_pow();
-        if powint > 0 {
-            let (work_sender, work_receiver) = mpsc::channel();
-            std::thread::spawn(move || {
-                work_logger(work_receiver, powint);
-            });
-            GLOBALS
-                .identity
-                .sign_event_with_pow(pre_event, powint, Some(work_sender))
-                .await?
-        } else {
-            GLOBALS.identity.sign_event(pre_event).await?
+    let event = sign_with_pow(pre_event).await?;
+
+    let relays = relay::relays_to_post_to(&event)?;
+
+    Ok(vec![(event, relays)])
+}
+
+/// Prepare a NIP-28 public chat channel message, possibly replying to another message
+pub async fn prepare_post_channel(
+    author: PublicKey,
+    content: String,
+    mut tags: Vec<Tag>,
+    channel: Id,
+    in_reply_to: Option<Event>,
+    created_at: Unixtime,
+) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
+    add_gossip_tag(&mut tags);
+
+    let channel_relays = match crate::channels::channel(channel)? {
+        Some(c) => c.relays,
+        None => vec![],
+    };
+    let relay_hint = channel_relays.first().map(|r| r.to_unchecked_url());
+
+    tags.push(
+        ParsedTag::Event {
+            id: channel,
+            recommended_relay_url: relay_hint.clone(),
+            marker: Some("root".to_owned()),
+            author_pubkey: None,
+        }
+        .into_tag(),
+    );
+
+    if let Some(ref parent) = in_reply_to {
+        tags.push(
+            ParsedTag::Event {
+                id: parent.id,
+                recommended_relay_url: relay_hint,
+                marker: Some("reply".to_owned()),
+                author_pubkey: None,
+            }
+            .into_tag(),
+        );
+        if parent.pubkey != author {
+            nostr_types::add_pubkey_to_tags(&mut tags, parent.pubkey, None);
+        }
+    }
+
+    add_tags_mirroring_content(&content, &mut tags, false).await;
+
+    let pre_event = PreEvent {
+        pubkey: author,
+        created_at,
+        kind: EventKind::ChannelMessage,
+        tags,
+        content,
+    };
+
+    let event = sign_with_pow(pre_event).await?;
+
+    // Post to our outbox relays and to the relays of the channel
+    let mut relays = relay::relays_to_post_to(&event)?;
+    for url in channel_relays {
+        if !relays.contains(&url) {
+            relays.push(url);
+        }
+    }
+
+    Ok(vec![(event, relays)])
+}
+
+/// Prepare a NIP-29 relay-based group chat message, possibly replying to another message
+pub async fn prepare_post_group(
+    author: PublicKey,
+    content: String,
+    mut tags: Vec<Tag>,
+    relay: RelayUrl,
+    group_id: String,
+    in_reply_to: Option<Event>,
+    created_at: Unixtime,
+) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
+    add_gossip_tag(&mut tags);
+
+    tags.push(Tag::new(&["h", &group_id]));
+
+    // Replies quote the message they reply to (NIP-C7)
+    if let Some(ref parent) = in_reply_to {
+        tags.push(Tag::new(&[
+            "q",
+            &parent.id.as_hex_string(),
+            relay.as_str(),
+            &parent.pubkey.as_hex_string(),
+        ]));
+        if parent.pubkey != author {
+            nostr_types::add_pubkey_to_tags(&mut tags, parent.pubkey, None);
+        }
+    }
+
+    add_tags_mirroring_content(&content, &mut tags, false).await;
+
+    let pre_event = PreEvent {
+        pubkey: author,
+        created_at,
+        kind: EventKind::GroupChatMessage,
+        tags,
+        content,
+    };
+
+    let event = sign_with_pow(pre_event).await?;
+
+    // Group events only belong on the relay that manages the group
+    Ok(vec![(event, vec![relay])])
+}
+
+/// Prepare a NIP-23 long-form article (kind 30023), or a draft of one (kind 30024).
+///
+/// If the article has no identifier yet, one is made from the title. Publishing keeps
+/// the `published_at` of an earlier version of the article.
+pub async fn prepare_post_article(
+    author: PublicKey,
+    mut fields: ArticleFields,
+    draft: bool,
+    created_at: Unixtime,
+) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
+    if fields.d.is_empty() {
+        fields.d = crate::misc::new_identifier(&fields.title);
+    }
+
+    let mut tags: Vec<Tag> = Vec::new();
+    add_gossip_tag(&mut tags);
+
+    tags.push(Tag::new(&["d", &fields.d]));
+    if !fields.title.is_empty() {
+        tags.push(Tag::new(&["title", &fields.title]));
+    }
+    if !fields.summary.is_empty() {
+        tags.push(Tag::new(&["summary", &fields.summary]));
+    }
+    if !fields.image.is_empty() {
+        tags.push(Tag::new(&["image", &fields.image]));
+    }
+
+    if !draft {
+        let published_at = match GLOBALS.db().get_replaceable_event(
+            EventKind::LongFormContent,
+            author,
+            &fields.d,
+        )? {
+            Some(earlier) => crate::article::Article::from_event(&earlier)
+                .and_then(|a| a.published_at)
+                .unwrap_or(created_at),
+            None => created_at,
+        };
+        tags.push(Tag::new(&["published_at", &published_at.0.to_string()]));
+    }
+
+    for hashtag in fields.hashtags.iter() {
+        let hashtag = hashtag.trim().trim_start_matches('#');
+        if !hashtag.is_empty() {
+            tags.push(ParsedTag::Hashtag(hashtag.to_lowercase()).into_tag());
         }
+    }
+
+    add_tags_mirroring_content(&fields.content, &mut tags, false).await;
+
+    let pre_event = PreEvent {
+        pubkey: author,
+        created_at,
+        kind: if draft {
+            EventKind::DraftLongFormContent
+        } else {
+            EventKind::LongFormContent
+        },
+        tags,
+        content: fields.content,
     };
 
+    let event = sign_with_pow(pre_event).await?;
+
     let relays = relay::relays_to_post_to(&event)?;
 
     Ok(vec![(event, relays)])
 }
 
+/// Prepare a reply (kind 1622) to a NIP-34 patch, issue, or reply.
+///
+/// The reply is tagged with the repository and the patch or issue at the root of the
+/// thread, and goes to the relays of the repository as well as our own.
+pub async fn prepare_post_git_reply(
+    author: PublicKey,
+    content: String,
+    mut tags: Vec<Tag>,
+    parent: Event,
+    created_at: Unixtime,
+) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
+    add_gossip_tag(&mut tags);
+
+    let root = crate::git::root_of(&parent).unwrap_or(parent.id);
+    let repo_relays = add_git_thread_tags(author, &mut tags, &parent, root)?;
+
+    if parent.id != root {
+        tags.push(
+            ParsedTag::Event {
+                id: parent.id,
+                recommended_relay_url: repo_relays.first().map(|r| r.to_unchecked_url()),
+                marker: Some("reply".to_owned()),
+                author_pubkey: None,
+            }
+            .into_tag(),
+        );
+    }
+
+    add_tags_mirroring_content(&content, &mut tags, false).await;
+
+    let pre_event = PreEvent {
+        pubkey: author,
+        created_at,
+        kind: EventKind::GitReply,
+        tags,
+        content,
+    };
+
+    let event = sign_with_pow(pre_event).await?;
+
+    let mut relays = relay::relays_to_post_to(&event)?;
+    for url in repo_relays {
+        if !relays.contains(&url) {
+            relays.push(url);
+        }
+    }
+
+    Ok(vec![(event, relays)])
+}
+
+/// Prepare a status change (kinds 1630-1633) of a NIP-34 patch or issue
+pub async fn prepare_post_git_status(
+    author: PublicKey,
+    root: Event,
+    status: crate::git::GitStatus,
+    created_at: Unixtime,
+) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
+    let mut tags: Vec<Tag> = Vec::new();
+    add_gossip_tag(&mut tags);
+
+    let repo_relays = add_git_thread_tags(author, &mut tags, &root, root.id)?;
+
+    let pre_event = PreEvent {
+        pubkey: author,
+        created_at,
+        kind: status.kind(),
+        tags,
+        content: "".to_owned(),
+    };
+
+    let event = GLOBALS.identity.sign_event(pre_event).await?;
+
+    let mut relays = relay::relays_to_post_to(&event)?;
+    for url in repo_relays {
+        if !relays.contains(&url) {
+            relays.push(url);
+        }
+    }
+
+    Ok(vec![(event, relays)])
+}
+
+// Tag the root patch or issue, the repository, its maintainers, and the author we
+// respond to. Returns the relays of the repository.
+fn add_git_thread_tags(
+    author: PublicKey,
+    tags: &mut Vec<Tag>,
+    parent: &Event,
+    root: Id,
+) -> Result<Vec<RelayUrl>, Error> {
+    let repo = crate::git::repo_of(parent);
+    let announcement = match &repo {
+        Some((repo_author, d)) => crate::git::announcement(*repo_author, d)?,
+        None => None,
+    };
+    let repo_relays = match &announcement {
+        Some(a) => a.relays.clone(),
+        None => vec![],
+    };
+
+    tags.push(
+        ParsedTag::Event {
+            id: root,
+            recommended_relay_url: repo_relays.first().map(|r| r.to_unchecked_url()),
+            marker: Some("root".to_owned()),
+            author_pubkey: None,
+        }
+        .into_tag(),
+    );
+
+    if let Some((repo_author, d)) = &repo {
+        let address = crate::git::repo_address(*repo_author, d);
+        match repo_relays.first() {
+            Some(url) => tags.push(Tag::new(&["a", &address, url.as_str()])),
+            None => tags.push(Tag::new(&["a", &address])),
+        }
+        if *repo_author != author {
+            nostr_types::add_pubkey_to_tags(tags, *repo_author, None);
+        }
+    }
+
+    if let Some(a) = &announcement {
+        for pubkey in a.maintainers.iter() {
+            if *pubkey != author {
+                nostr_types::add_pubkey_to_tags(tags, *pubkey, None);
+            }
+        }
+    }
+
+    if parent.pubkey != author {
+        nostr_types::add_pubkey_to_tags(tags, parent.pubkey, None);
+    }
+
+    Ok(repo_relays)
+}
+
 pub async fn prepare_post_nip04(
     author: PublicKey,
     content: String,
@@ -290,6 +624,17 @@ async fn add_tags_mirroring_content(content: &str, tags: &mut Vec<Tag>, direct_m
 }
 
 async fn add_imeta_tag(urlstr: &str, mimetype: &str, tags: &mut Vec<Tag>) {
+    // Keep an imeta tag we were given already (e.g. from the server we uploaded to)
+    let url_field = format!("url {}", urlstr);
+    if tags.iter().filter(|t| t.tagname() == "imeta").any(|t| {
+        (1..)
+            .map(|i| t.get_index(i))
+            .take_while(|f| !f.is_empty())
+            .any(|f| f == url_field)
+    }) {
+        return;
+    }
+
     //turn into a nostr_types::Url
     let url = match Url::try_from_str(urlstr) {
         Ok(url) => url,
@@ -478,6 +823,23 @@ fn add_event_to_tags(
     nostr_types::add_event_to_tags(existing_tags, added, relay_url, marker, opt_pubkey, true)
 }
 
+// Sign the event, doing proof of work first if the user asked for it
+async fn sign_with_pow(pre_event: PreEvent) -> Result<Event, Error> {
+    let powint = GLOBALS.db().read_setting_pow();
+    if powint > 0 {
+        let (work_sender, work_receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            work_logger(work_receiver, powint);
+        });
+        GLOBALS
+            .identity
+            .sign_event_with_pow(pre_event, powint, Some(work_sender))
+            .await
+    } else {
+        GLOBALS.identity.sign_event(pre_event).await
+    }
+}
+
 fn work_logger(work_receiver: mpsc::Receiver<u8>, powint: u8) {
     while let Ok(work) = work_receiver.recv() {
         if work >= powint {
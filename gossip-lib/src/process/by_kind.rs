@@ -255,6 +255,13 @@ pub async fn process_nostr_connect(event: &Event, seen_on: Option<RelayUrl>) ->
     Ok(())
 }
 
+// EventKind::WalletResponse
+pub fn process_wallet_response(event: &Event) -> Result<(), Error> {
+    GLOBALS.wallet.handle_response(event);
+
+    Ok(())
+}
+
 // EventKind::UserServerList
 pub fn process_user_server_list(event: &Event, ours: bool) -> Result<(), Error> {
     if ours {
@@ -446,3 +453,51 @@ pub fn process_request_to_vanish(event: &Event) -> Result<(), Error> {
 
     Ok(())
 }
+
+// EventKind::ChannelCreation
+pub fn process_channel_creation(event: &Event) -> Result<(), Error> {
+    crate::channels::process_channel_creation(event)
+}
+
+// EventKind::ChannelMetadata
+// Only the channel creator may change the channel metadata
+pub fn process_channel_metadata(event: &Event) -> Result<(), Error> {
+    crate::channels::process_channel_metadata(event)
+}
+
+// EventKind::ProfileBadges, EventKind::BadgeAward
+// Fetch the definitions of badges that we need to show them
+pub fn process_badges(event: &Event) -> Result<(), Error> {
+    crate::badges::fetch_missing_definitions(event)
+}
+
+// EventKind::CommunityDefinition
+pub fn process_community_definition(event: &Event) -> Result<(), Error> {
+    crate::communities::process_community_definition(event)
+}
+
+// EventKind::CommunityPostApproval
+// Approvals carry the post they approve, which we may not get any other way
+pub async fn process_community_post_approval(event: &Event, verify: bool) -> Result<(), Error> {
+    if let Ok(post) = serde_json::from_str::<Event>(&event.content) {
+        if GLOBALS.db().read_event(post.id)?.is_none() {
+            Box::pin(crate::process::process_new_event(
+                &post, None, None, verify, false,
+            ))
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+// Group metadata (kind 39000)
+// Only the relay managing the group may set the group metadata
+pub fn process_group_metadata(event: &Event, seen_on: Option<&RelayUrl>) -> Result<(), Error> {
+    crate::groups::process_group_metadata(event, seen_on)
+}
+
+// Job results (kinds 6000-6999)
+// Content discovery results list notes we will want to show
+pub fn process_job_result(event: &Event) -> Result<(), Error> {
+    crate::dvm::process_job_result(event)
+}
@@ -213,6 +213,24 @@ pub async fn process_new_event(
             rumor_event = rumor.into_event_with_bad_signature();
             rumor_event.id = event.id; // Lie so it's handled with the giftwrap's id
             event = &rumor_event;
+
+            // Process with spam filter, now that we can see inside
+            if GLOBALS.db().read_setting_apply_spam_filter_on_dms() {
+                use crate::spam_filter::{filter_rumor, EventFilterAction};
+                // Remember the verdict so DM listings don't need to run the filter again
+                match filter_rumor(event, spamsafe) {
+                    EventFilterAction::Allow => {}
+                    EventFilterAction::Deny => {
+                        GLOBALS.db().mark_dm_spam(event.id, None)?;
+                        return Ok(());
+                    }
+                    EventFilterAction::MuteAuthor => {
+                        GLOBALS.db().mark_dm_spam(event.id, None)?;
+                        GLOBALS.people.mute(&event.pubkey, true, Private(false))?;
+                        return Ok(());
+                    }
+                }
+            }
         } else {
             // Not for us.
             return Ok(());
@@ -265,6 +283,11 @@ pub async fn process_new_event(
     let invalid_ids = process_relationships_of_event(event, None).await?;
     GLOBALS.ui_invalidate_notes(&invalid_ids);
 
+    // Labels and reports may change what the moderators hide
+    if event.kind == EventKind::Label || event.kind == EventKind::Reporting {
+        crate::moderation::clear_cache();
+    }
+
     if event.kind.is_feed_displayable() {
         process_feed_displayable_content(event, seen_on.as_ref(), now)?;
     }
@@ -287,8 +310,20 @@ pub async fn process_new_event(
         EventKind::DmRelayList => by_kind::process_dm_relay_list(event)?,
         EventKind::Repost => by_kind::process_repost(event, verify).await?,
         EventKind::NostrConnect => by_kind::process_nostr_connect(event, seen_on.clone()).await?,
+        EventKind::WalletResponse => by_kind::process_wallet_response(event)?,
         EventKind::UserServerList => by_kind::process_user_server_list(event, ours)?,
         EventKind::RequestToVanish => by_kind::process_request_to_vanish(event)?,
+        EventKind::ChannelCreation => by_kind::process_channel_creation(event)?,
+        EventKind::ChannelMetadata => by_kind::process_channel_metadata(event)?,
+        EventKind::ProfileBadges | EventKind::BadgeAward => by_kind::process_badges(event)?,
+        EventKind::CommunityDefinition => by_kind::process_community_definition(event)?,
+        EventKind::CommunityPostApproval => {
+            by_kind::process_community_post_approval(event, verify).await?
+        }
+        k if k.is_job_result() => by_kind::process_job_result(event)?,
+        k if k == crate::groups::group_metadata_kind() => {
+            by_kind::process_group_metadata(event, seen_on.as_ref())?
+        }
         _ => {}
     }
 
@@ -414,6 +449,8 @@ pub(crate) async fn process_relationships_of_event(
                     RelationshipById::Timestamps,
                     Some(txn),
                 )?;
+                // Check the timestamps again when the event is next shown
+                GLOBALS.timestamp_status.remove(&id);
             }
         }
     }
@@ -534,6 +571,7 @@ pub(crate) async fn process_relationships_of_event(
                     },
                     Some(txn),
                 )?;
+                invalidate.push(id);
             } else if let Ok(ParsedTag::Address { address, .. }) = tag.parse() {
                 GLOBALS.db().write_relationship_by_addr(
                     address,
@@ -652,6 +690,7 @@ pub(crate) async fn process_relationships_of_event(
                     RelationshipById::Reports(report.to_owned()),
                     Some(txn),
                 )?;
+                invalidate.push(id);
             }
         }
     }
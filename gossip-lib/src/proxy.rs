@@ -0,0 +1,164 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::RelayUrl;
+use percent_encoding::percent_decode_str;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tungstenite::handshake::client::{Request, Response};
+use tungstenite::protocol::WebSocketConfig;
+
+/// The proxy to use for HTTP fetches (media, NIP-05, LNURL, blossom, etc), or None
+/// to connect directly
+pub fn http_proxy() -> Option<String> {
+    let proxy = GLOBALS.db().read_setting_proxy();
+    let proxy = proxy.trim();
+    if proxy.is_empty() {
+        None
+    } else {
+        Some(proxy.to_owned())
+    }
+}
+
+/// The proxy to use for a relay, or None to connect directly.
+///
+/// Relays listed in the `relay_proxy_overrides` setting use the proxy given there
+/// (or "direct"), and all other relays use the `proxy` setting.
+pub fn relay_proxy(url: &RelayUrl) -> Option<String> {
+    let overrides = GLOBALS.db().read_setting_relay_proxy_overrides();
+    for line in overrides.lines() {
+        let mut parts = line.split_whitespace();
+        let (relay, proxy) = match (parts.next(), parts.next()) {
+            (Some(relay), Some(proxy)) => (relay, proxy),
+            _ => continue,
+        };
+        match RelayUrl::try_from_str(relay) {
+            Ok(relay) if relay == *url => {
+                if proxy.eq_ignore_ascii_case("direct") {
+                    return None;
+                } else {
+                    return Some(proxy.to_owned());
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    http_proxy()
+}
+
+/// Check that a proxy setting is something we can use
+pub fn validate_proxy(proxy: &str) -> Result<(), Error> {
+    Socks5Proxy::parse(proxy).map(|_| ())
+}
+
+/// A reqwest client builder that goes through the proxy, if any
+pub(crate) fn http_client_builder(proxy: Option<&str>) -> Result<reqwest::ClientBuilder, Error> {
+    let builder = reqwest::Client::builder();
+    match proxy {
+        None => Ok(builder),
+        Some(proxy) => {
+            let proxy = Socks5Proxy::parse(proxy)?;
+            Ok(builder.proxy(reqwest::Proxy::all(proxy.url.as_str())?))
+        }
+    }
+}
+
+/// Open a websocket to a relay, through the proxy if any
+pub(crate) async fn connect_websocket(
+    req: Request,
+    config: WebSocketConfig,
+    proxy: Option<&str>,
+) -> Result<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response), Error> {
+    let host = req.uri().host().unwrap_or_default().to_owned();
+
+    let proxy = match proxy {
+        Some(proxy) => Socks5Proxy::parse(proxy)?,
+        None => {
+            if host.ends_with(".onion") {
+                return Err(ErrorKind::Proxy(format!(
+                    "{host} is an onion service and can only be reached through a proxy such as Tor"
+                ))
+                .into());
+            }
+            return Ok(
+                tokio_tungstenite::connect_async_with_config(req, Some(config), false).await?,
+            );
+        }
+    };
+
+    let port = req
+        .uri()
+        .port_u16()
+        .unwrap_or(if req.uri().scheme_str() == Some("ws") {
+            80
+        } else {
+            443
+        });
+
+    // The target is always given to the proxy by name, so the proxy does the
+    // DNS lookup and we don't leak it.
+    let proxy_addr = (proxy.host.as_str(), proxy.port);
+    let target = (host.as_str(), port);
+    let socks_stream = match &proxy.auth {
+        Some((username, password)) => {
+            tokio_socks::tcp::Socks5Stream::connect_with_password(
+                proxy_addr, target, username, password,
+            )
+            .await
+        }
+        None => tokio_socks::tcp::Socks5Stream::connect(proxy_addr, target).await,
+    }
+    .map_err(|e| ErrorKind::Proxy(format!("{}:{}: {e}", proxy.host, proxy.port)))?;
+
+    // Once the SOCKS handshake is done the TCP stream is a plain tunnel to the relay
+    let stream = socks_stream.into_inner();
+
+    Ok(tokio_tungstenite::client_async_tls_with_config(req, stream, Some(config), None).await?)
+}
+
+struct Socks5Proxy {
+    // Always socks5h, so that reqwest lets the proxy resolve names
+    url: url::Url,
+    host: String,
+    port: u16,
+    auth: Option<(String, String)>,
+}
+
+impl Socks5Proxy {
+    fn parse(proxy: &str) -> Result<Socks5Proxy, Error> {
+        let mut url = url::Url::parse(proxy)?;
+        match url.scheme() {
+            "socks5" | "socks5h" => {}
+            scheme => {
+                return Err(ErrorKind::Proxy(format!(
+                    "Unsupported proxy scheme {scheme}, use socks5h://host:port"
+                ))
+                .into())
+            }
+        }
+        let _ = url.set_scheme("socks5h");
+
+        let host = match url.host_str() {
+            Some(host) => host.to_owned(),
+            None => return Err(ErrorKind::UrlHasNoHostname.into()),
+        };
+        let port = url.port().unwrap_or(1080);
+        // The username and password are percent-encoded in the URL
+        let decode = |s: &str| percent_decode_str(s).decode_utf8_lossy().into_owned();
+        let auth = if url.username().is_empty() {
+            None
+        } else {
+            Some((
+                decode(url.username()),
+                decode(url.password().unwrap_or_default()),
+            ))
+        };
+
+        Ok(Socks5Proxy {
+            url,
+            host,
+            port,
+            auth,
+        })
+    }
+}
@@ -10,6 +10,7 @@
 // relay::get_best_relays_with_score(pubkey, usage, score_factors) // for relay picker, and internal
 // relay::recommended_relay_hint(reply_to_id)?    // for a hint
 // relay::relays_for_seeking_replies(&event)?     // to find replies
+// relay::with_read_relays(relays)?              // a channel's, community's or repository's, and ours
 // relay::relays_to_post_to(&event)?              // where to post
 // future: get_all_pubkey_outboxes_for_batch_search(pubkey)?     // for seeker exhaustive search
 
@@ -119,6 +120,23 @@ pub fn recommended_relay_hint(reply_to: Id) -> Result<Option<RelayUrl>, Error> {
     Ok(None)
 }
 
+/// These relays followed by our read relays, without duplicates
+pub fn with_read_relays<I>(relays: I) -> Result<Vec<RelayUrl>, Error>
+where
+    I: IntoIterator<Item = RelayUrl>,
+{
+    let mut output: Vec<RelayUrl> = Vec::new();
+    for url in relays
+        .into_iter()
+        .chain(Relay::choose_relay_urls(Relay::READ, |_| true)?)
+    {
+        if !output.contains(&url) {
+            output.push(url);
+        }
+    }
+    Ok(output)
+}
+
 // Which relays are best for a reply to this event (used to find replies to this event)
 // FIXME this may go away once seeker uses 'sort relays' below, I'm not sure.
 pub fn relays_for_seeking_replies(event: &Event) -> Result<Vec<RelayUrl>, Error> {
@@ -0,0 +1,116 @@
+use crate::comms::RelayConnectionReason;
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::storage::{ScheduledPostsTable, Table};
+use nostr_types::{Event, Unixtime};
+
+pub type ScheduledPost = crate::storage::types::ScheduledPost1;
+
+/// The scheduled posts of the current account, soonest first
+pub fn scheduled_posts() -> Result<Vec<ScheduledPost>, Error> {
+    let author = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(vec![]),
+    };
+    let mut posts = ScheduledPostsTable::filter_records(|p| p.author == author)?;
+    posts.sort_by_key(|p| p.publish_at.0);
+    Ok(posts)
+}
+
+/// If we can sign a scheduled post now, without asking a remote signer
+pub(crate) fn can_sign_in_advance() -> bool {
+    GLOBALS.identity.has_private_key() && GLOBALS.identity.is_unlocked()
+}
+
+/// Sign the event for a scheduled post. It is dated at its publish time.
+pub(crate) async fn sign_scheduled_post(post: &ScheduledPost) -> Result<Event, Error> {
+    match GLOBALS.identity.public_key() {
+        Some(pk) if pk == post.author => {}
+        Some(_) => {
+            return Err(
+                ErrorKind::General("Scheduled post belongs to another account".to_owned()).into(),
+            )
+        }
+        None => return Err(ErrorKind::NoPublicKey.into()),
+    }
+
+    let mut prepared = crate::post::prepare_post_public(
+        post.author,
+        post.content.clone(),
+        post.tags.clone(),
+        post.in_reply_to,
+        post.annotation,
+        post.publish_at,
+    )
+    .await?;
+
+    match prepared.pop() {
+        Some((event, _)) => Ok(event),
+        None => Err(ErrorKind::General("Scheduled post prepared no event".to_owned()).into()),
+    }
+}
+
+/// Publish the scheduled posts of the current account whose time has come.
+///
+/// Posts of other accounts wait until that account is the current one again, as
+/// they go to that account's relays.
+pub(crate) async fn publish_due_posts() -> Result<(), Error> {
+    let author = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(()),
+    };
+
+    let now = Unixtime::now();
+    let due =
+        ScheduledPostsTable::filter_records(|p| p.author == author && p.publish_at.0 <= now.0)?;
+
+    for mut post in due {
+        let event = match post.event {
+            Some(ref event) => event.clone(),
+            None => {
+                // A local key has to be unlocked first. We try again next time.
+                if GLOBALS.identity.has_private_key() && !GLOBALS.identity.is_unlocked() {
+                    continue;
+                }
+                match sign_scheduled_post(&post).await {
+                    Ok(event) => {
+                        // Keep it, so a retry sends the same event
+                        post.event = Some(event.clone());
+                        ScheduledPostsTable::write_record(&mut post, None)?;
+                        event
+                    }
+                    Err(e) => {
+                        tracing::warn!("Could not sign scheduled post: {}", e);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        // If anything fails, the post stays scheduled and we try again next time
+        if let Err(e) = publish(event).await {
+            tracing::warn!("Could not publish scheduled post: {}", e);
+            continue;
+        }
+
+        // The outbox retries any relays that don't take it, so we are done with it
+        ScheduledPostsTable::delete_record(post.key, None)?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Published a scheduled post.".to_owned());
+    }
+
+    Ok(())
+}
+
+async fn publish(event: Event) -> Result<(), Error> {
+    // Process the event locally
+    crate::process::process_new_event(&event, None, None, false, false).await?;
+
+    let relay_urls = crate::relay::relays_to_post_to(&event)?;
+    crate::outbox::post_events(vec![event], relay_urls, RelayConnectionReason::PostEvent)?;
+
+    Ok(())
+}
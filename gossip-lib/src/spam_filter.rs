@@ -1,10 +1,46 @@
 use crate::globals::GLOBALS;
 use crate::people::PersonList;
 use crate::profile::Profile;
-use crate::storage::{PersonTable, Table};
+use crate::relationship::RelationshipById;
+use crate::storage::{FollowingsTable, PersonTable, Table};
 use nostr_types::{Event, EventKind, Id, PublicKey, Tag, Unixtime};
-use rhai::{CallFnOptions, Engine, Scope, AST};
+use rhai::{Array, CallFnOptions, Dynamic, Engine, Scope, AST};
+use std::collections::HashMap;
 use std::fs;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// The state of the spam filter script, for display
+#[derive(Debug, Clone, Default)]
+pub enum SpamFilterStatus {
+    /// There is no filter.rhai in the profile directory
+    #[default]
+    NotPresent,
+
+    /// The script compiled and is in use
+    Loaded(Unixtime),
+
+    /// The script failed to compile. If an earlier version had compiled, it is
+    /// still in use.
+    CompileError(String),
+}
+
+// The modification time of the script we last loaded (or tried to)
+static SCRIPT_MODIFIED: Mutex<Option<SystemTime>> = Mutex::new(None);
+
+// How many known people follow each person, for followers_known(). This is
+// rebuilt by a background task so that scripts never wait on the scan.
+static FOLLOWERS_KNOWN: Mutex<Option<HashMap<PublicKey, i64>>> = Mutex::new(None);
+
+// How many events we have by each person, for event_count_by(), remembered for
+// a while so that a busy author isn't recounted for every event
+static EVENT_COUNTS: Mutex<Option<(Instant, HashMap<PublicKey, i64>)>> = Mutex::new(None);
+const EVENT_COUNTS_STALE: Duration = Duration::from_secs(600);
+const EVENT_COUNTS_MAX_PEOPLE: usize = 10_000;
+
+// event_count_by() stops counting here. Scripts use it to tell new or quiet
+// accounts from established ones, so exact large counts don't matter.
+const EVENT_COUNT_LIMIT: usize = 1000;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum EventFilterAction {
@@ -19,6 +55,7 @@ pub enum EventFilterCaller {
     Thread,
     Inbox,
     Global,
+    DirectMessage,
 }
 
 #[derive(Debug, Clone)]
@@ -33,22 +70,91 @@ struct EventParams {
     pub spamsafe: bool,
 }
 
-pub fn load_script(engine: &Engine) -> Option<AST> {
+/// Create the engine for running the spam filter script, with the functions
+/// scripts can call registered
+pub(crate) fn build_engine() -> Engine {
+    let mut engine = Engine::new();
+
+    engine
+        .register_fn("is_followed", |pubkey: &str| -> bool {
+            match PublicKey::try_from_hex_string(pubkey, true) {
+                Ok(pubkey) => GLOBALS
+                    .people
+                    .is_person_in_list(&pubkey, PersonList::Followed),
+                Err(_) => false,
+            }
+        })
+        .register_fn("fof", |pubkey: &str| -> i64 {
+            match PublicKey::try_from_hex_string(pubkey, true) {
+                Ok(pubkey) => GLOBALS.db().read_fof(pubkey).unwrap_or(0) as i64,
+                Err(_) => 0,
+            }
+        })
+        .register_fn("relays_seen_on", |id: &str| -> Array {
+            match Id::try_from_hex_string(id) {
+                Ok(id) => GLOBALS
+                    .db()
+                    .get_event_seen_on_relay(id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(url, _)| Dynamic::from(url.as_str().to_owned()))
+                    .collect(),
+                Err(_) => Array::new(),
+            }
+        })
+        .register_fn("event_count_by", |pubkey: &str| -> i64 {
+            match PublicKey::try_from_hex_string(pubkey, true) {
+                Ok(pubkey) => event_count_by(pubkey),
+                Err(_) => 0,
+            }
+        })
+        .register_fn("followers_known", |pubkey: &str| -> i64 {
+            match PublicKey::try_from_hex_string(pubkey, true) {
+                Ok(pubkey) => followers_known(pubkey),
+                Err(_) => 0,
+            }
+        })
+        .register_fn("has_label", |id: &str, namespace: &str| -> bool {
+            match Id::try_from_hex_string(id) {
+                Ok(id) => GLOBALS
+                    .db()
+                    .find_relationships_by_id(id)
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|(_, rel)| match rel {
+                        RelationshipById::Labels { namespace: ns, .. } => ns == namespace,
+                        _ => false,
+                    }),
+                Err(_) => false,
+            }
+        })
+        .register_fn("rule", |name: &str, action: i64| -> i64 {
+            *GLOBALS.spam_filter_hits.entry(name.to_owned()).or_insert(0) += 1;
+            action
+        });
+
+    engine
+}
+
+/// Load and compile filter.rhai from the profile directory
+pub(crate) fn load_script(engine: &Engine) -> (Option<AST>, SpamFilterStatus) {
     let mut path = match Profile::profile_dir() {
         Ok(p) => p,
         Err(e) => {
             tracing::error!("Profile failed: {}", e);
-            return None;
+            return (None, SpamFilterStatus::NotPresent);
         }
     };
 
     path.push("filter.rhai");
 
+    *SCRIPT_MODIFIED.lock().unwrap() = fs::metadata(&path).and_then(|m| m.modified()).ok();
+
     let script = match fs::read_to_string(&path) {
         Ok(script) => script,
         Err(e) => {
             tracing::info!("No spam filter: {}", e);
-            return None;
+            return (None, SpamFilterStatus::NotPresent);
         }
     };
 
@@ -56,13 +162,87 @@ pub fn load_script(engine: &Engine) -> Option<AST> {
         Ok(ast) => ast,
         Err(e) => {
             tracing::error!("Failed to compile spam filter: {}", e);
-            return None;
+            return (None, SpamFilterStatus::CompileError(format!("{}", e)));
         }
     };
 
     tracing::info!("Spam filter loaded.");
 
-    Some(ast)
+    (Some(ast), SpamFilterStatus::Loaded(Unixtime::now()))
+}
+
+/// Reload filter.rhai if it has changed since we last loaded it.
+///
+/// If the new script fails to compile we keep using the old one.
+pub(crate) fn reload_script_if_changed() {
+    let path = match Profile::profile_dir() {
+        Ok(p) => p.join("filter.rhai"),
+        Err(_) => return,
+    };
+
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    if modified == *SCRIPT_MODIFIED.lock().unwrap() {
+        return;
+    }
+
+    let (ast, status) = load_script(&GLOBALS.spam_filter_engine);
+    match status {
+        SpamFilterStatus::CompileError(_) => {}
+        _ => {
+            *GLOBALS.spam_filter.write() = ast;
+            GLOBALS.spam_filter_hits.clear();
+        }
+    }
+    *GLOBALS.spam_filter_status.write() = status;
+}
+
+fn followers_known(pubkey: PublicKey) -> i64 {
+    match &*FOLLOWERS_KNOWN.lock().unwrap() {
+        Some(map) => map.get(&pubkey).copied().unwrap_or(0),
+        None => 0,
+    }
+}
+
+/// Recount how many known people follow each person, for the spam filter's
+/// followers_known(). This scans every contact list we have, so it runs in the
+/// background now and then.
+pub(crate) fn rebuild_followers_known() {
+    let mut map: HashMap<PublicKey, i64> = HashMap::new();
+    if let Ok(txn) = GLOBALS.db().get_read_txn() {
+        if let Ok(iter) = FollowingsTable::iter(&txn) {
+            for following in iter {
+                for followed in following.followed.iter() {
+                    *map.entry(*followed).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    *FOLLOWERS_KNOWN.lock().unwrap() = Some(map);
+}
+
+fn event_count_by(pubkey: PublicKey) -> i64 {
+    let mut guard = EVENT_COUNTS.lock().unwrap();
+
+    let stale = match &*guard {
+        Some((when, map)) => {
+            when.elapsed() > EVENT_COUNTS_STALE || map.len() >= EVENT_COUNTS_MAX_PEOPLE
+        }
+        None => true,
+    };
+    if stale {
+        *guard = Some((Instant::now(), HashMap::new()));
+    }
+
+    let map = match &mut *guard {
+        Some((_, map)) => map,
+        None => return 0,
+    };
+    *map.entry(pubkey).or_insert_with(|| {
+        GLOBALS
+            .db()
+            .count_events_by_author(pubkey, EVENT_COUNT_LIMIT)
+            .unwrap_or(0) as i64
+    })
 }
 
 pub fn filter_event(event: Event, caller: EventFilterCaller, spamsafe: bool) -> EventFilterAction {
@@ -70,12 +250,11 @@ pub fn filter_event(event: Event, caller: EventFilterCaller, spamsafe: bool) ->
     let id = event.id;
     let pow = event.pow();
 
-    if GLOBALS.spam_filter.is_none() {
+    if GLOBALS.spam_filter.read().is_none() {
         EventFilterAction::Allow
     } else if event.kind == EventKind::GiftWrap {
         // Spam filtering is NOT async and we cannot unwrap GiftWrap events using a
-        // remote signer.  So we simply dont' apply spam filtering to GiftWraps
-        // anymore.  FIXME Ww could post-apply it somehow outside of this function.
+        // remote signer here.  Callers unwrap them first and use filter_rumor().
         EventFilterAction::Allow
     } else {
         let event_params = EventParams {
@@ -92,6 +271,32 @@ pub fn filter_event(event: Event, caller: EventFilterCaller, spamsafe: bool) ->
     }
 }
 
+/// Filter the rumor of an unwrapped GiftWrap.
+///
+/// `rumor` is the rumor as an event (see `Rumor::into_event_with_bad_signature`).
+/// Rumors we authored ourselves are always allowed.
+pub fn filter_rumor(rumor: &Event, spamsafe: bool) -> EventFilterAction {
+    if GLOBALS.spam_filter.read().is_none() {
+        return EventFilterAction::Allow;
+    }
+
+    if Some(rumor.pubkey) == GLOBALS.identity.public_key() {
+        return EventFilterAction::Allow;
+    }
+
+    let event_params = EventParams {
+        id: rumor.id,
+        pubkey: rumor.pubkey,
+        kind: rumor.kind,
+        content: rumor.content.clone(),
+        tags: rumor.tags.clone(),
+        pow: rumor.pow(),
+        caller: EventFilterCaller::DirectMessage,
+        spamsafe,
+    };
+    inner_filter(event_params)
+}
+
 fn inner_filter(event_params: EventParams) -> EventFilterAction {
     let EventParams {
         id,
@@ -104,8 +309,8 @@ fn inner_filter(event_params: EventParams) -> EventFilterAction {
         spamsafe,
     } = event_params;
 
-    // Only apply to feed-displayable events
-    if !kind.is_feed_displayable() {
+    // Only apply to feed-displayable events (and the rumors of DMs, which are not)
+    if !kind.is_feed_displayable() && !matches!(caller, EventFilterCaller::DirectMessage) {
         return EventFilterAction::Allow;
     }
 
@@ -178,7 +383,8 @@ fn inner_filter(event_params: EventParams) -> EventFilterAction {
 
 fn filter_with_script(mut scope: Scope) -> EventFilterAction {
     // Get the pre-computed AST
-    let ast = match &GLOBALS.spam_filter {
+    let ast_guard = GLOBALS.spam_filter.read();
+    let ast = match &*ast_guard {
         Some(ast) => ast,
         None => return EventFilterAction::Allow,
     };
@@ -0,0 +1,322 @@
+use crate::error::{Error, ErrorKind};
+use crate::storage::{RawDatabase, Storage};
+use heed::{types::Bytes, DatabaseFlags, RoTxn, RwTxn};
+use nostr_types::{Event, EventKind, Id};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Word -> (Id, Count)
+// (dup keys, so multiple Ids per word)
+//   key: key!(word.as_bytes())
+//   val: id.as_slice() ++ count.to_be_bytes() | Id(val[0..32].try_into()?), u16::from_be_bytes(val[32..34].try_into()?)
+
+static EVENT_WORDS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut EVENT_WORDS1_DB: Option<RawDatabase> = None;
+
+/// Words shorter than this (in chars) are not indexed
+const MIN_WORD_CHARS: usize = 2;
+
+/// Words longer than this (in bytes) are not indexed. These are almost always
+/// bech32 strings, hex, base64 or other junk that nobody searches for.
+const MAX_WORD_BYTES: usize = 40;
+
+/// Tags whose values are indexed along with the content
+const INDEXED_TEXT_TAGS: [&str; 5] = ["subject", "title", "summary", "alt", "t"];
+
+/// Split text into lowercased words, in order, as they are indexed.
+///
+/// Words are maximal runs of alphanumeric characters. Words that are too short
+/// or too long to be useful are dropped.
+pub(crate) fn split_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= MIN_WORD_CHARS && w.len() <= MAX_WORD_BYTES)
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Whether the text of events of this kind belongs in the word index.
+///
+/// We index every feed displayable kind regardless of the user's current settings
+/// (these are filtered at search time) but never index anything that is encrypted
+/// or private.
+pub(crate) fn is_word_indexed_kind(kind: EventKind) -> bool {
+    kind.is_feed_displayable()
+        && kind != EventKind::EncryptedDirectMessage
+        && kind != EventKind::DmChat
+        && kind != EventKind::GiftWrap
+}
+
+/// All the words of an event, in order. This is the content followed by the
+/// values of descriptive tags.
+pub(crate) fn event_words(event: &Event) -> Vec<String> {
+    let mut words = split_words(&event.content);
+    for tag in &event.tags {
+        if INDEXED_TEXT_TAGS.contains(&tag.tagname()) {
+            words.extend(split_words(tag.get_index(1)));
+        }
+    }
+    words
+}
+
+/// A parsed search query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SearchQuery {
+    /// Distinct words that must all be present
+    pub words: Vec<String>,
+
+    /// Phrases (from double-quoted sections) whose words must appear in order
+    pub phrases: Vec<Vec<String>>,
+
+    /// The last word of the query, when it is not quoted, only has to be the
+    /// start of a word (so "gos" finds "gossip")
+    pub prefix: Option<String>,
+}
+
+impl SearchQuery {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.words.is_empty() && self.prefix.is_none()
+    }
+}
+
+/// Parse a search query
+pub(crate) fn parse_search_query(text: &str) -> SearchQuery {
+    let mut words: Vec<String> = Vec::new();
+    let mut phrases: Vec<Vec<String>> = Vec::new();
+    let parts: Vec<&str> = text.split('"').collect();
+    let mut prefix: Option<String> = None;
+    for (n, part) in parts.iter().enumerate() {
+        let mut part_words = split_words(part);
+        // Odd parts are inside quotes
+        if n % 2 == 1 {
+            if part_words.len() > 1 {
+                phrases.push(part_words.clone());
+            }
+        } else if n == parts.len() - 1 {
+            prefix = part_words.pop();
+        }
+        words.extend(part_words);
+    }
+    words.sort();
+    words.dedup();
+    SearchQuery {
+        words,
+        phrases,
+        prefix,
+    }
+}
+
+/// The words of an event with the number of times each occurs
+fn event_word_counts(event: &Event) -> HashMap<String, u16> {
+    let mut counts: HashMap<String, u16> = HashMap::new();
+    for word in event_words(event) {
+        counts
+            .entry(word)
+            .and_modify(|c| *c = c.saturating_add(1))
+            .or_insert(1);
+    }
+    counts
+}
+
+fn word_val(id: Id, count: u16) -> [u8; 34] {
+    let mut val = [0u8; 34];
+    val[0..32].copy_from_slice(id.as_slice());
+    val[32..34].copy_from_slice(&count.to_be_bytes());
+    val
+}
+
+impl Storage {
+    pub(super) fn db_event_words1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = EVENT_WORDS1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = EVENT_WORDS1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = EVENT_WORDS1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    .flags(DatabaseFlags::DUP_SORT | DatabaseFlags::DUP_FIXED)
+                    .name("event_words1")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                EVENT_WORDS1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn get_event_words1_size(&self) -> Result<usize, Error> {
+        let txn = self.env.read_txn()?;
+        let stat = self.db_event_words1()?.stat(&txn)?;
+        Ok(stat.page_size as usize
+            * (stat.branch_pages + stat.leaf_pages + stat.overflow_pages + 2))
+    }
+
+    pub(crate) fn write_event_words1<'a>(
+        &'a self,
+        event: &Event,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        if !is_word_indexed_kind(event.kind) {
+            return Ok(());
+        }
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        for (word, count) in event_word_counts(event).iter() {
+            let key = key!(word.as_bytes());
+            self.db_event_words1()?
+                .put(txn, key, &word_val(event.id, *count))?;
+        }
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    pub(crate) fn delete_event_words1<'a>(
+        &'a self,
+        event: &Event,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        if !is_word_indexed_kind(event.kind) {
+            return Ok(());
+        }
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        // The values are fully determined by the event, so we can delete
+        // the exact duplicates without scanning.
+        for (word, count) in event_word_counts(event).iter() {
+            let key = key!(word.as_bytes());
+            self.db_event_words1()?
+                .delete_one_duplicate(txn, key, &word_val(event.id, *count))?;
+        }
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    /// Get the events containing a word, with the number of times it occurs in each.
+    pub(crate) fn get_event_ids_with_word1(
+        &self,
+        word: &str,
+        txn: &RoTxn<'_>,
+    ) -> Result<HashMap<Id, u16>, Error> {
+        let key = key!(word.as_bytes());
+        if key.is_empty() {
+            return Err(ErrorKind::Empty("word".to_owned()).into());
+        }
+        let mut output: HashMap<Id, u16> = HashMap::new();
+        let iter = match self.db_event_words1()?.get_duplicates(txn, key)? {
+            Some(i) => i,
+            None => return Ok(output),
+        };
+        for result in iter {
+            let (_key, val) = result?;
+            let id = Id(val[0..32].try_into()?);
+            let count = u16::from_be_bytes(val[32..34].try_into()?);
+            output.insert(id, count);
+        }
+        Ok(output)
+    }
+
+    /// Get the events containing a word that starts with the prefix, with the
+    /// number of times such words occur in each.
+    pub(crate) fn get_event_ids_with_word_prefix1(
+        &self,
+        prefix: &str,
+        txn: &RoTxn<'_>,
+    ) -> Result<HashMap<Id, u16>, Error> {
+        let key = key!(prefix.as_bytes());
+        if key.is_empty() {
+            return Err(ErrorKind::Empty("word".to_owned()).into());
+        }
+        let mut output: HashMap<Id, u16> = HashMap::new();
+        for result in self.db_event_words1()?.prefix_iter(txn, key)? {
+            let (_key, val) = result?;
+            let id = Id(val[0..32].try_into()?);
+            let count = u16::from_be_bytes(val[32..34].try_into()?);
+            output
+                .entry(id)
+                .and_modify(|c| *c = c.saturating_add(count))
+                .or_insert(count);
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_words() {
+        let words = split_words("Hello, World! I'm   testing gossip-lib's search_index (v2).");
+        assert_eq!(
+            words,
+            vec!["hello", "world", "testing", "gossip", "lib", "search", "index", "v2"]
+        );
+
+        // Unicode is lowercased and kept together
+        assert_eq!(split_words("Ünïcode WÖRTER"), vec!["ünïcode", "wörter"]);
+
+        // Over-long junk is dropped
+        let npub = "npub1acg6thl5psv62405rljzkj8spesceyfz2c32udakc2ak0dmvfeyse9p35c";
+        assert_eq!(split_words(&format!("see {npub}")), vec!["see"]);
+    }
+
+    #[test]
+    fn test_parse_search_query() {
+        let query = parse_search_query(r#"relay "outbox model" gossip relay"#);
+        assert_eq!(query.words, vec!["gossip", "model", "outbox", "relay"]);
+        assert_eq!(
+            query.phrases,
+            vec![vec!["outbox".to_owned(), "model".to_owned()]]
+        );
+        assert_eq!(query.prefix, None);
+
+        // An unterminated quote still counts as a phrase
+        let query = parse_search_query(r#"say "hello world"#);
+        assert_eq!(query.words, vec!["hello", "say", "world"]);
+        assert_eq!(
+            query.phrases,
+            vec![vec!["hello".to_owned(), "world".to_owned()]]
+        );
+        assert_eq!(query.prefix, None);
+    }
+
+    #[test]
+    fn test_parse_search_query_prefix() {
+        // The last unquoted word is a prefix
+        let query = parse_search_query("gos");
+        assert!(query.words.is_empty());
+        assert_eq!(query.prefix.as_deref(), Some("gos"));
+        assert!(!query.is_empty());
+
+        let query = parse_search_query(r#""outbox model" Gossi"#);
+        assert_eq!(query.words, vec!["model", "outbox"]);
+        assert_eq!(query.prefix.as_deref(), Some("gossi"));
+
+        // Only the last word
+        let query = parse_search_query("relay gos");
+        assert_eq!(query.words, vec!["relay"]);
+        assert_eq!(query.prefix.as_deref(), Some("gos"));
+
+        // Too short to be indexed
+        assert!(parse_search_query("g").is_empty());
+    }
+}
@@ -89,6 +89,8 @@ impl Storage {
                 } // upstream bug
                 self.add_hashtag(&hashtag, event.id, Some(txn))?;
             }
+
+            self.write_event_words(event, Some(txn))?;
         }
 
         maybe_local_txn_commit!(local_txn);
@@ -0,0 +1,50 @@
+use super::types::Group1;
+use super::Table;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use heed::types::Bytes;
+use heed::Database;
+use std::sync::Mutex;
+
+static GROUPS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut GROUPS1_DB: Option<Database<Bytes, Bytes>> = None;
+
+pub struct Groups1Table {}
+
+impl Table for Groups1Table {
+    type Item = Group1;
+
+    fn lmdb_name() -> &'static str {
+        "groups1"
+    }
+
+    fn db() -> Result<Database<Bytes, Bytes>, Error> {
+        unsafe {
+            if let Some(db) = GROUPS1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = GROUPS1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = GROUPS1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = GLOBALS.db().env.write_txn()?;
+                let db = GLOBALS
+                    .db()
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    .name(Self::lmdb_name())
+                    .create(&mut txn)?;
+                txn.commit()?;
+                GROUPS1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use super::types::HiddenBadge1;
+use super::Table;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use heed::types::Bytes;
+use heed::Database;
+use std::sync::Mutex;
+
+static HIDDEN_BADGES1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut HIDDEN_BADGES1_DB: Option<Database<Bytes, Bytes>> = None;
+
+pub struct HiddenBadges1Table {}
+
+impl Table for HiddenBadges1Table {
+    type Item = HiddenBadge1;
+
+    fn lmdb_name() -> &'static str {
+        "hidden_badges1"
+    }
+
+    fn db() -> Result<Database<Bytes, Bytes>, Error> {
+        unsafe {
+            if let Some(db) = HIDDEN_BADGES1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = HIDDEN_BADGES1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = HIDDEN_BADGES1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = GLOBALS.db().env.write_txn()?;
+                let db = GLOBALS
+                    .db()
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    .name(Self::lmdb_name())
+                    .create(&mut txn)?;
+                txn.commit()?;
+                HIDDEN_BADGES1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m49_trigger(&self) -> Result<(), Error> {
+        self.db_event_words1()?;
+        Ok(())
+    }
+
+    pub(super) fn m49_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Flagging that indexes need to be rebuilt...");
+
+        // Rebuild indexes, which builds the new word index
+        self.set_flag_rebuild_indexes_needed(true, Some(txn))?;
+
+        Ok(())
+    }
+}
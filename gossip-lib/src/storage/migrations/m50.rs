@@ -0,0 +1,23 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m50_trigger(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    pub(super) fn m50_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Flagging that indexes need to be rebuilt...");
+
+        // Rebuild indexes, which indexes channel messages by kind
+        self.set_flag_rebuild_indexes_needed(true, Some(txn))?;
+
+        Ok(())
+    }
+}
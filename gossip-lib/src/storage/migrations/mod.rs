@@ -26,6 +26,9 @@ mod m45;
 mod m46;
 mod m47;
 mod m48;
+mod m49;
+mod m50;
+mod m51;
 
 use super::Storage;
 use crate::error::{Error, ErrorKind};
@@ -33,7 +36,7 @@ use heed::RwTxn;
 
 impl Storage {
     const MIN_MIGRATION_LEVEL: u32 = 25;
-    const MAX_MIGRATION_LEVEL: u32 = 48;
+    const MAX_MIGRATION_LEVEL: u32 = 51;
 
     /// Initialize the database from empty
     pub(super) async fn init_from_empty(&self) -> Result<(), Error> {
@@ -111,6 +114,9 @@ impl Storage {
             46 => self.m46_trigger()?,
             47 => self.m47_trigger()?,
             48 => self.m48_trigger()?,
+            49 => self.m49_trigger()?,
+            50 => self.m50_trigger()?,
+            51 => self.m51_trigger()?,
             _ => panic!("Unreachable migration level"),
         }
 
@@ -144,6 +150,9 @@ impl Storage {
             46 => self.m46_migrate(&prefix, txn)?,
             47 => self.m47_migrate(&prefix, txn)?,
             48 => self.m48_migrate(&prefix, txn)?,
+            49 => self.m49_migrate(&prefix, txn)?,
+            50 => self.m50_migrate(&prefix, txn)?,
+            51 => self.m51_migrate(&prefix, txn)?,
             _ => panic!("Unreachable migration level"),
         };
 
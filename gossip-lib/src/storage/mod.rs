@@ -22,6 +22,39 @@ pub mod followings_table;
 pub use followings_table::FollowingsTable;
 pub mod handlers_table;
 pub use handlers_table::HandlersTable;
+pub mod outbox1_table;
+pub use outbox1_table::Outbox1Table;
+pub type OutboxTable = Outbox1Table;
+pub mod scheduled_posts1_table;
+pub use scheduled_posts1_table::ScheduledPosts1Table;
+pub type ScheduledPostsTable = ScheduledPosts1Table;
+pub mod drafts1_table;
+pub use drafts1_table::Drafts1Table;
+pub type DraftsTable = Drafts1Table;
+pub mod accounts1_table;
+pub use accounts1_table::Accounts1Table;
+pub type AccountsTable = Accounts1Table;
+pub mod channels1_table;
+pub use channels1_table::Channels1Table;
+pub type ChannelsTable = Channels1Table;
+pub mod groups1_table;
+pub use groups1_table::Groups1Table;
+pub type GroupsTable = Groups1Table;
+pub mod repositories1_table;
+pub use repositories1_table::Repositories1Table;
+pub type RepositoriesTable = Repositories1Table;
+pub mod communities1_table;
+pub use communities1_table::Communities1Table;
+pub type CommunitiesTable = Communities1Table;
+pub mod block_headers1_table;
+pub use block_headers1_table::BlockHeaders1Table;
+pub type BlockHeadersTable = BlockHeaders1Table;
+pub mod timestamp_requests1_table;
+pub use timestamp_requests1_table::TimestampRequests1Table;
+pub type TimestampRequestsTable = TimestampRequests1Table;
+pub mod hidden_badges1_table;
+pub use hidden_badges1_table::HiddenBadges1Table;
+pub type HiddenBadgesTable = HiddenBadges1Table;
 
 // database implementations
 mod configured_handlers;
@@ -35,6 +68,7 @@ mod event_seen_on_relay1;
 mod event_tci_index;
 use event_tci_index::TciKey;
 mod event_viewed1;
+mod event_words1;
 mod events3;
 mod fof;
 mod general;
@@ -53,6 +87,7 @@ mod relationships_by_id1;
 mod relationships_by_id2;
 mod relays2;
 mod relays3;
+mod spam_dms1;
 mod unindexed_giftwraps1;
 mod versioned;
 
@@ -66,6 +101,7 @@ use crate::person_relay::PersonRelay;
 use crate::profile::Profile;
 use crate::relationship::{RelationshipByAddr, RelationshipById};
 use crate::relay::Relay;
+use crate::wallet::WalletConnection;
 use dashmap::DashMap;
 use filetime::FileTime;
 use heed::types::{Bytes, Unit};
@@ -120,7 +156,10 @@ impl Storage {
         }
 
         // builder.max_readers(126); // this is the default
-        builder.max_dbs(32);
+
+        // Every table and index is a named database, including old ones that are
+        // still opened for migrations. There are more than 32 of them now.
+        builder.max_dbs(64);
 
         // This has to be big enough for all the data.
         // Note that it is the size of the map in VIRTUAL address space,
@@ -249,12 +288,14 @@ impl Storage {
         self.db_events()?;
         self.db_event_seen_on_relay()?;
         self.db_event_viewed()?;
+        self.db_event_words()?;
         self.db_hashtags()?;
         self.db_nip46servers()?;
         self.db_person_relays()?;
         self.db_relationships_by_id()?;
         self.db_relationships_by_addr()?;
         self.db_relays()?;
+        self.db_spam_dms()?;
         self.db_unindexed_giftwraps()?;
         self.db_person_lists()?;
         self.db_person_lists_metadata()?;
@@ -263,6 +304,17 @@ impl Storage {
         PersonTable::db()?;
         FollowingsTable::db()?;
         HandlersTable::db()?;
+        OutboxTable::db()?;
+        ScheduledPostsTable::db()?;
+        DraftsTable::db()?;
+        AccountsTable::db()?;
+        ChannelsTable::db()?;
+        GroupsTable::db()?;
+        RepositoriesTable::db()?;
+        CommunitiesTable::db()?;
+        BlockHeadersTable::db()?;
+        TimestampRequestsTable::db()?;
+        HiddenBadgesTable::db()?;
 
         // Do migrations
         match self.read_migration_level()? {
@@ -313,6 +365,16 @@ impl Storage {
         self.db_event_viewed1()
     }
 
+    #[inline]
+    pub(crate) fn db_event_words(&self) -> Result<RawDatabase, Error> {
+        self.db_event_words1()
+    }
+
+    #[inline]
+    pub(crate) fn db_spam_dms(&self) -> Result<RawDatabase, Error> {
+        self.db_spam_dms1()
+    }
+
     #[inline]
     pub(crate) fn db_hashtags(&self) -> Result<RawDatabase, Error> {
         self.db_hashtags1()
@@ -381,6 +443,18 @@ impl Storage {
         self.get_event_viewed1_size()
     }
 
+    /// The number of bytes in the spam_dms table
+    #[inline]
+    pub fn get_spam_dms_size(&self) -> Result<usize, Error> {
+        self.get_spam_dms1_size()
+    }
+
+    /// The number of bytes in the event_words table
+    #[inline]
+    pub fn get_event_words_size(&self) -> Result<usize, Error> {
+        self.get_event_words1_size()
+    }
+
     /// The number of bytes in the hashtags table
     pub fn get_hashtags_size(&self) -> Result<usize, Error> {
         let txn = self.env.read_txn()?;
@@ -584,6 +658,49 @@ impl Storage {
         }
     }
 
+    /// Write the NIP-47 wallet connection of an account (or remove it, if None)
+    pub fn write_wallet_connection<'a>(
+        &'a self,
+        account: PublicKey,
+        connection: Option<&WalletConnection>,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = wallet_connection_key(account);
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        match connection {
+            Some(connection) => {
+                let bytes = serde_json::to_vec(connection)?;
+                self.db_general()?.put(txn, &key, bytes.as_slice())?;
+            }
+            None => {
+                self.db_general()?.delete(txn, &key)?;
+            }
+        }
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    /// Read the NIP-47 wallet connection of an account
+    pub fn read_wallet_connection(
+        &self,
+        account: PublicKey,
+    ) -> Result<Option<WalletConnection>, Error> {
+        let key = wallet_connection_key(account);
+        let txn = self.env.read_txn()?;
+
+        match self.db_general()?.get(&txn, &key)? {
+            None => Ok(None),
+            Some(bytes) => {
+                let connection: WalletConnection = serde_json::from_slice(bytes)?;
+                Ok(Some(connection))
+            }
+        }
+    }
+
     /// Write NIP-46 unconnected server
     #[allow(dead_code)]
     pub fn write_nip46_unconnected_server<'a>(
@@ -688,15 +805,48 @@ impl Storage {
         bool,
         false
     );
+    def_setting!(proxy, b"proxy", String, "".to_string());
+    def_setting!(
+        relay_proxy_overrides,
+        b"relay_proxy_overrides",
+        String,
+        "".to_string()
+    );
     def_setting!(num_relays_per_person, b"num_relays_per_person", u8, 2);
     def_setting!(max_relays, b"max_relays", u8, 50);
     def_setting!(num_relays_for_counting, b"num_relays_for_counting", u8, 15);
+    def_setting!(
+        count_replies_and_reactions,
+        b"count_replies_and_reactions",
+        bool,
+        true
+    );
+    def_setting!(negentropy_sync, b"negentropy_sync", bool, true);
     def_setting!(load_more_count, b"load_more_count", u64, 35);
     def_setting!(reposts, b"reposts", bool, true);
     def_setting!(show_long_form, b"show_long_form", bool, false);
     def_setting!(show_mentions, b"show_mentions", bool, true);
     def_setting!(enable_picture_events, b"enable_picture_events", bool, true);
     def_setting!(enable_comments, b"enable_comments", bool, false);
+    def_setting!(enable_calendar, b"enable_calendar", bool, false);
+    def_setting!(
+        enable_public_channels,
+        b"enable_public_channels",
+        bool,
+        false
+    );
+    def_setting!(enable_relay_groups, b"enable_relay_groups", bool, false);
+    def_setting!(
+        enable_git_repositories,
+        b"enable_git_repositories",
+        bool,
+        false
+    );
+    def_setting!(enable_moderation, b"enable_moderation", bool, false);
+    def_setting!(enable_communities, b"enable_communities", bool, false);
+    def_setting!(enable_dvms, b"enable_dvms", bool, false);
+    def_setting!(enable_timestamps, b"enable_timestamps", bool, false);
+    def_setting!(enable_wallet, b"enable_wallet", bool, false);
     def_setting!(direct_messages, b"direct_messages", bool, true);
     def_setting!(
         future_allowance_secs,
@@ -914,8 +1064,27 @@ impl Storage {
         bool,
         false
     );
+    def_setting!(
+        apply_spam_filter_on_dms,
+        b"apply_spam_filter_on_dms",
+        bool,
+        true
+    );
     def_setting!(blossom_servers, b"blossom_servers", String, "".to_string());
+    def_setting!(nip96_servers, b"nip96_servers", String, "".to_string());
     def_setting!(undo_send_seconds, b"undo_send_seconds", u64, 10);
+    def_setting!(
+        ots_calendar,
+        b"ots_calendar",
+        String,
+        "https://alice.btc.calendar.opentimestamps.org".to_string()
+    );
+    def_setting!(
+        ots_header_source,
+        b"ots_header_source",
+        String,
+        "".to_string()
+    );
 
     // -------------------------------------------------------------------
 
@@ -1047,6 +1216,18 @@ impl Storage {
         self.is_event_viewed1(id)
     }
 
+    /// Mark a giftwrap as one whose rumor the spam filter rejected
+    #[inline]
+    pub fn mark_dm_spam<'a>(&'a self, id: Id, rw_txn: Option<&mut RwTxn<'a>>) -> Result<(), Error> {
+        self.mark_dm_spam1(id, rw_txn)
+    }
+
+    /// Did the spam filter reject the rumor inside this giftwrap?
+    #[inline]
+    pub fn is_dm_spam(&self, id: Id) -> Result<bool, Error> {
+        self.is_dm_spam1(id)
+    }
+
     /// Associate a hashtag to an event
     #[inline]
     pub fn add_hashtag<'a>(
@@ -1065,6 +1246,26 @@ impl Storage {
         self.get_event_ids_with_hashtag1(hashtag)
     }
 
+    /// Index the words of an event for full text search
+    #[inline]
+    pub(crate) fn write_event_words<'a>(
+        &'a self,
+        event: &Event,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_event_words1(event, rw_txn)
+    }
+
+    /// Remove the words of an event from the full text search index
+    #[inline]
+    pub(crate) fn delete_event_words<'a>(
+        &'a self,
+        event: &Event,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_event_words1(event, rw_txn)
+    }
+
     /// Write a relay record.
     ///
     /// NOTE: this overwrites. You may wish to read first, or you might prefer
@@ -1462,6 +1663,16 @@ impl Storage {
         let mut local_txn = None;
         let txn = maybe_local_txn!(self, rw_txn, local_txn);
 
+        // Delete from event_words. The words are in the key, so we need
+        // the event to know which entries to delete.
+        let maybe_event = match self.db_events()?.get(txn, id.as_slice())? {
+            Some(bytes) => Some(Event::read_from_buffer(bytes)?),
+            None => None,
+        };
+        if let Some(event) = maybe_event {
+            self.delete_event_words(&event, Some(txn))?;
+        }
+
         // Delete from the events table
         self.delete_event3(id, Some(txn))?;
 
@@ -1487,6 +1698,9 @@ impl Storage {
         // Delete from event_viewed
         self.db_event_viewed()?.delete(txn, id.as_slice())?;
 
+        // Delete from spam_dms
+        self.db_spam_dms()?.delete(txn, id.as_slice())?;
+
         // DO NOT delete from relationships. The related event still applies in case
         // this event comes back, ESPECIALLY deletion relationships!
 
@@ -1823,9 +2037,137 @@ impl Storage {
         Ok(output.into_iter().rev().take(limit).collect())
     }
 
-    /// Search all events for the text, case insensitive. Both content and tags
-    /// are searched.
+    /// Count the events we have by the author (from the event_akci_index), up
+    /// to `limit`
+    pub fn count_events_by_author(&self, author: PublicKey, limit: usize) -> Result<usize, Error> {
+        let txn = self.env.read_txn()?;
+        let mut count: usize = 0;
+        for result in self
+            .db_event_akci_index()?
+            .prefix_iter(&txn, author.as_slice())?
+            .take(limit)
+        {
+            let _ = result?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Get the created_at and id of up to `limit` of the newest events of each
+    /// author-kind pair, created at or before `until`.
+    ///
+    /// This only reads the event_akci_index, not the events themselves, so it is
+    /// cheap enough to build negentropy sets from.
+    pub fn get_recent_event_items(
+        &self,
+        authors: &[PublicKey],
+        kinds: &[EventKind],
+        until: Unixtime,
+        limit: usize,
+    ) -> Result<Vec<(Unixtime, Id)>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(Unixtime, Id)> = Vec::new();
+        for author in authors {
+            for kind in kinds {
+                let start_prefix = AkciKey::from_parts(*author, *kind, until, Id([0; 32]));
+                let end_prefix = AkciKey::from_parts(*author, *kind, Unixtime(0), Id([255; 32]));
+                let range = (
+                    Bound::Included(start_prefix.as_slice()),
+                    Bound::Included(end_prefix.as_slice()),
+                );
+                for result in self.db_event_akci_index()?.range(&txn, &range)?.take(limit) {
+                    let (keybytes, _) = result?;
+                    let (_, _, created_at, id) = AkciKey::from_bytes(keybytes)?.into_parts()?;
+                    output.push((created_at, id));
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Search events for the text, case insensitive, using the word index.
+    ///
+    /// Every word must be present, except that the last word (if not quoted)
+    /// only has to start a word. Double-quoted parts of the text are phrases
+    /// whose words must also appear consecutively. Results are ranked by how
+    /// rare the words are and how often they occur, then newest first.
+    ///
+    /// If the text has no indexable words, all events are scanned instead.
     pub fn search_events(&self, text: &str) -> Result<Vec<Event>, Error> {
+        let query = event_words1::parse_search_query(text);
+        if query.is_empty() {
+            return self.scan_events_for_text(text);
+        }
+        let phrases = query.phrases;
+
+        let event_kinds = crate::feed::feed_displayable_event_kinds(true);
+
+        let txn = self.env.read_txn()?;
+        let total = self.db_events()?.stat(&txn)?.entries as f64;
+
+        // Rarest word first, so it drives the candidate set
+        let mut postings: Vec<HashMap<Id, u16>> = Vec::with_capacity(query.words.len() + 1);
+        for word in &query.words {
+            postings.push(self.get_event_ids_with_word1(word, &txn)?);
+        }
+        if let Some(prefix) = &query.prefix {
+            postings.push(self.get_event_ids_with_word_prefix1(prefix, &txn)?);
+        }
+        postings.sort_by_key(|p| p.len());
+
+        let mut scored: Vec<(f64, Event)> = Vec::new();
+        'candidates: for id in postings[0].keys() {
+            let mut score: f64 = 0.0;
+            for posting in &postings {
+                let count = match posting.get(id) {
+                    Some(c) => *c as f64,
+                    None => continue 'candidates,
+                };
+                let idf = (1.0 + total / posting.len() as f64).ln();
+                score += idf * count / (count + 1.2);
+            }
+
+            let bytes = match self.db_events()?.get(&txn, id.as_slice())? {
+                Some(bytes) => bytes,
+                None => continue, // stale index entry
+            };
+
+            // event kind must match
+            match Event::get_kind_from_speedy_bytes(bytes) {
+                Some(kind) if event_kinds.contains(&kind) => (),
+                _ => continue,
+            }
+
+            let event = Event::read_from_buffer(bytes)?;
+
+            if !phrases.is_empty() {
+                let event_words = event_words1::event_words(&event);
+                let all_phrases_match = phrases.iter().all(|phrase| {
+                    event_words
+                        .windows(phrase.len())
+                        .any(|w| w == phrase.as_slice())
+                });
+                if !all_phrases_match {
+                    continue;
+                }
+            }
+
+            scored.push((score, event));
+        }
+
+        scored.sort_by(|a, b| {
+            // ORDER score desc, created_at desc
+            b.0.total_cmp(&a.0)
+                .then(b.1.created_at.cmp(&a.1.created_at))
+                .then(b.1.id.cmp(&a.1.id))
+        });
+
+        Ok(scored.drain(..).map(|(_, e)| e).collect())
+    }
+
+    // Search all events for the text, case insensitive. Both content and tags
+    // are searched. This is slow, and only used when the word index cannot help.
+    fn scan_events_for_text(&self, text: &str) -> Result<Vec<Event>, Error> {
         let event_kinds = crate::feed::feed_displayable_event_kinds(true);
 
         let needle = regex::escape(text.to_lowercase().as_str());
@@ -2392,6 +2734,8 @@ impl Storage {
             }
         })?;
 
+        let filter_spam = self.read_setting_apply_spam_filter_on_dms();
+
         // Map from channel to latest-message-time and unread-count
         let mut map: HashMap<DmChannel, DmChannelData> = HashMap::new();
 
@@ -2433,6 +2777,10 @@ impl Storage {
                     );
                 }
             } else if event.kind == EventKind::GiftWrap {
+                // The spam filter ran when the giftwrap was processed
+                if filter_spam && self.is_dm_spam(event.id)? {
+                    continue;
+                }
                 if let Ok(rumor) = GLOBALS.identity.unwrap_giftwrap(event).await {
                     let rumor_event = rumor.into_event_with_bad_signature();
                     let time = rumor_event.created_at;
@@ -2495,10 +2843,15 @@ impl Storage {
             }
         }
 
+        let filter_spam = self.read_setting_apply_spam_filter_on_dms();
+
         // Sort by rumor's time reversed, not giftwrap's time
         let mut sortable: Vec<(Unixtime, Event)> = Vec::new();
         for e in output.into_iter() {
             if e.kind == EventKind::GiftWrap {
+                if filter_spam && self.is_dm_spam(e.id)? {
+                    continue;
+                }
                 if let Ok(rumor) = GLOBALS.identity.unwrap_giftwrap(&e).await {
                     sortable.push((rumor.created_at, e))
                 } else {
@@ -2526,6 +2879,7 @@ impl Storage {
         self.db_event_akci_index()?.clear(txn)?;
         self.db_event_kci_index()?.clear(txn)?;
         self.db_event_tci_index()?.clear(txn)?;
+        self.db_event_words()?.clear(txn)?;
         self.db_hashtags()?.clear(txn)?;
 
         let loop_txn = self.env.read_txn()?;
@@ -2562,6 +2916,7 @@ impl Storage {
                 } // upstream bug
                 self.add_hashtag(&hashtag, event.id, Some(txn))?;
             }
+            self.write_event_words(&event, Some(txn))?;
         }
         self.set_flag_rebuild_indexes_needed(false, Some(txn))?;
 
@@ -2881,3 +3236,9 @@ impl Storage {
         Ok(iter)
     }
 }
+
+// Each account has its own wallet connection, since its secret is kept under
+// that account's passphrase
+fn wallet_connection_key(account: PublicKey) -> Vec<u8> {
+    format!("wallet_connection.{}", account.as_hex_string()).into_bytes()
+}
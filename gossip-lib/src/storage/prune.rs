@@ -155,6 +155,18 @@ impl Storage {
             txn.commit()?;
             tracing::info!("PRUNE: deleted {} records from event_viewed", ids.len());
 
+            // Delete from spam_dms
+            let mut txn = self.env.write_txn()?;
+            for (n, id) in ids.iter().enumerate() {
+                self.db_spam_dms()?.delete(&mut txn, id.as_slice())?;
+                if n % 100_000 == 0 {
+                    txn.commit()?;
+                    txn = self.env.write_txn()?;
+                }
+            }
+            txn.commit()?;
+            tracing::info!("PRUNE: deleted {} records from spam_dms", ids.len());
+
             // Delete from hashtags
             tracing::info!(
                 "PRUNE: deleting {} records from hashtags",
@@ -167,6 +179,28 @@ impl Storage {
             }
             txn.commit()?;
 
+            // Delete from event_words
+            // (the words are the keys, so we need each event to find them)
+            tracing::info!(
+                "PRUNE: deleting words of {} events from event_words",
+                ids.len()
+            );
+            let mut txn = self.env.write_txn()?;
+            for (n, id) in ids.iter().enumerate() {
+                let maybe_event = match self.db_events()?.get(&txn, id.as_slice())? {
+                    Some(bytes) => Some(Event::read_from_buffer(bytes)?),
+                    None => None,
+                };
+                if let Some(event) = maybe_event {
+                    self.delete_event_words(&event, Some(&mut txn))?;
+                }
+                if n % 100_000 == 0 {
+                    txn.commit()?;
+                    txn = self.env.write_txn()?;
+                }
+            }
+            txn.commit()?;
+
             // Delete from relationships
             tracing::info!(
                 "PRUNE: deleting {} relationships",
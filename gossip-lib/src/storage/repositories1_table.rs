@@ -0,0 +1,50 @@
+use super::types::Repository1;
+use super::Table;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use heed::types::Bytes;
+use heed::Database;
+use std::sync::Mutex;
+
+static REPOSITORIES1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut REPOSITORIES1_DB: Option<Database<Bytes, Bytes>> = None;
+
+pub struct Repositories1Table {}
+
+impl Table for Repositories1Table {
+    type Item = Repository1;
+
+    fn lmdb_name() -> &'static str {
+        "repositories1"
+    }
+
+    fn db() -> Result<Database<Bytes, Bytes>, Error> {
+        unsafe {
+            if let Some(db) = REPOSITORIES1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = REPOSITORIES1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = REPOSITORIES1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = GLOBALS.db().env.write_txn()?;
+                let db = GLOBALS
+                    .db()
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    .name(Self::lmdb_name())
+                    .create(&mut txn)?;
+                txn.commit()?;
+                REPOSITORIES1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use super::types::TimestampRequest1;
+use super::Table;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use heed::types::Bytes;
+use heed::Database;
+use std::sync::Mutex;
+
+static TIMESTAMP_REQUESTS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut TIMESTAMP_REQUESTS1_DB: Option<Database<Bytes, Bytes>> = None;
+
+pub struct TimestampRequests1Table {}
+
+impl Table for TimestampRequests1Table {
+    type Item = TimestampRequest1;
+
+    fn lmdb_name() -> &'static str {
+        "timestamp_requests1"
+    }
+
+    fn db() -> Result<Database<Bytes, Bytes>, Error> {
+        unsafe {
+            if let Some(db) = TIMESTAMP_REQUESTS1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = TIMESTAMP_REQUESTS1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = TIMESTAMP_REQUESTS1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = GLOBALS.db().env.write_txn()?;
+                let db = GLOBALS
+                    .db()
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    .name(Self::lmdb_name())
+                    .create(&mut txn)?;
+                txn.commit()?;
+                TIMESTAMP_REQUESTS1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use super::{ByteRep, PersonList1, PersonListMetadata3, Record};
+use crate::error::Error;
+use nostr_types::{PublicKey, RelayUrl, Unixtime};
+use speedy::{Readable, Writable};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// An account the user can switch to. While it is not the active account, the
+/// things that belong to it are kept here.
+#[derive(Debug, Clone, Readable, Writable)]
+pub struct Account1 {
+    /// The public key of the account
+    pub pubkey: PublicKey,
+
+    /// The identity, serialized as JSON the same way the active identity is
+    pub identity: String,
+
+    /// Who is in which person list, and if privately
+    pub person_lists: Vec<(PublicKey, Vec<(PersonList1, bool)>)>,
+
+    /// The person lists and their metadata
+    pub person_list_metadata: Vec<(PersonList1, PersonListMetadata3)>,
+
+    /// Relay usage bits that belong to the account (read, write, inbox, outbox, dm)
+    pub relay_usage: Vec<(RelayUrl, u64)>,
+
+    /// When the account was last switched to or away from
+    pub last_used: Unixtime,
+}
+
+impl ByteRep for Account1 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_to_vec()?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::read_from_buffer(bytes)?)
+    }
+}
+
+impl Record for Account1 {
+    type Key = PublicKey;
+
+    /// Create a new record
+    fn new(k: Self::Key) -> Option<Self> {
+        Some(Account1 {
+            pubkey: k,
+            identity: "".to_owned(),
+            person_lists: vec![],
+            person_list_metadata: vec![],
+            relay_usage: vec![],
+            last_used: Unixtime::now(),
+        })
+    }
+
+    // Get the key of a record
+    fn key(&self) -> Self::Key {
+        self.pubkey
+    }
+}
@@ -0,0 +1,43 @@
+use super::{ByteRep, Record};
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// A Bitcoin block header, kept for verifying OpenTimestamps attestations offline
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable, Serialize, Deserialize)]
+pub struct BlockHeader1 {
+    /// The height of the block
+    pub height: u64,
+
+    /// The 80 byte header, as serialized in the block
+    pub header: Vec<u8>,
+}
+
+impl ByteRep for BlockHeader1 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_to_vec()?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::read_from_buffer(bytes)?)
+    }
+}
+
+impl Record for BlockHeader1 {
+    type Key = u64;
+
+    /// Create a new record
+    fn new(k: Self::Key) -> Option<Self> {
+        Some(BlockHeader1 {
+            height: k,
+            header: vec![],
+        })
+    }
+
+    // Get the key of a record
+    fn key(&self) -> Self::Key {
+        self.height
+    }
+}
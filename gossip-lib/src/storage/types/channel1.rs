@@ -0,0 +1,68 @@
+use super::{ByteRep, Record};
+use crate::error::Error;
+use nostr_types::{Id, PublicKey, RelayUrl, Unixtime};
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// A NIP-28 public chat channel
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable, Serialize, Deserialize)]
+pub struct Channel1 {
+    /// The id of the channel creation event
+    pub id: Id,
+
+    /// Who created the channel. This is unknown until we have the creation event.
+    pub creator: Option<PublicKey>,
+
+    /// The name of the channel
+    pub name: String,
+
+    /// What the channel is about
+    pub about: String,
+
+    /// A picture for the channel
+    pub picture: String,
+
+    /// Relays the channel lives on
+    pub relays: Vec<RelayUrl>,
+
+    /// When the metadata we have was created
+    pub metadata_at: Unixtime,
+
+    /// If the user has joined the channel
+    pub joined: bool,
+}
+
+impl ByteRep for Channel1 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_to_vec()?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::read_from_buffer(bytes)?)
+    }
+}
+
+impl Record for Channel1 {
+    type Key = Id;
+
+    /// Create a new record
+    fn new(k: Self::Key) -> Option<Self> {
+        Some(Channel1 {
+            id: k,
+            creator: None,
+            name: "".to_owned(),
+            about: "".to_owned(),
+            picture: "".to_owned(),
+            relays: vec![],
+            metadata_at: Unixtime(0),
+            joined: false,
+        })
+    }
+
+    // Get the key of a record
+    fn key(&self) -> Self::Key {
+        self.id
+    }
+}
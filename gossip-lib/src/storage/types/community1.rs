@@ -0,0 +1,59 @@
+use super::{ByteRep, Record};
+use crate::error::Error;
+use nostr_types::{PublicKey, RelayUrl};
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// A NIP-72 moderated community, as defined by its owner
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable, Serialize, Deserialize)]
+pub struct Community1 {
+    /// Who defined the community
+    pub author: PublicKey,
+
+    /// The identifier of the community definition
+    pub d: String,
+
+    /// Relays we were told the community can be found on
+    pub relays: Vec<RelayUrl>,
+
+    /// If the user has joined the community
+    pub joined: bool,
+}
+
+impl ByteRep for Community1 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_to_vec()?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::read_from_buffer(bytes)?)
+    }
+}
+
+impl Record for Community1 {
+    /// The address of the community definition, `34550:<pubkey-hex>:<d>`
+    type Key = String;
+
+    /// Create a new record
+    fn new(k: Self::Key) -> Option<Self> {
+        let mut parts = k.splitn(3, ':');
+        if parts.next()? != "34550" {
+            return None;
+        }
+        let author = PublicKey::try_from_hex_string(parts.next()?, true).ok()?;
+        let d = parts.next()?;
+        Some(Community1 {
+            author,
+            d: d.to_owned(),
+            relays: vec![],
+            joined: false,
+        })
+    }
+
+    // Get the key of a record
+    fn key(&self) -> Self::Key {
+        format!("34550:{}:{}", self.author.as_hex_string(), self.d)
+    }
+}
@@ -0,0 +1,120 @@
+use super::{ByteRep, Record};
+use crate::error::Error;
+use nostr_types::{Id, PublicKey, Tag, Unixtime};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use speedy::{Readable, Writable};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// What a draft is for
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable, Serialize, Deserialize)]
+pub enum DraftTarget1 {
+    /// A new post
+    Post,
+
+    /// A reply to this event
+    Reply(Id),
+
+    /// An annotation of this event
+    Annotation(Id),
+
+    /// A direct message to the DM channel with these people
+    DirectMessage(Vec<PublicKey>),
+}
+
+impl DraftTarget1 {
+    /// The key the draft for this target is stored under
+    pub fn key(&self) -> String {
+        match self {
+            DraftTarget1::Post => "post".to_owned(),
+            DraftTarget1::Reply(id) => format!("reply:{}", id.as_hex_string()),
+            DraftTarget1::Annotation(id) => format!("annotation:{}", id.as_hex_string()),
+            DraftTarget1::DirectMessage(pubkeys) => {
+                // Hashed, as a list of keys could be too long for an LMDB key
+                let mut hasher = sha2::Sha256::new();
+                for pk in pubkeys {
+                    hasher.update(pk.as_bytes());
+                }
+                format!("dm:{}", hex::encode(hasher.finalize()))
+            }
+        }
+    }
+
+    /// The target a key was made from. DM keys are hashed, so they give None.
+    pub fn from_key(key: &str) -> Option<DraftTarget1> {
+        if key == "post" {
+            Some(DraftTarget1::Post)
+        } else if let Some(hex) = key.strip_prefix("reply:") {
+            Id::try_from_hex_string(hex).ok().map(DraftTarget1::Reply)
+        } else if let Some(hex) = key.strip_prefix("annotation:") {
+            Id::try_from_hex_string(hex)
+                .ok()
+                .map(DraftTarget1::Annotation)
+        } else {
+            None
+        }
+    }
+}
+
+/// A post that is still being written
+#[derive(Debug, Clone, Readable, Writable, Serialize, Deserialize)]
+pub struct Draft1 {
+    /// The key of the draft, from the account it belongs to and its target
+    pub key: String,
+
+    /// What the draft is for
+    pub target: DraftTarget1,
+
+    /// The text of the draft
+    pub content: String,
+
+    /// Tags the user added (content warning, subject, etc)
+    pub tags: Vec<Tag>,
+
+    /// Text in the draft that gets replaced with a nostr: url when posting,
+    /// with the bech32 form of that url
+    pub mentions: Vec<(String, String)>,
+
+    /// When the draft was last saved
+    pub updated_at: Unixtime,
+
+    /// If `content` holds the content, tags and mentions NIP-44 encrypted to
+    /// ourself (as for direct messages), with `tags` and `mentions` left empty
+    pub encrypted: bool,
+}
+
+impl ByteRep for Draft1 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_to_vec()?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::read_from_buffer(bytes)?)
+    }
+}
+
+impl Record for Draft1 {
+    type Key = String;
+
+    /// Create a new record
+    fn new(k: Self::Key) -> Option<Self> {
+        // The key is the account's public key (hex), a colon, and the target's key
+        let (_, target_key) = k.split_once(':')?;
+        let target = DraftTarget1::from_key(target_key)?;
+        Some(Draft1 {
+            key: k,
+            target,
+            content: "".to_owned(),
+            tags: vec![],
+            mentions: vec![],
+            updated_at: Unixtime::now(),
+            encrypted: false,
+        })
+    }
+
+    // Get the key of a record
+    fn key(&self) -> Self::Key {
+        self.key.clone()
+    }
+}
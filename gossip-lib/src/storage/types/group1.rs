@@ -0,0 +1,75 @@
+use super::{ByteRep, Record};
+use crate::error::Error;
+use nostr_types::{RelayUrl, Unixtime};
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// A NIP-29 relay-based group
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable, Serialize, Deserialize)]
+pub struct Group1 {
+    /// The relay that manages the group
+    pub relay: RelayUrl,
+
+    /// The id of the group on that relay
+    pub group_id: String,
+
+    /// The name of the group
+    pub name: String,
+
+    /// What the group is about
+    pub about: String,
+
+    /// A picture for the group
+    pub picture: String,
+
+    /// If only members can read the group
+    pub private: bool,
+
+    /// If joining requires approval from the group admins
+    pub closed: bool,
+
+    /// When the metadata we have was created
+    pub metadata_at: Unixtime,
+
+    /// If the user has joined the group
+    pub joined: bool,
+}
+
+impl ByteRep for Group1 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_to_vec()?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::read_from_buffer(bytes)?)
+    }
+}
+
+impl Record for Group1 {
+    /// The relay url and group id, separated by a `'`
+    type Key = String;
+
+    /// Create a new record
+    fn new(k: Self::Key) -> Option<Self> {
+        let (relay, group_id) = k.rsplit_once('\'')?;
+        let relay = RelayUrl::try_from_str(relay).ok()?;
+        Some(Group1 {
+            relay,
+            group_id: group_id.to_owned(),
+            name: "".to_owned(),
+            about: "".to_owned(),
+            picture: "".to_owned(),
+            private: false,
+            closed: false,
+            metadata_at: Unixtime(0),
+            joined: false,
+        })
+    }
+
+    // Get the key of a record
+    fn key(&self) -> Self::Key {
+        format!("{}'{}", self.relay, self.group_id)
+    }
+}
@@ -0,0 +1,44 @@
+use super::{ByteRep, Record};
+use crate::error::Error;
+use nostr_types::{Id, Unixtime};
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// A NIP-58 badge award to the user that the user does not want to show or be offered
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable, Serialize, Deserialize)]
+pub struct HiddenBadge1 {
+    /// The badge award event
+    pub award: Id,
+
+    /// When the user hid it
+    pub hidden_at: Unixtime,
+}
+
+impl ByteRep for HiddenBadge1 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_to_vec()?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::read_from_buffer(bytes)?)
+    }
+}
+
+impl Record for HiddenBadge1 {
+    type Key = Id;
+
+    /// Create a new record
+    fn new(k: Self::Key) -> Option<Self> {
+        Some(HiddenBadge1 {
+            award: k,
+            hidden_at: Unixtime::now(),
+        })
+    }
+
+    // Get the key of a record
+    fn key(&self) -> Self::Key {
+        self.award
+    }
+}
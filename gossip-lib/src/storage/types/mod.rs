@@ -22,9 +22,42 @@ pub use person_relay1::PersonRelay1;
 mod person_relay2;
 pub use person_relay2::PersonRelay2;
 
+mod account1;
+pub use account1::Account1;
+
+mod channel1;
+pub use channel1::Channel1;
+
+mod draft1;
+pub use draft1::{Draft1, DraftTarget1};
+
 mod following;
 pub use following::Following;
 
+mod group1;
+pub use group1::Group1;
+
+mod repository1;
+pub use repository1::Repository1;
+
+mod community1;
+pub use community1::Community1;
+
+mod block_header1;
+pub use block_header1::BlockHeader1;
+
+mod timestamp_request1;
+pub use timestamp_request1::TimestampRequest1;
+
+mod hidden_badge1;
+pub use hidden_badge1::HiddenBadge1;
+
+mod outbox_entry1;
+pub use outbox_entry1::{DeliveryState1, OutboxDelivery1, OutboxEntry1};
+
+mod scheduled_post1;
+pub use scheduled_post1::ScheduledPost1;
+
 mod relationship1;
 pub use relationship1::Relationship1;
 
@@ -77,6 +110,16 @@ impl ByteRep for PublicKey {
     }
 }
 
+impl ByteRep for u64 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.to_be_bytes().to_vec())
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(u64::from_be_bytes(bytes.try_into()?))
+    }
+}
+
 impl ByteRep for String {
     fn to_bytes(&self) -> Result<Vec<u8>, Error> {
         Ok(self.as_bytes().to_vec())
@@ -0,0 +1,104 @@
+use super::{ByteRep, Record};
+use crate::error::Error;
+use nostr_types::{Id, PublicKey, RelayUrl, Unixtime};
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// Where the delivery of one of our events to one relay stands
+#[derive(Debug, Clone, Copy, Readable, Writable, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DeliveryState1 {
+    /// Sent (or about to be), waiting for an OK
+    Pending,
+
+    /// The relay accepted it
+    Accepted,
+
+    /// The relay refused it for a reason that retrying will not fix
+    Rejected,
+
+    /// It did not get there, or the relay refused it for a reason that may pass.
+    /// It will be retried.
+    Failed,
+
+    /// It failed too many times and we stopped trying
+    GaveUp,
+}
+
+/// The delivery of one of our events to one relay
+#[derive(Debug, Clone, Readable, Writable, Serialize, Deserialize)]
+pub struct OutboxDelivery1 {
+    /// The relay
+    pub relay: RelayUrl,
+
+    /// Where it stands
+    pub state: DeliveryState1,
+
+    /// How many times we have sent it
+    pub attempts: u32,
+
+    /// When we last sent it
+    pub last_attempt: Option<Unixtime>,
+
+    /// The message the relay gave with its OK (or why we could not deliver it)
+    pub message: String,
+}
+
+/// An outbox record, tracking where one of our events has been delivered
+#[derive(Debug, Clone, Readable, Writable, Serialize, Deserialize)]
+pub struct OutboxEntry1 {
+    /// The event
+    pub id: Id,
+
+    /// The account that sent it. Failed deliveries are only retried while that
+    /// account is the current one.
+    pub account: Option<PublicKey>,
+
+    /// When it was first queued
+    pub queued_at: Unixtime,
+
+    /// One delivery per target relay
+    pub deliveries: Vec<OutboxDelivery1>,
+}
+
+impl OutboxEntry1 {
+    /// If no delivery is still in progress or waiting to be retried
+    pub fn is_finished(&self) -> bool {
+        self.deliveries.iter().all(|d| {
+            matches!(
+                d.state,
+                DeliveryState1::Accepted | DeliveryState1::Rejected | DeliveryState1::GaveUp
+            )
+        })
+    }
+}
+
+impl ByteRep for OutboxEntry1 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_to_vec()?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::read_from_buffer(bytes)?)
+    }
+}
+
+impl Record for OutboxEntry1 {
+    type Key = Id;
+
+    /// Create a new record
+    fn new(k: Self::Key) -> Option<Self> {
+        Some(OutboxEntry1 {
+            id: k,
+            account: None,
+            queued_at: Unixtime::now(),
+            deliveries: vec![],
+        })
+    }
+
+    // Get the key of a record
+    fn key(&self) -> Self::Key {
+        self.id
+    }
+}
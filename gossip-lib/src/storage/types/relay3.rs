@@ -195,6 +195,14 @@ impl Relay3 {
             || (self.rank > 0 && self.success_rate() > 0.50 && self.success_count > 15)
     }
 
+    /// Whether the relay advertises support for the NIP in its NIP-11 document
+    pub fn supports_nip(&self, nip: u32) -> bool {
+        match &self.nip11 {
+            Some(nip11) => nip11.supported_nips.contains(&nip),
+            None => false,
+        }
+    }
+
     /// This gives a pure score for the relay outside of context
     ///
     /// Output ranges from 0.0 (worst) to 1.0 (best)
@@ -0,0 +1,59 @@
+use super::{ByteRep, Record};
+use crate::error::Error;
+use nostr_types::{PublicKey, RelayUrl};
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// A NIP-34 git repository, as announced by its author
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable, Serialize, Deserialize)]
+pub struct Repository1 {
+    /// Who announced the repository
+    pub author: PublicKey,
+
+    /// The identifier of the repository announcement
+    pub d: String,
+
+    /// Relays we were told the repository can be found on
+    pub relays: Vec<RelayUrl>,
+
+    /// If the user follows the repository
+    pub followed: bool,
+}
+
+impl ByteRep for Repository1 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_to_vec()?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::read_from_buffer(bytes)?)
+    }
+}
+
+impl Record for Repository1 {
+    /// The address of the repository announcement, `30617:<pubkey-hex>:<d>`
+    type Key = String;
+
+    /// Create a new record
+    fn new(k: Self::Key) -> Option<Self> {
+        let mut parts = k.splitn(3, ':');
+        if parts.next()? != "30617" {
+            return None;
+        }
+        let author = PublicKey::try_from_hex_string(parts.next()?, true).ok()?;
+        let d = parts.next()?;
+        Some(Repository1 {
+            author,
+            d: d.to_owned(),
+            relays: vec![],
+            followed: false,
+        })
+    }
+
+    // Get the key of a record
+    fn key(&self) -> Self::Key {
+        format!("30617:{}:{}", self.author.as_hex_string(), self.d)
+    }
+}
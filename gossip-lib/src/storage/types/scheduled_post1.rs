@@ -0,0 +1,62 @@
+use super::{ByteRep, Record};
+use crate::error::Error;
+use nostr_types::{Event, Id, PublicKey, Tag, Unixtime};
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// A post waiting to be published at a later time
+#[derive(Debug, Clone, Readable, Writable, Serialize, Deserialize)]
+pub struct ScheduledPost1 {
+    /// A random key identifying this scheduled post
+    pub key: u64,
+
+    /// The account that wrote it. It is only signed and published by that account.
+    pub author: PublicKey,
+
+    /// When to publish it
+    pub publish_at: Unixtime,
+
+    /// The content of the post
+    pub content: String,
+
+    /// Tags the user added (content warning, subject, etc)
+    pub tags: Vec<Tag>,
+
+    /// What it replies to, if anything
+    pub in_reply_to: Option<Id>,
+
+    /// If it is an annotation
+    pub annotation: bool,
+
+    /// The signed event, if we could sign it when it was scheduled.
+    /// Otherwise (e.g. with a remote signer) it is signed when it is published.
+    pub event: Option<Event>,
+}
+
+impl ByteRep for ScheduledPost1 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_to_vec()?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::read_from_buffer(bytes)?)
+    }
+}
+
+impl Record for ScheduledPost1 {
+    type Key = u64;
+
+    /// Create a new record
+    ///
+    /// A scheduled post cannot be made without knowing its author
+    fn new(_k: Self::Key) -> Option<Self> {
+        None
+    }
+
+    // Get the key of a record
+    fn key(&self) -> Self::Key {
+        self.key
+    }
+}
@@ -0,0 +1,53 @@
+use super::{ByteRep, Record};
+use crate::error::Error;
+use nostr_types::{Id, Unixtime};
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+
+// THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
+
+/// A request to an OpenTimestamps calendar to timestamp one of our events,
+/// waiting for the calendar to get it into a Bitcoin block
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable, Serialize, Deserialize)]
+pub struct TimestampRequest1 {
+    /// The event being timestamped
+    pub id: Id,
+
+    /// The calendar server we asked
+    pub calendar: String,
+
+    /// The (incomplete) timestamp of the event id, serialized without the file header
+    pub proof: Vec<u8>,
+
+    /// When we asked
+    pub requested_at: Unixtime,
+}
+
+impl ByteRep for TimestampRequest1 {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.write_to_vec()?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::read_from_buffer(bytes)?)
+    }
+}
+
+impl Record for TimestampRequest1 {
+    type Key = Id;
+
+    /// Create a new record
+    fn new(k: Self::Key) -> Option<Self> {
+        Some(TimestampRequest1 {
+            id: k,
+            calendar: "".to_owned(),
+            proof: vec![],
+            requested_at: Unixtime::now(),
+        })
+    }
+
+    // Get the key of a record
+    fn key(&self) -> Self::Key {
+        self.id
+    }
+}
@@ -75,6 +75,29 @@ async fn do_online_tasks(tick: usize) {
     if tick % 3 == 0 {
         GLOBALS.people.maybe_fetch_metadata().await;
     }
+
+    // Publish scheduled posts that are due every 10 ticks
+    if tick % 10 == 0 {
+        if let Err(e) = crate::scheduled_post::publish_due_posts().await {
+            tracing::error!("{}", e);
+        }
+    }
+
+    // Check on the timestamps we requested every 1200 ticks (10 minutes)
+    if tick % 1200 == 0 {
+        std::mem::drop(tokio::spawn(Box::pin(async move {
+            if let Err(e) = crate::ots::upgrade_timestamps().await {
+                tracing::error!("{}", e);
+            }
+        })));
+    }
+
+    // Retry failed deliveries of our events every 20 ticks
+    if tick % 20 == 0 {
+        if let Err(e) = crate::outbox::retry_failed_deliveries() {
+            tracing::error!("{}", e);
+        }
+    }
 }
 
 async fn do_general_tasks(tick: usize) {
@@ -89,6 +112,19 @@ async fn do_general_tasks(tick: usize) {
         update_inbox_indicator().await;
     }
 
+    // Reload the spam filter script if it changed, every 4 ticks
+    if tick % 4 == 0 {
+        crate::spam_filter::reload_script_if_changed();
+    }
+
+    // Recount followers for the spam filter, on the first tick and then every
+    // 1200 ticks (10 minutes)
+    if tick % 1200 == 1 {
+        std::mem::drop(tokio::task::spawn_blocking(
+            crate::spam_filter::rebuild_followers_known,
+        ));
+    }
+
     // Update handlers for quick menu rendering
     let _ = GLOBALS.update_handlers();
 }
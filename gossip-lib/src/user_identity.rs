@@ -49,6 +49,7 @@ impl UserIdentity {
     // Any function that changes UserIdentity and changes the key should run this instead
     fn on_keychange(&self) -> Result<(), Error> {
         self.on_change()?;
+        crate::accounts::remember_active_account()?;
         if !matches!(*self.inner.read_arc(), Identity::None) {
             // Rebuild the event tag index if the identity changes
             // since the 'p' tags it needs to index just changed.
@@ -101,6 +102,13 @@ impl UserIdentity {
         Ok(())
     }
 
+    /// Replace the identity, e.g. when switching accounts
+    pub(crate) fn set_identity(&self, identity: Identity) -> Result<(), Error> {
+        *self.inner.write_arc() = identity;
+        self.on_keychange()?;
+        Ok(())
+    }
+
     pub(crate) fn set_public_key(&self, public_key: PublicKey) -> Result<(), Error> {
         *self.inner.write_arc() = Identity::Public(public_key);
         self.on_keychange()?;
@@ -0,0 +1,540 @@
+use crate::comms::{RelayConnectionReason, RelayJob, ToMinionPayload, ToMinionPayloadDetail};
+use crate::error::{Error, ErrorKind};
+use crate::filter_set::FilterSet;
+use crate::globals::GLOBALS;
+use dashmap::{DashMap, DashSet};
+use nostr_types::{
+    ContentEncryptionAlgorithm, Event, EventKind, Id, Identity, ParsedTag, PreEvent, PrivateKey,
+    PublicKey, RelayUrl, Tag, Unixtime,
+};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+// How long we wait for the wallet service to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A connection to a NIP-47 wallet service, as given to us in a
+/// `nostr+walletconnect://` connection string
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WalletConnection {
+    /// The wallet service
+    pub wallet: PublicKey,
+
+    /// The relays the wallet service listens on
+    pub relays: Vec<RelayUrl>,
+
+    /// The lightning address of the wallet, if it gave us one
+    pub lud16: Option<String>,
+
+    /// The secret the wallet service gave us to sign our requests with. It is
+    /// kept encrypted under the user's passphrase, like their own private key.
+    pub identity: Identity,
+}
+
+impl WalletConnection {
+    /// Parse a `nostr+walletconnect://` connection string, encrypting its secret
+    /// with the passphrase
+    pub fn from_uri(uri: &str, pass: &str) -> Result<WalletConnection, Error> {
+        let bad = |s: &str| -> Error { ErrorKind::Wallet(s.to_owned()).into() };
+
+        let uri = uri.trim();
+        let rest = uri
+            .strip_prefix("nostr+walletconnect:")
+            .or_else(|| uri.strip_prefix("nostrwalletconnect:"))
+            .ok_or_else(|| bad("Not a nostr+walletconnect:// connection string"))?;
+        let rest = rest.trim_start_matches('/');
+        let (wallet, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+        let wallet = PublicKey::try_from_hex_string(wallet.trim_end_matches('/'), true)
+            .map_err(|_| bad("The wallet public key is not valid"))?;
+
+        let mut relays: Vec<RelayUrl> = Vec::new();
+        let mut secret: Option<PrivateKey> = None;
+        let mut lud16: Option<String> = None;
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match &*key {
+                "relay" => {
+                    if let Ok(url) = RelayUrl::try_from_str(&value) {
+                        relays.push(url);
+                    }
+                }
+                "secret" => secret = PrivateKey::try_from_hex_string(&value).ok(),
+                "lud16" => lud16 = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+
+        if relays.is_empty() {
+            return Err(bad("The connection string has no relay"));
+        }
+        let secret = secret.ok_or_else(|| bad("The connection string has no valid secret"))?;
+
+        let log_n = GLOBALS.db().read_setting_log_n();
+        let identity = Identity::from_private_key(secret, pass, log_n)?;
+
+        Ok(WalletConnection {
+            wallet,
+            relays,
+            lud16,
+            identity,
+        })
+    }
+}
+
+/// A payment, as listed by the wallet service
+#[derive(Debug, Clone, Deserialize)]
+pub struct Transaction {
+    /// "incoming" or "outgoing"
+    #[serde(rename = "type")]
+    pub direction: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[serde(default)]
+    pub payment_hash: Option<String>,
+
+    /// Amount in millisatoshis
+    pub amount: u64,
+
+    /// Fees paid in millisatoshis
+    #[serde(default)]
+    pub fees_paid: Option<u64>,
+
+    pub created_at: i64,
+
+    #[serde(default)]
+    pub settled_at: Option<i64>,
+}
+
+impl Transaction {
+    pub fn is_incoming(&self) -> bool {
+        self.direction == "incoming"
+    }
+}
+
+/// Nostr Wallet Connect (NIP-47). The user's wallet, if they connected one.
+#[derive(Debug, Default)]
+pub struct Wallet {
+    inner: Arc<RwLock<Option<WalletConnection>>>,
+
+    // Requests waiting for a response, by request id
+    waiting: DashMap<Id, oneshot::Sender<Event>>,
+
+    // Invoices we asked the wallet to pay that it has not answered about yet.
+    // These are never sent again, so a slow wallet can't pay them twice.
+    pending_payments: DashSet<String>,
+
+    /// Balance in millisatoshis, as of the last time we asked
+    pub balance: RwLock<Option<u64>>,
+
+    /// Recent payments, as of the last time we asked
+    pub transactions: RwLock<Vec<Transaction>>,
+}
+
+impl Wallet {
+    /// Load the wallet connection of the active account. This is run again
+    /// whenever the account changes.
+    pub(crate) fn load(&self) -> Result<(), Error> {
+        let connection = match GLOBALS.identity.public_key() {
+            Some(pubkey) => GLOBALS.db().read_wallet_connection(pubkey)?,
+            None => None,
+        };
+        *self.inner.write_arc() = connection;
+        *self.balance.write() = None;
+        self.transactions.write().clear();
+        self.waiting.clear();
+        Ok(())
+    }
+
+    // Any function that changes the Wallet should run this to save back changes
+    fn on_change(&self) -> Result<(), Error> {
+        let Some(pubkey) = GLOBALS.identity.public_key() else {
+            return Ok(());
+        };
+        let binding = self.inner.read_arc();
+        GLOBALS
+            .db()
+            .write_wallet_connection(pubkey, binding.as_ref(), None)?;
+        Ok(())
+    }
+
+    pub(crate) fn connect(&self, uri: &str, pass: &str) -> Result<(), Error> {
+        if GLOBALS.identity.public_key().is_none() {
+            return Err(ErrorKind::NoPublicKey.into());
+        }
+        let connection = WalletConnection::from_uri(uri, pass)?;
+        *self.inner.write_arc() = Some(connection);
+        *self.balance.write() = None;
+        self.transactions.write().clear();
+        self.on_change()?;
+        Ok(())
+    }
+
+    pub(crate) fn disconnect(&self) -> Result<(), Error> {
+        *self.inner.write_arc() = None;
+        *self.balance.write() = None;
+        self.transactions.write().clear();
+        self.on_change()?;
+        Ok(())
+    }
+
+    pub(crate) fn unlock(&self, pass: &str) -> Result<(), Error> {
+        if let Some(connection) = self.inner.write_arc().as_mut() {
+            connection.identity.unlock(pass)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn change_passphrase(&self, old: &str, new: &str) -> Result<(), Error> {
+        let log_n = GLOBALS.db().read_setting_log_n();
+        if let Some(connection) = self.inner.write_arc().as_mut() {
+            connection.identity.change_passphrase(old, new, log_n)?;
+        }
+        self.on_change()?;
+        Ok(())
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.inner.read_arc().is_some()
+    }
+
+    /// If we can make payments right now
+    pub fn is_ready(&self) -> bool {
+        GLOBALS.db().read_setting_enable_wallet()
+            && self
+                .inner
+                .read_arc()
+                .as_ref()
+                .is_some_and(|c| c.identity.is_unlocked())
+    }
+
+    /// The wallet service's public key
+    pub fn wallet_pubkey(&self) -> Option<PublicKey> {
+        self.inner.read_arc().as_ref().map(|c| c.wallet)
+    }
+
+    /// The public key we sign our requests with
+    pub fn client_pubkey(&self) -> Option<PublicKey> {
+        self.inner
+            .read_arc()
+            .as_ref()
+            .and_then(|c| c.identity.public_key())
+    }
+
+    pub fn relays(&self) -> Vec<RelayUrl> {
+        self.inner
+            .read_arc()
+            .as_ref()
+            .map(|c| c.relays.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn lud16(&self) -> Option<String> {
+        self.inner.read_arc().as_ref().and_then(|c| c.lud16.clone())
+    }
+
+    /// Ask for the wallet service's capabilities, so we know which encryption it speaks
+    pub(crate) fn fetch_info(&self) {
+        if !GLOBALS.db().read_setting_enable_wallet() {
+            return;
+        }
+        let (wallet, relays) = match self.inner.read_arc().as_ref() {
+            Some(c) => (c.wallet, c.relays.clone()),
+            None => return,
+        };
+        crate::manager::run_jobs_on_all_relays(
+            relays,
+            vec![RelayJob {
+                reason: RelayConnectionReason::Wallet,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::WalletInfo(wallet)),
+                },
+            }],
+        );
+    }
+
+    // NIP-44 if the wallet service says it can, otherwise NIP-04
+    fn encryption(&self, wallet: PublicKey) -> ContentEncryptionAlgorithm {
+        let info = GLOBALS
+            .db()
+            .get_replaceable_event(EventKind::WalletInfo, wallet, "")
+            .ok()
+            .flatten();
+        let nip44 = info.is_some_and(|event| {
+            event.tags.iter().any(|t| {
+                t.tagname() == "encryption" && t.value().split_whitespace().any(|v| v == "nip44_v2")
+            })
+        });
+        if nip44 {
+            ContentEncryptionAlgorithm::Nip44v2
+        } else {
+            ContentEncryptionAlgorithm::Nip04
+        }
+    }
+
+    /// Send a request to the wallet service and wait for its result
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, Error> {
+        let (id, receiver) = self.send_request(method, params).await?;
+
+        let response = match tokio::time::timeout(REQUEST_TIMEOUT, receiver).await {
+            Ok(Ok(response)) => response,
+            _ => {
+                self.waiting.remove(&id);
+                return Err(ErrorKind::Wallet("The wallet did not answer".to_owned()).into());
+            }
+        };
+
+        let value = self.read_response(&response).await?;
+        take_result(value)
+    }
+
+    // Sign and send a request, returning its id and where its response will arrive
+    async fn send_request(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<(Id, oneshot::Receiver<Event>), Error> {
+        // Copy what we need so we don't hold the lock while signing
+        let (wallet, relays, identity) = match self.inner.read_arc().as_ref() {
+            Some(c) => (c.wallet, c.relays.clone(), c.identity.clone()),
+            None => return Err(ErrorKind::Wallet("No wallet is connected".to_owned()).into()),
+        };
+        if !self.is_ready() {
+            return Err(ErrorKind::Wallet("The wallet is locked".to_owned()).into());
+        }
+
+        let algo = self.encryption(wallet);
+        let nip44 = matches!(algo, ContentEncryptionAlgorithm::Nip44v2);
+        let plaintext = serde_json::to_string(&json!({
+            "method": method,
+            "params": params,
+        }))?;
+
+        let pubkey = identity.public_key().ok_or(ErrorKind::NoPublicKey)?;
+        let content = identity.encrypt(&wallet, &plaintext, algo).await?;
+        let mut tags = vec![ParsedTag::Pubkey {
+            pubkey: wallet,
+            recommended_relay_url: None,
+            petname: None,
+        }
+        .into_tag()];
+        if nip44 {
+            tags.push(Tag::new(&["encryption", "nip44_v2"]));
+        }
+        let pre_event = PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::WalletRequest,
+            tags,
+            content,
+        };
+        let event = identity.sign_event(pre_event).await?;
+
+        // Be ready for the response before we ask
+        let (sender, receiver) = oneshot::channel();
+        self.waiting.insert(event.id, sender);
+        let id = event.id;
+
+        // Listen for responses, then send the request
+        crate::manager::run_jobs_on_all_relays(
+            relays,
+            vec![
+                RelayJob {
+                    reason: RelayConnectionReason::Wallet,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::WalletResponses),
+                    },
+                },
+                RelayJob {
+                    reason: RelayConnectionReason::Wallet,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event]),
+                    },
+                },
+            ],
+        );
+
+        Ok((id, receiver))
+    }
+
+    // Decrypt a response from the wallet service
+    async fn read_response(&self, response: &Event) -> Result<Value, Error> {
+        let identity = match self.inner.read_arc().as_ref() {
+            Some(c) => c.identity.clone(),
+            None => return Err(ErrorKind::Wallet("No wallet is connected".to_owned()).into()),
+        };
+        let plaintext = identity
+            .decrypt(&response.pubkey, &response.content)
+            .await?;
+        Ok(serde_json::from_str(&plaintext)?)
+    }
+
+    /// Pay a lightning invoice, returning the preimage.
+    ///
+    /// If the wallet does not answer in time, the payment is left pending and
+    /// `None` is returned, as the wallet may still pay it. A pending invoice is
+    /// not sent again. Paying it again asks the wallet what became of it instead.
+    pub async fn pay_invoice(&self, invoice: &str) -> Result<Option<String>, Error> {
+        if !self.pending_payments.insert(invoice.to_owned()) {
+            return self.settle_payment(invoice).await;
+        }
+
+        let (_id, mut receiver) = match self
+            .send_request("pay_invoice", json!({ "invoice": invoice }))
+            .await
+        {
+            Ok(sent) => sent,
+            Err(e) => {
+                self.pending_payments.remove(invoice);
+                return Err(e);
+            }
+        };
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, &mut receiver).await {
+            Ok(Ok(response)) => self.finish_payment(invoice, &response).await,
+            Ok(Err(_)) => Ok(None),
+            Err(_) => {
+                // Keep listening, the answer may still come
+                let invoice = invoice.to_owned();
+                std::mem::drop(tokio::spawn(Box::pin(async move {
+                    let Ok(response) = receiver.await else {
+                        return;
+                    };
+                    let message = match GLOBALS.wallet.finish_payment(&invoice, &response).await {
+                        Ok(Some(_)) => "Your wallet paid a pending payment.".to_owned(),
+                        Ok(None) => return,
+                        Err(e) => format!("A pending wallet payment failed: {}", e),
+                    };
+                    GLOBALS.status_queue.write().write(message);
+                    crate::overlord::Overlord::refresh_wallet();
+                })));
+                Ok(None)
+            }
+        }
+    }
+
+    // Read the wallet's answer to a payment, returning the preimage. If we
+    // can't read it, the payment stays pending.
+    async fn finish_payment(
+        &self,
+        invoice: &str,
+        response: &Event,
+    ) -> Result<Option<String>, Error> {
+        let value = match self.read_response(response).await {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!("Could not read the wallet's answer to a payment: {}", e);
+                return Ok(None);
+            }
+        };
+
+        // Paid or refused, it is no longer pending
+        self.pending_payments.remove(invoice);
+
+        let result = take_result(value)?;
+        Ok(Some(
+            result
+                .get("preimage")
+                .and_then(|p| p.as_str())
+                .unwrap_or_default()
+                .to_owned(),
+        ))
+    }
+
+    // Ask the wallet what became of a pending payment
+    async fn settle_payment(&self, invoice: &str) -> Result<Option<String>, Error> {
+        let result = match self
+            .request("lookup_invoice", json!({ "invoice": invoice }))
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::warn!("Could not look up a pending payment: {}", e);
+                return Ok(None);
+            }
+        };
+
+        let preimage = result
+            .get("preimage")
+            .and_then(|p| p.as_str())
+            .unwrap_or_default();
+        let settled = !preimage.is_empty()
+            || result.get("settled_at").is_some_and(|s| !s.is_null())
+            || result.get("state").and_then(|s| s.as_str()) == Some("settled");
+        if settled {
+            self.pending_payments.remove(invoice);
+            return Ok(Some(preimage.to_owned()));
+        }
+
+        if result.get("state").and_then(|s| s.as_str()) == Some("failed") {
+            self.pending_payments.remove(invoice);
+            return Err(ErrorKind::Wallet("The payment failed".to_owned()).into());
+        }
+
+        Ok(None)
+    }
+
+    /// Ask for the balance, in millisatoshis
+    pub async fn get_balance(&self) -> Result<u64, Error> {
+        let result = self.request("get_balance", json!({})).await?;
+        let balance = result
+            .get("balance")
+            .and_then(|b| b.as_u64())
+            .ok_or_else(|| ErrorKind::Wallet("Balance not recognized".to_owned()))?;
+        *self.balance.write() = Some(balance);
+        Ok(balance)
+    }
+
+    /// Ask for the most recent payments
+    pub async fn list_transactions(&self, limit: u64) -> Result<Vec<Transaction>, Error> {
+        let mut result = self
+            .request("list_transactions", json!({ "limit": limit }))
+            .await?;
+        let transactions: Vec<Transaction> = match result.get_mut("transactions") {
+            Some(transactions) => serde_json::from_value(transactions.take())?,
+            None => Vec::new(),
+        };
+        *self.transactions.write() = transactions.clone();
+        Ok(transactions)
+    }
+
+    /// Hand a response from the wallet service to whoever is waiting for it
+    pub(crate) fn handle_response(&self, event: &Event) {
+        if Some(event.pubkey) != self.wallet_pubkey() {
+            return;
+        }
+        for tag in event.tags.iter() {
+            if let Ok(ParsedTag::Event { id, .. }) = tag.parse() {
+                if let Some((_, sender)) = self.waiting.remove(&id) {
+                    let _ = sender.send(event.clone());
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// The result of a response, or the error the wallet service answered with
+fn take_result(mut value: Value) -> Result<Value, Error> {
+    if let Some(error) = value.get("error").filter(|e| !e.is_null()) {
+        let code = error
+            .get("code")
+            .and_then(|c| c.as_str())
+            .unwrap_or("ERROR");
+        let message = error.get("message").and_then(|m| m.as_str()).unwrap_or("");
+        return Err(ErrorKind::Wallet(format!("{}: {}", code, message)).into());
+    }
+
+    Ok(value
+        .get_mut("result")
+        .map(Value::take)
+        .unwrap_or(Value::Null))
+}
@@ -0,0 +1,285 @@
+use crate::globals::GLOBALS;
+use crate::relationship::RelationshipById;
+use crate::storage::{PersonTable, Table};
+use nostr_types::{Event, EventKind, Id, MilliSatoshi, PublicKey, RelayUrl, Unixtime};
+
+/// A recipient of part of every zap on an event, from a `zap` tag (NIP-57 appendix G)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZapSplit {
+    pub pubkey: PublicKey,
+    pub relay: Option<RelayUrl>,
+    pub weight: Option<f64>,
+}
+
+/// The zap splits of an event. If there are none, the author gets the whole zap.
+pub fn zap_splits(event: &Event) -> Vec<ZapSplit> {
+    event
+        .tags
+        .iter()
+        .filter(|t| t.tagname() == "zap")
+        .filter_map(|t| {
+            let pubkey = PublicKey::try_from_hex_string(t.value(), true).ok()?;
+            let relay = RelayUrl::try_from_str(t.get_index(2)).ok();
+            let weight = t
+                .get_index(3)
+                .parse::<f64>()
+                .ok()
+                .filter(|w| w.is_finite() && *w >= 0.0);
+            Some(ZapSplit {
+                pubkey,
+                relay,
+                weight,
+            })
+        })
+        .collect()
+}
+
+/// The share (between 0 and 1) of a zap that each recipient gets. When no weights
+/// are given it is split evenly, otherwise recipients without a weight get nothing.
+pub fn zap_shares(splits: &[ZapSplit]) -> Vec<(PublicKey, f64)> {
+    let weighted = splits.iter().any(|s| s.weight.is_some());
+    let weights: Vec<f64> = splits
+        .iter()
+        .map(|s| match (weighted, s.weight) {
+            (false, _) => 1.0,
+            (true, Some(w)) => w,
+            (true, None) => 0.0,
+        })
+        .collect();
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return vec![];
+    }
+    splits
+        .iter()
+        .zip(weights)
+        .filter(|(_, w)| *w > 0.0)
+        .map(|(s, w)| (s.pubkey, w / total))
+        .collect()
+}
+
+/// Split a zap amount by the shares of its recipients. Each gets whole sats, the
+/// first one gets whatever is left over by rounding.
+pub fn split_zap_amount(
+    msats: MilliSatoshi,
+    shares: &[(PublicKey, f64)],
+) -> Vec<(PublicKey, MilliSatoshi)> {
+    let sats = msats.0 / 1000;
+    let mut amounts: Vec<(PublicKey, u64)> = shares
+        .iter()
+        .map(|(pk, share)| (*pk, (sats as f64 * share).floor() as u64))
+        .collect();
+    let given: u64 = amounts.iter().map(|(_, s)| s).sum();
+    if let Some(first) = amounts.first_mut() {
+        first.1 += sats.saturating_sub(given);
+    }
+    amounts
+        .drain(..)
+        .filter(|(_, s)| *s > 0)
+        .map(|(pk, s)| (pk, MilliSatoshi(s * 1000)))
+        .collect()
+}
+
+/// The lightning address (or lnurl) of a person, if we know it
+pub fn lnurl_of(pubkey: PublicKey) -> Option<String> {
+    PersonTable::read_record(pubkey, None)
+        .ok()
+        .flatten()
+        .and_then(|person| person.metadata().as_ref().and_then(|m| m.lnurl()))
+}
+
+/// A fundraising goal (NIP-75)
+#[derive(Debug, Clone)]
+pub struct ZapGoal {
+    pub id: Id,
+    pub author: PublicKey,
+    pub description: String,
+    pub summary: String,
+    pub image: Option<String>,
+    pub url: Option<String>,
+
+    /// The target amount
+    pub amount: MilliSatoshi,
+
+    /// Where zap receipts for this goal are published
+    pub relays: Vec<RelayUrl>,
+
+    /// Zaps after this time do not count
+    pub closed_at: Option<Unixtime>,
+}
+
+impl ZapGoal {
+    pub fn from_event(event: &Event) -> Option<ZapGoal> {
+        if event.kind != EventKind::ZapGoal {
+            return None;
+        }
+
+        let mut amount: Option<MilliSatoshi> = None;
+        let mut relays: Vec<RelayUrl> = Vec::new();
+        let mut closed_at: Option<Unixtime> = None;
+        let mut summary = String::new();
+        let mut image: Option<String> = None;
+        let mut url: Option<String> = None;
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "amount" => amount = tag.value().parse::<u64>().ok().map(MilliSatoshi),
+                "relays" => {
+                    relays = (1..)
+                        .map(|i| tag.get_index(i))
+                        .take_while(|r| !r.is_empty())
+                        .filter_map(|r| RelayUrl::try_from_str(r).ok())
+                        .collect()
+                }
+                "closed_at" => closed_at = tag.value().parse::<i64>().ok().map(Unixtime),
+                "summary" => summary = tag.value().to_owned(),
+                "image" => image = Some(tag.value().to_owned()),
+                "r" => url = Some(tag.value().to_owned()),
+                _ => {}
+            }
+        }
+
+        Some(ZapGoal {
+            id: event.id,
+            author: event.pubkey,
+            description: event.content.clone(),
+            summary,
+            image,
+            url,
+            amount: amount?,
+            relays,
+            closed_at,
+        })
+    }
+
+    /// Load a goal we have
+    pub fn load(id: Id) -> Option<ZapGoal> {
+        let event = GLOBALS.db().read_event(id).ok()??;
+        ZapGoal::from_event(&event)
+    }
+
+    /// How much has been zapped to the goal so far. Once the goal is closed,
+    /// only zap receipts from before it closed count.
+    pub fn raised(&self) -> MilliSatoshi {
+        let mut total = MilliSatoshi(0);
+        for (receipt_id, rel) in GLOBALS
+            .db()
+            .find_relationships_by_id(self.id)
+            .unwrap_or_default()
+        {
+            let RelationshipById::Zaps { amount, .. } = rel else {
+                continue;
+            };
+            if let Some(closed_at) = self.closed_at {
+                match GLOBALS.db().read_event(receipt_id) {
+                    Ok(Some(receipt)) if receipt.created_at <= closed_at => {}
+                    _ => continue,
+                }
+            }
+            total = total + amount;
+        }
+        total
+    }
+
+    /// How far along the goal is, from 0 to 1, with this much raised
+    pub fn progress(&self, raised: MilliSatoshi) -> f32 {
+        if self.amount.0 == 0 {
+            return 1.0;
+        }
+        (raised.0 as f64 / self.amount.0 as f64).min(1.0) as f32
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed_at.is_some_and(|t| t < Unixtime::now())
+    }
+}
+
+/// The goal that an event raises funds for, from its `goal` tag
+pub fn goal_of(event: &Event) -> Option<(Id, Option<RelayUrl>)> {
+    let tag = event.tags.iter().find(|t| t.tagname() == "goal")?;
+    let id = Id::try_from_hex_string(tag.value()).ok()?;
+    Some((id, RelayUrl::try_from_str(tag.get_index(2)).ok()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::PrivateKey;
+
+    fn split(weight: Option<f64>) -> ZapSplit {
+        ZapSplit {
+            pubkey: PrivateKey::generate().public_key(),
+            relay: None,
+            weight,
+        }
+    }
+
+    #[test]
+    fn test_zap_shares() {
+        // Without weights, evenly
+        let splits = vec![split(None), split(None), split(None), split(None)];
+        let shares = zap_shares(&splits);
+        assert_eq!(shares.len(), 4);
+        for (i, (pubkey, share)) in shares.iter().enumerate() {
+            assert_eq!(*pubkey, splits[i].pubkey);
+            assert_eq!(*share, 0.25);
+        }
+
+        // By weight, and those without a weight get nothing
+        let splits = vec![split(Some(1.0)), split(None), split(Some(3.0))];
+        let shares = zap_shares(&splits);
+        assert_eq!(
+            shares,
+            vec![(splits[0].pubkey, 0.25), (splits[2].pubkey, 0.75)]
+        );
+
+        // A zero weight gets nothing
+        let splits = vec![split(Some(0.0)), split(Some(2.0))];
+        assert_eq!(zap_shares(&splits), vec![(splits[1].pubkey, 1.0)]);
+
+        // Nobody gets anything if all the weights are zero
+        let splits = vec![split(Some(0.0)), split(Some(0.0)), split(None)];
+        assert!(zap_shares(&splits).is_empty());
+        assert!(zap_shares(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_split_zap_amount() {
+        let splits = vec![split(None), split(None), split(None)];
+        let shares = zap_shares(&splits);
+
+        // The first gets what is left over by rounding
+        let amounts = split_zap_amount(MilliSatoshi(1_000_000), &shares);
+        assert_eq!(
+            amounts,
+            vec![
+                (splits[0].pubkey, MilliSatoshi(334_000)),
+                (splits[1].pubkey, MilliSatoshi(333_000)),
+                (splits[2].pubkey, MilliSatoshi(333_000)),
+            ]
+        );
+
+        // Only whole sats are split
+        let amounts = split_zap_amount(MilliSatoshi(3_000_999), &shares);
+        let total: u64 = amounts.iter().map(|(_, a)| a.0).sum();
+        assert_eq!(total, 3_000_000);
+        assert!(amounts.iter().all(|(_, a)| a.0 == 1_000_000));
+
+        // Those whose share rounds down to nothing are left out
+        let amounts = split_zap_amount(MilliSatoshi(2_000), &shares);
+        assert_eq!(amounts, vec![(splits[0].pubkey, MilliSatoshi(2_000))]);
+
+        // Weighted
+        let splits = vec![split(Some(1.0)), split(Some(3.0))];
+        let amounts = split_zap_amount(MilliSatoshi(21_000), &zap_shares(&splits));
+        assert_eq!(
+            amounts,
+            vec![
+                (splits[0].pubkey, MilliSatoshi(6_000)),
+                (splits[1].pubkey, MilliSatoshi(15_000)),
+            ]
+        );
+
+        // Nobody to split it with
+        assert!(split_zap_amount(MilliSatoshi(21_000), &[]).is_empty());
+    }
+}